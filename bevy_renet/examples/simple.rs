@@ -91,7 +91,7 @@ fn main() {
     app.init_resource::<Lobby>();
 
     if is_host {
-        app.add_plugins(RenetServerPlugin);
+        app.add_plugins(RenetServerPlugin::default());
         app.add_plugins(NetcodeServerPlugin);
         let (server, transport) = new_renet_server();
         app.insert_resource(server);
@@ -102,7 +102,7 @@ fn main() {
             (server_update_system, server_sync_players, move_players_system).run_if(resource_exists::<RenetServer>),
         );
     } else {
-        app.add_plugins(RenetClientPlugin);
+        app.add_plugins(RenetClientPlugin::default());
         app.add_plugins(NetcodeClientPlugin);
         app.init_resource::<PlayerInput>();
         let (client, transport) = new_renet_client();
@@ -277,10 +277,12 @@ fn move_players_system(mut query: Query<(&mut Transform, &PlayerInput)>, time: R
     }
 }
 
-// If any error is found we just panic
-#[allow(clippy::never_loop)]
+// If an unrecoverable error is found we just panic
 fn panic_on_error_system(mut renet_error: EventReader<NetcodeTransportError>) {
     for e in renet_error.read() {
-        panic!("{}", e);
+        if !e.is_recoverable() {
+            panic!("{}", e);
+        }
+        eprintln!("{}", e);
     }
 }
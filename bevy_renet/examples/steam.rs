@@ -0,0 +1,102 @@
+use std::time::Duration;
+
+use bevy::{app::ScheduleRunnerPlugin, prelude::*};
+use bevy_renet::renet::{ConnectionConfig, DefaultChannel, RenetClient, RenetServer, ServerEvent};
+use bevy_renet::steam::{
+    steamworks::{self, SteamId},
+    SteamClientPlugin, SteamClientTransport, SteamServerBuilder, SteamServerPlugin, SteamTransportError,
+};
+use bevy_renet::{client_connected, RenetClientPlugin, RenetServerPlugin};
+
+// Steam needs to be running, 480 is the Spacewar test app id.
+// Run the server with `cargo run --example steam --features steam -- server`,
+// and the client with the steam id printed by the server, or without it to connect by localhost
+// `cargo run --example steam --features steam -- client [steam_id]`
+const APP_ID: u32 = 480;
+
+#[derive(Resource)]
+struct PingTimer(Timer);
+
+fn main() {
+    println!("Usage: run with \"server\" or \"client [server_steam_id]\" argument");
+    let args: Vec<String> = std::env::args().collect();
+
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins.set(ScheduleRunnerPlugin::run_loop(Duration::from_secs_f64(1.0 / 60.0))));
+
+    let (steam_client, single) = steamworks::Client::init_app(APP_ID).unwrap();
+    steam_client.networking_utils().init_relay_network_access();
+    // The steam plugins run the callbacks from the non-send SingleClient
+    app.insert_non_send_resource(single);
+
+    match args.get(1).map(String::as_str) {
+        Some("server") => {
+            let transport = SteamServerBuilder::new(&steam_client)
+                .max_clients(10)
+                .p2p()
+                .ip("127.0.0.1:5000".parse().unwrap())
+                .build()
+                .unwrap();
+            println!("Server steam id: {}", steam_client.user().steam_id().raw());
+
+            app.add_plugins((RenetServerPlugin::default(), SteamServerPlugin));
+            app.insert_resource(RenetServer::new(ConnectionConfig::default()));
+            app.insert_resource(transport);
+            app.add_systems(Update, (server_events, server_echo));
+        }
+        Some("client") => {
+            let transport = match args.get(2) {
+                Some(steam_id) => {
+                    let server_steam_id = SteamId::from_raw(steam_id.parse().unwrap());
+                    SteamClientTransport::new_p2p(&steam_client, &server_steam_id).unwrap()
+                }
+                None => SteamClientTransport::new_ip(&steam_client, "127.0.0.1:5000".parse().unwrap()).unwrap(),
+            };
+
+            app.add_plugins((RenetClientPlugin::default(), SteamClientPlugin));
+            app.insert_resource(RenetClient::new(ConnectionConfig::default()));
+            app.insert_resource(transport);
+            app.insert_resource(PingTimer(Timer::from_seconds(1.0, TimerMode::Repeating)));
+            app.add_systems(Update, ((client_ping, client_receive).run_if(client_connected), client_errors));
+        }
+        _ => return,
+    }
+
+    app.run();
+}
+
+fn server_events(mut server_events: EventReader<ServerEvent>) {
+    for event in server_events.read() {
+        match event {
+            ServerEvent::ClientConnected { client_id } => println!("Client {} connected", client_id),
+            ServerEvent::ClientDisconnected { client_id, reason } => println!("Client {} disconnected: {}", client_id, reason),
+        }
+    }
+}
+
+fn server_echo(mut server: ResMut<RenetServer>) {
+    for client_id in server.clients_id() {
+        while let Some(message) = server.receive_message(client_id, DefaultChannel::ReliableOrdered) {
+            server.send_message(client_id, DefaultChannel::ReliableOrdered, message);
+        }
+    }
+}
+
+fn client_ping(mut client: ResMut<RenetClient>, mut timer: ResMut<PingTimer>, time: Res<Time>) {
+    if timer.0.tick(time.delta()).just_finished() {
+        client.send_message(DefaultChannel::ReliableOrdered, "ping");
+    }
+}
+
+fn client_receive(mut client: ResMut<RenetClient>) {
+    while let Some(message) = client.receive_message(DefaultChannel::ReliableOrdered) {
+        println!("Server echoed: {}", String::from_utf8_lossy(&message));
+    }
+}
+
+fn client_errors(mut transport_errors: EventReader<SteamTransportError>, mut exit: EventWriter<AppExit>) {
+    for e in transport_errors.read() {
+        println!("Transport error: {}", e);
+        exit.write(AppExit::error());
+    }
+}
@@ -0,0 +1,131 @@
+use std::collections::BTreeSet;
+
+use bevy_app::prelude::*;
+use bevy_ecs::prelude::*;
+use renet::{ClientId, RenetClient, RenetServer};
+
+use crate::{RenetClientSchedules, RenetReceive, RenetServerSchedules};
+
+/// Sends a [`MessageAcked`] event for each message of the reliable channels that was acked by the peer.
+///
+/// The plugin enables the ack notifications of its channels in the [`RenetServer`] and [`RenetClient`], and drains them after [`RenetReceive`].
+/// The channels of the events registered with the `events` feature are tracked too, and their acks are also sent as `EventDelivered<T>`.
+///
+/// # Usage
+/// ```
+/// # use bevy_app::prelude::*;
+/// # use bevy_ecs::prelude::*;
+/// # use bevy_renet::acks::{MessageAcked, RenetAckPlugin};
+/// # use bevy_renet::renet::DefaultChannel;
+/// # use bevy_renet::RenetClientPlugin;
+/// # let mut app = App::new();
+/// app.add_plugins((RenetClientPlugin::default(), RenetAckPlugin::new([DefaultChannel::ReliableOrdered])));
+/// app.add_systems(Update, |mut events: EventReader<MessageAcked>| {
+///     for event in events.read() {
+///         println!("Message {} of channel {} was received", event.message_id, event.channel_id);
+///     }
+/// });
+/// ```
+#[derive(Debug)]
+pub struct RenetAckPlugin {
+    channels: Vec<u8>,
+}
+
+/// A message sent on a reliable channel was acked.
+///
+/// The message id is the one returned by `next_message_id` before sending it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Event)]
+pub struct MessageAcked {
+    /// The client that acked the message in the server, None in the client.
+    pub client_id: Option<ClientId>,
+    pub channel_id: u8,
+    pub message_id: u64,
+}
+
+// Channels drained by the plugin, shared with the network events
+#[derive(Debug, Default, Resource)]
+pub(crate) struct AckChannels(pub(crate) BTreeSet<u8>);
+
+impl RenetAckPlugin {
+    pub fn new<I: Into<u8>>(channels: impl IntoIterator<Item = I>) -> Self {
+        Self {
+            channels: channels.into_iter().map(Into::into).collect(),
+        }
+    }
+
+    pub(crate) fn emit_server_acks_system(
+        mut server: ResMut<RenetServer>,
+        channels: Res<AckChannels>,
+        mut events: EventWriter<MessageAcked>,
+    ) {
+        // Only the drained channels are tracked, the acks of the others would be stored forever
+        for &channel_id in channels.0.iter() {
+            if !server.channel_ack_notifications(channel_id) {
+                server.set_channel_ack_notifications(channel_id, true);
+            }
+        }
+
+        for client_id in server.clients_id() {
+            for &channel_id in channels.0.iter() {
+                for message_id in server.acked_messages(client_id, channel_id) {
+                    events.write(MessageAcked {
+                        client_id: Some(client_id),
+                        channel_id,
+                        message_id,
+                    });
+                }
+            }
+        }
+    }
+
+    pub(crate) fn emit_client_acks_system(
+        mut client: ResMut<RenetClient>,
+        channels: Res<AckChannels>,
+        mut events: EventWriter<MessageAcked>,
+    ) {
+        for &channel_id in channels.0.iter() {
+            if !client.channel_ack_notifications(channel_id) {
+                client.set_channel_ack_notifications(channel_id, true);
+            }
+        }
+
+        for &channel_id in channels.0.iter() {
+            for message_id in client.acked_messages(channel_id) {
+                events.write(MessageAcked {
+                    client_id: None,
+                    channel_id,
+                    message_id,
+                });
+            }
+        }
+    }
+}
+
+impl Plugin for RenetAckPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<MessageAcked>();
+        app.init_resource::<AckChannels>();
+        app.world_mut()
+            .resource_mut::<AckChannels>()
+            .0
+            .extend(self.channels.iter().copied());
+    }
+
+    fn finish(&self, app: &mut App) {
+        let server_schedules = app.world().get_resource::<RenetServerSchedules>().copied().unwrap_or_default();
+        let client_schedules = app.world().get_resource::<RenetClientSchedules>().copied().unwrap_or_default();
+
+        app.add_systems(
+            server_schedules.receive,
+            Self::emit_server_acks_system
+                .after(RenetReceive)
+                .run_if(resource_exists::<RenetServer>),
+        );
+        app.add_systems(
+            client_schedules.receive,
+            Self::emit_client_acks_system
+                .after(RenetReceive)
+                .run_if(resource_exists::<RenetClient>),
+        );
+    }
+}
@@ -0,0 +1,161 @@
+use std::collections::HashMap;
+
+use bevy_app::prelude::*;
+use bevy_ecs::prelude::*;
+use renet::{ClientId, RenetServer};
+
+use crate::{ClientTransportData, RenetReceive, RenetSend, RenetServerPlugin, RenetServerSchedules, TransportServerEvent};
+
+/// Spawns an entity with a [`ConnectedClient`] for each client connected to the [`RenetServer`], to add the player data to it.
+///
+/// The entity is spawned in [`RenetReceive`] when the client connects, so the systems reading the `ServerEvent`s
+/// after it can already find it in the [`ClientEntityMap`].
+/// When the client disconnects the entity is kept until [`RenetSend`], so the systems handling the disconnection can still read it.
+/// With the netcode or steam transport the entity also has a [`ClientUserData`] or [`ClientSteamId`].
+///
+/// # Usage
+/// ```
+/// # use bevy_app::prelude::*;
+/// # use bevy_ecs::prelude::*;
+/// # use bevy_renet::client_entities::{ClientEntitiesPlugin, ClientEntityMap};
+/// # use bevy_renet::renet::{ConnectionConfig, RenetServer, ServerEvent};
+/// # use bevy_renet::RenetServerPlugin;
+/// # #[derive(Component)]
+/// # struct Score(u32);
+/// # let mut app = App::new();
+/// app.add_plugins((RenetServerPlugin::default(), ClientEntitiesPlugin));
+/// app.add_systems(Update, |mut commands: Commands, mut events: EventReader<ServerEvent>, clients: Res<ClientEntityMap>| {
+///     for event in events.read() {
+///         if let ServerEvent::ClientConnected { client_id } = event {
+///             commands.entity(clients.get(*client_id).unwrap()).insert(Score(0));
+///         }
+///     }
+/// });
+/// ```
+#[derive(Debug)]
+pub struct ClientEntitiesPlugin;
+
+/// A client connected to the [`RenetServer`], spawned by the [`ClientEntitiesPlugin`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Component)]
+pub struct ConnectedClient {
+    pub client_id: ClientId,
+}
+
+/// The user data from the connect token of a [`ConnectedClient`] connected with the netcode transport.
+#[cfg(feature = "netcode")]
+#[derive(Debug, Clone, PartialEq, Eq, Component)]
+pub struct ClientUserData(pub Box<[u8; renet_netcode::NETCODE_USER_DATA_BYTES]>);
+
+/// The steam id of a [`ConnectedClient`] connected with the steam transport.
+#[cfg(feature = "steam")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Component)]
+pub struct ClientSteamId(pub renet_steam::steamworks::SteamId);
+
+/// The entities of the [`ConnectedClient`]s by client id.
+#[derive(Debug, Default, Resource)]
+pub struct ClientEntityMap {
+    entities: HashMap<ClientId, Entity>,
+}
+
+impl ClientEntityMap {
+    pub fn get(&self, client_id: ClientId) -> Option<Entity> {
+        self.entities.get(&client_id).copied()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (ClientId, Entity)> + '_ {
+        self.entities.iter().map(|(&client_id, &entity)| (client_id, entity))
+    }
+
+    pub fn len(&self) -> usize {
+        self.entities.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entities.is_empty()
+    }
+}
+
+// Entities of the disconnected clients, despawned in the send schedule
+#[derive(Debug, Default, Resource)]
+struct PendingDespawns(Vec<(ClientId, Entity)>);
+
+impl Plugin for ClientEntitiesPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ClientEntityMap>();
+        app.init_resource::<PendingDespawns>();
+    }
+
+    fn finish(&self, app: &mut App) {
+        let schedules = app.world().get_resource::<RenetServerSchedules>().copied().unwrap_or_default();
+
+        app.add_systems(
+            schedules.receive,
+            Self::spawn_system
+                .in_set(RenetReceive)
+                .after(RenetServerPlugin::emit_server_events_system),
+        );
+        app.add_systems(schedules.send, Self::despawn_system.in_set(RenetSend));
+        app.add_systems(schedules.send, Self::despawn_all_system.run_if(resource_removed::<RenetServer>));
+    }
+}
+
+impl ClientEntitiesPlugin {
+    fn spawn_system(
+        mut commands: Commands,
+        mut transport_events: EventReader<TransportServerEvent>,
+        mut client_entities: ResMut<ClientEntityMap>,
+        mut pending_despawns: ResMut<PendingDespawns>,
+    ) {
+        for event in transport_events.read() {
+            match event {
+                TransportServerEvent::ClientConnected { client_id, transport_data } => {
+                    #[allow(unused_mut)]
+                    let mut entity = commands.spawn(ConnectedClient { client_id: *client_id });
+                    match transport_data {
+                        ClientTransportData::None => {}
+                        #[cfg(feature = "netcode")]
+                        ClientTransportData::Netcode { user_data } => {
+                            entity.insert(ClientUserData(user_data.clone()));
+                        }
+                        #[cfg(feature = "steam")]
+                        ClientTransportData::Steam { steam_id } => {
+                            entity.insert(ClientSteamId(*steam_id));
+                        }
+                    }
+                    client_entities.entities.insert(*client_id, entity.id());
+                }
+                TransportServerEvent::ClientDisconnected { client_id, .. } => {
+                    if let Some(entity) = client_entities.get(*client_id) {
+                        pending_despawns.0.push((*client_id, entity));
+                    }
+                }
+            }
+        }
+    }
+
+    fn despawn_system(mut commands: Commands, mut client_entities: ResMut<ClientEntityMap>, mut pending_despawns: ResMut<PendingDespawns>) {
+        for (client_id, entity) in pending_despawns.0.drain(..) {
+            // The client could have connected again with a new entity
+            if client_entities.get(client_id) == Some(entity) {
+                client_entities.entities.remove(&client_id);
+            }
+            if let Ok(mut entity) = commands.get_entity(entity) {
+                entity.despawn();
+            }
+        }
+    }
+
+    // The server was removed without disconnect events
+    fn despawn_all_system(
+        mut commands: Commands,
+        mut client_entities: ResMut<ClientEntityMap>,
+        mut pending_despawns: ResMut<PendingDespawns>,
+    ) {
+        pending_despawns.0.clear();
+        for (_, entity) in client_entities.entities.drain() {
+            if let Ok(mut entity) = commands.get_entity(entity) {
+                entity.despawn();
+            }
+        }
+    }
+}
@@ -0,0 +1,137 @@
+use bevy_app::prelude::*;
+use bevy_ecs::prelude::*;
+use renet::{RenetClient, RenetServer};
+
+use crate::{RenetClientSchedules, RenetReceive, RenetServerSchedules};
+
+pub use renet::NetworkConditions;
+
+/// Applies the [`NetworkConditions`] resource to the [`RenetServer`], or to the [`RenetClient`] when there is no server,
+/// to play-test with artificial latency, jitter, loss and duplication. Remove the resource to disable them.
+///
+/// The conditions are applied to the packets before [`RenetReceive`], they work with any transport and change immediately.
+/// Only enable the `conditioner` feature in development builds.
+///
+/// With the `egui` feature, [`with_window`](Self::with_window) adds a window to change the conditions at runtime,
+/// requires the bevy_egui `EguiPlugin`.
+///
+/// # Usage
+/// ```
+/// # use std::time::Duration;
+/// # use bevy_app::prelude::*;
+/// # use bevy_renet::conditioner::{NetworkConditionerPlugin, NetworkConditions};
+/// # use bevy_renet::RenetClientPlugin;
+/// # let mut app = App::new();
+/// app.add_plugins((RenetClientPlugin::default(), NetworkConditionerPlugin::default()));
+/// app.insert_resource(NetworkConditions {
+///     latency: Duration::from_millis(100),
+///     loss_percent: 5.0,
+///     ..Default::default()
+/// });
+/// ```
+#[derive(Debug, Default)]
+pub struct NetworkConditionerPlugin {
+    #[cfg(feature = "egui")]
+    show_window: bool,
+}
+
+impl NetworkConditionerPlugin {
+    /// Adds an egui window to change the conditions.
+    #[cfg(feature = "egui")]
+    pub fn with_window(mut self) -> Self {
+        self.show_window = true;
+        self
+    }
+
+    pub fn update_server_system(conditions: Option<Res<NetworkConditions>>, mut server: ResMut<RenetServer>) {
+        let conditions = conditions.map(|conditions| *conditions);
+        if server.network_conditions() != conditions {
+            server.set_network_conditions(conditions);
+        }
+    }
+
+    pub fn update_client_system(conditions: Option<Res<NetworkConditions>>, mut client: ResMut<RenetClient>) {
+        let conditions = conditions.map(|conditions| *conditions);
+        if client.network_conditions() != conditions {
+            client.set_network_conditions(conditions);
+        }
+    }
+
+    #[cfg(feature = "egui")]
+    pub fn show_window_system(
+        mut commands: Commands,
+        mut egui_contexts: bevy_egui::EguiContexts,
+        conditions: Option<ResMut<NetworkConditions>>,
+        mut disabled_conditions: Local<NetworkConditions>,
+    ) {
+        use bevy_egui::egui;
+        use std::time::Duration;
+
+        let Some(ctx) = egui_contexts.try_ctx_mut() else {
+            return;
+        };
+
+        egui::Window::new("Network Conditions").show(ctx, |ui| {
+            let mut enabled = conditions.is_some();
+            if ui.checkbox(&mut enabled, "Enabled").changed() {
+                match &conditions {
+                    Some(conditions) if !enabled => {
+                        *disabled_conditions = **conditions;
+                        commands.remove_resource::<NetworkConditions>();
+                    }
+                    None if enabled => commands.insert_resource(*disabled_conditions),
+                    _ => {}
+                }
+            }
+
+            let Some(mut conditions) = conditions else {
+                return;
+            };
+            let mut latency_ms = conditions.latency.as_millis() as u64;
+            let mut jitter_ms = conditions.jitter.as_millis() as u64;
+            let mut loss_percent = conditions.loss_percent;
+            let mut duplication_percent = conditions.duplication_percent;
+            ui.add(egui::Slider::new(&mut latency_ms, 0..=1000).text("Latency (ms)"));
+            ui.add(egui::Slider::new(&mut jitter_ms, 0..=500).text("Jitter (ms)"));
+            ui.add(egui::Slider::new(&mut loss_percent, 0.0..=100.0).text("Loss (%)"));
+            ui.add(egui::Slider::new(&mut duplication_percent, 0.0..=100.0).text("Duplication (%)"));
+
+            let new_conditions = NetworkConditions {
+                latency: Duration::from_millis(latency_ms),
+                jitter: Duration::from_millis(jitter_ms),
+                loss_percent,
+                duplication_percent,
+            };
+            conditions.set_if_neq(new_conditions);
+        });
+    }
+}
+
+impl Plugin for NetworkConditionerPlugin {
+    fn build(&self, _app: &mut App) {}
+
+    fn finish(&self, app: &mut App) {
+        #[cfg(feature = "egui")]
+        if self.show_window {
+            app.add_systems(Update, Self::show_window_system);
+        }
+
+        let client_schedules = app.world().get_resource::<RenetClientSchedules>().copied().unwrap_or_default();
+        let server_schedules = app.world().get_resource::<RenetServerSchedules>().copied().unwrap_or_default();
+
+        app.add_systems(
+            server_schedules.receive,
+            Self::update_server_system
+                .before(RenetReceive)
+                .run_if(resource_exists::<RenetServer>),
+        );
+        // In a listen server the local client is already conditioned by the server
+        app.add_systems(
+            client_schedules.receive,
+            Self::update_client_system
+                .before(RenetReceive)
+                .run_if(resource_exists::<RenetClient>)
+                .run_if(not(resource_exists::<RenetServer>)),
+        );
+    }
+}
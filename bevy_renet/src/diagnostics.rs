@@ -0,0 +1,184 @@
+use std::collections::HashSet;
+
+use bevy_app::prelude::*;
+use bevy_diagnostic::{Diagnostic, DiagnosticMeasurement, DiagnosticPath, Diagnostics, DiagnosticsStore, RegisterDiagnostic};
+use bevy_ecs::prelude::*;
+use bevy_platform::time::Instant;
+use renet::{ClientId, RenetClient, RenetServer};
+
+use crate::{RenetClientSchedules, RenetReceive, RenetServerSchedules};
+
+/// Registers the network stats of the [`RenetClient`] and [`RenetServer`] in the bevy [`DiagnosticsStore`],
+/// so they are shown by the `LogDiagnosticsPlugin` and other diagnostics overlays.
+///
+/// The values are measured after [`RenetReceive`] each tick.
+/// Per client diagnostics for the server are disabled by default, see [`per_client`](Self::per_client).
+///
+/// # Usage
+/// ```
+/// # use bevy_app::prelude::*;
+/// # use bevy_renet::{diagnostics::RenetDiagnosticsPlugin, RenetServerPlugin};
+/// # let mut app = App::new();
+/// app.add_plugins((RenetServerPlugin::default(), RenetDiagnosticsPlugin::default().per_client(8)));
+/// ```
+#[derive(Debug, Default)]
+pub struct RenetDiagnosticsPlugin {
+    max_client_diagnostics: usize,
+}
+
+/// Clients of the server with per client diagnostics.
+#[derive(Debug, Default, Resource)]
+struct TrackedClients {
+    max_clients: usize,
+    clients: HashSet<ClientId>,
+}
+
+impl RenetDiagnosticsPlugin {
+    pub const CLIENT_RTT: DiagnosticPath = DiagnosticPath::const_new("renet/client/rtt");
+    pub const CLIENT_PACKET_LOSS: DiagnosticPath = DiagnosticPath::const_new("renet/client/packet_loss");
+    pub const CLIENT_SENT_KBPS: DiagnosticPath = DiagnosticPath::const_new("renet/client/sent_kbps");
+    pub const CLIENT_RECEIVED_KBPS: DiagnosticPath = DiagnosticPath::const_new("renet/client/received_kbps");
+    pub const SERVER_CONNECTED_CLIENTS: DiagnosticPath = DiagnosticPath::const_new("renet/server/connected_clients");
+    pub const SERVER_SENT_KBPS: DiagnosticPath = DiagnosticPath::const_new("renet/server/sent_kbps");
+    pub const SERVER_RECEIVED_KBPS: DiagnosticPath = DiagnosticPath::const_new("renet/server/received_kbps");
+
+    /// Also register the rtt, packet loss and bandwidth of each server client, for up to `max_clients` clients at a time.
+    ///
+    /// The paths are `renet/server/client/{client_id}/{rtt, packet_loss, sent_kbps, received_kbps}`,
+    /// the diagnostics of disconnected clients are disabled.
+    pub fn per_client(mut self, max_clients: usize) -> Self {
+        self.max_client_diagnostics = max_clients;
+        self
+    }
+
+    /// Returns the path of a per client diagnostic, `name` is one of rtt, packet_loss, sent_kbps or received_kbps.
+    pub fn client_path(client_id: ClientId, name: &str) -> DiagnosticPath {
+        DiagnosticPath::new(format!("renet/server/client/{client_id}/{name}"))
+    }
+
+    pub fn client_diagnostics_system(client: Res<RenetClient>, mut diagnostics: Diagnostics) {
+        let network_info = client.network_info();
+        diagnostics.add_measurement(&Self::CLIENT_RTT, || network_info.rtt * 1000.0);
+        diagnostics.add_measurement(&Self::CLIENT_PACKET_LOSS, || network_info.packet_loss * 100.0);
+        diagnostics.add_measurement(&Self::CLIENT_SENT_KBPS, || to_kbps(network_info.bytes_sent_per_second));
+        diagnostics.add_measurement(&Self::CLIENT_RECEIVED_KBPS, || to_kbps(network_info.bytes_received_per_second));
+    }
+
+    pub fn server_diagnostics_system(server: Res<RenetServer>, mut diagnostics: Diagnostics) {
+        diagnostics.add_measurement(&Self::SERVER_CONNECTED_CLIENTS, || server.connected_clients() as f64);
+        diagnostics.add_measurement(&Self::SERVER_SENT_KBPS, || {
+            to_kbps(server.clients_id_iter().map(|client_id| server.bytes_sent_per_sec(client_id)).sum())
+        });
+        diagnostics.add_measurement(&Self::SERVER_RECEIVED_KBPS, || {
+            to_kbps(
+                server
+                    .clients_id_iter()
+                    .map(|client_id| server.bytes_received_per_sec(client_id))
+                    .sum(),
+            )
+        });
+    }
+
+    fn server_client_diagnostics_system(
+        server: Res<RenetServer>,
+        mut tracked: ResMut<TrackedClients>,
+        mut store: ResMut<DiagnosticsStore>,
+    ) {
+        let TrackedClients { max_clients, clients } = &mut *tracked;
+        clients.retain(|&client_id| {
+            if server.is_connected(client_id) {
+                return true;
+            }
+            for (name, _) in CLIENT_DIAGNOSTICS {
+                if let Some(diagnostic) = store.get_mut(&Self::client_path(client_id, name)) {
+                    diagnostic.is_enabled = false;
+                    diagnostic.clear_history();
+                }
+            }
+            false
+        });
+
+        let now = Instant::now();
+        for client_id in server.clients_id_iter() {
+            if !clients.contains(&client_id) {
+                if clients.len() >= *max_clients {
+                    continue;
+                }
+                clients.insert(client_id);
+            }
+
+            let Ok(network_info) = server.network_info(client_id) else {
+                continue;
+            };
+            let values = [
+                network_info.rtt * 1000.0,
+                network_info.packet_loss * 100.0,
+                to_kbps(network_info.bytes_sent_per_second),
+                to_kbps(network_info.bytes_received_per_second),
+            ];
+            for ((name, suffix), value) in CLIENT_DIAGNOSTICS.into_iter().zip(values) {
+                let path = Self::client_path(client_id, name);
+                if store.get(&path).is_none() {
+                    store.add(Diagnostic::new(path.clone()).with_suffix(suffix));
+                }
+                let diagnostic = store.get_mut(&path).unwrap();
+                diagnostic.is_enabled = true;
+                diagnostic.add_measurement(DiagnosticMeasurement { time: now, value });
+            }
+        }
+    }
+}
+
+// Name and suffix of the per client diagnostics
+const CLIENT_DIAGNOSTICS: [(&str, &str); 4] = [
+    ("rtt", "ms"),
+    ("packet_loss", "%"),
+    ("sent_kbps", "kbps"),
+    ("received_kbps", "kbps"),
+];
+
+fn to_kbps(bytes_per_second: f64) -> f64 {
+    bytes_per_second * 8.0 / 1000.0
+}
+
+impl Plugin for RenetDiagnosticsPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_diagnostic(Diagnostic::new(Self::CLIENT_RTT).with_suffix("ms"))
+            .register_diagnostic(Diagnostic::new(Self::CLIENT_PACKET_LOSS).with_suffix("%"))
+            .register_diagnostic(Diagnostic::new(Self::CLIENT_SENT_KBPS).with_suffix("kbps"))
+            .register_diagnostic(Diagnostic::new(Self::CLIENT_RECEIVED_KBPS).with_suffix("kbps"))
+            .register_diagnostic(Diagnostic::new(Self::SERVER_CONNECTED_CLIENTS))
+            .register_diagnostic(Diagnostic::new(Self::SERVER_SENT_KBPS).with_suffix("kbps"))
+            .register_diagnostic(Diagnostic::new(Self::SERVER_RECEIVED_KBPS).with_suffix("kbps"));
+        app.insert_resource(TrackedClients {
+            max_clients: self.max_client_diagnostics,
+            clients: HashSet::new(),
+        });
+    }
+
+    fn finish(&self, app: &mut App) {
+        let client_schedules = app.world().get_resource::<RenetClientSchedules>().copied().unwrap_or_default();
+        let server_schedules = app.world().get_resource::<RenetServerSchedules>().copied().unwrap_or_default();
+
+        app.add_systems(
+            client_schedules.receive,
+            Self::client_diagnostics_system
+                .after(RenetReceive)
+                .run_if(resource_exists::<RenetClient>),
+        );
+        app.add_systems(
+            server_schedules.receive,
+            Self::server_diagnostics_system
+                .after(RenetReceive)
+                .run_if(resource_exists::<RenetServer>),
+        );
+        if self.max_client_diagnostics > 0 {
+            app.add_systems(
+                server_schedules.receive,
+                Self::server_client_diagnostics_system
+                    .after(RenetReceive)
+                    .run_if(resource_exists::<RenetServer>),
+            );
+        }
+    }
+}
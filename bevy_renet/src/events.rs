@@ -0,0 +1,297 @@
+use std::{collections::HashSet, marker::PhantomData};
+
+use bevy_app::prelude::*;
+use bevy_ecs::prelude::*;
+use renet::{ClientId, RenetClient, RenetServer};
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::{
+    acks::{AckChannels, MessageAcked, RenetAckPlugin},
+    RenetClientSchedules, RenetReceive, RenetSend, RenetServerSchedules,
+};
+
+/// An event sent by a client, received by the server.
+#[derive(Debug, Clone, PartialEq, Eq, Event)]
+pub struct FromClient<T: Event> {
+    pub client_id: ClientId,
+    pub event: T,
+}
+
+/// An event sent by the server to one or more clients.
+#[derive(Debug, Clone, PartialEq, Eq, Event)]
+pub struct ToClients<T: Event> {
+    pub mode: SendMode,
+    pub event: T,
+}
+
+/// Clients that should receive a [`ToClients`] event.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SendMode {
+    Direct(ClientId),
+    Broadcast,
+    BroadcastExcept(ClientId),
+}
+
+/// An event sent on a reliable channel was received by the peer, requires the [`RenetAckPlugin`].
+///
+/// Sent in the server for each client that received a [`ToClients`] event, and in the client for its own events.
+#[derive(Debug, Event)]
+pub struct EventDelivered<T: Event> {
+    /// The client that received the event in the server, None in the client.
+    pub client_id: Option<ClientId>,
+    marker: PhantomData<T>,
+}
+
+// Events sent with the ack notifications enabled and not acked yet, by channel, client (None in the client) and message id
+#[derive(Resource)]
+struct PendingDeliveries<T: Event> {
+    messages: HashSet<(u8, Option<ClientId>, u64)>,
+    marker: PhantomData<T>,
+}
+
+impl<T: Event> Default for PendingDeliveries<T> {
+    fn default() -> Self {
+        Self {
+            messages: HashSet::new(),
+            marker: PhantomData,
+        }
+    }
+}
+
+/// Registers events that are serialized with bincode and sent over a renet channel.
+///
+/// The systems are added to the schedules of the [`RenetServerPlugin`](crate::RenetServerPlugin) and
+/// [`RenetClientPlugin`](crate::RenetClientPlugin), so register the events after adding the plugins.
+/// Events are drained and sent before [`RenetSend`] and received after [`RenetReceive`],
+/// messages that fail to decode are logged and discarded.
+/// With the [`RenetAckPlugin`] the events sent on a reliable channel are followed by an [`EventDelivered`] once they are received.
+///
+/// # Usage
+/// ```
+/// # use bevy_app::prelude::*;
+/// # use bevy_ecs::prelude::*;
+/// # use bevy_renet::{events::NetworkEventAppExt, RenetClientPlugin, RenetServerPlugin};
+/// # use bevy_renet::renet::DefaultChannel;
+/// # use serde::{Deserialize, Serialize};
+/// #[derive(Event, Serialize, Deserialize)]
+/// struct PlayerInput { jump: bool }
+///
+/// #[derive(Event, Serialize, Deserialize)]
+/// struct Chat(String);
+///
+/// # let mut app = App::new();
+/// app.add_plugins((RenetServerPlugin::default(), RenetClientPlugin::default()));
+/// // Written with EventWriter<PlayerInput> in the client, read with EventReader<FromClient<PlayerInput>> in the server
+/// app.add_network_event::<PlayerInput>(DefaultChannel::Unreliable);
+/// // Written with EventWriter<ToClients<Chat>> in the server, read with EventReader<Chat> in the client
+/// app.add_server_event::<Chat>(DefaultChannel::ReliableOrdered);
+/// ```
+pub trait NetworkEventAppExt {
+    /// Registers an event sent from the clients to the server.
+    fn add_network_event<T: Event + Serialize + DeserializeOwned>(&mut self, channel_id: impl Into<u8>) -> &mut Self;
+
+    /// Registers an event sent from the server to the clients.
+    fn add_server_event<T: Event + Serialize + DeserializeOwned>(&mut self, channel_id: impl Into<u8>) -> &mut Self;
+}
+
+impl NetworkEventAppExt for App {
+    fn add_network_event<T: Event + Serialize + DeserializeOwned>(&mut self, channel_id: impl Into<u8>) -> &mut Self {
+        let channel_id = channel_id.into();
+        let client_schedules = self.world().get_resource::<RenetClientSchedules>().copied().unwrap_or_default();
+        let server_schedules = self.world().get_resource::<RenetServerSchedules>().copied().unwrap_or_default();
+
+        self.add_event::<T>();
+        self.add_event::<FromClient<T>>();
+        add_delivery_tracking::<T>(self, channel_id);
+        self.add_systems(
+            client_schedules.send,
+            (move |events: ResMut<Events<T>>, client: ResMut<RenetClient>, deliveries: Deliveries<T>| {
+                send_client_events(channel_id, events, client, deliveries)
+            })
+            .before(RenetSend)
+            .run_if(resource_exists::<RenetClient>),
+        );
+        self.add_systems(
+            server_schedules.receive,
+            (move |server: ResMut<RenetServer>, events: EventWriter<FromClient<T>>| receive_client_events(channel_id, server, events))
+                .after(RenetReceive)
+                .run_if(resource_exists::<RenetServer>),
+        );
+        self
+    }
+
+    fn add_server_event<T: Event + Serialize + DeserializeOwned>(&mut self, channel_id: impl Into<u8>) -> &mut Self {
+        let channel_id = channel_id.into();
+        let client_schedules = self.world().get_resource::<RenetClientSchedules>().copied().unwrap_or_default();
+        let server_schedules = self.world().get_resource::<RenetServerSchedules>().copied().unwrap_or_default();
+
+        self.add_event::<T>();
+        self.add_event::<ToClients<T>>();
+        add_delivery_tracking::<T>(self, channel_id);
+        self.add_systems(
+            server_schedules.send,
+            (move |events: ResMut<Events<ToClients<T>>>, server: ResMut<RenetServer>, deliveries: Deliveries<T>| {
+                send_server_events(channel_id, events, server, deliveries)
+            })
+            .before(RenetSend)
+            .run_if(resource_exists::<RenetServer>),
+        );
+        self.add_systems(
+            client_schedules.receive,
+            (move |client: ResMut<RenetClient>, events: EventWriter<T>| receive_server_events(channel_id, client, events))
+                .after(RenetReceive)
+                .run_if(resource_exists::<RenetClient>),
+        );
+        self
+    }
+}
+
+// The pending deliveries, only tracked when the ack events exist
+type Deliveries<'w, T> = (ResMut<'w, PendingDeliveries<T>>, Option<Res<'w, Events<MessageAcked>>>);
+
+fn add_delivery_tracking<T: Event>(app: &mut App, channel_id: u8) {
+    let client_schedules = app.world().get_resource::<RenetClientSchedules>().copied().unwrap_or_default();
+    let server_schedules = app.world().get_resource::<RenetServerSchedules>().copied().unwrap_or_default();
+
+    app.add_event::<EventDelivered<T>>();
+    app.init_resource::<PendingDeliveries<T>>();
+    app.init_resource::<AckChannels>();
+    app.world_mut().resource_mut::<AckChannels>().0.insert(channel_id);
+    if app.world().contains_resource::<PendingDeliveriesSystems<T>>() {
+        return;
+    }
+    app.init_resource::<PendingDeliveriesSystems<T>>();
+
+    app.add_systems(
+        server_schedules.receive,
+        emit_deliveries::<T>
+            .after(RenetAckPlugin::emit_server_acks_system)
+            .after(RenetAckPlugin::emit_client_acks_system)
+            .run_if(resource_exists::<Events<MessageAcked>>),
+    );
+    if client_schedules.receive != server_schedules.receive {
+        app.add_systems(
+            client_schedules.receive,
+            emit_deliveries::<T>
+                .after(RenetAckPlugin::emit_client_acks_system)
+                .run_if(resource_exists::<Events<MessageAcked>>),
+        );
+    }
+}
+
+// The delivery systems of an event are added once, when it's registered in both directions
+#[derive(Resource)]
+struct PendingDeliveriesSystems<T: Event>(PhantomData<T>);
+
+impl<T: Event> Default for PendingDeliveriesSystems<T> {
+    fn default() -> Self {
+        Self(PhantomData)
+    }
+}
+
+fn emit_deliveries<T: Event>(
+    mut pending: ResMut<PendingDeliveries<T>>,
+    mut acks: EventReader<MessageAcked>,
+    mut events: EventWriter<EventDelivered<T>>,
+    server: Option<Res<RenetServer>>,
+    client: Option<Res<RenetClient>>,
+) {
+    for ack in acks.read() {
+        if pending.messages.remove(&(ack.channel_id, ack.client_id, ack.message_id)) {
+            events.write(EventDelivered {
+                client_id: ack.client_id,
+                marker: PhantomData,
+            });
+        }
+    }
+
+    // The messages to disconnected peers are never acked
+    if !pending.messages.is_empty() {
+        pending.messages.retain(|(_, client_id, _)| match client_id {
+            Some(client_id) => server.as_ref().is_some_and(|server| server.is_connected(*client_id)),
+            None => client.as_ref().is_some_and(|client| !client.is_disconnected()),
+        });
+    }
+}
+
+fn send_client_events<T: Event + Serialize>(
+    channel_id: u8,
+    mut events: ResMut<Events<T>>,
+    mut client: ResMut<RenetClient>,
+    (mut pending, acks): Deliveries<T>,
+) {
+    for event in events.drain() {
+        match bincode::serialize(&event) {
+            Ok(message) => {
+                if let Some(message_id) = client.next_message_id(channel_id).filter(|_| acks.is_some()) {
+                    pending.messages.insert((channel_id, None, message_id));
+                }
+                client.send_message(channel_id, message);
+            }
+            Err(e) => log::error!("Failed to serialize {}: {}", std::any::type_name::<T>(), e),
+        }
+    }
+}
+
+fn receive_client_events<T: Event + DeserializeOwned>(
+    channel_id: u8,
+    mut server: ResMut<RenetServer>,
+    mut events: EventWriter<FromClient<T>>,
+) {
+    for client_id in server.clients_id() {
+        while let Some(message) = server.receive_message(client_id, channel_id) {
+            match bincode::deserialize(&message) {
+                Ok(event) => {
+                    events.write(FromClient { client_id, event });
+                }
+                Err(e) => log::warn!("Failed to decode {} from client {}: {}", std::any::type_name::<T>(), client_id, e),
+            }
+        }
+    }
+}
+
+fn send_server_events<T: Event + Serialize>(
+    channel_id: u8,
+    mut events: ResMut<Events<ToClients<T>>>,
+    mut server: ResMut<RenetServer>,
+    (mut pending, acks): Deliveries<T>,
+) {
+    for ToClients { mode, event } in events.drain() {
+        let message = match bincode::serialize(&event) {
+            Ok(message) => message,
+            Err(e) => {
+                log::error!("Failed to serialize {}: {}", std::any::type_name::<T>(), e);
+                continue;
+            }
+        };
+
+        if acks.is_some() {
+            let recipients = server.clients_id_iter().filter(|&client_id| match mode {
+                SendMode::Direct(target_id) => client_id == target_id,
+                SendMode::Broadcast => true,
+                SendMode::BroadcastExcept(except_id) => client_id != except_id,
+            });
+            let messages: Vec<_> = recipients
+                .filter_map(|client_id| Some((channel_id, Some(client_id), server.next_message_id(client_id, channel_id)?)))
+                .collect();
+            pending.messages.extend(messages);
+        }
+
+        match mode {
+            SendMode::Direct(client_id) => server.send_message(client_id, channel_id, message),
+            SendMode::Broadcast => server.broadcast_message(channel_id, message),
+            SendMode::BroadcastExcept(client_id) => server.broadcast_message_except(client_id, channel_id, message),
+        }
+    }
+}
+
+fn receive_server_events<T: Event + DeserializeOwned>(channel_id: u8, mut client: ResMut<RenetClient>, mut events: EventWriter<T>) {
+    while let Some(message) = client.receive_message(channel_id) {
+        match bincode::deserialize(&message) {
+            Ok(event) => {
+                events.write(event);
+            }
+            Err(e) => log::warn!("Failed to decode {} from the server: {}", std::any::type_name::<T>(), e),
+        }
+    }
+}
@@ -1,23 +1,55 @@
 pub use renet;
 
 use bevy_app::prelude::*;
-use bevy_ecs::prelude::*;
+use bevy_ecs::{
+    prelude::*,
+    schedule::{InternedScheduleLabel, ScheduleLabel},
+};
+use bevy_platform::time::Instant;
 use bevy_time::prelude::*;
 
-use renet::{RenetClient, RenetServer, ServerEvent};
+use renet::{ClientId, DisconnectReason, GracefulShutdown, RenetClient, RenetServer, ServerEvent};
+
+pub mod acks;
+pub mod client_entities;
+pub mod listen_server;
+pub mod network_stats;
+pub mod schema;
+pub mod server_time;
+
+#[cfg(not(target_family = "wasm"))]
+pub mod tick_rate;
 
 #[cfg(feature = "netcode")]
 pub mod netcode;
 
+#[cfg(feature = "netcode")]
+pub mod reconnect;
+
 #[cfg(feature = "steam")]
 pub mod steam;
 
+#[cfg(feature = "conditioner")]
+pub mod conditioner;
+
+#[cfg(feature = "diagnostics")]
+pub mod diagnostics;
+
+#[cfg(feature = "egui")]
+pub mod visualizer;
+
+#[cfg(feature = "overlay")]
+pub mod overlay;
+
+#[cfg(feature = "events")]
+pub mod events;
+
 /// This system set is where all transports receive messages
 ///
 /// If you want to ensure data has arrived in the [`RenetClient`] or [`RenetServer`], then schedule your
 /// system after this set.
 ///
-/// This system set runs in PreUpdate.
+/// This system set runs in PreUpdate by default, see [`RenetServerPlugin::receive_in`] and [`RenetClientPlugin::receive_in`].
 #[derive(Debug, SystemSet, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct RenetReceive;
 
@@ -26,20 +58,242 @@ pub struct RenetReceive;
 /// If you want to ensure your packets have been registered by the [`RenetClient`] or [`RenetServer`], then
 /// schedule your system before this set.
 ///
-/// This system set runs in PostUpdate.
+/// This system set runs in PostUpdate by default, see [`RenetServerPlugin::send_in`] and [`RenetClientPlugin::send_in`].
 #[derive(Debug, SystemSet, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct RenetSend;
 
-pub struct RenetServerPlugin;
+/// Schedules where the [`RenetServerPlugin`] and the server transport plugins add their systems.
+///
+/// Inserted by the [`RenetServerPlugin`], the transport plugins read it when the app is finished,
+/// so the plugins can be added in any order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Resource)]
+pub struct RenetServerSchedules {
+    /// Where the server is updated and the transports receive packets, PreUpdate by default.
+    pub receive: InternedScheduleLabel,
+    /// Where the transports send packets, PostUpdate by default.
+    pub send: InternedScheduleLabel,
+}
 
-pub struct RenetClientPlugin;
+impl Default for RenetServerSchedules {
+    fn default() -> Self {
+        Self {
+            receive: PreUpdate.intern(),
+            send: PostUpdate.intern(),
+        }
+    }
+}
+
+impl RenetServerSchedules {
+    /// Receive in FixedPreUpdate and send in FixedPostUpdate, see [`RenetServerPlugin::fixed_update`].
+    pub fn fixed() -> Self {
+        Self {
+            receive: FixedPreUpdate.intern(),
+            send: FixedPostUpdate.intern(),
+        }
+    }
+}
+
+/// Schedules where the [`RenetClientPlugin`] and the client transport plugins add their systems.
+///
+/// Inserted by the [`RenetClientPlugin`], the transport plugins read it when the app is finished,
+/// so the plugins can be added in any order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Resource)]
+pub struct RenetClientSchedules {
+    /// Where the client is updated and the transports receive packets, PreUpdate by default.
+    pub receive: InternedScheduleLabel,
+    /// Where the transports send packets, PostUpdate by default.
+    pub send: InternedScheduleLabel,
+}
+
+impl Default for RenetClientSchedules {
+    fn default() -> Self {
+        Self {
+            receive: PreUpdate.intern(),
+            send: PostUpdate.intern(),
+        }
+    }
+}
+
+impl RenetClientSchedules {
+    /// Receive in FixedPreUpdate and send in FixedPostUpdate, see [`RenetClientPlugin::fixed_update`].
+    pub fn fixed() -> Self {
+        Self {
+            receive: FixedPreUpdate.intern(),
+            send: FixedPostUpdate.intern(),
+        }
+    }
+}
+
+/// Connection transitions of the [`RenetClient`] in the current frame, updated by the [`RenetClientPlugin`]
+/// after [`RenetReceive`]. Used by the [`client_just_connected`] and [`client_just_disconnected`] run conditions.
+///
+/// Also inserted as a component on the client instance entities, see [`RenetClientPlugin`].
+#[derive(Debug, Resource, Component)]
+pub struct RenetClientTransitions {
+    was_connected: bool,
+    was_disconnected: bool,
+    just_connected: bool,
+    just_disconnected: bool,
+}
+
+impl Default for RenetClientTransitions {
+    fn default() -> Self {
+        Self {
+            was_connected: false,
+            was_disconnected: true,
+            just_connected: false,
+            just_disconnected: false,
+        }
+    }
+}
+
+impl RenetClientTransitions {
+    pub fn just_connected(&self) -> bool {
+        self.just_connected
+    }
+
+    pub fn just_disconnected(&self) -> bool {
+        self.just_disconnected
+    }
+
+    fn update(&mut self, connected: bool, disconnected: bool) {
+        self.just_connected = !self.was_connected && connected;
+        self.just_disconnected = !self.was_disconnected && disconnected;
+        self.was_connected = connected;
+        self.was_disconnected = disconnected;
+    }
+}
+
+/// The reason of the last disconnection of the [`RenetClient`], inserted by the [`RenetClientPlugin`] when the client disconnects
+/// and removed when it connects again, so it can be shown after the frame of the disconnection.
+/// See the [`client_disconnected_because`] run condition.
+#[derive(Debug, Clone, PartialEq, Eq, Resource)]
+pub struct LastDisconnect {
+    pub reason: DisconnectReason,
+    /// Why the transport disconnected, like the message of a kick, recorded by the netcode and steam client plugins.
+    pub transport_reason: Option<String>,
+    pub at: Instant,
+}
+
+/// Clients that connected or disconnected from the [`RenetServer`] in the current frame, updated by the [`RenetServerPlugin`]
+/// from the server events. Used by the [`any_client_just_connected`] and [`any_client_just_disconnected`] run conditions.
+#[derive(Debug, Default, Resource)]
+pub struct RenetServerTransitions {
+    connected: Vec<ClientId>,
+    disconnected: Vec<ClientId>,
+}
+
+impl RenetServerTransitions {
+    pub fn connected(&self) -> &[ClientId] {
+        &self.connected
+    }
+
+    pub fn disconnected(&self) -> &[ClientId] {
+        &self.disconnected
+    }
+}
+
+/// A [`ServerEvent`] with the client information from the active transport, sent by the [`RenetServerPlugin`]
+/// alongside the [`ServerEvent`].
+#[derive(Debug, Clone, PartialEq, Eq, Event)]
+pub enum TransportServerEvent {
+    ClientConnected {
+        client_id: ClientId,
+        transport_data: ClientTransportData,
+    },
+    ClientDisconnected {
+        client_id: ClientId,
+        reason: DisconnectReason,
+        /// The disconnect reason given by the transport, when available.
+        transport_detail: Option<String>,
+    },
+}
+
+/// Inserted by the [`RenetServerPlugin`] unless [`keep_connections_on_exit`](RenetServerPlugin::keep_connections_on_exit) is used,
+/// the transport plugins disconnect all clients when the app exits. See the [`server_exiting`] run condition.
+#[derive(Debug, Default, Resource)]
+pub struct RenetServerDisconnectOnExit;
+
+/// Inserted by the [`RenetClientPlugin`] unless [`keep_connections_on_exit`](RenetClientPlugin::keep_connections_on_exit) is used,
+/// the transport plugins disconnect the clients when the app exits. See the [`client_exiting`] run condition.
+#[derive(Debug, Default, Resource)]
+pub struct RenetClientDisconnectOnExit;
+
+/// Client information from the transport the client is connected with.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ClientTransportData {
+    /// No transport plugin with client information is active.
+    None,
+    #[cfg(feature = "netcode")]
+    Netcode {
+        /// The user data from the connect token.
+        user_data: Box<[u8; renet_netcode::NETCODE_USER_DATA_BYTES]>,
+    },
+    #[cfg(feature = "steam")]
+    Steam { steam_id: renet_steam::steamworks::SteamId },
+}
+
+/// Updates the [`RenetServer`] resource and sends the [`ServerEvent`]s.
+///
+/// # Usage
+/// ```
+/// # use bevy_app::{prelude::*, FixedPostUpdate, FixedPreUpdate};
+/// # use bevy_renet::RenetServerPlugin;
+/// # let mut app = App::new();
+/// // Receive packets before the fixed simulation step and send them after it
+/// app.add_plugins(RenetServerPlugin::default().receive_in(FixedPreUpdate).send_in(FixedPostUpdate));
+/// ```
+#[derive(Debug, Default)]
+pub struct RenetServerPlugin {
+    schedules: RenetServerSchedules,
+    fixed_update: bool,
+    keep_connections_on_exit: bool,
+}
+
+/// Updates the [`RenetClient`] resource.
+///
+/// Entities with a [`RenetClient`] component are also updated, to run multiple clients in one app.
+/// They are client instances, their transport is a component on the same entity, and the `client_instance_*`
+/// run conditions check their state.
+///
+/// The schedules can be configured like the [`RenetServerPlugin`].
+///
+/// # Usage
+/// ```
+/// # use bevy_app::prelude::*;
+/// # use bevy_ecs::prelude::*;
+/// # use bevy_renet::{client_instance_connected, RenetClientPlugin};
+/// # use bevy_renet::renet::{ConnectionConfig, RenetClient};
+/// # let mut app = App::new();
+/// app.add_plugins(RenetClientPlugin::default());
+/// // Spawn each client with its transport component
+/// let player_one = app.world_mut().spawn(RenetClient::new(ConnectionConfig::default())).id();
+/// app.add_systems(Update, send_player_one_input.run_if(client_instance_connected(player_one)));
+/// # fn send_player_one_input() {}
+/// ```
+#[derive(Debug, Default)]
+pub struct RenetClientPlugin {
+    schedules: RenetClientSchedules,
+    fixed_update: bool,
+    keep_connections_on_exit: bool,
+}
 
 impl Plugin for RenetServerPlugin {
     fn build(&self, app: &mut App) {
         app.init_resource::<Events<ServerEvent>>();
-        app.add_systems(PreUpdate, Self::update_system.run_if(resource_exists::<RenetServer>));
+        app.init_resource::<Events<TransportServerEvent>>();
+        app.init_resource::<RenetServerTransitions>();
+        assert!(
+            !self.fixed_update || self.schedules == RenetServerSchedules::fixed(),
+            "RenetServerPlugin::fixed_update can't be combined with custom receive or send schedules"
+        );
+        app.insert_resource(self.schedules);
+        if !self.keep_connections_on_exit {
+            app.init_resource::<RenetServerDisconnectOnExit>();
+        }
+        app.add_systems(self.schedules.receive, Self::update_system.run_if(resource_exists::<RenetServer>));
         app.add_systems(
-            PreUpdate,
+            self.schedules.receive,
             Self::emit_server_events_system
                 .in_set(RenetReceive)
                 .run_if(resource_exists::<RenetServer>)
@@ -49,12 +303,94 @@ impl Plugin for RenetServerPlugin {
 }
 
 impl RenetServerPlugin {
+    /// Run the server and the transports with the fixed timestep, so packets are never processed in the middle of a fixed step.
+    ///
+    /// The transports receive in FixedPreUpdate and send in FixedPostUpdate, the server is updated with the fixed delta.
+    /// Panics when the app is built if combined with [`receive_in`](Self::receive_in) or [`send_in`](Self::send_in).
+    pub fn fixed_update(mut self) -> Self {
+        self.schedules = RenetServerSchedules::fixed();
+        self.fixed_update = true;
+        self
+    }
+
+    /// Set the schedule where the server is updated and the transports receive packets.
+    pub fn receive_in(mut self, schedule: impl ScheduleLabel) -> Self {
+        self.schedules.receive = schedule.intern();
+        self
+    }
+
+    /// Set the schedule where the transports send packets.
+    pub fn send_in(mut self, schedule: impl ScheduleLabel) -> Self {
+        self.schedules.send = schedule.intern();
+        self
+    }
+
+    /// Don't disconnect the clients when the app exits, for apps that manage the shutdown themselves.
+    ///
+    /// By default the transport plugins send the pending packets and disconnect all clients on [`AppExit`],
+    /// so they don't wait for the timeout.
+    pub fn keep_connections_on_exit(mut self) -> Self {
+        self.keep_connections_on_exit = true;
+        self
+    }
+
+    // In the fixed schedules Time is the fixed clock, so the server is updated with the fixed timestep
     pub fn update_system(mut server: ResMut<RenetServer>, time: Res<Time>) {
         server.update(time.delta());
     }
 
-    pub fn emit_server_events_system(mut server: ResMut<RenetServer>, mut server_events: EventWriter<ServerEvent>) {
+    pub fn emit_server_events_system(
+        mut server: ResMut<RenetServer>,
+        mut server_events: EventWriter<ServerEvent>,
+        mut transport_events: EventWriter<TransportServerEvent>,
+        mut transitions: ResMut<RenetServerTransitions>,
+        #[cfg(feature = "netcode")] netcode_transport: Option<Res<renet_netcode::NetcodeServerTransport>>,
+        #[cfg(feature = "steam")] steam_transport: Option<Res<renet_steam::SteamServerTransport>>,
+    ) {
+        transitions.connected.clear();
+        transitions.disconnected.clear();
         while let Some(event) = server.get_event() {
+            match event {
+                ServerEvent::ClientConnected { client_id } => {
+                    transitions.connected.push(client_id);
+
+                    #[allow(unused_mut)]
+                    let mut transport_data = ClientTransportData::None;
+                    #[cfg(feature = "netcode")]
+                    if let Some(user_data) = netcode_transport.as_ref().and_then(|transport| transport.user_data(client_id)) {
+                        transport_data = ClientTransportData::Netcode {
+                            user_data: Box::new(user_data),
+                        };
+                    }
+                    #[cfg(feature = "steam")]
+                    if steam_transport.is_some() {
+                        transport_data = ClientTransportData::Steam {
+                            steam_id: renet_steam::steamworks::SteamId::from_raw(client_id),
+                        };
+                    }
+
+                    transport_events.write(TransportServerEvent::ClientConnected { client_id, transport_data });
+                }
+                ServerEvent::ClientDisconnected { client_id, reason } => {
+                    transitions.disconnected.push(client_id);
+
+                    #[allow(unused_mut)]
+                    let mut transport_detail = None;
+                    #[cfg(feature = "steam")]
+                    if let Some(end_reason) = steam_transport
+                        .as_ref()
+                        .and_then(|transport| transport.disconnect_end_reason(client_id))
+                    {
+                        transport_detail = Some(format!("{:?}", end_reason));
+                    }
+
+                    transport_events.write(TransportServerEvent::ClientDisconnected {
+                        client_id,
+                        reason,
+                        transport_detail,
+                    });
+                }
+            }
             server_events.write(event);
         }
     }
@@ -62,14 +398,108 @@ impl RenetServerPlugin {
 
 impl Plugin for RenetClientPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(PreUpdate, Self::update_system.run_if(resource_exists::<RenetClient>));
+        app.init_resource::<RenetClientTransitions>();
+        assert!(
+            !self.fixed_update || self.schedules == RenetClientSchedules::fixed(),
+            "RenetClientPlugin::fixed_update can't be combined with custom receive or send schedules"
+        );
+        app.insert_resource(self.schedules);
+        if !self.keep_connections_on_exit {
+            app.init_resource::<RenetClientDisconnectOnExit>();
+        }
+        app.add_systems(self.schedules.receive, Self::update_system.run_if(resource_exists::<RenetClient>));
+        app.add_systems(self.schedules.receive, Self::update_transitions_system.after(RenetReceive));
+        app.add_systems(
+            self.schedules.receive,
+            Self::update_last_disconnect_system
+                .after(Self::update_transitions_system)
+                .run_if(resource_exists::<RenetClient>),
+        );
+        app.add_systems(self.schedules.receive, Self::update_instances_system.before(RenetReceive));
+        app.add_systems(self.schedules.receive, Self::update_instance_transitions_system.after(RenetReceive));
     }
 }
 
 impl RenetClientPlugin {
+    /// Run the client and the transports with the fixed timestep, see [`RenetServerPlugin::fixed_update`].
+    pub fn fixed_update(mut self) -> Self {
+        self.schedules = RenetClientSchedules::fixed();
+        self.fixed_update = true;
+        self
+    }
+
+    /// Set the schedule where the client is updated and the transports receive packets.
+    pub fn receive_in(mut self, schedule: impl ScheduleLabel) -> Self {
+        self.schedules.receive = schedule.intern();
+        self
+    }
+
+    /// Set the schedule where the transports send packets.
+    pub fn send_in(mut self, schedule: impl ScheduleLabel) -> Self {
+        self.schedules.send = schedule.intern();
+        self
+    }
+
+    /// Don't disconnect the clients when the app exits, see [`RenetServerPlugin::keep_connections_on_exit`].
+    pub fn keep_connections_on_exit(mut self) -> Self {
+        self.keep_connections_on_exit = true;
+        self
+    }
+
     pub fn update_system(mut client: ResMut<RenetClient>, time: Res<Time>) {
         client.update(time.delta());
     }
+
+    pub fn update_transitions_system(client: Option<Res<RenetClient>>, mut transitions: ResMut<RenetClientTransitions>) {
+        let connected = client.as_ref().map(|client| client.is_connected()).unwrap_or(false);
+        let disconnected = client.map(|client| client.is_disconnected()).unwrap_or(true);
+
+        transitions.update(connected, disconnected);
+    }
+
+    /// Inserts the [`LastDisconnect`] when the client disconnects, and removes it when the client connects.
+    pub fn update_last_disconnect_system(
+        mut commands: Commands,
+        client: Res<RenetClient>,
+        transitions: Res<RenetClientTransitions>,
+        last_disconnect: Option<Res<LastDisconnect>>,
+    ) {
+        if transitions.just_disconnected {
+            if let Some(reason) = client.disconnect_reason() {
+                commands.insert_resource(LastDisconnect {
+                    reason,
+                    transport_reason: None,
+                    at: Instant::now(),
+                });
+            }
+        } else if client.is_connected() && last_disconnect.is_some() {
+            commands.remove_resource::<LastDisconnect>();
+        }
+    }
+
+    pub fn update_instances_system(mut clients: Query<&mut RenetClient>, time: Res<Time>) {
+        for mut client in clients.iter_mut() {
+            client.update(time.delta());
+        }
+    }
+
+    /// Inserts and updates the [`RenetClientTransitions`] component of the client instances.
+    pub fn update_instance_transitions_system(
+        mut commands: Commands,
+        mut clients: Query<(Entity, &RenetClient, Option<&mut RenetClientTransitions>)>,
+    ) {
+        for (entity, client, transitions) in clients.iter_mut() {
+            let (connected, disconnected) = (client.is_connected(), client.is_disconnected());
+            match transitions {
+                Some(mut transitions) => transitions.update(connected, disconnected),
+                None => {
+                    let mut transitions = RenetClientTransitions::default();
+                    transitions.update(connected, disconnected);
+                    commands.entity(entity).insert(transitions);
+                }
+            }
+        }
+    }
 }
 
 pub fn client_connected(client: Option<Res<RenetClient>>) -> bool {
@@ -93,18 +523,137 @@ pub fn client_connecting(client: Option<Res<RenetClient>>) -> bool {
     }
 }
 
-pub fn client_just_connected(mut last_connected: Local<bool>, client: Option<Res<RenetClient>>) -> bool {
-    let connected = client.map(|client| client.is_connected()).unwrap_or(false);
+/// True if the client instance of the entity is connected.
+pub fn client_instance_connected(entity: Entity) -> impl FnMut(Query<&RenetClient>) -> bool + Clone {
+    move |clients: Query<&RenetClient>| clients.get(entity).is_ok_and(|client| client.is_connected())
+}
+
+/// True if the client instance of the entity is disconnected, or if the entity has no client.
+pub fn client_instance_disconnected(entity: Entity) -> impl FnMut(Query<&RenetClient>) -> bool + Clone {
+    move |clients: Query<&RenetClient>| clients.get(entity).map_or(true, |client| client.is_disconnected())
+}
+
+/// True if the client instance of the entity is connecting.
+pub fn client_instance_connecting(entity: Entity) -> impl FnMut(Query<&RenetClient>) -> bool + Clone {
+    move |clients: Query<&RenetClient>| clients.get(entity).is_ok_and(|client| client.is_connecting())
+}
+
+/// True on the frame the client instance of the entity connected, requires the [`RenetClientPlugin`].
+pub fn client_instance_just_connected(entity: Entity) -> impl FnMut(Query<&RenetClientTransitions>) -> bool + Clone {
+    move |transitions: Query<&RenetClientTransitions>| transitions.get(entity).is_ok_and(|transitions| transitions.just_connected)
+}
+
+/// True on the frame the client instance of the entity disconnected, requires the [`RenetClientPlugin`].
+pub fn client_instance_just_disconnected(entity: Entity) -> impl FnMut(Query<&RenetClientTransitions>) -> bool + Clone {
+    move |transitions: Query<&RenetClientTransitions>| transitions.get(entity).is_ok_and(|transitions| transitions.just_disconnected)
+}
+
+/// True if any client instance is connected.
+pub fn any_client_instance_connected(clients: Query<&RenetClient>) -> bool {
+    clients.iter().any(|client| client.is_connected())
+}
+
+/// True on the frame the client connected, requires the [`RenetClientPlugin`].
+pub fn client_just_connected(transitions: Option<Res<RenetClientTransitions>>) -> bool {
+    transitions.is_some_and(|transitions| transitions.just_connected)
+}
+
+/// True on the frame the client disconnected, requires the [`RenetClientPlugin`].
+pub fn client_just_disconnected(transitions: Option<Res<RenetClientTransitions>>) -> bool {
+    transitions.is_some_and(|transitions| transitions.just_disconnected)
+}
+
+/// True if the last disconnection of the client matches the predicate, until the client connects again.
+/// Requires the [`RenetClientPlugin`], see [`LastDisconnect`].
+///
+/// # Usage
+/// ```
+/// # use bevy_app::prelude::*;
+/// # use bevy_ecs::prelude::*;
+/// # use bevy_renet::client_disconnected_because;
+/// # use bevy_renet::renet::DisconnectReason;
+/// # let mut app = App::new();
+/// app.add_systems(Update, show_kicked_message.run_if(client_disconnected_because(|reason| *reason == DisconnectReason::DisconnectedByServer)));
+/// # fn show_kicked_message() {}
+/// ```
+pub fn client_disconnected_because(
+    predicate: impl Fn(&DisconnectReason) -> bool + Clone,
+) -> impl FnMut(Option<Res<LastDisconnect>>) -> bool + Clone {
+    move |last_disconnect: Option<Res<LastDisconnect>>| last_disconnect.is_some_and(|last_disconnect| predicate(&last_disconnect.reason))
+}
+
+/// True when the app exits and the server should be disconnected, see [`RenetServerPlugin::keep_connections_on_exit`].
+pub fn server_exiting(exit: EventReader<AppExit>, disconnect_on_exit: Option<Res<RenetServerDisconnectOnExit>>) -> bool {
+    disconnect_on_exit.is_some() && !exit.is_empty()
+}
+
+/// Begins the [`GracefulShutdown`] of a server transport, for custom transports. Add it to the [`Last`] schedule
+/// with the [`server_exiting`] condition so the clients are disconnected when the app exits:
+/// ```
+/// # use bevy_app::prelude::*;
+/// # use bevy_ecs::prelude::*;
+/// # use bevy_renet::{renet::{GracefulShutdown, RenetServer}, server_exiting, shutdown_transport_on_exit};
+/// # #[derive(Resource)]
+/// # struct MyTransport;
+/// # impl GracefulShutdown for MyTransport {
+/// #     fn begin_shutdown(&mut self, _server: &mut RenetServer) {}
+/// #     fn is_shutdown_complete(&self) -> bool { true }
+/// # }
+/// # let mut app = App::new();
+/// app.add_systems(
+///     Last,
+///     shutdown_transport_on_exit::<MyTransport>
+///         .run_if(server_exiting)
+///         .run_if(resource_exists::<MyTransport>)
+///         .run_if(resource_exists::<RenetServer>),
+/// );
+/// ```
+pub fn shutdown_transport_on_exit<T: GracefulShutdown + Resource>(mut transport: ResMut<T>, mut server: ResMut<RenetServer>) {
+    transport.begin_shutdown(&mut server);
+}
+
+/// Removes a transport resource when the app exits, even with `keep_connections_on_exit`, so it's dropped at the end of the
+/// exit frame instead of with the world. Add it to the [`Last`] schedule after the systems that disconnect or shut down the transport:
+/// ```
+/// # use bevy_app::prelude::*;
+/// # use bevy_ecs::prelude::*;
+/// # use bevy_renet::{renet::{GracefulShutdown, RenetServer}, remove_transport_on_exit, server_exiting, shutdown_transport_on_exit};
+/// # #[derive(Resource)]
+/// # struct MyTransport;
+/// # impl GracefulShutdown for MyTransport {
+/// #     fn begin_shutdown(&mut self, _server: &mut RenetServer) {}
+/// #     fn is_shutdown_complete(&self) -> bool { true }
+/// # }
+/// # let mut app = App::new();
+/// app.add_systems(
+///     Last,
+///     (
+///         shutdown_transport_on_exit::<MyTransport>
+///             .run_if(server_exiting)
+///             .run_if(resource_exists::<MyTransport>)
+///             .run_if(resource_exists::<RenetServer>),
+///         remove_transport_on_exit::<MyTransport>
+///             .run_if(on_event::<AppExit>)
+///             .run_if(resource_exists::<MyTransport>),
+///     )
+///         .chain(),
+/// );
+/// ```
+pub fn remove_transport_on_exit<T: Resource>(mut commands: Commands) {
+    commands.remove_resource::<T>();
+}
 
-    let just_connected = !*last_connected && connected;
-    *last_connected = connected;
-    just_connected
+/// True when the app exits and the clients should be disconnected, see [`RenetClientPlugin::keep_connections_on_exit`].
+pub fn client_exiting(exit: EventReader<AppExit>, disconnect_on_exit: Option<Res<RenetClientDisconnectOnExit>>) -> bool {
+    disconnect_on_exit.is_some() && !exit.is_empty()
 }
 
-pub fn client_just_disconnected(mut last_connected: Local<bool>, client: Option<Res<RenetClient>>) -> bool {
-    let disconnected = client.map(|client| client.is_disconnected()).unwrap_or(true);
+/// True on frames a client connected to the server, requires the [`RenetServerPlugin`].
+pub fn any_client_just_connected(transitions: Option<Res<RenetServerTransitions>>) -> bool {
+    transitions.is_some_and(|transitions| !transitions.connected.is_empty())
+}
 
-    let just_disconnected = *last_connected && disconnected;
-    *last_connected = !disconnected;
-    just_disconnected
+/// True on frames a client disconnected from the server, requires the [`RenetServerPlugin`].
+pub fn any_client_just_disconnected(transitions: Option<Res<RenetServerTransitions>>) -> bool {
+    transitions.is_some_and(|transitions| !transitions.disconnected.is_empty())
 }
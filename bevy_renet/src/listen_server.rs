@@ -0,0 +1,142 @@
+use bevy_app::prelude::*;
+use bevy_ecs::prelude::*;
+use renet::{ClientId, RenetClient, RenetServer};
+
+use crate::{RenetSend, RenetServerSchedules};
+
+/// Runs a client in the same app as the [`RenetServer`], for hosts that also play (listen server).
+///
+/// The host [`RenetClient`] is connected to the server in memory, without a transport, and remote players
+/// connect with the regular transports at the same time. The server sends the `ClientConnected` and `ClientDisconnected`
+/// events for the host like for any other client.
+/// Requires the [`RenetServerPlugin`](crate::RenetServerPlugin) and [`RenetClientPlugin`](crate::RenetClientPlugin),
+/// connect and disconnect the host with [`ListenServerCommandsExt`].
+///
+/// The host client id must not be used by remote clients.
+///
+/// # Usage
+/// ```
+/// # use bevy_app::prelude::*;
+/// # use bevy_ecs::prelude::*;
+/// # use bevy_renet::listen_server::{ListenServerCommandsExt, ListenServerHost, ListenServerPlugin};
+/// # use bevy_renet::renet::{ConnectionConfig, RenetClient, RenetServer};
+/// # use bevy_renet::{RenetClientPlugin, RenetServerPlugin};
+/// # let mut app = App::new();
+/// # app.init_resource::<bevy_time::Time>();
+/// app.add_plugins((RenetServerPlugin::default(), RenetClientPlugin::default(), ListenServerPlugin));
+/// app.insert_resource(RenetServer::new(ConnectionConfig::default()));
+/// app.add_systems(Startup, |mut commands: Commands| commands.connect_host(0));
+/// # app.update();
+/// # assert_eq!(app.world().resource::<ListenServerHost>().client_id(), 0);
+/// # assert!(app.world().resource::<RenetClient>().is_connected());
+/// # assert!(app.world().resource::<RenetServer>().is_connected(0));
+/// # app.world_mut().commands().disconnect_host();
+/// # app.update();
+/// # assert!(!app.world().contains_resource::<RenetClient>());
+/// # assert!(!app.world().resource::<RenetServer>().has_client(0));
+/// ```
+#[derive(Debug)]
+pub struct ListenServerPlugin;
+
+/// The client id of the host client, inserted with the host [`RenetClient`] by [`ListenServerCommandsExt::connect_host`].
+#[derive(Debug, Resource)]
+pub struct ListenServerHost {
+    client_id: ClientId,
+}
+
+impl ListenServerHost {
+    pub fn client_id(&self) -> ClientId {
+        self.client_id
+    }
+}
+
+impl Plugin for ListenServerPlugin {
+    fn build(&self, _app: &mut App) {}
+
+    fn finish(&self, app: &mut App) {
+        let schedules = app.world().get_resource::<RenetServerSchedules>().copied().unwrap_or_default();
+
+        // Before the transports, they would also take the packets of the host
+        app.add_systems(
+            schedules.send,
+            Self::update_host_system
+                .before(RenetSend)
+                .run_if(resource_exists::<ListenServerHost>)
+                .run_if(resource_exists::<RenetServer>),
+        );
+    }
+}
+
+impl ListenServerPlugin {
+    /// Exchanges the packets of the host client and the server.
+    pub fn update_host_system(
+        mut commands: Commands,
+        host: Res<ListenServerHost>,
+        mut server: ResMut<RenetServer>,
+        client: Option<ResMut<RenetClient>>,
+    ) {
+        let client_id = host.client_id;
+        let Some(mut client) = client.filter(|client| !client.is_disconnected()) else {
+            // The host client was removed or disconnected without disconnect_host
+            server.remove_connection(client_id);
+            commands.remove_resource::<ListenServerHost>();
+            return;
+        };
+
+        if server.process_local_client(client_id, &mut client).is_err() {
+            // Disconnected by the server
+            client.disconnect_due_to_transport();
+            commands.remove_resource::<ListenServerHost>();
+        }
+    }
+}
+
+/// Connects and disconnects the host client of the [`ListenServerPlugin`].
+pub trait ListenServerCommandsExt {
+    /// Inserts the host [`RenetClient`] connected to the [`RenetServer`] with the given client id,
+    /// and the [`ListenServerHost`] resource.
+    fn connect_host(&mut self, client_id: ClientId);
+
+    /// Disconnects the host from the [`RenetServer`] and removes the host [`RenetClient`],
+    /// the remote clients stay connected.
+    fn disconnect_host(&mut self);
+}
+
+impl ListenServerCommandsExt for Commands<'_, '_> {
+    fn connect_host(&mut self, client_id: ClientId) {
+        self.queue(move |world: &mut World| {
+            if world.contains_resource::<ListenServerHost>() {
+                log::error!("Failed to connect the host client {client_id}: a host is already connected");
+                return;
+            }
+            let Some(mut server) = world.get_resource_mut::<RenetServer>() else {
+                log::error!("Failed to connect the host client {client_id}: no RenetServer");
+                return;
+            };
+            if server.has_client(client_id) {
+                log::error!("Failed to connect the host client {client_id}: client id already in use");
+                return;
+            }
+
+            let client = server.new_local_client(client_id);
+            world.insert_resource(client);
+            world.insert_resource(ListenServerHost { client_id });
+        });
+    }
+
+    fn disconnect_host(&mut self) {
+        self.queue(|world: &mut World| {
+            let Some(host) = world.remove_resource::<ListenServerHost>() else {
+                return;
+            };
+            let client = world.remove_resource::<RenetClient>();
+            let Some(mut server) = world.get_resource_mut::<RenetServer>() else {
+                return;
+            };
+            match client {
+                Some(mut client) => server.disconnect_local_client(host.client_id, &mut client),
+                None => server.remove_connection(host.client_id),
+            }
+        });
+    }
+}
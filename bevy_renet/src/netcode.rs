@@ -1,22 +1,69 @@
-use renet::{RenetClient, RenetServer};
+use renet::{GracefulShutdown, RenetClient, RenetServer};
 pub use renet_netcode::*;
 
 use bevy_app::prelude::*;
 use bevy_ecs::prelude::*;
 use bevy_time::prelude::*;
 
-use crate::{RenetClientPlugin, RenetReceive, RenetSend, RenetServerPlugin};
+use crate::{
+    client_exiting, client_just_disconnected, server_exiting, LastDisconnect, RenetClientPlugin, RenetClientSchedules, RenetReceive,
+    RenetSend, RenetServerPlugin, RenetServerSchedules,
+};
 
+/// Updates the [`NetcodeServerTransport`] resource with the [`RenetServer`].
+///
+/// Not supported on wasm, where the plugin adds no systems since there are no UDP sockets.
 pub struct NetcodeServerPlugin;
 
+/// Updates the [`RenetClient`] with the [`NetcodeClientTransport`] resource, and the client instances with a transport component.
+///
+/// The transport can be swapped at runtime: remove the [`NetcodeClientTransport`] and the [`RenetClient`],
+/// then insert a new [`RenetClient`] with the new transport, in the same frame or later.
+/// When only the transport is removed, the client it was driving is disconnected with [`DisconnectReason::Transport`],
+/// a disconnected client can't connect again.
+///
+/// Not supported on wasm, where the plugin adds no systems since there are no UDP sockets.
+///
+/// [`DisconnectReason::Transport`]: renet::DisconnectReason::Transport
 pub struct NetcodeClientPlugin;
 
+/// Insert this resource to refresh the connect token when it expires before the client connected, instead of disconnecting.
+///
+/// The plugin sends a [`ConnectTokenExpired`] event and waits for a [`ReconnectWithToken`] event with the new token,
+/// the [`RenetClient`] is kept as connecting in the meantime.
+#[derive(Debug, Default, Resource)]
+pub struct NetcodeTokenRefresh;
+
+/// Sent when the connect token expired while connecting, see [`NetcodeTokenRefresh`].
+#[derive(Debug, Event)]
+pub struct ConnectTokenExpired;
+
+/// Restarts the connection with a new connect token, see [`NetcodeTokenRefresh`].
+#[derive(Debug, Event)]
+pub struct ReconnectWithToken(pub ConnectToken);
+
+/// A transport error of a client instance, see [`RenetClientPlugin`].
+#[derive(Debug, Event)]
+pub struct NetcodeClientInstanceError {
+    pub entity: Entity,
+    pub error: NetcodeTransportError,
+}
+
 impl Plugin for NetcodeServerPlugin {
     fn build(&self, app: &mut App) {
         app.add_event::<NetcodeTransportError>();
+    }
+
+    fn finish(&self, app: &mut App) {
+        // The UDP sockets are not supported in the browser
+        if cfg!(target_family = "wasm") {
+            log::warn!("The NetcodeServerPlugin is not supported on wasm, its systems are not added");
+            return;
+        }
+        let schedules = app.world().get_resource::<RenetServerSchedules>().copied().unwrap_or_default();
 
         app.add_systems(
-            PreUpdate,
+            schedules.receive,
             Self::update_system
                 .in_set(RenetReceive)
                 .run_if(resource_exists::<NetcodeServerTransport>)
@@ -26,7 +73,7 @@ impl Plugin for NetcodeServerPlugin {
         );
 
         app.add_systems(
-            PostUpdate,
+            schedules.send,
             Self::send_packets
                 .in_set(RenetSend)
                 .run_if(resource_exists::<NetcodeServerTransport>)
@@ -36,6 +83,7 @@ impl Plugin for NetcodeServerPlugin {
         app.add_systems(
             Last,
             Self::disconnect_on_exit
+                .run_if(server_exiting)
                 .run_if(resource_exists::<NetcodeServerTransport>)
                 .run_if(resource_exists::<RenetServer>),
         );
@@ -54,43 +102,88 @@ impl NetcodeServerPlugin {
         }
     }
 
-    pub fn send_packets(mut transport: ResMut<NetcodeServerTransport>, mut server: ResMut<RenetServer>) {
-        transport.send_packets(&mut server);
+    pub fn send_packets(
+        mut transport: ResMut<NetcodeServerTransport>,
+        mut server: ResMut<RenetServer>,
+        mut transport_errors: EventWriter<NetcodeTransportError>,
+    ) {
+        if let Err(e) = transport.send_packets(&mut server) {
+            transport_errors.write(e);
+        }
     }
 
-    pub fn disconnect_on_exit(exit: EventReader<AppExit>, mut transport: ResMut<NetcodeServerTransport>, mut server: ResMut<RenetServer>) {
-        if !exit.is_empty() {
-            transport.disconnect_all(&mut server);
-        }
+    /// Sends the pending packets and disconnects all clients, runs when the app exits.
+    pub fn disconnect_on_exit(mut transport: ResMut<NetcodeServerTransport>, mut server: ResMut<RenetServer>) {
+        transport.begin_shutdown(&mut server);
     }
 }
 
 impl Plugin for NetcodeClientPlugin {
     fn build(&self, app: &mut App) {
         app.add_event::<NetcodeTransportError>();
+        app.add_event::<ConnectTokenExpired>();
+        app.add_event::<ReconnectWithToken>();
+        app.add_event::<NetcodeClientInstanceError>();
+    }
+
+    fn finish(&self, app: &mut App) {
+        // The UDP sockets are not supported in the browser
+        if cfg!(target_family = "wasm") {
+            log::warn!("The NetcodeClientPlugin is not supported on wasm, its systems are not added");
+            return;
+        }
+        let schedules = app.world().get_resource::<RenetClientSchedules>().copied().unwrap_or_default();
 
         app.add_systems(
-            PreUpdate,
-            Self::update_system
+            schedules.receive,
+            (
+                Self::token_refresh_system
+                    .run_if(resource_exists::<NetcodeTokenRefresh>)
+                    .run_if(resource_exists::<NetcodeClientTransport>)
+                    .run_if(resource_exists::<RenetClient>),
+                Self::update_system
+                    .run_if(resource_exists::<NetcodeClientTransport>)
+                    .run_if(resource_exists::<RenetClient>)
+                    .run_if(not(Self::awaiting_token_refresh)),
+            )
+                .chain()
                 .in_set(RenetReceive)
-                .run_if(resource_exists::<NetcodeClientTransport>)
-                .run_if(resource_exists::<RenetClient>)
                 .after(RenetClientPlugin::update_system),
         );
+        app.add_systems(schedules.receive, Self::transport_removed_system.before(RenetReceive));
         app.add_systems(
-            PostUpdate,
+            schedules.receive,
+            Self::record_disconnect_reason_system
+                .after(RenetClientPlugin::update_last_disconnect_system)
+                .run_if(client_just_disconnected)
+                .run_if(resource_exists::<NetcodeClientTransport>)
+                .run_if(resource_exists::<LastDisconnect>),
+        );
+        app.add_systems(
+            schedules.send,
             Self::send_packets
                 .in_set(RenetSend)
                 .run_if(resource_exists::<NetcodeClientTransport>)
-                .run_if(resource_exists::<RenetClient>),
+                .run_if(resource_exists::<RenetClient>)
+                .run_if(not(Self::awaiting_token_refresh)),
         );
 
         app.add_systems(
             Last,
             Self::disconnect_on_exit
+                .run_if(client_exiting)
                 .run_if(resource_exists::<NetcodeClientTransport>)
                 .run_if(resource_exists::<RenetClient>),
         );
+
+        app.add_systems(
+            schedules.receive,
+            Self::update_instances_system
+                .in_set(RenetReceive)
+                .after(RenetClientPlugin::update_instances_system),
+        );
+        app.add_systems(schedules.send, Self::send_instances_packets.in_set(RenetSend));
+        app.add_systems(Last, Self::disconnect_instances_on_exit.run_if(client_exiting));
     }
 }
 
@@ -116,9 +209,102 @@ impl NetcodeClientPlugin {
         }
     }
 
-    pub fn disconnect_on_exit(exit: EventReader<AppExit>, mut transport: ResMut<NetcodeClientTransport>) {
-        if !exit.is_empty() {
+    /// Sends the pending packets and disconnects, runs when the app exits.
+    pub fn disconnect_on_exit(mut transport: ResMut<NetcodeClientTransport>, mut client: ResMut<RenetClient>) {
+        if let Err(e) = transport.send_packets(&mut client) {
+            log::error!("Failed to send the last packets: {e}");
+        }
+        transport.disconnect();
+    }
+
+    /// Records the disconnect reason of the transport in the [`LastDisconnect`].
+    pub fn record_disconnect_reason_system(transport: Res<NetcodeClientTransport>, mut last_disconnect: ResMut<LastDisconnect>) {
+        last_disconnect.transport_reason = transport.disconnect_reason().map(|reason| reason.to_string());
+    }
+
+    /// Disconnects the [`RenetClient`] when the transport was removed, unless the client was also replaced.
+    pub fn transport_removed_system(
+        transport: Option<Res<NetcodeClientTransport>>,
+        client: Option<ResMut<RenetClient>>,
+        mut had_transport: Local<bool>,
+    ) {
+        let removed = *had_transport && transport.is_none();
+        *had_transport = transport.is_some();
+        if !removed {
+            return;
+        }
+        // A client inserted after being removed is a new one
+        if let Some(mut client) = client.filter(|client| !client.is_added()) {
+            client.disconnect_due_to_transport();
+        }
+    }
+
+    /// Updates the client instances, entities with a [`RenetClient`] and a [`NetcodeClientTransport`] component.
+    /// The [`NetcodeTokenRefresh`] is only supported by the client resource.
+    pub fn update_instances_system(
+        mut clients: Query<(Entity, &mut NetcodeClientTransport, &mut RenetClient)>,
+        time: Res<Time>,
+        mut transport_errors: EventWriter<NetcodeClientInstanceError>,
+    ) {
+        for (entity, mut transport, mut client) in clients.iter_mut() {
+            if let Err(error) = transport.update(time.delta(), &mut client) {
+                transport_errors.write(NetcodeClientInstanceError { entity, error });
+            }
+        }
+    }
+
+    pub fn send_instances_packets(
+        mut clients: Query<(Entity, &mut NetcodeClientTransport, &mut RenetClient)>,
+        mut transport_errors: EventWriter<NetcodeClientInstanceError>,
+    ) {
+        for (entity, mut transport, mut client) in clients.iter_mut() {
+            if let Err(error) = transport.send_packets(&mut client) {
+                transport_errors.write(NetcodeClientInstanceError { entity, error });
+            }
+        }
+    }
+
+    pub fn disconnect_instances_on_exit(mut clients: Query<(&mut NetcodeClientTransport, &mut RenetClient)>) {
+        for (mut transport, mut client) in clients.iter_mut() {
+            if let Err(e) = transport.send_packets(&mut client) {
+                log::error!("Failed to send the last packets: {e}");
+            }
             transport.disconnect();
         }
     }
+
+    /// Sends [`ConnectTokenExpired`] when the token expires, and reconnects on [`ReconnectWithToken`].
+    pub fn token_refresh_system(
+        mut transport: ResMut<NetcodeClientTransport>,
+        mut reconnects: EventReader<ReconnectWithToken>,
+        mut expired_events: EventWriter<ConnectTokenExpired>,
+        mut transport_errors: EventWriter<NetcodeTransportError>,
+        mut expired_sent: Local<bool>,
+    ) {
+        if let Some(ReconnectWithToken(connect_token)) = reconnects.read().last() {
+            let authentication = ClientAuthentication::Secure {
+                connect_token: connect_token.clone(),
+            };
+            match transport.reconnect(authentication) {
+                Ok(()) => *expired_sent = false,
+                Err(e) => {
+                    transport_errors.write(e.into());
+                }
+            }
+        }
+
+        let expired = transport.disconnect_reason() == Some(NetcodeDisconnectReason::ConnectTokenExpired);
+        if expired && !*expired_sent {
+            expired_events.write(ConnectTokenExpired);
+            *expired_sent = true;
+        }
+    }
+
+    /// Run condition that is true while the client waits for a [`ReconnectWithToken`] event.
+    pub fn awaiting_token_refresh(transport: Option<Res<NetcodeClientTransport>>, refresh: Option<Res<NetcodeTokenRefresh>>) -> bool {
+        match (transport, refresh) {
+            (Some(transport), Some(_)) => transport.disconnect_reason() == Some(NetcodeDisconnectReason::ConnectTokenExpired),
+            _ => false,
+        }
+    }
 }
@@ -0,0 +1,130 @@
+use std::time::Duration;
+
+use bevy_app::prelude::*;
+use bevy_ecs::prelude::*;
+use bevy_time::prelude::*;
+use renet::{ClientId, NetworkInfo, RenetClient, RenetServer};
+
+use crate::{RenetClientSchedules, RenetReceive, RenetServerSchedules};
+
+/// Sends a [`NetworkStatsSample`] event with the network info of the [`RenetClient`], and of each client of the [`RenetServer`],
+/// every [`NetworkStatsInterval`].
+///
+/// The samples are taken after [`RenetReceive`], the client only sends them while connected.
+///
+/// # Usage
+/// ```
+/// # use bevy_app::prelude::*;
+/// # use bevy_ecs::prelude::*;
+/// # use bevy_renet::network_stats::{NetworkStatsPlugin, NetworkStatsSample};
+/// # use bevy_renet::RenetServerPlugin;
+/// # let mut app = App::new();
+/// app.add_plugins((RenetServerPlugin::default(), NetworkStatsPlugin));
+/// app.add_systems(Update, |mut samples: EventReader<NetworkStatsSample>| {
+///     for sample in samples.read() {
+///         println!("Client {:?} ping: {:.0}ms", sample.client_id, sample.rtt * 1000.0);
+///     }
+/// });
+/// ```
+#[derive(Debug)]
+pub struct NetworkStatsPlugin;
+
+/// Time between two [`NetworkStatsSample`]s, 1 second by default. Can be changed at runtime.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Resource)]
+pub struct NetworkStatsInterval(pub Duration);
+
+impl Default for NetworkStatsInterval {
+    fn default() -> Self {
+        Self(Duration::from_secs(1))
+    }
+}
+
+/// The network stats of a connection, sent by the [`NetworkStatsPlugin`].
+#[derive(Debug, Clone, Copy, PartialEq, Event)]
+pub struct NetworkStatsSample {
+    /// The client of the sample in the server, None in the client.
+    pub client_id: Option<ClientId>,
+    /// Round-trip time in seconds.
+    pub rtt: f64,
+    /// Ratio of lost packets, from 0 to 1.
+    pub packet_loss: f64,
+    pub sent_kbps: f64,
+    pub received_kbps: f64,
+}
+
+impl NetworkStatsSample {
+    fn new(client_id: Option<ClientId>, network_info: NetworkInfo) -> Self {
+        Self {
+            client_id,
+            rtt: network_info.rtt,
+            packet_loss: network_info.packet_loss,
+            sent_kbps: network_info.bytes_sent_per_second * 8.0 / 1000.0,
+            received_kbps: network_info.bytes_received_per_second * 8.0 / 1000.0,
+        }
+    }
+}
+
+impl NetworkStatsPlugin {
+    pub fn client_stats_system(
+        client: Res<RenetClient>,
+        interval: Res<NetworkStatsInterval>,
+        time: Res<Time>,
+        mut elapsed: Local<Duration>,
+        mut samples: EventWriter<NetworkStatsSample>,
+    ) {
+        if !is_sample_due(&mut elapsed, time.delta(), interval.0) || !client.is_connected() {
+            return;
+        }
+
+        samples.write(NetworkStatsSample::new(None, client.network_info()));
+    }
+
+    pub fn server_stats_system(
+        server: Res<RenetServer>,
+        interval: Res<NetworkStatsInterval>,
+        time: Res<Time>,
+        mut elapsed: Local<Duration>,
+        mut samples: EventWriter<NetworkStatsSample>,
+    ) {
+        if !is_sample_due(&mut elapsed, time.delta(), interval.0) {
+            return;
+        }
+
+        for client_id in server.clients_id_iter() {
+            if let Ok(network_info) = server.network_info(client_id) {
+                samples.write(NetworkStatsSample::new(Some(client_id), network_info));
+            }
+        }
+    }
+}
+
+fn is_sample_due(elapsed: &mut Duration, delta: Duration, interval: Duration) -> bool {
+    *elapsed += delta;
+    if *elapsed < interval {
+        return false;
+    }
+
+    *elapsed = Duration::ZERO;
+    true
+}
+
+impl Plugin for NetworkStatsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<NetworkStatsInterval>();
+        app.add_event::<NetworkStatsSample>();
+    }
+
+    fn finish(&self, app: &mut App) {
+        let client_schedules = app.world().get_resource::<RenetClientSchedules>().copied().unwrap_or_default();
+        let server_schedules = app.world().get_resource::<RenetServerSchedules>().copied().unwrap_or_default();
+
+        app.add_systems(
+            client_schedules.receive,
+            Self::client_stats_system.after(RenetReceive).run_if(resource_exists::<RenetClient>),
+        );
+        app.add_systems(
+            server_schedules.receive,
+            Self::server_stats_system.after(RenetReceive).run_if(resource_exists::<RenetServer>),
+        );
+    }
+}
@@ -0,0 +1,148 @@
+use std::fmt::Write;
+
+use bevy_app::prelude::*;
+use bevy_ecs::prelude::*;
+use bevy_text::TextFont;
+use bevy_ui::{prelude::*, Val};
+
+use crate::network_stats::{NetworkStatsPlugin, NetworkStatsSample};
+
+/// Shows the [`NetworkStatsSample`]s in a text node on the top left corner of the screen, without egui.
+///
+/// The client shows its rtt, packet loss and bandwidth, the server a table of the clients using the most bandwidth.
+/// Adds the [`NetworkStatsPlugin`] if it was not added, the text is updated with each sample.
+/// Toggle the overlay with the [`RenetStatsOverlay`] resource. Requires a camera to render the bevy UI.
+///
+/// # Usage
+/// ```
+/// # use bevy_app::prelude::*;
+/// # use bevy_ecs::prelude::*;
+/// # use bevy_renet::overlay::{RenetStatsOverlay, RenetStatsOverlayPlugin};
+/// # let mut app = App::new();
+/// app.add_plugins(RenetStatsOverlayPlugin::default().max_clients(5));
+/// // Bind it to a debug key
+/// fn toggle_overlay(mut overlay: ResMut<RenetStatsOverlay>) {
+///     overlay.visible = !overlay.visible;
+/// }
+/// ```
+#[derive(Debug)]
+pub struct RenetStatsOverlayPlugin {
+    max_clients: usize,
+}
+
+impl Default for RenetStatsOverlayPlugin {
+    fn default() -> Self {
+        Self { max_clients: 8 }
+    }
+}
+
+/// Whether the stats overlay is shown, visible by default.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Resource)]
+pub struct RenetStatsOverlay {
+    pub visible: bool,
+}
+
+impl Default for RenetStatsOverlay {
+    fn default() -> Self {
+        Self { visible: true }
+    }
+}
+
+/// Marker of the text node spawned by the [`RenetStatsOverlayPlugin`].
+#[derive(Debug, Component)]
+pub struct RenetStatsOverlayText;
+
+#[derive(Debug, Resource)]
+struct OverlayMaxClients(usize);
+
+impl RenetStatsOverlayPlugin {
+    /// Maximum number of clients in the server table, 8 by default.
+    pub fn max_clients(mut self, max_clients: usize) -> Self {
+        self.max_clients = max_clients;
+        self
+    }
+
+    fn spawn_text_system(mut commands: Commands, texts: Query<(), With<RenetStatsOverlayText>>) {
+        if !texts.is_empty() {
+            return;
+        }
+
+        commands.spawn((
+            RenetStatsOverlayText,
+            Text::default(),
+            TextFont::from_font_size(14.0),
+            Node {
+                position_type: PositionType::Absolute,
+                top: Val::Px(8.0),
+                left: Val::Px(8.0),
+                ..Default::default()
+            },
+        ));
+    }
+
+    fn update_text_system(
+        mut samples: EventReader<NetworkStatsSample>,
+        max_clients: Res<OverlayMaxClients>,
+        overlay: Res<RenetStatsOverlay>,
+        mut texts: Query<(&mut Text, &mut Node), With<RenetStatsOverlayText>>,
+    ) {
+        let samples: Vec<NetworkStatsSample> = samples.read().copied().collect();
+        for (mut text, mut node) in texts.iter_mut() {
+            let display = if overlay.visible { Display::Flex } else { Display::None };
+            if node.display != display {
+                node.display = display;
+            }
+            if !samples.is_empty() {
+                text.0 = format_samples(&samples, max_clients.0);
+            }
+        }
+    }
+}
+
+// In a listen server the table of the clients is shown instead of the local client
+fn format_samples(samples: &[NetworkStatsSample], max_clients: usize) -> String {
+    let mut text = String::new();
+    let mut clients: Vec<&NetworkStatsSample> = samples.iter().filter(|sample| sample.client_id.is_some()).collect();
+    if clients.is_empty() {
+        if let Some(sample) = samples.last() {
+            let _ = write!(
+                text,
+                "RTT: {:.0} ms\nLoss: {:.1}%\nSent: {:.1} kbps\nReceived: {:.1} kbps",
+                sample.rtt * 1000.0,
+                sample.packet_loss * 100.0,
+                sample.sent_kbps,
+                sample.received_kbps
+            );
+        }
+        return text;
+    }
+
+    clients.sort_by(|a, b| (b.sent_kbps + b.received_kbps).total_cmp(&(a.sent_kbps + a.received_kbps)));
+    let _ = write!(text, "Clients: {}", clients.len());
+    for sample in clients.iter().take(max_clients) {
+        let _ = write!(
+            text,
+            "\n{}: {:.0} ms, {:.1}% loss, {:.1} / {:.1} kbps",
+            sample.client_id.unwrap_or_default(),
+            sample.rtt * 1000.0,
+            sample.packet_loss * 100.0,
+            sample.sent_kbps,
+            sample.received_kbps
+        );
+    }
+    if clients.len() > max_clients {
+        let _ = write!(text, "\n{} more", clients.len() - max_clients);
+    }
+    text
+}
+
+impl Plugin for RenetStatsOverlayPlugin {
+    fn build(&self, app: &mut App) {
+        if !app.is_plugin_added::<NetworkStatsPlugin>() {
+            app.add_plugins(NetworkStatsPlugin);
+        }
+        app.init_resource::<RenetStatsOverlay>();
+        app.insert_resource(OverlayMaxClients(self.max_clients));
+        app.add_systems(Update, (Self::spawn_text_system, Self::update_text_system).chain());
+    }
+}
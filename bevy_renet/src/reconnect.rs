@@ -0,0 +1,249 @@
+use std::time::Duration;
+
+use bevy_app::prelude::*;
+use bevy_ecs::prelude::*;
+use bevy_time::prelude::*;
+use renet::{ConnectionConfig, DisconnectReason, RenetClient};
+use renet_netcode::{ClientAuthentication, NetcodeClientTransport, NetcodeDisconnectReason, NetcodeError, NetcodeTransportError};
+
+use crate::{RenetClientSchedules, RenetReceive};
+
+type AuthenticationProvider = Box<dyn FnMut(u32) -> Option<ClientAuthentication> + Send + Sync>;
+
+/// Reconnects the netcode [`RenetClient`] with backoff when it was disconnected, while a [`ReconnectPolicy`] resource exists.
+///
+/// On each attempt the client is replaced with a new one and the [`NetcodeClientTransport`] restarts the connection
+/// with the authentication from the policy.
+/// Clients disconnected with [`RenetClient::disconnect`] or [`NetcodeClientTransport::disconnect`] are not reconnected,
+/// and the attempts stop when the server or the token refuse the connection, see [`ReconnectFailedReason::Refused`].
+/// The steam transport reconnects by itself, see `SteamClientTransport::enable_auto_reconnect`.
+///
+/// # Usage
+/// ```
+/// # use bevy_app::prelude::*;
+/// # use bevy_renet::netcode::{ClientAuthentication, NetcodeClientPlugin};
+/// # use bevy_renet::reconnect::{ReconnectPolicy, RenetReconnectPlugin};
+/// # use bevy_renet::renet::ConnectionConfig;
+/// # use bevy_renet::RenetClientPlugin;
+/// # let mut app = App::new();
+/// app.add_plugins((RenetClientPlugin::default(), NetcodeClientPlugin, RenetReconnectPlugin));
+/// app.insert_resource(ReconnectPolicy::new(ConnectionConfig::default(), |_attempt| {
+///     // Fetch or generate a new connect token, or return None if it's not available yet
+///     Some(ClientAuthentication::Unsecure {
+///         protocol_id: 7,
+///         client_id: 0,
+///         server_addr: "127.0.0.1:5000".parse().unwrap(),
+///         user_data: None,
+///     })
+/// }));
+/// ```
+pub struct RenetReconnectPlugin;
+
+/// When and how the [`RenetReconnectPlugin`] reconnects the client, remove it to stop reconnecting.
+#[derive(Resource)]
+pub struct ReconnectPolicy {
+    /// Attempts before giving up, None to retry forever.
+    pub max_attempts: Option<u32>,
+    /// Delay before the first attempt.
+    pub initial_delay: Duration,
+    /// Maximum delay between attempts.
+    pub max_delay: Duration,
+    /// The delay is multiplied by this on each following attempt.
+    pub backoff_factor: f32,
+    connection_config: ConnectionConfig,
+    authentication: AuthenticationProvider,
+}
+
+impl ReconnectPolicy {
+    /// Creates a policy with 5 attempts, waiting 1 second before the first one and doubling the delay up to 30 seconds.
+    ///
+    /// The clients are recreated with `connection_config`, and `authentication` is called with the attempt number
+    /// to get the authentication of each attempt. When it returns None the attempt fails.
+    pub fn new(
+        connection_config: ConnectionConfig,
+        authentication: impl FnMut(u32) -> Option<ClientAuthentication> + Send + Sync + 'static,
+    ) -> Self {
+        Self {
+            max_attempts: Some(5),
+            initial_delay: Duration::from_secs(1),
+            max_delay: Duration::from_secs(30),
+            backoff_factor: 2.0,
+            connection_config,
+            authentication: Box::new(authentication),
+        }
+    }
+
+    pub fn max_attempts(mut self, max_attempts: Option<u32>) -> Self {
+        self.max_attempts = max_attempts;
+        self
+    }
+
+    pub fn backoff(mut self, initial_delay: Duration, max_delay: Duration, backoff_factor: f32) -> Self {
+        self.initial_delay = initial_delay;
+        self.max_delay = max_delay;
+        self.backoff_factor = backoff_factor;
+        self
+    }
+
+    /// Returns the delay before an attempt, starting from attempt 1.
+    /// The delay stays at the max delay once reached, for any number of attempts.
+    pub fn delay(&self, attempt: u32) -> Duration {
+        let exponent = attempt.saturating_sub(1).min(i32::MAX as u32) as i32;
+        let secs = self.initial_delay.as_secs_f32() * self.backoff_factor.powi(exponent);
+        // Clamped before building the Duration, it panics on overflow
+        match Duration::try_from_secs_f32(secs) {
+            Ok(delay) => delay.min(self.max_delay),
+            Err(_) => self.max_delay,
+        }
+    }
+}
+
+/// Sent when the [`RenetReconnectPlugin`] starts a reconnection attempt.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Event)]
+pub struct ReconnectAttempt {
+    pub attempt: u32,
+}
+
+/// Sent when the [`RenetReconnectPlugin`] stops reconnecting.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Event)]
+pub struct ReconnectFailed {
+    pub attempts: u32,
+    pub reason: ReconnectFailedReason,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReconnectFailedReason {
+    /// All the attempts of the [`ReconnectPolicy`] failed.
+    MaxAttempts,
+    /// The server denied the connection or the authentication is invalid, retrying wouldn't help.
+    Refused,
+}
+
+/// Sent when the client connected again after one or more attempts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Event)]
+pub struct Reconnected {
+    pub attempts: u32,
+}
+
+#[derive(Debug, Default, Resource)]
+struct ReconnectState {
+    attempts: u32,
+    next_attempt: Option<Timer>,
+    stopped: bool,
+}
+
+impl Plugin for RenetReconnectPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ReconnectState>();
+        app.add_event::<NetcodeTransportError>();
+        app.add_event::<ReconnectAttempt>();
+        app.add_event::<ReconnectFailed>();
+        app.add_event::<Reconnected>();
+    }
+
+    fn finish(&self, app: &mut App) {
+        let schedules = app.world().get_resource::<RenetClientSchedules>().copied().unwrap_or_default();
+
+        app.add_systems(
+            schedules.receive,
+            Self::reconnect_system
+                .after(RenetReceive)
+                .run_if(resource_exists::<ReconnectPolicy>)
+                .run_if(resource_exists::<NetcodeClientTransport>)
+                .run_if(resource_exists::<RenetClient>),
+        );
+    }
+}
+
+impl RenetReconnectPlugin {
+    #[allow(clippy::too_many_arguments)]
+    fn reconnect_system(
+        mut client: ResMut<RenetClient>,
+        mut transport: ResMut<NetcodeClientTransport>,
+        mut policy: ResMut<ReconnectPolicy>,
+        mut state: ResMut<ReconnectState>,
+        time: Res<Time>,
+        mut transport_errors: EventReader<NetcodeTransportError>,
+        mut attempt_events: EventWriter<ReconnectAttempt>,
+        mut failed_events: EventWriter<ReconnectFailed>,
+        mut reconnected_events: EventWriter<Reconnected>,
+    ) {
+        // A new client or policy was inserted, start over
+        if client.is_added() || policy.is_added() {
+            *state = ReconnectState::default();
+        }
+
+        let refused = transport_errors.read().filter(|error| is_refused(error)).count() > 0;
+        if client.is_connected() {
+            if state.attempts > 0 {
+                reconnected_events.write(Reconnected { attempts: state.attempts });
+            }
+            *state = ReconnectState::default();
+            return;
+        }
+        if !client.is_disconnected() || state.stopped || disconnected_by_client(&client, &transport) {
+            return;
+        }
+
+        if refused {
+            failed_events.write(stop(&mut state, ReconnectFailedReason::Refused));
+            return;
+        }
+
+        let Some(timer) = state.next_attempt.as_mut() else {
+            if policy.max_attempts.is_some_and(|max_attempts| state.attempts >= max_attempts) {
+                failed_events.write(stop(&mut state, ReconnectFailedReason::MaxAttempts));
+            } else {
+                let delay = policy.delay(state.attempts + 1);
+                state.next_attempt = Some(Timer::new(delay, TimerMode::Once));
+            }
+            return;
+        };
+        if !timer.tick(time.delta()).finished() {
+            return;
+        }
+
+        state.next_attempt = None;
+        state.attempts += 1;
+        attempt_events.write(ReconnectAttempt { attempt: state.attempts });
+
+        let Some(authentication) = (policy.authentication)(state.attempts) else {
+            return;
+        };
+        match transport.reconnect(authentication) {
+            Ok(()) => *client = RenetClient::new(policy.connection_config.clone()),
+            Err(e) => {
+                log::error!("Failed to reconnect: {e}");
+                failed_events.write(stop(&mut state, ReconnectFailedReason::Refused));
+            }
+        }
+    }
+}
+
+fn stop(state: &mut ReconnectState, reason: ReconnectFailedReason) -> ReconnectFailed {
+    state.stopped = true;
+    state.next_attempt = None;
+    ReconnectFailed {
+        attempts: state.attempts,
+        reason,
+    }
+}
+
+fn disconnected_by_client(client: &RenetClient, transport: &NetcodeClientTransport) -> bool {
+    client.disconnect_reason() == Some(DisconnectReason::DisconnectedByClient)
+        || transport.disconnect_reason() == Some(NetcodeDisconnectReason::DisconnectedByClient)
+}
+
+fn is_refused(error: &NetcodeTransportError) -> bool {
+    matches!(
+        error,
+        NetcodeTransportError::Netcode(
+            NetcodeError::Disconnected(NetcodeDisconnectReason::ConnectionDenied)
+                | NetcodeError::Expired
+                | NetcodeError::TokenRevoked
+                | NetcodeError::InvalidProtocolID
+                | NetcodeError::InvalidVersion
+                | NetcodeError::NotInHostList
+        )
+    )
+}
@@ -0,0 +1,173 @@
+use std::collections::HashSet;
+
+use bevy_app::prelude::*;
+use bevy_ecs::prelude::*;
+use renet::{ClientId, RenetClient, RenetServer, SchemaHandshake, SchemaStatus};
+
+use crate::{RenetClientSchedules, RenetReceive, RenetServerSchedules};
+
+/// Exchanges the application schema version when the clients connect, see [`SchemaHandshake`].
+///
+/// The handshake is set on the [`RenetServer`] and [`RenetClient`] when they are inserted, so their messages are held
+/// until it completes. Sends a [`SchemaNegotiated`] or [`SchemaRejected`] event when the handshake of a connection completes.
+/// Add the channel with [`SchemaHandshake::channel_config`] to both the server and client channels of the `ConnectionConfig`.
+///
+/// # Usage
+/// ```
+/// # use bevy_app::prelude::*;
+/// # use bevy_ecs::prelude::*;
+/// # use bevy_renet::renet::SchemaHandshake;
+/// # use bevy_renet::schema::{SchemaHandshakePlugin, SchemaNegotiated};
+/// # use bevy_renet::RenetClientPlugin;
+/// const SCHEMA_CHANNEL: u8 = 3;
+///
+/// # let mut app = App::new();
+/// app.add_plugins((RenetClientPlugin::default(), SchemaHandshakePlugin::new(SchemaHandshake::new(SCHEMA_CHANNEL, 4))));
+/// app.add_systems(Update, |mut events: EventReader<SchemaNegotiated>| {
+///     for event in events.read() {
+///         println!("Using schema version {}", event.version);
+///     }
+/// });
+/// ```
+#[derive(Debug)]
+pub struct SchemaHandshakePlugin {
+    handshake: SchemaHandshake,
+}
+
+/// The schema handshake of a connection completed, its messages are delivered from now on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Event)]
+pub struct SchemaNegotiated {
+    /// The client in the server, None in the client.
+    pub client_id: Option<ClientId>,
+    pub version: u32,
+    pub features: u64,
+}
+
+/// The schema version of the peer is not compatible, the connection is disconnected with `DisconnectReason::SchemaMismatch`.
+///
+/// In the server, clients disconnected in the same frame they were rejected may only be reported by the disconnection.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Event)]
+pub struct SchemaRejected {
+    /// The client in the server, None in the client.
+    pub client_id: Option<ClientId>,
+    pub remote_version: u32,
+}
+
+impl SchemaHandshakePlugin {
+    pub fn new(handshake: SchemaHandshake) -> Self {
+        Self { handshake }
+    }
+
+    fn emit_server_events_system(
+        server: Res<RenetServer>,
+        mut reported: Local<HashSet<ClientId>>,
+        mut negotiated: EventWriter<SchemaNegotiated>,
+        mut rejected: EventWriter<SchemaRejected>,
+    ) {
+        reported.retain(|&client_id| server.has_client(client_id));
+        for client_id in server.clients_id_iter() {
+            if reported.contains(&client_id) {
+                continue;
+            }
+            match server.schema_status(client_id) {
+                Some(SchemaStatus::Accepted { version, features }) => {
+                    negotiated.write(SchemaNegotiated {
+                        client_id: Some(client_id),
+                        version,
+                        features,
+                    });
+                }
+                Some(SchemaStatus::Rejected { remote_version }) => {
+                    rejected.write(SchemaRejected {
+                        client_id: Some(client_id),
+                        remote_version,
+                    });
+                }
+                Some(SchemaStatus::Pending) | None => continue,
+            }
+            reported.insert(client_id);
+        }
+    }
+
+    fn emit_client_events_system(
+        client: Res<RenetClient>,
+        mut reported: Local<bool>,
+        mut negotiated: EventWriter<SchemaNegotiated>,
+        mut rejected: EventWriter<SchemaRejected>,
+    ) {
+        match client.schema_status() {
+            // A new client replaced the previous one
+            Some(SchemaStatus::Pending) | None => *reported = false,
+            _ if *reported => {}
+            Some(SchemaStatus::Accepted { version, features }) => {
+                negotiated.write(SchemaNegotiated {
+                    client_id: None,
+                    version,
+                    features,
+                });
+                *reported = true;
+            }
+            Some(SchemaStatus::Rejected { remote_version }) => {
+                rejected.write(SchemaRejected {
+                    client_id: None,
+                    remote_version,
+                });
+                *reported = true;
+            }
+        }
+    }
+}
+
+impl Plugin for SchemaHandshakePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<SchemaNegotiated>();
+        app.add_event::<SchemaRejected>();
+    }
+
+    fn finish(&self, app: &mut App) {
+        let server_schedules = app.world().get_resource::<RenetServerSchedules>().copied().unwrap_or_default();
+        let client_schedules = app.world().get_resource::<RenetClientSchedules>().copied().unwrap_or_default();
+
+        // Set before the first packets are received, and only once if the resources are reinserted
+        let handshake = self.handshake.clone();
+        app.add_systems(
+            server_schedules.receive,
+            (move |mut server: ResMut<RenetServer>| {
+                if server.schema_handshake().is_none() {
+                    server.set_schema_handshake(Some(handshake.clone()));
+                }
+            })
+            .before(RenetReceive)
+            .run_if(resource_added::<RenetServer>),
+        );
+        let handshake = self.handshake.clone();
+        app.add_systems(
+            client_schedules.receive,
+            (move |mut client: ResMut<RenetClient>| {
+                if client.schema_status().is_none() {
+                    client.set_schema_handshake(handshake.clone());
+                }
+            })
+            .before(RenetReceive)
+            .run_if(resource_added::<RenetClient>),
+        );
+
+        app.add_systems(
+            server_schedules.receive,
+            Self::emit_server_events_system
+                .after(RenetReceive)
+                .run_if(resource_exists::<RenetServer>),
+        );
+        app.add_systems(
+            client_schedules.receive,
+            Self::emit_client_events_system
+                .after(RenetReceive)
+                .run_if(resource_exists::<RenetClient>),
+        );
+    }
+}
+
+/// Run condition, true once the schema handshake of the [`RenetClient`] completed.
+pub fn client_schema_negotiated(client: Option<Res<RenetClient>>) -> bool {
+    client.is_some_and(|client| client.negotiated_version().is_some())
+}
@@ -0,0 +1,314 @@
+use std::{collections::VecDeque, time::Duration};
+
+use bevy_app::prelude::*;
+use bevy_ecs::prelude::*;
+use bevy_platform::time::Instant;
+use renet::{ChannelConfig, ClientId, RenetClient, RenetServer, SendType};
+
+use crate::{RenetClientSchedules, RenetReceive, RenetSend, RenetServerSchedules};
+
+// Number of recent sync samples, the one with the lowest round trip time is used
+const SAMPLE_WINDOW: usize = 8;
+const OFFSET_SMOOTHING: f64 = 0.2;
+const DRIFT_SMOOTHING: f64 = 0.05;
+const REQUEST_BYTES: usize = 8;
+const REPLY_BYTES: usize = 24;
+
+/// Synchronizes the clock of the clients with the server, see [`ServerTime`].
+///
+/// The clients send sync requests on a channel reserved for it, and the server answers them with its time.
+/// Add the channel with [`channel_config`](Self::channel_config) to both the server and client channels of the `ConnectionConfig`,
+/// and don't send other messages on it.
+///
+/// # Usage
+/// ```
+/// # use bevy_app::prelude::*;
+/// # use bevy_renet::renet::{ConnectionConfig, DefaultChannel};
+/// # use bevy_renet::{server_time::ServerTimePlugin, RenetClientPlugin};
+/// const SYNC_CHANNEL: u8 = 3;
+///
+/// let mut channels = DefaultChannel::config();
+/// channels.push(ServerTimePlugin::channel_config(SYNC_CHANNEL));
+/// let connection_config = ConnectionConfig {
+///     server_channels_config: channels.clone(),
+///     client_channels_config: channels,
+///     ..Default::default()
+/// };
+///
+/// # let mut app = App::new();
+/// app.add_plugins((RenetClientPlugin::default(), ServerTimePlugin::new(SYNC_CHANNEL)));
+/// ```
+#[derive(Debug)]
+pub struct ServerTimePlugin {
+    channel_id: u8,
+    sync_interval: Duration,
+}
+
+/// The estimated time of the server, inserted by the [`ServerTimePlugin`].
+///
+/// The server time is the time since the server started. In the server it's exact,
+/// in the clients it's estimated from the sync messages and is not available before the first answer.
+#[derive(Debug, Resource)]
+pub struct ServerTime {
+    epoch: Instant,
+    is_server: bool,
+    // Server time - local time since epoch, in seconds
+    offset: Option<f64>,
+    // Change of the offset per second
+    drift: f64,
+    last_sync: Instant,
+    uncertainty: Duration,
+    rtt: Duration,
+    samples: VecDeque<(f64, f64)>,
+}
+
+#[derive(Debug, Default, Resource)]
+struct PendingSyncReplies(Vec<(ClientId, f64, f64)>);
+
+impl ServerTimePlugin {
+    /// The clients request the server time every 500 milliseconds.
+    pub fn new(channel_id: impl Into<u8>) -> Self {
+        Self {
+            channel_id: channel_id.into(),
+            sync_interval: Duration::from_millis(500),
+        }
+    }
+
+    pub fn sync_interval(mut self, sync_interval: Duration) -> Self {
+        self.sync_interval = sync_interval;
+        self
+    }
+
+    /// Config of the sync channel, add it to the server and client channels.
+    pub fn channel_config(channel_id: impl Into<u8>) -> ChannelConfig {
+        ChannelConfig {
+            channel_id: channel_id.into(),
+            max_memory_usage_bytes: 64 * 1024,
+            send_type: SendType::Unreliable,
+        }
+    }
+}
+
+impl Plugin for ServerTimePlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(ServerTime::new(Instant::now()));
+        app.init_resource::<PendingSyncReplies>();
+    }
+
+    fn finish(&self, app: &mut App) {
+        let client_schedules = app.world().get_resource::<RenetClientSchedules>().copied().unwrap_or_default();
+        let server_schedules = app.world().get_resource::<RenetServerSchedules>().copied().unwrap_or_default();
+        let channel_id = self.channel_id;
+        let sync_interval = self.sync_interval;
+
+        // The server receive and send times are taken as close as possible to the transports
+        app.add_systems(
+            server_schedules.receive,
+            (move |server: ResMut<RenetServer>, server_time: ResMut<ServerTime>, pending: ResMut<PendingSyncReplies>| {
+                receive_sync_requests(channel_id, server, server_time, pending)
+            })
+            .after(RenetReceive)
+            .run_if(resource_exists::<RenetServer>),
+        );
+        app.add_systems(
+            server_schedules.send,
+            (move |server: ResMut<RenetServer>, server_time: Res<ServerTime>, pending: ResMut<PendingSyncReplies>| {
+                send_sync_replies(channel_id, server, server_time, pending)
+            })
+            .before(RenetSend)
+            .run_if(resource_exists::<RenetServer>),
+        );
+
+        // In a listen server the host uses the server clock
+        app.add_systems(
+            client_schedules.receive,
+            (move |client: ResMut<RenetClient>, server_time: ResMut<ServerTime>| receive_sync_replies(channel_id, client, server_time))
+                .after(RenetReceive)
+                .run_if(resource_exists::<RenetClient>)
+                .run_if(not(resource_exists::<RenetServer>)),
+        );
+        app.add_systems(
+            client_schedules.send,
+            (move |client: ResMut<RenetClient>, server_time: Res<ServerTime>, last_request: Local<Option<Instant>>| {
+                send_sync_request(channel_id, sync_interval, client, server_time, last_request)
+            })
+            .before(RenetSend)
+            .run_if(resource_exists::<RenetClient>)
+            .run_if(not(resource_exists::<RenetServer>)),
+        );
+    }
+}
+
+impl ServerTime {
+    fn new(epoch: Instant) -> Self {
+        Self {
+            epoch,
+            is_server: false,
+            offset: None,
+            drift: 0.0,
+            last_sync: epoch,
+            uncertainty: Duration::ZERO,
+            rtt: Duration::ZERO,
+            samples: VecDeque::with_capacity(SAMPLE_WINDOW),
+        }
+    }
+
+    /// Returns true once the server time is known.
+    pub fn is_synced(&self) -> bool {
+        self.offset.is_some()
+    }
+
+    /// Returns the current server time.
+    pub fn now(&self) -> Option<Duration> {
+        self.to_server_time(Instant::now())
+    }
+
+    /// Converts a local instant to the server time.
+    pub fn to_server_time(&self, instant: Instant) -> Option<Duration> {
+        let offset = self.offset?;
+        let local = signed_secs(self.epoch, instant);
+        let drift = self.drift * signed_secs(self.last_sync, instant);
+
+        Some(Duration::from_secs_f64((local + offset + drift).max(0.0)))
+    }
+
+    /// Maximum error of the estimated server time, half the round trip time of the best sync sample.
+    pub fn uncertainty(&self) -> Duration {
+        self.uncertainty
+    }
+
+    /// Estimated drift between the local and the server clock, in seconds per second.
+    pub fn drift(&self) -> f64 {
+        self.drift
+    }
+
+    /// Round trip time of the connection, updated each frame from the [`RenetClient`].
+    pub fn rtt(&self) -> Duration {
+        self.rtt
+    }
+
+    fn local_secs(&self, instant: Instant) -> f64 {
+        signed_secs(self.epoch, instant)
+    }
+
+    fn add_sample(&mut self, offset: f64, rtt: f64, now: Instant) {
+        if self.samples.len() == SAMPLE_WINDOW {
+            self.samples.pop_front();
+        }
+        self.samples.push_back((offset, rtt));
+        let (best_offset, best_rtt) = self
+            .samples
+            .iter()
+            .copied()
+            .fold((offset, rtt), |best, sample| if sample.1 < best.1 { sample } else { best });
+
+        let elapsed = signed_secs(self.last_sync, now);
+        self.offset = Some(match self.offset {
+            None => best_offset,
+            Some(previous) => {
+                let predicted = previous + self.drift * elapsed;
+                let offset = predicted + OFFSET_SMOOTHING * (best_offset - predicted);
+                if elapsed > 0.0 {
+                    self.drift += DRIFT_SMOOTHING * ((offset - previous) / elapsed - self.drift);
+                }
+                offset
+            }
+        });
+        self.last_sync = now;
+        self.uncertainty = Duration::from_secs_f64(best_rtt / 2.0);
+    }
+
+    fn reset(&mut self) {
+        *self = Self::new(self.epoch);
+    }
+}
+
+fn signed_secs(from: Instant, to: Instant) -> f64 {
+    match to.checked_duration_since(from) {
+        Some(duration) => duration.as_secs_f64(),
+        None => -from.duration_since(to).as_secs_f64(),
+    }
+}
+
+fn receive_sync_requests(
+    channel_id: u8,
+    mut server: ResMut<RenetServer>,
+    mut server_time: ResMut<ServerTime>,
+    mut pending: ResMut<PendingSyncReplies>,
+) {
+    if !server_time.is_server {
+        server_time.reset();
+        server_time.is_server = true;
+        server_time.offset = Some(0.0);
+    }
+
+    let received_at = server_time.local_secs(Instant::now());
+    for client_id in server.clients_id() {
+        while let Some(message) = server.receive_message(client_id, channel_id) {
+            let Ok(request) = <[u8; REQUEST_BYTES]>::try_from(&message[..]) else {
+                log::warn!("Invalid time sync request from client {client_id}");
+                continue;
+            };
+            pending.0.push((client_id, f64::from_le_bytes(request), received_at));
+        }
+    }
+}
+
+fn send_sync_replies(
+    channel_id: u8,
+    mut server: ResMut<RenetServer>,
+    server_time: Res<ServerTime>,
+    mut pending: ResMut<PendingSyncReplies>,
+) {
+    let sent_at = server_time.local_secs(Instant::now());
+    for (client_id, requested_at, received_at) in pending.0.drain(..) {
+        let mut reply = Vec::with_capacity(REPLY_BYTES);
+        reply.extend_from_slice(&requested_at.to_le_bytes());
+        reply.extend_from_slice(&received_at.to_le_bytes());
+        reply.extend_from_slice(&sent_at.to_le_bytes());
+        server.send_message(client_id, channel_id, reply);
+    }
+}
+
+fn send_sync_request(
+    channel_id: u8,
+    sync_interval: Duration,
+    mut client: ResMut<RenetClient>,
+    server_time: Res<ServerTime>,
+    mut last_request: Local<Option<Instant>>,
+) {
+    let now = Instant::now();
+    if !client.is_connected() || last_request.is_some_and(|last_request| now - last_request < sync_interval) {
+        return;
+    }
+
+    *last_request = Some(now);
+    client.send_message(channel_id, server_time.local_secs(now).to_le_bytes().to_vec());
+}
+
+fn receive_sync_replies(channel_id: u8, mut client: ResMut<RenetClient>, mut server_time: ResMut<ServerTime>) {
+    if client.is_disconnected() {
+        // The next server can have another clock
+        if server_time.is_synced() {
+            server_time.reset();
+        }
+        return;
+    }
+    server_time.rtt = Duration::from_secs_f64(client.rtt());
+
+    let now = Instant::now();
+    let received_at = server_time.local_secs(now);
+    while let Some(message) = client.receive_message(channel_id) {
+        if message.len() != REPLY_BYTES {
+            log::warn!("Invalid time sync reply from the server");
+            continue;
+        }
+        let value = |index: usize| f64::from_le_bytes(message[index * 8..(index + 1) * 8].try_into().unwrap());
+        let (requested_at, server_received_at, server_sent_at) = (value(0), value(1), value(2));
+
+        // NTP style estimation, excluding the time the request waited in the server
+        let rtt = ((received_at - requested_at) - (server_sent_at - server_received_at)).max(0.0);
+        let offset = ((server_received_at - requested_at) + (server_sent_at - received_at)) / 2.0;
+        server_time.add_sample(offset, rtt, now);
+    }
+}
@@ -1,16 +1,39 @@
-use bevy_app::{prelude::*, AppExit};
+use bevy_app::prelude::*;
 use bevy_ecs::prelude::*;
 use renet::{RenetClient, RenetServer};
-use steamworks::SteamError;
+use steamworks::{ServerManager, SingleClient, SteamError};
 
-use crate::{RenetClientPlugin, RenetReceive, RenetSend, RenetServerPlugin};
+use crate::{
+    client_exiting, client_just_disconnected, remove_transport_on_exit, server_exiting, LastDisconnect, RenetClientPlugin,
+    RenetClientSchedules, RenetReceive, RenetSend, RenetServerPlugin, RenetServerSchedules,
+};
 
 pub use renet_steam::*;
 
+/// Updates the [`SteamServerTransport`] resource with the [`RenetServer`].
+///
+/// Insert the steamworks [`SingleClient`] as a non-send resource to have the steam callbacks run before the transport update.
+/// The transport is removed when the [`RenetServer`] resource is removed.
+///
+/// When the app exits the transport is shut down and removed at the end of the frame, so its connections and listen sockets
+/// are closed while the steamworks client is alive, instead of racing it when the world is dropped.
 pub struct SteamServerPlugin;
 
+/// Updates the [`SteamClientTransport`] resource with the [`RenetClient`].
+///
+/// Insert the steamworks [`SingleClient`] as a non-send resource to have the steam callbacks run before the transport update.
+/// The transport is disconnected and removed when the [`RenetClient`] resource is removed, and the client is disconnected
+/// when only the transport is removed. To swap the transport at runtime, remove the transport and the [`RenetClient`],
+/// then insert a new [`RenetClient`] with the new transport.
+///
+/// When the app exits the transport is removed at the end of the frame, closing the connection while the steamworks client is alive.
+/// The connection is closed by the transport drop, see [`SteamClientTransport::set_close_on_drop`].
 pub struct SteamClientPlugin;
 
+// Added by both the server and the client plugins, so the callbacks only run once per frame.
+// The callbacks always run in PreUpdate, before the transports when they receive in PreUpdate.
+struct SteamCallbacksPlugin;
+
 #[derive(Debug, Event)]
 pub struct SteamTransportError(pub SteamError);
 
@@ -20,66 +43,143 @@ impl std::fmt::Display for SteamTransportError {
     }
 }
 
+impl std::error::Error for SteamTransportError {}
+
+impl From<SteamTransportError> for renet::RenetError {
+    fn from(inner: SteamTransportError) -> Self {
+        renet::RenetError::Transport(Box::new(inner))
+    }
+}
+
+impl Plugin for SteamCallbacksPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(PreUpdate, Self::run_callbacks_system.before(RenetReceive));
+    }
+}
+
+impl SteamCallbacksPlugin {
+    fn add_to(app: &mut App) {
+        if !app.is_plugin_added::<Self>() {
+            app.add_plugins(Self);
+        }
+    }
+
+    fn run_callbacks_system(client: Option<NonSend<SingleClient>>, server: Option<NonSend<SingleClient<ServerManager>>>) {
+        if let Some(client) = client {
+            client.run_callbacks();
+        }
+        if let Some(server) = server {
+            server.run_callbacks();
+        }
+    }
+}
+
 impl Plugin for SteamServerPlugin {
     fn build(&self, app: &mut App) {
+        SteamCallbacksPlugin::add_to(app);
+    }
+
+    fn finish(&self, app: &mut App) {
+        let schedules = app.world().get_resource::<RenetServerSchedules>().copied().unwrap_or_default();
+
         app.add_systems(
-            PreUpdate,
+            schedules.receive,
             Self::update_system
                 .in_set(RenetReceive)
+                .run_if(resource_exists::<SteamServerTransport>)
                 .run_if(resource_exists::<RenetServer>)
                 .after(RenetServerPlugin::update_system)
                 .before(RenetServerPlugin::emit_server_events_system),
         );
 
         app.add_systems(
-            PostUpdate,
-            Self::send_packets.in_set(RenetSend).run_if(resource_exists::<RenetServer>),
+            schedules.send,
+            Self::send_packets
+                .in_set(RenetSend)
+                .run_if(resource_exists::<SteamServerTransport>)
+                .run_if(resource_exists::<RenetServer>),
         );
 
-        app.add_systems(Last, Self::disconnect_on_exit.run_if(resource_exists::<RenetServer>));
+        app.add_systems(
+            Last,
+            (
+                Self::disconnect_on_exit
+                    .run_if(server_exiting)
+                    .run_if(resource_exists::<SteamServerTransport>)
+                    .run_if(resource_exists::<RenetServer>),
+                Self::cleanup_system
+                    .run_if(resource_exists::<SteamServerTransport>)
+                    .run_if(not(resource_exists::<RenetServer>)),
+                Self::shutdown_on_exit
+                    .run_if(on_event::<AppExit>)
+                    .run_if(resource_exists::<SteamServerTransport>)
+                    .run_if(resource_exists::<RenetServer>),
+                remove_transport_on_exit::<SteamServerTransport>
+                    .run_if(on_event::<AppExit>)
+                    .run_if(resource_exists::<SteamServerTransport>),
+            )
+                .chain(),
+        );
     }
 }
 
 impl SteamServerPlugin {
-    pub fn update_system(mut transport: Option<NonSendMut<SteamServerTransport>>, mut server: ResMut<RenetServer>) {
-        if let Some(transport) = transport.as_mut() {
-            transport.update(&mut server);
-        }
+    pub fn update_system(mut transport: ResMut<SteamServerTransport>, mut server: ResMut<RenetServer>) {
+        transport.update(&mut server);
     }
 
-    pub fn send_packets(mut transport: Option<NonSendMut<SteamServerTransport>>, mut server: ResMut<RenetServer>) {
-        if let Some(transport) = transport.as_mut() {
-            transport.send_packets(&mut server);
-        }
+    pub fn send_packets(mut transport: ResMut<SteamServerTransport>, mut server: ResMut<RenetServer>) {
+        transport.send_packets(&mut server);
     }
 
-    pub fn disconnect_on_exit(
-        exit: EventReader<AppExit>,
-        mut transport: Option<NonSendMut<SteamServerTransport>>,
-        mut server: ResMut<RenetServer>,
-    ) {
-        if let Some(transport) = transport.as_mut() {
-            if !exit.is_empty() {
-                transport.disconnect_all(&mut server, false);
-            }
-        }
+    /// Sends the pending packets and disconnects all clients, runs when the app exits.
+    ///
+    /// The connections are closed without flushing, steam only delivers flushed packets while the transport is updated.
+    pub fn disconnect_on_exit(mut transport: ResMut<SteamServerTransport>, mut server: ResMut<RenetServer>) {
+        transport.send_packets(&mut server);
+        transport.disconnect_all(&mut server, false);
+    }
+
+    /// Removes the transport once the [`RenetServer`] was removed, closing the remaining connections.
+    pub fn cleanup_system(mut commands: Commands) {
+        commands.remove_resource::<SteamServerTransport>();
+    }
+
+    /// Shuts down the transport when the app exits, even with `keep_connections_on_exit`.
+    /// The transport is then removed by [`remove_transport_on_exit`], so it's never dropped after the steamworks client.
+    pub fn shutdown_on_exit(mut transport: ResMut<SteamServerTransport>, mut server: ResMut<RenetServer>) {
+        transport.shutdown(&mut server, None, false);
     }
 }
 
 impl Plugin for SteamClientPlugin {
     fn build(&self, app: &mut App) {
+        SteamCallbacksPlugin::add_to(app);
         app.add_event::<SteamTransportError>();
+    }
+
+    fn finish(&self, app: &mut App) {
+        let schedules = app.world().get_resource::<RenetClientSchedules>().copied().unwrap_or_default();
 
         app.add_systems(
-            PreUpdate,
+            schedules.receive,
             Self::update_system
                 .in_set(RenetReceive)
                 .run_if(resource_exists::<SteamClientTransport>)
                 .run_if(resource_exists::<RenetClient>)
                 .after(RenetClientPlugin::update_system),
         );
+        app.add_systems(schedules.receive, Self::transport_removed_system.before(RenetReceive));
+        app.add_systems(
+            schedules.receive,
+            Self::record_disconnect_reason_system
+                .after(RenetClientPlugin::update_last_disconnect_system)
+                .run_if(client_just_disconnected)
+                .run_if(resource_exists::<SteamClientTransport>)
+                .run_if(resource_exists::<LastDisconnect>),
+        );
         app.add_systems(
-            PostUpdate,
+            schedules.send,
             Self::send_packets
                 .in_set(RenetSend)
                 .run_if(resource_exists::<SteamClientTransport>)
@@ -88,9 +188,19 @@ impl Plugin for SteamClientPlugin {
 
         app.add_systems(
             Last,
-            Self::disconnect_on_exit
-                .run_if(resource_exists::<SteamClientTransport>)
-                .run_if(resource_exists::<RenetClient>),
+            (
+                Self::disconnect_on_exit
+                    .run_if(client_exiting)
+                    .run_if(resource_exists::<SteamClientTransport>)
+                    .run_if(resource_exists::<RenetClient>),
+                Self::cleanup_system
+                    .run_if(resource_exists::<SteamClientTransport>)
+                    .run_if(not(resource_exists::<RenetClient>)),
+                remove_transport_on_exit::<SteamClientTransport>
+                    .run_if(on_event::<AppExit>)
+                    .run_if(resource_exists::<SteamClientTransport>),
+            )
+                .chain(),
         );
     }
 }
@@ -110,9 +220,42 @@ impl SteamClientPlugin {
         }
     }
 
-    pub fn disconnect_on_exit(exit: EventReader<AppExit>, mut transport: ResMut<SteamClientTransport>) {
-        if !exit.is_empty() {
-            transport.disconnect();
+    /// Sends the pending packets and disconnects, runs when the app exits.
+    pub fn disconnect_on_exit(mut transport: ResMut<SteamClientTransport>, mut client: ResMut<RenetClient>) {
+        if let Err(e) = transport.send_packets(&mut client) {
+            log::error!("Failed to send the last packets: {e}");
+        }
+        transport.disconnect();
+    }
+
+    /// Records the disconnect reason of the transport in the [`LastDisconnect`],
+    /// the message given when closing the connection or else the end reason.
+    pub fn record_disconnect_reason_system(transport: Res<SteamClientTransport>, mut last_disconnect: ResMut<LastDisconnect>) {
+        last_disconnect.transport_reason = transport
+            .disconnect_message()
+            .or_else(|| transport.disconnect_reason().map(|reason| format!("{reason:?}")));
+    }
+
+    /// Disconnects the [`RenetClient`] when the transport was removed, unless the client was also replaced.
+    pub fn transport_removed_system(
+        transport: Option<Res<SteamClientTransport>>,
+        client: Option<ResMut<RenetClient>>,
+        mut had_transport: Local<bool>,
+    ) {
+        let removed = *had_transport && transport.is_none();
+        *had_transport = transport.is_some();
+        if !removed {
+            return;
         }
+        // A client inserted after being removed is a new one
+        if let Some(mut client) = client.filter(|client| !client.is_added()) {
+            client.disconnect_due_to_transport();
+        }
+    }
+
+    /// Disconnects and removes the transport once the [`RenetClient`] was removed.
+    pub fn cleanup_system(mut commands: Commands, mut transport: ResMut<SteamClientTransport>) {
+        transport.disconnect();
+        commands.remove_resource::<SteamClientTransport>();
     }
 }
@@ -0,0 +1,113 @@
+use std::time::{Duration, Instant};
+
+use bevy_app::prelude::*;
+use bevy_ecs::prelude::*;
+use bevy_time::{prelude::*, Fixed};
+
+use crate::RenetServerSchedules;
+
+/// Caps the app to a tick rate by sleeping the remainder of each tick, for headless servers that would spin as fast as possible.
+///
+/// Renet is updated with the real elapsed time, so the sleep is included in its delta.
+/// A tick that took longer than the tick duration is not caught up, the next tick starts right away
+/// and the transports send once instead of a burst of ticks.
+///
+/// With [`RenetServerPlugin::fixed_update`](crate::RenetServerPlugin::fixed_update) the fixed timestep is set to the tick rate,
+/// and the virtual time is limited to one tick per frame, so a long tick runs one fixed step instead of catching up.
+/// Add this plugin after the [`RenetServerPlugin`](crate::RenetServerPlugin).
+///
+/// # Usage
+/// ```no_run
+/// # use bevy_app::prelude::*;
+/// # use bevy_renet::{tick_rate::RenetTickRatePlugin, RenetServerPlugin};
+/// # let mut app = App::new();
+/// app.add_plugins((RenetServerPlugin::default(), RenetTickRatePlugin::from_hz(60.0).unwrap()));
+/// ```
+#[derive(Debug)]
+pub struct RenetTickRatePlugin {
+    tick: Duration,
+}
+
+/// The tick rate of the [`RenetTickRatePlugin`], can be changed at runtime.
+#[derive(Debug, Resource)]
+pub struct TickRate {
+    tick: Duration,
+    next_tick: Option<Instant>,
+}
+
+/// The tick rate is not a positive and finite number of ticks per second.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct InvalidTickRate(pub f64);
+
+impl std::fmt::Display for InvalidTickRate {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(fmt, "invalid tick rate of {} Hz", self.0)
+    }
+}
+
+impl std::error::Error for InvalidTickRate {}
+
+impl RenetTickRatePlugin {
+    /// Returns an error if the rate is zero, negative or not finite.
+    pub fn from_hz(hz: f64) -> Result<Self, InvalidTickRate> {
+        if !(hz.is_finite() && hz > 0.0) {
+            return Err(InvalidTickRate(hz));
+        }
+        let tick = Duration::try_from_secs_f64(1.0 / hz).map_err(|_| InvalidTickRate(hz))?;
+        Ok(Self { tick })
+    }
+
+    pub fn from_duration(tick: Duration) -> Self {
+        Self { tick }
+    }
+
+    /// Sleeps until the next tick, runs at the end of [`Last`].
+    pub fn sleep_system(mut tick_rate: ResMut<TickRate>) {
+        let now = Instant::now();
+        let next_tick = match tick_rate.next_tick {
+            Some(next_tick) if next_tick > now => {
+                std::thread::sleep(next_tick - now);
+                next_tick
+            }
+            // First tick, or the tick was too long: start over instead of catching up
+            _ => now,
+        };
+        tick_rate.next_tick = Some(next_tick + tick_rate.tick);
+    }
+}
+
+impl TickRate {
+    pub fn tick(&self) -> Duration {
+        self.tick
+    }
+
+    /// Returns when the next tick starts, None before the first tick.
+    pub fn next_tick(&self) -> Option<Instant> {
+        self.next_tick
+    }
+
+    /// Changes the tick duration, the fixed timestep is not updated.
+    pub fn set_tick(&mut self, tick: Duration) {
+        self.tick = tick;
+    }
+}
+
+impl Plugin for RenetTickRatePlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(TickRate {
+            tick: self.tick,
+            next_tick: None,
+        });
+        app.add_systems(Last, Self::sleep_system);
+    }
+
+    fn finish(&self, app: &mut App) {
+        let schedules = app.world().get_resource::<RenetServerSchedules>().copied();
+        if schedules == Some(RenetServerSchedules::fixed()) {
+            app.insert_resource(Time::<Fixed>::from_duration(self.tick));
+            if let Some(mut virtual_time) = app.world_mut().get_resource_mut::<Time<Virtual>>() {
+                virtual_time.set_max_delta(self.tick);
+            }
+        }
+    }
+}
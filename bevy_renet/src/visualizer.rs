@@ -0,0 +1,136 @@
+use bevy_app::prelude::*;
+use bevy_ecs::{prelude::*, system::SystemParam};
+use bevy_egui::{egui, EguiContexts};
+use renet::{RenetClient, RenetServer, ServerEvent};
+
+use crate::{RenetClientSchedules, RenetReceive, RenetServerSchedules};
+
+pub use renet_visualizer::*;
+
+/// Inserts the [`RenetClientVisualizer`] and [`RenetServerVisualizer`] resources and feeds them with the network info
+/// of the [`RenetClient`] and [`RenetServer`] after [`RenetReceive`]. Clients are added and removed from the server visualizer
+/// when they connect and disconnect.
+///
+/// By default the metrics are drawn in an egui window, requires the bevy_egui `EguiPlugin`.
+/// Use [`without_window`](Self::without_window) and the [`RenetVisualizer`] system param to draw them in your own UI instead.
+///
+/// N: determines how many values are shown in the graph, see [`RenetClientVisualizer`].
+pub struct RenetVisualizerPlugin<const N: usize = 200> {
+    show_window: bool,
+}
+
+impl<const N: usize> Default for RenetVisualizerPlugin<N> {
+    fn default() -> Self {
+        Self { show_window: true }
+    }
+}
+
+impl<const N: usize> RenetVisualizerPlugin<N> {
+    /// Only feed the visualizers, without drawing the metrics window.
+    pub fn without_window(mut self) -> Self {
+        self.show_window = false;
+        self
+    }
+
+    pub fn update_client_system(client: Res<RenetClient>, mut visualizer: ResMut<RenetClientVisualizer<N>>) {
+        visualizer.add_network_info(client.network_info());
+        for channel_info in client.channels_network_info() {
+            visualizer.add_channel_network_info(channel_info);
+        }
+    }
+
+    pub fn update_server_system(
+        mut server_events: EventReader<ServerEvent>,
+        server: Res<RenetServer>,
+        mut visualizer: ResMut<RenetServerVisualizer<N>>,
+    ) {
+        for event in server_events.read() {
+            match event {
+                ServerEvent::ClientConnected { client_id } => visualizer.add_client(*client_id),
+                ServerEvent::ClientDisconnected { client_id, .. } => visualizer.remove_client(*client_id),
+            }
+        }
+        visualizer.update(&server);
+    }
+
+    pub fn show_window_system(mut egui_contexts: EguiContexts, mut visualizer: RenetVisualizer<N>) {
+        let Some(ctx) = egui_contexts.try_ctx_mut() else {
+            return;
+        };
+        visualizer.show_windows(ctx);
+    }
+}
+
+impl<const N: usize> Plugin for RenetVisualizerPlugin<N> {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<RenetClientVisualizer<N>>();
+        app.init_resource::<RenetServerVisualizer<N>>();
+        if self.show_window {
+            app.add_systems(Update, Self::show_window_system);
+        }
+    }
+
+    fn finish(&self, app: &mut App) {
+        let client_schedules = app.world().get_resource::<RenetClientSchedules>().copied().unwrap_or_default();
+        let server_schedules = app.world().get_resource::<RenetServerSchedules>().copied().unwrap_or_default();
+
+        app.add_systems(
+            client_schedules.receive,
+            Self::update_client_system
+                .after(RenetReceive)
+                .run_if(resource_exists::<RenetClient>),
+        );
+        app.add_systems(
+            server_schedules.receive,
+            Self::update_server_system
+                .after(RenetReceive)
+                .run_if(resource_exists::<RenetServer>),
+        );
+    }
+}
+
+/// Draws the metrics of the visualizers fed by the [`RenetVisualizerPlugin`], only for the active [`RenetClient`] and [`RenetServer`].
+///
+/// # Usage
+/// ```
+/// # use bevy_renet::visualizer::RenetVisualizer;
+/// # use bevy_egui::{egui, EguiContexts};
+/// fn network_panel(mut egui_contexts: EguiContexts, mut visualizer: RenetVisualizer) {
+///     egui::SidePanel::left("network").show(egui_contexts.ctx_mut(), |ui| {
+///         visualizer.draw(ui);
+///     });
+/// }
+/// ```
+#[derive(SystemParam)]
+pub struct RenetVisualizer<'w, const N: usize = 200> {
+    client: Option<Res<'w, RenetClient>>,
+    server: Option<Res<'w, RenetServer>>,
+    client_visualizer: ResMut<'w, RenetClientVisualizer<N>>,
+    server_visualizer: ResMut<'w, RenetServerVisualizer<N>>,
+}
+
+impl<const N: usize> RenetVisualizer<'_, N> {
+    /// Draws the metrics without a window.
+    pub fn draw(&mut self, ui: &mut egui::Ui) {
+        if self.client.is_some() {
+            self.client_visualizer.draw_pause_toggle(ui);
+            ui.horizontal(|ui| {
+                self.client_visualizer.draw_all(ui);
+            });
+            self.client_visualizer.draw_channels(ui);
+        }
+        if self.server.is_some() {
+            self.server_visualizer.draw_all(ui);
+        }
+    }
+
+    /// Renders the client and server windows.
+    pub fn show_windows(&mut self, ctx: &egui::Context) {
+        if self.client.is_some() {
+            self.client_visualizer.show_window(ctx);
+        }
+        if self.server.is_some() {
+            self.server_visualizer.show_window(ctx);
+        }
+    }
+}
@@ -0,0 +1,88 @@
+use bevy_app::prelude::*;
+use bevy_ecs::prelude::*;
+use bevy_renet::acks::{MessageAcked, RenetAckPlugin};
+use bevy_renet::renet::{ConnectionConfig, DefaultChannel, RenetClient, RenetServer};
+use bevy_renet::RenetServerPlugin;
+use bevy_time::TimePlugin;
+
+fn new_app() -> (App, RenetClient) {
+    let mut app = App::new();
+    app.add_plugins((
+        TimePlugin,
+        RenetServerPlugin::default(),
+        RenetAckPlugin::new([DefaultChannel::ReliableOrdered]),
+    ));
+    app.finish();
+    app.cleanup();
+    let mut server = RenetServer::new(ConnectionConfig::default());
+    let client = server.new_local_client(0);
+    app.insert_resource(server);
+    app.update();
+    (app, client)
+}
+
+// The local client receives the messages and acks them in the next update
+fn exchange(app: &mut App, client: &mut RenetClient) {
+    app.update();
+    app.world_mut()
+        .resource_mut::<RenetServer>()
+        .process_local_client(0, client)
+        .unwrap();
+    app.update();
+}
+
+fn read_acks(app: &mut App) -> Vec<MessageAcked> {
+    app.world_mut().resource_mut::<Events<MessageAcked>>().drain().collect()
+}
+
+#[test]
+fn emits_message_acked() {
+    let (mut app, mut client) = new_app();
+    let server = app.world().resource::<RenetServer>();
+    assert!(server.channel_ack_notifications(DefaultChannel::ReliableOrdered));
+    // The acks of the other channels would never be drained
+    assert!(!server.channel_ack_notifications(DefaultChannel::ReliableUnordered));
+
+    let mut server = app.world_mut().resource_mut::<RenetServer>();
+    let message_id = server.next_message_id(0, DefaultChannel::ReliableOrdered).unwrap();
+    server.send_message(0, DefaultChannel::ReliableOrdered, vec![1]);
+    // Not tracked by the plugin
+    server.send_message(0, DefaultChannel::ReliableUnordered, vec![2]);
+    exchange(&mut app, &mut client);
+
+    assert_eq!(
+        read_acks(&mut app),
+        vec![MessageAcked {
+            client_id: Some(0),
+            channel_id: DefaultChannel::ReliableOrdered.into(),
+            message_id,
+        }]
+    );
+}
+
+#[cfg(feature = "events")]
+#[test]
+fn emits_event_delivered() {
+    use bevy_renet::events::{EventDelivered, NetworkEventAppExt, SendMode, ToClients};
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Debug, Event, Serialize, Deserialize)]
+    struct Chat(String);
+
+    let (mut app, mut client) = new_app();
+    app.add_server_event::<Chat>(DefaultChannel::ReliableUnordered);
+    app.world_mut().send_event(ToClients {
+        mode: SendMode::Broadcast,
+        event: Chat("hello".to_string()),
+    });
+    // Raw messages on the same channel are not events
+    app.world_mut()
+        .resource_mut::<RenetServer>()
+        .send_message(0, DefaultChannel::ReliableUnordered, vec![1]);
+    exchange(&mut app, &mut client);
+
+    assert_eq!(read_acks(&mut app).len(), 2);
+    let delivered: Vec<_> = app.world_mut().resource_mut::<Events<EventDelivered<Chat>>>().drain().collect();
+    assert_eq!(delivered.len(), 1);
+    assert_eq!(delivered[0].client_id, Some(0));
+}
@@ -0,0 +1,82 @@
+use bevy_app::prelude::*;
+use bevy_ecs::prelude::*;
+use bevy_renet::client_entities::{ClientEntitiesPlugin, ClientEntityMap, ConnectedClient};
+use bevy_renet::renet::{ClientId, ConnectionConfig, RenetServer, ServerEvent};
+use bevy_renet::{RenetReceive, RenetServerPlugin};
+use bevy_time::TimePlugin;
+
+#[derive(Debug, Default, Resource)]
+struct SeenClients(Vec<(ClientId, bool, Option<ConnectedClient>)>);
+
+// Records the entity of the client of each server event
+fn read_events_system(
+    mut events: EventReader<ServerEvent>,
+    client_entities: Res<ClientEntityMap>,
+    clients: Query<&ConnectedClient>,
+    mut seen: ResMut<SeenClients>,
+) {
+    for event in events.read() {
+        let (client_id, connected) = match event {
+            ServerEvent::ClientConnected { client_id } => (*client_id, true),
+            ServerEvent::ClientDisconnected { client_id, .. } => (*client_id, false),
+        };
+        let client = client_entities.get(client_id).and_then(|entity| clients.get(entity).ok()).copied();
+        seen.0.push((client_id, connected, client));
+    }
+}
+
+fn new_app() -> App {
+    let mut app = App::new();
+    app.add_plugins((TimePlugin, RenetServerPlugin::default(), ClientEntitiesPlugin));
+    app.init_resource::<SeenClients>();
+    app.add_systems(PreUpdate, read_events_system.after(RenetReceive));
+    app.finish();
+    app.cleanup();
+    app.insert_resource(RenetServer::new(ConnectionConfig::default()));
+    app
+}
+
+fn client_count(app: &mut App) -> usize {
+    app.world_mut().query::<&ConnectedClient>().iter(app.world()).count()
+}
+
+#[test]
+fn spawns_and_despawns_client_entities() {
+    let mut app = new_app();
+    let mut server = app.world_mut().resource_mut::<RenetServer>();
+    let _client_a = server.new_local_client(0);
+    let _client_b = server.new_local_client(1);
+    app.update();
+
+    assert_eq!(client_count(&mut app), 2);
+    assert_eq!(app.world().resource::<ClientEntityMap>().len(), 2);
+    let seen = std::mem::take(&mut app.world_mut().resource_mut::<SeenClients>().0);
+    assert_eq!(seen.len(), 2);
+    assert!(seen
+        .iter()
+        .all(|(client_id, _, client)| client == &Some(ConnectedClient { client_id: *client_id })));
+
+    app.world_mut().resource_mut::<RenetServer>().remove_connection(0);
+    app.update();
+
+    // The entity was available to the systems reading the disconnection
+    let seen = std::mem::take(&mut app.world_mut().resource_mut::<SeenClients>().0);
+    assert_eq!(seen, vec![(0, false, Some(ConnectedClient { client_id: 0 }))]);
+    assert_eq!(client_count(&mut app), 1);
+    let client_entities = app.world().resource::<ClientEntityMap>();
+    assert_eq!(client_entities.get(0), None);
+    assert!(client_entities.get(1).is_some());
+}
+
+#[test]
+fn despawns_client_entities_when_server_is_removed() {
+    let mut app = new_app();
+    let _client = app.world_mut().resource_mut::<RenetServer>().new_local_client(0);
+    app.update();
+    assert_eq!(client_count(&mut app), 1);
+
+    app.world_mut().remove_resource::<RenetServer>();
+    app.update();
+    assert_eq!(client_count(&mut app), 0);
+    assert!(app.world().resource::<ClientEntityMap>().is_empty());
+}
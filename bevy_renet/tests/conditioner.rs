@@ -0,0 +1,52 @@
+use std::time::Duration;
+
+use bevy_app::prelude::*;
+use bevy_renet::conditioner::{NetworkConditionerPlugin, NetworkConditions};
+use bevy_renet::renet::{ConnectionConfig, RenetClient, RenetServer};
+use bevy_renet::{RenetClientPlugin, RenetServerPlugin};
+use bevy_time::TimePlugin;
+
+const CONDITIONS: NetworkConditions = NetworkConditions {
+    latency: Duration::from_millis(100),
+    jitter: Duration::ZERO,
+    loss_percent: 10.0,
+    duplication_percent: 0.0,
+};
+
+#[test]
+fn applies_conditions_to_the_client() {
+    let mut app = App::new();
+    app.add_plugins((TimePlugin, RenetClientPlugin::default(), NetworkConditionerPlugin::default()));
+    app.finish();
+    app.cleanup();
+    app.insert_resource(RenetClient::new(ConnectionConfig::default()));
+    app.insert_resource(CONDITIONS);
+    app.update();
+    assert_eq!(app.world().resource::<RenetClient>().network_conditions(), Some(CONDITIONS));
+
+    app.world_mut().remove_resource::<NetworkConditions>();
+    app.update();
+    assert_eq!(app.world().resource::<RenetClient>().network_conditions(), None);
+}
+
+#[test]
+fn listen_server_conditions_only_the_server() {
+    let mut app = App::new();
+    app.add_plugins((
+        TimePlugin,
+        RenetServerPlugin::default(),
+        RenetClientPlugin::default(),
+        NetworkConditionerPlugin::default(),
+    ));
+    app.finish();
+    app.cleanup();
+    let mut server = RenetServer::new(ConnectionConfig::default());
+    let client = server.new_local_client(0);
+    app.insert_resource(server);
+    app.insert_resource(client);
+    app.insert_resource(CONDITIONS);
+    app.update();
+
+    assert_eq!(app.world().resource::<RenetServer>().network_conditions(), Some(CONDITIONS));
+    assert_eq!(app.world().resource::<RenetClient>().network_conditions(), None);
+}
@@ -0,0 +1,46 @@
+use bevy_app::prelude::*;
+use bevy_ecs::prelude::*;
+use bevy_renet::renet::{ConnectionConfig, DisconnectReason, RenetClient, RenetServer};
+use bevy_renet::{client_disconnected_because, LastDisconnect, RenetClientPlugin};
+use bevy_time::TimePlugin;
+
+#[derive(Debug, Default, Resource)]
+struct ConnectionLost(bool);
+
+fn new_app() -> App {
+    let mut app = App::new();
+    app.add_plugins((TimePlugin, RenetClientPlugin::default()));
+    app.init_resource::<ConnectionLost>();
+    app.add_systems(
+        Update,
+        (|mut lost: ResMut<ConnectionLost>| lost.0 = true)
+            .run_if(client_disconnected_because(|reason| *reason == DisconnectReason::Transport)),
+    );
+    app.finish();
+    app.cleanup();
+    app
+}
+
+#[test]
+fn keeps_last_disconnect_until_connected() {
+    let mut app = new_app();
+    let mut server = RenetServer::new(ConnectionConfig::default());
+    app.insert_resource(server.new_local_client(0));
+    app.update();
+    assert!(!app.world().contains_resource::<LastDisconnect>());
+
+    app.world_mut().resource_mut::<RenetClient>().disconnect_due_to_transport();
+    app.update();
+    assert_eq!(app.world().resource::<LastDisconnect>().reason, DisconnectReason::Transport);
+    assert!(app.world().resource::<ConnectionLost>().0);
+
+    // Still available in the next frames and while connecting again
+    app.world_mut().resource_mut::<ConnectionLost>().0 = false;
+    app.insert_resource(RenetClient::new(ConnectionConfig::default()));
+    app.update();
+    assert!(app.world().resource::<ConnectionLost>().0);
+
+    app.insert_resource(server.new_local_client(1));
+    app.update();
+    assert!(!app.world().contains_resource::<LastDisconnect>());
+}
@@ -0,0 +1,36 @@
+use std::time::Duration;
+
+use bevy_app::prelude::*;
+use bevy_ecs::prelude::*;
+use bevy_renet::network_stats::{NetworkStatsInterval, NetworkStatsPlugin, NetworkStatsSample};
+use bevy_renet::renet::{ConnectionConfig, RenetServer};
+use bevy_renet::RenetServerPlugin;
+use bevy_time::TimePlugin;
+
+fn read_samples(app: &mut App) -> Vec<NetworkStatsSample> {
+    app.world_mut().resource_mut::<Events<NetworkStatsSample>>().drain().collect()
+}
+
+#[test]
+fn emits_samples_at_interval() {
+    let mut app = App::new();
+    app.add_plugins((TimePlugin, RenetServerPlugin::default(), NetworkStatsPlugin));
+    app.finish();
+    app.cleanup();
+    assert_eq!(app.world().resource::<NetworkStatsInterval>().0, Duration::from_secs(1));
+
+    let mut server = RenetServer::new(ConnectionConfig::default());
+    let _client_0 = server.new_local_client(0);
+    let _client_1 = server.new_local_client(1);
+    app.insert_resource(server);
+
+    // Far from the default interval
+    app.update();
+    assert!(read_samples(&mut app).is_empty());
+
+    app.insert_resource(NetworkStatsInterval(Duration::ZERO));
+    app.update();
+    let mut client_ids: Vec<_> = read_samples(&mut app).iter().map(|sample| sample.client_id).collect();
+    client_ids.sort();
+    assert_eq!(client_ids, vec![Some(0), Some(1)]);
+}
@@ -0,0 +1,48 @@
+use std::time::Duration;
+
+use bevy_app::prelude::*;
+use bevy_ecs::prelude::*;
+use bevy_renet::network_stats::NetworkStatsInterval;
+use bevy_renet::overlay::{RenetStatsOverlay, RenetStatsOverlayPlugin, RenetStatsOverlayText};
+use bevy_renet::renet::{ConnectionConfig, RenetServer};
+use bevy_renet::RenetServerPlugin;
+use bevy_time::TimePlugin;
+use bevy_ui::widget::Text;
+use bevy_ui::{Display, Node};
+
+fn overlay(app: &mut App) -> (String, Display) {
+    let world = app.world_mut();
+    let (text, node) = world
+        .query_filtered::<(&Text, &Node), With<RenetStatsOverlayText>>()
+        .single(world)
+        .unwrap();
+    (text.0.clone(), node.display)
+}
+
+#[test]
+fn shows_server_clients() {
+    let mut app = App::new();
+    app.add_plugins((
+        TimePlugin,
+        RenetServerPlugin::default(),
+        RenetStatsOverlayPlugin::default().max_clients(1),
+    ));
+    app.finish();
+    app.cleanup();
+    app.insert_resource(NetworkStatsInterval(Duration::ZERO));
+
+    let mut server = RenetServer::new(ConnectionConfig::default());
+    let _client_0 = server.new_local_client(0);
+    let _client_1 = server.new_local_client(1);
+    app.insert_resource(server);
+    app.update();
+
+    let (text, display) = overlay(&mut app);
+    assert!(text.starts_with("Clients: 2"));
+    assert!(text.ends_with("1 more"));
+    assert_eq!(display, Display::Flex);
+
+    app.world_mut().resource_mut::<RenetStatsOverlay>().visible = false;
+    app.update();
+    assert_eq!(overlay(&mut app).1, Display::None);
+}
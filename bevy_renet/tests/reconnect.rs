@@ -0,0 +1,22 @@
+use std::time::Duration;
+
+use bevy_renet::reconnect::ReconnectPolicy;
+use bevy_renet::renet::ConnectionConfig;
+
+#[test]
+fn reconnect_delay_backoff() {
+    let policy = ReconnectPolicy::new(ConnectionConfig::default(), |_| None);
+    assert_eq!(policy.delay(1), Duration::from_secs(1));
+    assert_eq!(policy.delay(2), Duration::from_secs(2));
+    assert_eq!(policy.delay(3), Duration::from_secs(4));
+    assert_eq!(policy.delay(10), Duration::from_secs(30));
+}
+
+#[test]
+fn reconnect_delay_doesnt_overflow() {
+    let policy = ReconnectPolicy::new(ConnectionConfig::default(), |_| None).max_attempts(None);
+    // The factor overflows the Duration after about 65 attempts, and f32 after 128
+    for attempt in [65, 100, 200, u32::MAX] {
+        assert_eq!(policy.delay(attempt), Duration::from_secs(30));
+    }
+}
@@ -0,0 +1,126 @@
+use bevy_app::prelude::*;
+use bevy_ecs::prelude::*;
+use bevy_renet::renet::{ConnectionConfig, DefaultChannel, DisconnectReason, RenetClient, RenetServer, SchemaHandshake};
+use bevy_renet::schema::{SchemaHandshakePlugin, SchemaNegotiated, SchemaRejected};
+use bevy_renet::{RenetClientPlugin, RenetServerPlugin};
+use bevy_time::TimePlugin;
+
+const SCHEMA_CHANNEL: u8 = 3;
+
+fn connection_config() -> ConnectionConfig {
+    let mut channels = DefaultChannel::config();
+    channels.push(SchemaHandshake::channel_config(SCHEMA_CHANNEL));
+    ConnectionConfig {
+        server_channels_config: channels.clone(),
+        client_channels_config: channels,
+        ..Default::default()
+    }
+}
+
+fn new_app(plugin: impl Plugin, version: u32) -> App {
+    let mut app = App::new();
+    app.add_plugins((
+        TimePlugin,
+        plugin,
+        SchemaHandshakePlugin::new(SchemaHandshake::new(SCHEMA_CHANNEL, version).accept_versions(3..=4)),
+    ));
+    app.finish();
+    app.cleanup();
+    app
+}
+
+#[derive(Debug, Default)]
+struct HandshakeEvents {
+    negotiated: Vec<SchemaNegotiated>,
+    rejected: Vec<SchemaRejected>,
+}
+
+fn read_events(app: &mut App, events: &mut HandshakeEvents) {
+    events
+        .negotiated
+        .extend(app.world_mut().resource_mut::<Events<SchemaNegotiated>>().drain());
+    events
+        .rejected
+        .extend(app.world_mut().resource_mut::<Events<SchemaRejected>>().drain());
+}
+
+// Connects a client app with the version to a server app accepting versions 3 and 4,
+// returns the client and the events of the server and the client
+fn run_handshake(client_version: u32) -> (RenetClient, HandshakeEvents, HandshakeEvents) {
+    let mut server_app = new_app(RenetServerPlugin::default(), 4);
+    server_app.insert_resource(RenetServer::new(connection_config()));
+    server_app.update();
+    // The handshake is set on the server before the client connects
+    let client = server_app.world_mut().resource_mut::<RenetServer>().new_local_client(0);
+
+    let mut client_app = new_app(RenetClientPlugin::default(), client_version);
+    client_app.insert_resource(client);
+    let mut server_events = HandshakeEvents::default();
+    let mut client_events = HandshakeEvents::default();
+    for _ in 0..4 {
+        client_app.update();
+        server_app.update();
+        read_events(&mut server_app, &mut server_events);
+        read_events(&mut client_app, &mut client_events);
+        let mut client = client_app.world_mut().remove_resource::<RenetClient>().unwrap();
+        server_app
+            .world_mut()
+            .resource_mut::<RenetServer>()
+            .process_local_client(0, &mut client)
+            .unwrap();
+        client_app.insert_resource(client);
+    }
+
+    let client = client_app.world_mut().remove_resource::<RenetClient>().unwrap();
+    (client, server_events, client_events)
+}
+
+#[test]
+fn negotiates_schema() {
+    let (client, server_events, client_events) = run_handshake(3);
+
+    assert_eq!(
+        server_events.negotiated,
+        vec![SchemaNegotiated {
+            client_id: Some(0),
+            version: 3,
+            features: 0,
+        }]
+    );
+    assert_eq!(
+        client_events.negotiated,
+        vec![SchemaNegotiated {
+            client_id: None,
+            version: 3,
+            features: 0,
+        }]
+    );
+    assert_eq!(client.negotiated_version(), Some(3));
+}
+
+#[test]
+fn rejects_schema() {
+    let (client, server_events, client_events) = run_handshake(2);
+
+    assert_eq!(
+        server_events.rejected,
+        vec![SchemaRejected {
+            client_id: Some(0),
+            remote_version: 2,
+        }]
+    );
+    assert_eq!(
+        client_events.rejected,
+        vec![SchemaRejected {
+            client_id: None,
+            remote_version: 4,
+        }]
+    );
+    assert_eq!(
+        client.disconnect_reason(),
+        Some(DisconnectReason::SchemaMismatch {
+            local_version: 2,
+            remote_version: 4,
+        })
+    );
+}
@@ -0,0 +1,83 @@
+use std::time::{Duration, Instant};
+
+use bevy_app::prelude::*;
+use bevy_renet::renet::{ConnectionConfig, DefaultChannel, RenetClient, RenetServer};
+use bevy_renet::{server_time::ServerTime, server_time::ServerTimePlugin, RenetClientPlugin, RenetServerPlugin};
+use bevy_time::TimePlugin;
+
+const SYNC_CHANNEL: u8 = 3;
+
+fn connection_config() -> ConnectionConfig {
+    let mut channels = DefaultChannel::config();
+    channels.push(ServerTimePlugin::channel_config(SYNC_CHANNEL));
+    ConnectionConfig {
+        server_channels_config: channels.clone(),
+        client_channels_config: channels,
+        ..Default::default()
+    }
+}
+
+fn new_app(plugin: impl Plugin) -> App {
+    let mut app = App::new();
+    app.add_plugins((
+        TimePlugin,
+        plugin,
+        ServerTimePlugin::new(SYNC_CHANNEL).sync_interval(Duration::from_millis(10)),
+    ));
+    app.finish();
+    app.cleanup();
+    app
+}
+
+#[test]
+fn client_estimates_server_time() {
+    let mut server_app = new_app(RenetServerPlugin::default());
+    let mut server = RenetServer::new(connection_config());
+    let client = server.new_local_client(0);
+    server_app.insert_resource(server);
+    server_app.update();
+    assert!(server_app.world().resource::<ServerTime>().is_synced());
+
+    // The client clock starts later than the server clock
+    std::thread::sleep(Duration::from_millis(50));
+    let mut client_app = new_app(RenetClientPlugin::default());
+    client_app.insert_resource(client);
+    assert!(!client_app.world().resource::<ServerTime>().is_synced());
+
+    for _ in 0..20 {
+        client_app.update();
+        server_app.update();
+        let mut client = client_app.world_mut().remove_resource::<RenetClient>().unwrap();
+        server_app
+            .world_mut()
+            .resource_mut::<RenetServer>()
+            .process_local_client(0, &mut client)
+            .unwrap();
+        client_app.insert_resource(client);
+        std::thread::sleep(Duration::from_millis(5));
+    }
+
+    let client_time = client_app.world().resource::<ServerTime>();
+    let server_time = server_app.world().resource::<ServerTime>();
+    assert!(client_time.is_synced());
+
+    let now = Instant::now();
+    let client_estimate = client_time.to_server_time(now).unwrap();
+    let server_now = server_time.to_server_time(now).unwrap();
+    let error = client_estimate.abs_diff(server_now);
+    assert!(server_now >= Duration::from_millis(50));
+    assert!(error <= client_time.uncertainty() + Duration::from_millis(5), "error: {error:?}");
+}
+
+#[test]
+fn server_time_resets_on_disconnect() {
+    let mut server = RenetServer::new(connection_config());
+    let mut client = server.new_local_client(0);
+    client.disconnect();
+
+    let mut client_app = new_app(RenetClientPlugin::default());
+    client_app.insert_resource(client);
+    client_app.update();
+    assert!(!client_app.world().resource::<ServerTime>().is_synced());
+    assert!(client_app.world().resource::<ServerTime>().now().is_none());
+}
@@ -0,0 +1,36 @@
+use bevy_app::prelude::*;
+use bevy_renet::renet::{ConnectionConfig, RenetServer};
+use bevy_renet::steam::{steamworks, SteamServerBuilder, SteamServerPlugin, SteamServerTransport};
+use bevy_renet::RenetServerPlugin;
+use bevy_time::TimePlugin;
+
+// The transport must be closed in the exit frame, before the world drops the steamworks client.
+// The exit ordering is covered without steam in transport_exit.rs
+#[test]
+#[ignore = "requires a running steam client"]
+fn removes_transport_on_exit() {
+    let (steam_client, single) = steamworks::Client::init_app(480).unwrap();
+    let transport = SteamServerBuilder::new(&steam_client).p2p().build().unwrap();
+
+    let mut app = App::new();
+    app.add_plugins((
+        TimePlugin,
+        RenetServerPlugin::default().keep_connections_on_exit(),
+        SteamServerPlugin,
+    ));
+    app.finish();
+    app.cleanup();
+    app.insert_non_send_resource(single);
+    app.insert_resource(RenetServer::new(ConnectionConfig::default()));
+    app.insert_resource(transport);
+    // The steamworks client is dropped with the app
+    drop(steam_client);
+
+    app.update();
+    assert!(app.world().contains_resource::<SteamServerTransport>());
+
+    app.world_mut().send_event(AppExit::Success);
+    app.update();
+    assert!(!app.world().contains_resource::<SteamServerTransport>());
+    assert!(app.world().contains_resource::<RenetServer>());
+}
@@ -0,0 +1,84 @@
+use std::time::{Duration, Instant};
+
+use bevy_app::prelude::*;
+use bevy_ecs::prelude::*;
+use bevy_renet::{
+    tick_rate::{InvalidTickRate, RenetTickRatePlugin, TickRate},
+    RenetServerPlugin,
+};
+use bevy_time::{prelude::*, Fixed, TimePlugin};
+
+const TICK: Duration = Duration::from_millis(20);
+
+#[derive(Debug, Default, Resource)]
+struct FixedSteps(u32);
+
+fn count_fixed_steps(mut steps: ResMut<FixedSteps>) {
+    steps.0 += 1;
+}
+
+fn new_app(server_plugin: RenetServerPlugin) -> App {
+    let mut app = App::new();
+    app.add_plugins((TimePlugin, server_plugin, RenetTickRatePlugin::from_duration(TICK)));
+    app.init_resource::<FixedSteps>();
+    app.add_systems(FixedPostUpdate, count_fixed_steps);
+    app.finish();
+    app.cleanup();
+    app
+}
+
+#[test]
+fn caps_tick_rate() {
+    let mut app = new_app(RenetServerPlugin::default());
+
+    let start = Instant::now();
+    for _ in 0..6 {
+        app.update();
+    }
+    // The first tick doesn't sleep
+    assert!(start.elapsed() >= 5 * TICK);
+
+    // Renet is updated with the real elapsed time, that includes the sleeps before the last 4 ticks
+    assert!(app.world().resource::<Time>().elapsed() >= 4 * TICK);
+}
+
+#[test]
+fn long_tick_is_not_caught_up() {
+    let mut app = new_app(RenetServerPlugin::default());
+    app.update();
+    std::thread::sleep(5 * TICK);
+
+    // The next tick is scheduled from now instead of running the missed ticks without sleeping
+    let before = Instant::now();
+    app.update();
+    let after = Instant::now();
+    let next_tick = app.world().resource::<TickRate>().next_tick().unwrap();
+    assert!(next_tick >= before + TICK);
+    assert!(next_tick <= after + TICK);
+}
+
+#[test]
+fn fixed_update_runs_one_step_per_tick() {
+    let mut app = new_app(RenetServerPlugin::default().fixed_update());
+    assert_eq!(app.world().resource::<Time<Fixed>>().timestep(), TICK);
+
+    for _ in 0..5 {
+        app.update();
+    }
+    std::thread::sleep(5 * TICK);
+    let steps = app.world().resource::<FixedSteps>().0;
+    app.update();
+
+    // A long tick doesn't catch up with a burst of fixed steps
+    assert!(app.world().resource::<FixedSteps>().0 - steps <= 1);
+}
+
+#[test]
+fn invalid_tick_rate() {
+    assert!(RenetTickRatePlugin::from_hz(60.0).is_ok());
+    assert_eq!(RenetTickRatePlugin::from_hz(0.0).unwrap_err(), InvalidTickRate(0.0));
+    assert!(RenetTickRatePlugin::from_hz(-1.0).is_err());
+    assert!(RenetTickRatePlugin::from_hz(f64::NAN).is_err());
+    assert!(RenetTickRatePlugin::from_hz(f64::INFINITY).is_err());
+    assert!(RenetTickRatePlugin::from_hz(f64::MIN_POSITIVE).is_err());
+}
@@ -0,0 +1,79 @@
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
+
+use bevy_app::prelude::*;
+use bevy_ecs::prelude::*;
+use bevy_renet::renet::{ConnectionConfig, GracefulShutdown, RenetServer};
+use bevy_renet::{remove_transport_on_exit, server_exiting, shutdown_transport_on_exit, RenetServerPlugin};
+use bevy_time::TimePlugin;
+
+// Stands in for a transport that must be closed before another resource is dropped, like the steam transports
+#[derive(Resource)]
+struct MockTransport {
+    shutdowns: Arc<AtomicUsize>,
+}
+
+impl GracefulShutdown for MockTransport {
+    fn begin_shutdown(&mut self, _server: &mut RenetServer) {
+        self.shutdowns.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn is_shutdown_complete(&self) -> bool {
+        true
+    }
+}
+
+fn new_app(server_plugin: RenetServerPlugin, shutdowns: Arc<AtomicUsize>) -> App {
+    let mut app = App::new();
+    app.add_plugins((TimePlugin, server_plugin));
+    app.add_systems(
+        Last,
+        (
+            shutdown_transport_on_exit::<MockTransport>
+                .run_if(server_exiting)
+                .run_if(resource_exists::<MockTransport>)
+                .run_if(resource_exists::<RenetServer>),
+            remove_transport_on_exit::<MockTransport>
+                .run_if(on_event::<AppExit>)
+                .run_if(resource_exists::<MockTransport>),
+        )
+            .chain(),
+    );
+    app.finish();
+    app.cleanup();
+    app.insert_resource(RenetServer::new(ConnectionConfig::default()));
+    app.insert_resource(MockTransport { shutdowns });
+    app
+}
+
+#[test]
+fn shuts_down_and_removes_transport_on_exit() {
+    let shutdowns = Arc::new(AtomicUsize::new(0));
+    let mut app = new_app(RenetServerPlugin::default(), shutdowns.clone());
+
+    app.update();
+    assert!(app.world().contains_resource::<MockTransport>());
+    assert_eq!(shutdowns.load(Ordering::Relaxed), 0);
+
+    app.world_mut().send_event(AppExit::Success);
+    app.update();
+    // Shut down before being removed, in the exit frame
+    assert_eq!(shutdowns.load(Ordering::Relaxed), 1);
+    assert!(!app.world().contains_resource::<MockTransport>());
+    assert!(app.world().contains_resource::<RenetServer>());
+}
+
+#[test]
+fn removes_transport_on_exit_keeping_connections() {
+    let shutdowns = Arc::new(AtomicUsize::new(0));
+    let mut app = new_app(RenetServerPlugin::default().keep_connections_on_exit(), shutdowns.clone());
+
+    app.update();
+    app.world_mut().send_event(AppExit::Success);
+    app.update();
+    assert_eq!(shutdowns.load(Ordering::Relaxed), 0);
+    assert!(!app.world().contains_resource::<MockTransport>());
+    assert!(app.world().contains_resource::<RenetServer>());
+}
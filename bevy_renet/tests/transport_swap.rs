@@ -0,0 +1,97 @@
+use std::{
+    net::UdpSocket,
+    time::{Duration, SystemTime},
+};
+
+use bevy_app::prelude::*;
+use bevy_renet::netcode::{ClientAuthentication, NetcodeClientPlugin, NetcodeClientTransport, NetcodeDisconnectReason};
+use bevy_renet::renet::{ConnectionConfig, DefaultChannel, DisconnectReason, RenetClient, RenetServer};
+use bevy_renet::{LastDisconnect, RenetClientPlugin};
+use bevy_time::TimePlugin;
+
+fn netcode_transport() -> NetcodeClientTransport {
+    // Nothing listens on the server address, the client stays connecting
+    let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+    let authentication = ClientAuthentication::Unsecure {
+        protocol_id: 7,
+        client_id: 0,
+        server_addr: "127.0.0.1:1".parse().unwrap(),
+        user_data: None,
+    };
+    let current_time = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH).unwrap();
+    NetcodeClientTransport::new(current_time, authentication, socket).unwrap()
+}
+
+fn new_app() -> App {
+    let mut app = App::new();
+    app.add_plugins((TimePlugin, RenetClientPlugin::default(), NetcodeClientPlugin));
+    app.finish();
+    app.cleanup();
+    app
+}
+
+// Exchanges the packets of the client with the server in memory
+fn update_loopback(app: &mut App, server: &mut RenetServer) {
+    let mut client = app.world_mut().resource_mut::<RenetClient>();
+    server.update(Duration::from_millis(16));
+    server.process_local_client(0, &mut client).unwrap();
+}
+
+#[test]
+fn removing_transport_disconnects_client() {
+    let mut app = new_app();
+    app.insert_resource(RenetClient::new(ConnectionConfig::default()));
+    app.insert_resource(netcode_transport());
+    app.update();
+    assert!(app.world().resource::<RenetClient>().is_connecting());
+
+    app.world_mut().remove_resource::<NetcodeClientTransport>();
+    app.update();
+    app.update();
+    assert!(app.world().resource::<RenetClient>().is_disconnected());
+    assert_eq!(app.world().resource::<LastDisconnect>().reason, DisconnectReason::Transport);
+    assert_eq!(app.world().resource::<LastDisconnect>().transport_reason, None);
+}
+
+#[test]
+fn records_transport_disconnect_reason() {
+    let mut app = new_app();
+    app.insert_resource(RenetClient::new(ConnectionConfig::default()));
+    app.insert_resource(netcode_transport());
+    app.update();
+
+    app.world_mut().resource_mut::<NetcodeClientTransport>().disconnect();
+    app.update();
+    let last_disconnect = app.world().resource::<LastDisconnect>();
+    assert_eq!(last_disconnect.reason, DisconnectReason::Transport);
+    assert_eq!(
+        last_disconnect.transport_reason.as_deref(),
+        Some(NetcodeDisconnectReason::DisconnectedByClient.to_string().as_str())
+    );
+}
+
+#[test]
+fn swap_netcode_to_loopback_transport() {
+    let mut app = new_app();
+    app.insert_resource(RenetClient::new(ConnectionConfig::default()));
+    app.insert_resource(netcode_transport());
+    app.update();
+
+    // Swap in the same frame: the new client is kept
+    let mut server = RenetServer::new(ConnectionConfig::default());
+    app.world_mut().remove_resource::<NetcodeClientTransport>();
+    app.world_mut().remove_resource::<RenetClient>();
+    app.insert_resource(server.new_local_client(0));
+    for _ in 0..3 {
+        app.update();
+        update_loopback(&mut app, &mut server);
+    }
+
+    assert!(app.world().resource::<RenetClient>().is_connected());
+    assert!(server.is_connected(0));
+    app.world_mut()
+        .resource_mut::<RenetClient>()
+        .send_message(DefaultChannel::ReliableOrdered, "after swap");
+    update_loopback(&mut app, &mut server);
+    assert_eq!(server.receive_message(0, DefaultChannel::ReliableOrdered).unwrap(), "after swap");
+}
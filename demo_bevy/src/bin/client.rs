@@ -7,14 +7,15 @@ use bevy::{
     prelude::*,
 };
 use bevy_egui::{EguiContexts, EguiPlugin};
+#[cfg(any(feature = "netcode", feature = "steam"))]
+use bevy_renet::client_connected;
 use bevy_renet::{
-    client_connected,
     renet::{ClientId, RenetClient},
     RenetClientPlugin,
 };
-use demo_bevy::{
-    connection_config, setup_level, ClientChannel, NetworkedEntities, PlayerCommand, PlayerInput, ServerChannel, ServerMessages,
-};
+#[cfg(any(feature = "netcode", feature = "steam"))]
+use demo_bevy::connection_config;
+use demo_bevy::{setup_level, ClientChannel, NetworkedEntities, PlayerCommand, PlayerInput, ServerChannel, ServerMessages};
 use renet_visualizer::{RenetClientVisualizer, RenetVisualizerStyle};
 
 #[derive(Component)]
@@ -69,11 +70,13 @@ fn add_netcode_network(app: &mut App) {
     app.insert_resource(transport);
     app.insert_resource(CurrentClientId(client_id));
 
-    // If any error is found we just panic
-    #[allow(clippy::never_loop)]
+    // If an unrecoverable error is found we just panic
     fn panic_on_error_system(mut renet_error: EventReader<NetcodeTransportError>) {
         for e in renet_error.read() {
-            panic!("{}", e);
+            if !e.is_recoverable() {
+                panic!("{}", e);
+            }
+            eprintln!("{}", e);
         }
     }
 
@@ -83,7 +86,7 @@ fn add_netcode_network(app: &mut App) {
 #[cfg(feature = "steam")]
 fn add_steam_network(app: &mut App) {
     use bevy_renet::steam::{SteamClientPlugin, SteamClientTransport, SteamTransportError};
-    use steamworks::{SingleClient, SteamId};
+    use steamworks::SteamId;
 
     let (steam_client, single) = steamworks::Client::init_app(480).unwrap();
 
@@ -113,12 +116,8 @@ fn add_steam_network(app: &mut App) {
 
     app.configure_sets(Update, Connected.run_if(client_connected));
 
+    // The plugin runs the steam callbacks
     app.insert_non_send_resource(single);
-    fn steam_callbacks(client: NonSend<SingleClient>) {
-        client.run_callbacks();
-    }
-
-    app.add_systems(PreUpdate, steam_callbacks);
 
     // If any error is found we just panic
     #[allow(clippy::never_loop)]
@@ -134,7 +133,7 @@ fn add_steam_network(app: &mut App) {
 fn main() {
     let mut app = App::new();
     app.add_plugins(DefaultPlugins);
-    app.add_plugins(RenetClientPlugin);
+    app.add_plugins(RenetClientPlugin::default());
     app.add_plugins(FrameTimeDiagnosticsPlugin::default());
     app.add_plugins(LogDiagnosticsPlugin::default());
     app.add_plugins(EguiPlugin {
@@ -60,7 +60,6 @@ fn add_netcode_network(app: &mut App) {
 fn add_steam_network(app: &mut App) {
     use bevy_renet::steam::{AccessPermission, SteamServerConfig, SteamServerPlugin, SteamServerSocketOptions, SteamServerTransport};
     use demo_bevy::connection_config;
-    use steamworks::SingleClient;
 
     let (steam_client, single) = steamworks::Client::init_app(480).unwrap();
 
@@ -80,21 +79,16 @@ fn add_steam_network(app: &mut App) {
 
     app.add_plugins(SteamServerPlugin);
     app.insert_resource(server);
-    app.insert_non_send_resource(transport);
+    app.insert_resource(transport);
+    // The plugin runs the steam callbacks
     app.insert_non_send_resource(single);
-
-    fn steam_callbacks(client: NonSend<SingleClient>) {
-        client.run_callbacks();
-    }
-
-    app.add_systems(PreUpdate, steam_callbacks);
 }
 
 fn main() {
     let mut app = App::new();
     app.add_plugins(DefaultPlugins);
 
-    app.add_plugins(RenetServerPlugin);
+    app.add_plugins(RenetServerPlugin::default());
     app.add_plugins(FrameTimeDiagnosticsPlugin::default());
     app.add_plugins(LogDiagnosticsPlugin::default());
     app.add_plugins(EguiPlugin {
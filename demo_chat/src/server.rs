@@ -97,7 +97,9 @@ impl ChatServer {
             }
         }
 
-        self.transport.send_packets(&mut self.server);
+        if let Err(e) = self.transport.send_packets(&mut self.server) {
+            log::error!("Failed to send packets: {e}");
+        }
 
         Ok(())
     }
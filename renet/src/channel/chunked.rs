@@ -0,0 +1,345 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    time::Instant,
+};
+
+use bytes::{Bytes, BytesMut};
+
+use crate::{error::ChannelError, packet::SLICE_SIZE};
+
+/// Size in bytes of the largest message that can be sent over a [`SendType::ReliableChunked`](crate::SendType::ReliableChunked) channel.
+pub const MAX_CHUNKED_MESSAGE_SIZE: usize = 64 * 1024 * 1024;
+
+// Message id (u64), fragment index (u32) and fragment count (u32)
+pub(crate) const CHUNK_HEADER_SIZE: usize = 16;
+
+// Each fragment must fit in the memory of the reliable channel receiving it, otherwise the receiver disconnects.
+// Fragments bigger than a slice are sliced, and the receiver reserves the memory of all their slices.
+pub(crate) fn valid_chunk_config(chunk_size: usize, max_message_size: usize, max_memory_usage_bytes: usize) -> bool {
+    let fragment_size = chunk_size + CHUNK_HEADER_SIZE;
+    let reserved_bytes = match fragment_size > SLICE_SIZE {
+        true => fragment_size.div_ceil(SLICE_SIZE) * SLICE_SIZE,
+        false => fragment_size,
+    };
+    chunk_size > 0 && reserved_bytes <= max_memory_usage_bytes && max_message_size <= MAX_CHUNKED_MESSAGE_SIZE
+}
+
+#[derive(Debug)]
+struct PendingMessage {
+    message_id: u64,
+    message: Bytes,
+    // Chunk size when the message was queued, a config change only applies to the next messages
+    chunk_size: usize,
+    next_fragment: u32,
+    num_fragments: u32,
+}
+
+// Splits the messages of a chunked channel in fragments, sent as individual reliable messages
+// when the channel has memory available for them.
+#[derive(Debug)]
+pub(crate) struct ChunkSender {
+    chunk_size: usize,
+    max_message_size: usize,
+    next_message_id: u64,
+    pending: VecDeque<PendingMessage>,
+    // Bytes of the fragments not queued in the reliable channel yet
+    pending_bytes: usize,
+    // Reliable message id of the last fragment of each message, to notify its ack
+    last_fragments: HashMap<u64, u64>,
+}
+
+impl ChunkSender {
+    pub fn new(chunk_size: usize, max_message_size: usize) -> Self {
+        assert!(chunk_size > 0, "chunk size of a chunked channel must not be zero");
+        Self {
+            chunk_size,
+            max_message_size,
+            next_message_id: 0,
+            pending: VecDeque::new(),
+            pending_bytes: 0,
+            last_fragments: HashMap::new(),
+        }
+    }
+
+    pub fn set_config(&mut self, chunk_size: usize, max_message_size: usize) {
+        assert!(chunk_size > 0, "chunk size of a chunked channel must not be zero");
+        self.chunk_size = chunk_size;
+        self.max_message_size = max_message_size;
+    }
+
+    pub fn max_message_size(&self) -> usize {
+        self.max_message_size
+    }
+
+    pub fn next_message_id(&self) -> u64 {
+        self.next_message_id
+    }
+
+    pub fn pending_bytes(&self) -> usize {
+        self.pending_bytes
+    }
+
+    pub fn pending_message_count(&self) -> usize {
+        self.pending.len()
+    }
+
+    pub fn available_memory(&self) -> usize {
+        self.max_message_size.saturating_sub(self.pending_bytes)
+    }
+
+    pub fn can_push(&self, size_bytes: usize) -> bool {
+        size_bytes <= self.available_memory()
+    }
+
+    // Returns the id of the message, notified when all its fragments are acked
+    pub fn push(&mut self, message: Bytes) -> Result<u64, ChannelError> {
+        if !self.can_push(message.len()) {
+            return Err(ChannelError::ReliableChannelMaxMemoryReached);
+        }
+
+        let message_id = self.next_message_id;
+        self.next_message_id += 1;
+        self.pending_bytes += message.len();
+        // Empty messages are still sent as a single fragment
+        let num_fragments = message.len().div_ceil(self.chunk_size).max(1) as u32;
+        self.pending.push_back(PendingMessage {
+            message_id,
+            message,
+            chunk_size: self.chunk_size,
+            next_fragment: 0,
+            num_fragments,
+        });
+
+        Ok(message_id)
+    }
+
+    // Size of the next fragment with its header
+    pub fn next_fragment_size(&self) -> Option<usize> {
+        let pending = self.pending.front()?;
+        let start = pending.next_fragment as usize * pending.chunk_size;
+        let end = (start + pending.chunk_size).min(pending.message.len());
+        Some(end - start + CHUNK_HEADER_SIZE)
+    }
+
+    // Returns the next fragment to queue with the reliable message id it will have
+    pub fn pop_fragment(&mut self, reliable_message_id: u64) -> Option<Bytes> {
+        let pending = self.pending.front_mut()?;
+        let start = pending.next_fragment as usize * pending.chunk_size;
+        let end = (start + pending.chunk_size).min(pending.message.len());
+
+        let mut fragment = BytesMut::with_capacity(end - start + CHUNK_HEADER_SIZE);
+        fragment.extend_from_slice(&pending.message_id.to_le_bytes());
+        fragment.extend_from_slice(&pending.next_fragment.to_le_bytes());
+        fragment.extend_from_slice(&pending.num_fragments.to_le_bytes());
+        fragment.extend_from_slice(&pending.message[start..end]);
+
+        pending.next_fragment += 1;
+        self.pending_bytes -= end - start;
+        if pending.next_fragment == pending.num_fragments {
+            self.last_fragments.insert(reliable_message_id, pending.message_id);
+            self.pending.pop_front();
+        }
+
+        Some(fragment.freeze())
+    }
+
+    // Returns the id of the message when the last fragment is acked
+    pub fn fragment_acked(&mut self, reliable_message_id: u64) -> Option<u64> {
+        self.last_fragments.remove(&reliable_message_id)
+    }
+}
+
+#[derive(Debug)]
+struct PartialMessage {
+    message_id: u64,
+    next_fragment: u32,
+    num_fragments: u32,
+    buffer: BytesMut,
+}
+
+// Reassembles the fragments received in order from a chunked channel
+#[derive(Debug)]
+pub(crate) struct ChunkAssembler {
+    partial: Option<PartialMessage>,
+    messages: VecDeque<(Bytes, Option<Instant>)>,
+    // Bytes of the partial and assembled messages, up to the max message size of the channel
+    memory_usage_bytes: usize,
+    max_memory_usage_bytes: usize,
+}
+
+impl ChunkAssembler {
+    pub fn new(max_message_size: usize) -> Self {
+        Self {
+            partial: None,
+            messages: VecDeque::new(),
+            memory_usage_bytes: 0,
+            max_memory_usage_bytes: max_message_size,
+        }
+    }
+
+    pub fn process_fragment(&mut self, fragment: Bytes, arrived_at: Option<Instant>) -> Result<(), ChannelError> {
+        if fragment.len() < CHUNK_HEADER_SIZE {
+            return Err(ChannelError::InvalidChunk);
+        }
+        let message_id = u64::from_le_bytes(fragment[0..8].try_into().unwrap());
+        let fragment_index = u32::from_le_bytes(fragment[8..12].try_into().unwrap());
+        let num_fragments = u32::from_le_bytes(fragment[12..16].try_into().unwrap());
+        let payload = &fragment[CHUNK_HEADER_SIZE..];
+
+        let partial = match &mut self.partial {
+            Some(partial) => {
+                if partial.message_id != message_id || partial.next_fragment != fragment_index || partial.num_fragments != num_fragments {
+                    return Err(ChannelError::InvalidChunk);
+                }
+                partial
+            }
+            None => {
+                if fragment_index != 0 || num_fragments == 0 {
+                    return Err(ChannelError::InvalidChunk);
+                }
+                self.partial.insert(PartialMessage {
+                    message_id,
+                    next_fragment: 0,
+                    num_fragments,
+                    buffer: BytesMut::new(),
+                })
+            }
+        };
+
+        if self.memory_usage_bytes + payload.len() > self.max_memory_usage_bytes {
+            return Err(ChannelError::ReliableChannelMaxMemoryReached);
+        }
+        self.memory_usage_bytes += payload.len();
+        partial.buffer.extend_from_slice(payload);
+        partial.next_fragment += 1;
+
+        if partial.next_fragment == partial.num_fragments {
+            let partial = self.partial.take().unwrap();
+            self.messages.push_back((partial.buffer.freeze(), arrived_at));
+        }
+
+        Ok(())
+    }
+
+    pub fn receive_message_with_timestamp(&mut self) -> Option<(Bytes, Option<Instant>)> {
+        let (message, arrived_at) = self.messages.pop_front()?;
+        self.memory_usage_bytes -= message.len();
+        Some((message, arrived_at))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fragment_and_reassemble() {
+        let mut sender = ChunkSender::new(4, MAX_CHUNKED_MESSAGE_SIZE);
+        let message = Bytes::from_static(&[1, 2, 3, 4, 5, 6, 7, 8, 9, 10]);
+        assert_eq!(sender.push(message.clone()), Ok(0));
+        assert_eq!(sender.push(Bytes::new()), Ok(1));
+        assert_eq!(sender.pending_bytes(), 10);
+
+        let mut assembler = ChunkAssembler::new(MAX_CHUNKED_MESSAGE_SIZE);
+        let mut reliable_message_id = 0;
+        while let Some(size) = sender.next_fragment_size() {
+            let fragment = sender.pop_fragment(reliable_message_id).unwrap();
+            assert_eq!(fragment.len(), size);
+            assembler.process_fragment(fragment, None).unwrap();
+            reliable_message_id += 1;
+        }
+        // 3 fragments for the first message and 1 for the empty one
+        assert_eq!(reliable_message_id, 4);
+        assert_eq!(sender.pending_bytes(), 0);
+
+        assert_eq!(assembler.receive_message_with_timestamp(), Some((message, None)));
+        assert_eq!(assembler.receive_message_with_timestamp(), Some((Bytes::new(), None)));
+        assert_eq!(assembler.receive_message_with_timestamp(), None);
+
+        assert_eq!(sender.fragment_acked(0), None);
+        assert_eq!(sender.fragment_acked(2), Some(0));
+        assert_eq!(sender.fragment_acked(3), Some(1));
+    }
+
+    #[test]
+    fn invalid_fragments() {
+        let mut sender = ChunkSender::new(2, MAX_CHUNKED_MESSAGE_SIZE);
+        sender.push(Bytes::from_static(&[1, 2, 3, 4])).unwrap();
+        let first = sender.pop_fragment(0).unwrap();
+        let second = sender.pop_fragment(1).unwrap();
+
+        let mut assembler = ChunkAssembler::new(MAX_CHUNKED_MESSAGE_SIZE);
+        assert_eq!(assembler.process_fragment(second.clone(), None), Err(ChannelError::InvalidChunk));
+
+        let mut assembler = ChunkAssembler::new(MAX_CHUNKED_MESSAGE_SIZE);
+        assembler.process_fragment(first.clone(), None).unwrap();
+        assert_eq!(assembler.process_fragment(first, None), Err(ChannelError::InvalidChunk));
+        assert_eq!(assembler.process_fragment(second.slice(..4), None), Err(ChannelError::InvalidChunk));
+
+        assert_eq!(
+            sender.push(vec![0; MAX_CHUNKED_MESSAGE_SIZE + 1].into()),
+            Err(ChannelError::ReliableChannelMaxMemoryReached)
+        );
+    }
+    #[test]
+    fn assembler_memory_limit() {
+        let mut sender = ChunkSender::new(4, 10);
+        assert!(!sender.can_push(11));
+        sender.push(Bytes::from_static(&[1; 8])).unwrap();
+        sender.push(Bytes::from_static(&[2; 2])).unwrap();
+        assert_eq!(sender.available_memory(), 0);
+
+        // The assembler holds the messages not received yet, up to the max message size
+        let mut assembler = ChunkAssembler::new(9);
+        for reliable_message_id in 0..2 {
+            let fragment = sender.pop_fragment(reliable_message_id).unwrap();
+            assembler.process_fragment(fragment, None).unwrap();
+        }
+        let fragment = sender.pop_fragment(2).unwrap();
+        assert_eq!(
+            assembler.process_fragment(fragment, None),
+            Err(ChannelError::ReliableChannelMaxMemoryReached)
+        );
+    }
+
+    #[test]
+    fn config_change_mid_message() {
+        let mut sender = ChunkSender::new(100, 10_000);
+        let message = Bytes::from((0..5000).map(|i| i as u8).collect::<Vec<u8>>());
+        sender.push(message.clone()).unwrap();
+        let mut assembler = ChunkAssembler::new(10_000);
+        let mut reliable_message_id = 0;
+        for _ in 0..3 {
+            assembler
+                .process_fragment(sender.pop_fragment(reliable_message_id).unwrap(), None)
+                .unwrap();
+            reliable_message_id += 1;
+        }
+
+        // The partly sent message keeps its chunk size, the next one uses the new size
+        sender.set_config(1000, 10_000);
+        sender.push(Bytes::from(vec![7; 2000])).unwrap();
+        while let Some(size) = sender.next_fragment_size() {
+            let fragment = sender.pop_fragment(reliable_message_id).unwrap();
+            assert_eq!(fragment.len(), size);
+            assembler.process_fragment(fragment, None).unwrap();
+            reliable_message_id += 1;
+        }
+        // 50 fragments for the first message and 2 for the second
+        assert_eq!(reliable_message_id, 52);
+        assert_eq!(sender.pending_bytes(), 0);
+        assert_eq!(assembler.receive_message_with_timestamp(), Some((message, None)));
+        assert_eq!(assembler.receive_message_with_timestamp(), Some((Bytes::from(vec![7; 2000]), None)));
+    }
+
+    #[test]
+    fn chunk_config() {
+        assert!(valid_chunk_config(100, 1000, 116));
+        assert!(!valid_chunk_config(100, 1000, 115));
+        assert!(!valid_chunk_config(0, 1000, 1000));
+        assert!(!valid_chunk_config(100, MAX_CHUNKED_MESSAGE_SIZE + 1, 1000));
+        // Sliced fragments reserve whole slices in the receiver
+        assert!(!valid_chunk_config(SLICE_SIZE, 10_000, SLICE_SIZE + CHUNK_HEADER_SIZE));
+        assert!(valid_chunk_config(SLICE_SIZE, 10_000, 2 * SLICE_SIZE));
+    }
+}
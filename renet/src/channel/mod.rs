@@ -1,9 +1,12 @@
+pub(crate) mod chunked;
 pub(crate) mod reliable;
 pub(crate) mod slice_constructor;
 pub(crate) mod unreliable;
 
 use std::time::Duration;
 
+pub use chunked::MAX_CHUNKED_MESSAGE_SIZE;
+pub use reliable::AckCallback;
 pub(crate) use slice_constructor::SliceConstructor;
 
 /// Delivery guarantee of a channel
@@ -19,8 +22,59 @@ pub enum SendType {
     ReliableUnordered {
         resend_time: Duration,
     },
+    /// Messages are guaranteed to be received and in the same order they were sent, like `ReliableOrdered`.
+    /// Messages are split in fragments of `chunk_size` bytes, queued as reliable messages when the channel has memory for them,
+    /// so messages up to `max_message_size` bytes can be sent regardless of `max_memory_usage_bytes`.
+    /// A message is received only when all its fragments arrived.
+    ///
+    /// A fragment is `chunk_size` plus 16 bytes of header and must fit in `max_memory_usage_bytes`, fragments bigger than
+    /// a packet slice take whole slices in the receiver. `max_message_size` is at most [`MAX_CHUNKED_MESSAGE_SIZE`], and also
+    /// bounds the bytes buffered by the sender and the receiver. See [`ChannelConfig::is_valid`].
+    ReliableChunked {
+        resend_time: Duration,
+        chunk_size: usize,
+        max_message_size: usize,
+    },
+}
+
+/// Delivery guarantee of a channel without its configuration, used for introspection.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ChannelType {
+    Unreliable,
+    ReliableOrdered,
+    ReliableUnordered,
+    ReliableChunked,
+}
+
+impl From<&SendType> for ChannelType {
+    fn from(send_type: &SendType) -> Self {
+        match send_type {
+            SendType::Unreliable => ChannelType::Unreliable,
+            SendType::ReliableOrdered { .. } => ChannelType::ReliableOrdered,
+            SendType::ReliableUnordered { .. } => ChannelType::ReliableUnordered,
+            SendType::ReliableChunked { .. } => ChannelType::ReliableChunked,
+        }
+    }
 }
 
+/// Direction of a channel in [`ConnectionConfig::from_channels`], from the point of view of the server.
+///
+/// [`ConnectionConfig::from_channels`]: crate::ConnectionConfig::from_channels
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Direction {
+    /// The server sends messages to the clients.
+    Send,
+    /// The clients send messages to the server.
+    Receive,
+    /// Both the server and clients send messages, with the same configuration.
+    Bidirectional,
+}
+
+/// Definition of a channel for [`ConnectionConfig::from_channels`], the channel id replaces the one in the [`ChannelConfig`].
+///
+/// [`ConnectionConfig::from_channels`]: crate::ConnectionConfig::from_channels
+pub type ChannelDef = (u8, ChannelConfig, Direction);
+
 /// Configuration of a channel for a server or client
 /// Channels are unilateral and message based.
 #[derive(Debug, Clone)]
@@ -36,6 +90,21 @@ pub struct ChannelConfig {
     pub send_type: SendType,
 }
 
+impl ChannelConfig {
+    /// Returns false if the chunk size or max message size of a [`SendType::ReliableChunked`] channel is out of bounds,
+    /// connections panic when created with an invalid channel config.
+    pub fn is_valid(&self) -> bool {
+        match self.send_type {
+            SendType::ReliableChunked {
+                chunk_size,
+                max_message_size,
+                ..
+            } => chunked::valid_chunk_config(chunk_size, max_message_size, self.max_memory_usage_bytes),
+            _ => true,
+        }
+    }
+}
+
 /// Utility enumerator when using the default channels configuration.
 /// The default configuration has 3 channels: unreliable, reliable ordered, and reliable unordered.
 pub enum DefaultChannel {
@@ -1,11 +1,14 @@
 use std::{
     collections::{btree_map, BTreeMap, BTreeSet, HashMap},
-    time::Duration,
+    time::{Duration, Instant},
 };
 
 use bytes::Bytes;
 
-use super::SliceConstructor;
+use super::{
+    chunked::{ChunkAssembler, ChunkSender},
+    SliceConstructor,
+};
 use crate::{
     error::ChannelError,
     packet::{Packet, Slice, SLICE_SIZE},
@@ -27,6 +30,18 @@ enum UnackedMessage {
     },
 }
 
+/// Called once when the message it was sent with is acked, see [`RenetClient::send_message_with_ack_callback`](crate::RenetClient::send_message_with_ack_callback).
+pub type AckCallback = Box<dyn FnOnce() + Send + Sync>;
+
+#[derive(Default)]
+struct AckCallbacks(HashMap<u64, AckCallback>);
+
+impl std::fmt::Debug for AckCallbacks {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AckCallbacks").field("len", &self.0.len()).finish()
+    }
+}
+
 #[derive(Debug)]
 pub struct SendChannelReliable {
     channel_id: u8,
@@ -35,6 +50,11 @@ pub struct SendChannelReliable {
     resend_time: Duration,
     max_memory_usage_bytes: usize,
     memory_usage_bytes: usize,
+    // Ids of the acked messages, only tracked with ack notifications
+    acked_messages: Option<Vec<u64>>,
+    ack_callbacks: AckCallbacks,
+    // Only in chunked channels, the messages are split in fragments sent as reliable messages
+    chunks: Option<ChunkSender>,
 }
 
 #[derive(Debug)]
@@ -49,11 +69,13 @@ enum ReliableOrder {
 #[derive(Debug)]
 pub struct ReceiveChannelReliable {
     slices: HashMap<u64, SliceConstructor>,
-    messages: BTreeMap<u64, Bytes>,
+    messages: BTreeMap<u64, (Bytes, Option<Instant>)>,
     oldest_pending_message_id: u64,
     reliable_order: ReliableOrder,
     memory_usage_bytes: usize,
     max_memory_usage_bytes: usize,
+    // Only in chunked channels, the received messages are fragments of a message
+    chunks: Option<ChunkAssembler>,
 }
 
 impl UnackedMessage {
@@ -80,15 +102,121 @@ impl SendChannelReliable {
             resend_time,
             max_memory_usage_bytes,
             memory_usage_bytes: 0,
+            acked_messages: None,
+            ack_callbacks: AckCallbacks::default(),
+            chunks: None,
+        }
+    }
+
+    pub fn new_chunked(
+        channel_id: u8,
+        resend_time: Duration,
+        max_memory_usage_bytes: usize,
+        chunk_size: usize,
+        max_message_size: usize,
+    ) -> Self {
+        Self {
+            chunks: Some(ChunkSender::new(chunk_size, max_message_size)),
+            ..Self::new(channel_id, resend_time, max_memory_usage_bytes)
         }
     }
 
     pub fn available_memory(&self) -> usize {
-        self.max_memory_usage_bytes - self.memory_usage_bytes
+        match &self.chunks {
+            Some(chunks) => chunks.available_memory(),
+            None => self.max_memory_usage_bytes.saturating_sub(self.memory_usage_bytes),
+        }
+    }
+
+    pub fn max_message_size(&self) -> usize {
+        match &self.chunks {
+            Some(chunks) => chunks.max_message_size(),
+            None => self.max_memory_usage_bytes,
+        }
+    }
+
+    pub fn memory_usage_bytes(&self) -> usize {
+        self.memory_usage_bytes + self.chunks.as_ref().map_or(0, |chunks| chunks.pending_bytes())
+    }
+
+    // Chunked channels also buffer the fragments not queued yet
+    pub fn memory_limit_bytes(&self) -> usize {
+        match &self.chunks {
+            Some(chunks) => self.max_memory_usage_bytes + chunks.max_message_size(),
+            None => self.max_memory_usage_bytes,
+        }
+    }
+
+    // Bytes of the messages sent at least once that were not acked yet
+    pub fn bytes_in_flight(&self) -> usize {
+        self.unacked_messages
+            .values()
+            .map(|unacked_message| match unacked_message {
+                UnackedMessage::Small { message, last_sent } => last_sent.map_or(0, |_| message.len()),
+                UnackedMessage::Sliced {
+                    message,
+                    num_slices,
+                    acked,
+                    last_sent,
+                    ..
+                } => (0..*num_slices)
+                    .filter(|&i| !acked[i] && last_sent[i].is_some())
+                    .map(|i| (message.len() - i * SLICE_SIZE).min(SLICE_SIZE))
+                    .sum(),
+            })
+            .sum()
+    }
+
+    // The queued messages are kept, even above the new memory limit
+    pub fn set_config(&mut self, resend_time: Duration, max_memory_usage_bytes: usize) {
+        self.resend_time = resend_time;
+        self.max_memory_usage_bytes = max_memory_usage_bytes;
+    }
+
+    pub fn set_chunk_config(&mut self, chunk_size: usize, max_message_size: usize) {
+        if let Some(chunks) = &mut self.chunks {
+            chunks.set_config(chunk_size, max_message_size);
+        }
     }
 
     pub fn can_send_message(&self, size_bytes: usize) -> bool {
-        size_bytes + self.memory_usage_bytes <= self.max_memory_usage_bytes
+        match &self.chunks {
+            Some(chunks) => chunks.can_push(size_bytes),
+            None => size_bytes + self.memory_usage_bytes <= self.max_memory_usage_bytes,
+        }
+    }
+
+    // Chunked channels count the fragments and the messages not split yet
+    pub fn unacked_message_count(&self) -> usize {
+        self.unacked_messages.len() + self.chunks.as_ref().map_or(0, |chunks| chunks.pending_message_count())
+    }
+
+    pub fn next_message_id(&self) -> u64 {
+        match &self.chunks {
+            Some(chunks) => chunks.next_message_id(),
+            None => self.next_reliable_message_id,
+        }
+    }
+
+    // Id on the wire, chunked channels count the fragments
+    pub fn next_reliable_message_id(&self) -> u64 {
+        self.next_reliable_message_id
+    }
+
+    pub fn set_ack_notifications(&mut self, enabled: bool) {
+        match (enabled, &self.acked_messages) {
+            (true, None) => self.acked_messages = Some(Vec::new()),
+            (false, Some(_)) => self.acked_messages = None,
+            _ => {}
+        }
+    }
+
+    pub fn ack_notifications(&self) -> bool {
+        self.acked_messages.is_some()
+    }
+
+    pub fn drain_acked_messages(&mut self) -> impl Iterator<Item = u64> + '_ {
+        self.acked_messages.iter_mut().flat_map(|acked_messages| acked_messages.drain(..))
     }
 
     pub fn get_packets_to_send(&mut self, packet_sequence: &mut u64, available_bytes: &mut u64, current_time: Duration) -> Vec<Packet> {
@@ -202,6 +330,17 @@ impl SendChannelReliable {
     }
 
     pub fn send_message(&mut self, message: Bytes) -> Result<(), ChannelError> {
+        match &mut self.chunks {
+            Some(chunks) => {
+                chunks.push(message)?;
+                self.queue_fragments();
+                Ok(())
+            }
+            None => self.queue_message(message),
+        }
+    }
+
+    fn queue_message(&mut self, message: Bytes) -> Result<(), ChannelError> {
         if self.memory_usage_bytes + message.len() > self.max_memory_usage_bytes {
             return Err(ChannelError::ReliableChannelMaxMemoryReached);
         }
@@ -219,6 +358,57 @@ impl SendChannelReliable {
         Ok(())
     }
 
+    pub fn send_message_with_ack_callback(&mut self, message: Bytes, on_ack: AckCallback) -> Result<(), ChannelError> {
+        let message_id = self.next_message_id();
+        self.send_message(message)?;
+        self.ack_callbacks.0.insert(message_id, on_ack);
+
+        Ok(())
+    }
+
+    // Queues the fragments of the chunked messages while there is memory for them
+    fn queue_fragments(&mut self) {
+        let Some(chunks) = &mut self.chunks else {
+            return;
+        };
+        while let Some(fragment_size) = chunks.next_fragment_size() {
+            // The channel config guarantees a fragment fits in the channel memory,
+            // but it's still sent alone after the memory limit was lowered, otherwise the channel would be stuck
+            if !self.unacked_messages.is_empty() && self.memory_usage_bytes + fragment_size > self.max_memory_usage_bytes {
+                break;
+            }
+            let fragment = chunks.pop_fragment(self.next_reliable_message_id).unwrap();
+            self.memory_usage_bytes += fragment.len();
+            let unacked_message = if fragment.len() > SLICE_SIZE {
+                UnackedMessage::new_sliced(fragment)
+            } else {
+                UnackedMessage::Small {
+                    message: fragment,
+                    last_sent: None,
+                }
+            };
+            self.unacked_messages.insert(self.next_reliable_message_id, unacked_message);
+            self.next_reliable_message_id += 1;
+        }
+    }
+
+    fn on_message_acked(&mut self, mut message_id: u64) {
+        if self.chunks.is_some() {
+            // Acked fragments free memory for the next ones, the message is acked with its last fragment
+            self.queue_fragments();
+            match self.chunks.as_mut().and_then(|chunks| chunks.fragment_acked(message_id)) {
+                Some(chunked_message_id) => message_id = chunked_message_id,
+                None => return,
+            }
+        }
+        if let Some(acked_messages) = &mut self.acked_messages {
+            acked_messages.push(message_id);
+        }
+        if let Some(on_ack) = self.ack_callbacks.0.remove(&message_id) {
+            on_ack();
+        }
+    }
+
     pub fn process_message_ack(&mut self, message_id: u64) {
         if self.unacked_messages.contains_key(&message_id) {
             let unacked_message = self.unacked_messages.remove(&message_id).unwrap();
@@ -226,6 +416,7 @@ impl SendChannelReliable {
                 unreachable!("called ack on small message but found sliced");
             };
             self.memory_usage_bytes -= payload.len();
+            self.on_message_acked(message_id);
         }
     }
 
@@ -255,6 +446,7 @@ impl SendChannelReliable {
         if *num_acked_slices == *num_slices {
             self.memory_usage_bytes -= message.len();
             self.unacked_messages.remove(&message_id);
+            self.on_message_acked(message_id);
         }
     }
 }
@@ -275,10 +467,18 @@ impl ReceiveChannelReliable {
             reliable_order,
             memory_usage_bytes: 0,
             max_memory_usage_bytes,
+            chunks: None,
+        }
+    }
+
+    pub fn new_chunked(max_memory_usage_bytes: usize, max_message_size: usize) -> Self {
+        Self {
+            chunks: Some(ChunkAssembler::new(max_message_size)),
+            ..Self::new(max_memory_usage_bytes, true)
         }
     }
 
-    pub fn process_message(&mut self, message: Bytes, message_id: u64) -> Result<(), ChannelError> {
+    pub fn process_message(&mut self, message: Bytes, message_id: u64, arrived_at: Option<Instant>) -> Result<(), ChannelError> {
         if message_id < self.oldest_pending_message_id {
             // Discard old message already received
             return Ok(());
@@ -292,7 +492,7 @@ impl ReceiveChannelReliable {
                     }
                     self.memory_usage_bytes += message.len();
 
-                    entry.insert(message);
+                    entry.insert((message, arrived_at));
                 }
             }
             ReliableOrder::Unordered {
@@ -310,15 +510,22 @@ impl ReceiveChannelReliable {
                     self.memory_usage_bytes += message.len();
 
                     received_messages.insert(message_id);
-                    self.messages.insert(message_id, message);
+                    self.messages.insert(message_id, (message, arrived_at));
                 }
             }
         }
 
+        if self.chunks.is_some() {
+            // Fragments are reassembled as soon as they are in order, freeing the channel memory
+            while let Some((fragment, arrived_at)) = self.pop_message() {
+                self.chunks.as_mut().unwrap().process_fragment(fragment, arrived_at)?;
+            }
+        }
+
         Ok(())
     }
 
-    pub fn process_slice(&mut self, slice: Slice) -> Result<(), ChannelError> {
+    pub fn process_slice(&mut self, slice: Slice, arrived_at: Option<Instant>) -> Result<(), ChannelError> {
         if self.messages.contains_key(&slice.message_id) || slice.message_id < self.oldest_pending_message_id {
             // Message already assembled
             return Ok(());
@@ -340,24 +547,48 @@ impl ReceiveChannelReliable {
         if let Some(message) = slice_constructor.process_slice(slice.slice_index, &slice.payload)? {
             // Memory usage is re-added with the exactly message size
             self.memory_usage_bytes -= slice.num_slices * SLICE_SIZE;
-            self.process_message(message, slice.message_id)?;
+            self.process_message(message, slice.message_id, arrived_at)?;
             self.slices.remove(&slice.message_id);
         }
 
         Ok(())
     }
 
+    // Lowest message id not received yet, all the messages before it were received
+    pub fn expected_message_id(&self) -> u64 {
+        let received = |message_id: u64| match &self.reliable_order {
+            ReliableOrder::Ordered => self.messages.contains_key(&message_id),
+            ReliableOrder::Unordered { received_messages, .. } => received_messages.contains(&message_id),
+        };
+        let mut message_id = self.oldest_pending_message_id;
+        while received(message_id) {
+            message_id += 1;
+        }
+        message_id
+    }
+
     pub fn receive_message(&mut self) -> Option<Bytes> {
+        self.receive_message_with_timestamp().map(|(message, _)| message)
+    }
+
+    pub fn receive_message_with_timestamp(&mut self) -> Option<(Bytes, Option<Instant>)> {
+        match &mut self.chunks {
+            Some(chunks) => chunks.receive_message_with_timestamp(),
+            None => self.pop_message(),
+        }
+    }
+
+    fn pop_message(&mut self) -> Option<(Bytes, Option<Instant>)> {
         match &mut self.reliable_order {
             ReliableOrder::Ordered => {
-                let message = self.messages.remove(&self.oldest_pending_message_id)?;
+                let (message, arrived_at) = self.messages.remove(&self.oldest_pending_message_id)?;
 
                 self.oldest_pending_message_id += 1;
                 self.memory_usage_bytes -= message.len();
-                Some(message)
+                Some((message, arrived_at))
             }
             ReliableOrder::Unordered { received_messages, .. } => {
-                let (message_id, message) = self.messages.pop_first()?;
+                let (message_id, (message, arrived_at)) = self.messages.pop_first()?;
 
                 if self.oldest_pending_message_id == message_id {
                     // Remove all next items that could have been received out of order,
@@ -369,7 +600,7 @@ impl ReceiveChannelReliable {
                 }
 
                 self.memory_usage_bytes -= message.len();
-                Some(message)
+                Some((message, arrived_at))
             }
         }
     }
@@ -408,7 +639,7 @@ mod tests {
                 unreachable!();
             };
             for (message, message_id) in messages {
-                recv.process_message(message_id, message).unwrap();
+                recv.process_message(message_id, message, None).unwrap();
             }
         }
 
@@ -463,13 +694,13 @@ mod tests {
         assert_eq!(messages.len(), 3);
 
         // Process and receive out of order
-        recv.process_message(messages[2].1.clone(), messages[2].0).unwrap();
+        recv.process_message(messages[2].1.clone(), messages[2].0, None).unwrap();
         let new_message3 = recv.receive_message().unwrap();
 
-        recv.process_message(messages[1].1.clone(), messages[1].0).unwrap();
+        recv.process_message(messages[1].1.clone(), messages[1].0, None).unwrap();
         let new_message2 = recv.receive_message().unwrap();
 
-        recv.process_message(messages[0].1.clone(), messages[0].0).unwrap();
+        recv.process_message(messages[0].1.clone(), messages[0].0, None).unwrap();
         let new_message1 = recv.receive_message().unwrap();
 
         assert_eq!(message1, new_message1);
@@ -506,6 +737,21 @@ mod tests {
         assert!(packets.is_empty());
     }
 
+    #[test]
+    fn expected_message_id() {
+        for ordered in [true, false] {
+            let mut recv = ReceiveChannelReliable::new(10000, ordered);
+            recv.process_message(Bytes::from("0"), 0, None).unwrap();
+            recv.process_message(Bytes::from("2"), 2, None).unwrap();
+            assert_eq!(recv.expected_message_id(), 1);
+
+            recv.process_message(Bytes::from("1"), 1, None).unwrap();
+            assert_eq!(recv.expected_message_id(), 3);
+            while recv.receive_message().is_some() {}
+            assert_eq!(recv.expected_message_id(), 3);
+        }
+    }
+
     #[test]
     fn slice_packet() {
         let max_memory: usize = 10000;
@@ -525,7 +771,7 @@ mod tests {
             let Packet::ReliableSlice { channel_id: 0, slice, .. } = packet else {
                 unreachable!();
             };
-            recv.process_slice(slice).unwrap();
+            recv.process_slice(slice, None).unwrap();
         }
 
         let new_message = recv.receive_message().unwrap();
@@ -575,7 +821,7 @@ mod tests {
                 unreachable!();
             };
             for (message, message_id) in messages {
-                let Err(e) = recv.process_message(message_id, message) else {
+                let Err(e) = recv.process_message(message_id, message, None) else {
                     unreachable!();
                 };
                 assert_eq!(e, ChannelError::ReliableChannelMaxMemoryReached);
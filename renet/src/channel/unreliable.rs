@@ -1,6 +1,6 @@
 use std::{
     collections::{BTreeMap, VecDeque},
-    time::Duration,
+    time::{Duration, Instant},
 };
 
 use bytes::Bytes;
@@ -23,7 +23,7 @@ pub struct SendChannelUnreliable {
 #[derive(Debug)]
 pub struct ReceiveChannelUnreliable {
     channel_id: u8,
-    messages: VecDeque<Bytes>,
+    messages: VecDeque<(Bytes, Option<Instant>)>,
     slices: BTreeMap<u64, SliceConstructor>,
     slices_last_received: BTreeMap<u64, Duration>,
     max_memory_usage_bytes: usize,
@@ -46,7 +46,20 @@ impl SendChannelUnreliable {
     }
 
     pub fn available_memory(&self) -> usize {
-        self.max_memory_usage_bytes - self.memory_usage_bytes
+        self.max_memory_usage_bytes.saturating_sub(self.memory_usage_bytes)
+    }
+
+    pub fn max_memory_usage_bytes(&self) -> usize {
+        self.max_memory_usage_bytes
+    }
+
+    pub fn memory_usage_bytes(&self) -> usize {
+        self.memory_usage_bytes
+    }
+
+    // The queued messages are kept, even above the new memory limit
+    pub fn set_max_memory_usage_bytes(&mut self, max_memory_usage_bytes: usize) {
+        self.max_memory_usage_bytes = max_memory_usage_bytes;
     }
 
     pub fn get_packets_to_send(&mut self, packet_sequence: &mut u64, available_bytes: &mut u64) -> Vec<Packet> {
@@ -116,6 +129,13 @@ impl SendChannelUnreliable {
         packets
     }
 
+    /// Removes and returns the oldest message that wasn't sent yet.
+    pub fn drop_oldest_message(&mut self) -> Option<Bytes> {
+        let message = self.unreliable_messages.pop_front()?;
+        self.memory_usage_bytes -= message.len();
+        Some(message)
+    }
+
     pub fn send_message(&mut self, message: Bytes) {
         if self.memory_usage_bytes + message.len() > self.max_memory_usage_bytes {
             log::warn!(
@@ -149,7 +169,7 @@ impl ReceiveChannelUnreliable {
         }
     }
 
-    pub fn process_message(&mut self, message: Bytes) {
+    pub fn process_message(&mut self, message: Bytes, arrived_at: Option<Instant>) {
         if self.memory_usage_bytes + message.len() > self.max_memory_usage_bytes {
             log::warn!(
                 "dropped unreliable message received because channel {} is memory limited",
@@ -159,10 +179,10 @@ impl ReceiveChannelUnreliable {
         }
 
         self.memory_usage_bytes += message.len();
-        self.messages.push_back(message);
+        self.messages.push_back((message, arrived_at));
     }
 
-    pub fn process_slice(&mut self, slice: Slice, current_time: Duration) -> Result<(), ChannelError> {
+    pub fn process_slice(&mut self, slice: Slice, current_time: Duration, arrived_at: Option<Instant>) -> Result<(), ChannelError> {
         if !self.slices.contains_key(&slice.message_id) {
             let message_len = slice.num_slices * SLICE_SIZE;
             if self.memory_usage_bytes + message_len > self.max_memory_usage_bytes {
@@ -186,7 +206,7 @@ impl ReceiveChannelUnreliable {
             self.slices_last_received.remove(&slice.message_id);
             self.memory_usage_bytes -= slice.num_slices * SLICE_SIZE;
             self.memory_usage_bytes += message.len();
-            self.messages.push_back(message);
+            self.messages.push_back((message, arrived_at));
         } else {
             self.slices_last_received.insert(slice.message_id, current_time);
         }
@@ -215,9 +235,13 @@ impl ReceiveChannelUnreliable {
     }
 
     pub fn receive_message(&mut self) -> Option<Bytes> {
-        if let Some(message) = self.messages.pop_front() {
+        self.receive_message_with_timestamp().map(|(message, _)| message)
+    }
+
+    pub fn receive_message_with_timestamp(&mut self) -> Option<(Bytes, Option<Instant>)> {
+        if let Some((message, arrived_at)) = self.messages.pop_front() {
             self.memory_usage_bytes -= message.len();
-            return Some(message);
+            return Some((message, arrived_at));
         };
 
         None
@@ -250,7 +274,7 @@ mod tests {
                 unreachable!();
             };
             for message in messages {
-                recv.process_message(message);
+                recv.process_message(message, None);
             }
         }
 
@@ -283,7 +307,7 @@ mod tests {
             let Packet::UnreliableSlice { slice, .. } = packet else {
                 unreachable!();
             };
-            recv.process_slice(slice, current_time).unwrap();
+            recv.process_slice(slice, current_time, None).unwrap();
         }
 
         let new_message = recv.receive_message().unwrap();
@@ -316,7 +340,7 @@ mod tests {
             // Second message was dropped
             assert_eq!(messages.len(), 1);
             for message in messages {
-                recv.process_message(message);
+                recv.process_message(message, None);
             }
         }
 
@@ -0,0 +1,124 @@
+use std::collections::{HashMap, HashSet};
+
+use bytes::Bytes;
+
+use crate::{ClientId, RenetClient};
+
+/// Identifier of a [`ChannelGroup`] registered in the server.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct GroupId(pub(crate) u64);
+
+/// A named subset of clients that messages can be sent to.
+/// Created with [`RenetServer::create_channel_group`][crate::RenetServer::create_channel_group],
+/// or [`RenetServer::create_group_channel`][crate::RenetServer::create_group_channel] to bind it to a channel.
+///
+/// Clients are removed from the groups when they disconnect.
+#[derive(Debug, Clone)]
+pub struct ChannelGroup {
+    name: String,
+    clients: HashSet<ClientId>,
+    channel_id: Option<u8>,
+}
+
+impl ChannelGroup {
+    pub(crate) fn new(name: &str, clients: HashSet<ClientId>, channel_id: Option<u8>) -> Self {
+        Self {
+            name: name.to_string(),
+            clients,
+            channel_id,
+        }
+    }
+
+    /// Returns the name of the group, empty for group channels.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the clients that are members of the group.
+    pub fn clients(&self) -> &HashSet<ClientId> {
+        &self.clients
+    }
+
+    /// Returns whether the client is a member of the group.
+    pub fn contains(&self, client_id: ClientId) -> bool {
+        self.clients.contains(&client_id)
+    }
+
+    /// Returns the channel used by [`GroupChannel::send`], if the group is a group channel.
+    pub fn channel_id(&self) -> Option<u8> {
+        self.channel_id
+    }
+
+    pub(crate) fn update(&mut self, add: &[ClientId], remove: &[ClientId]) {
+        self.clients.extend(add);
+        for client_id in remove {
+            self.clients.remove(client_id);
+        }
+    }
+
+    pub(crate) fn remove(&mut self, client_id: ClientId) {
+        self.clients.remove(&client_id);
+    }
+}
+
+/// Handle of a group channel created with [`RenetServer::create_group_channel`][crate::RenetServer::create_group_channel].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct GroupChannelHandle(pub(crate) GroupId);
+
+impl GroupChannelHandle {
+    /// Returns the id of the [`ChannelGroup`] backing the group channel.
+    pub fn group_id(self) -> GroupId {
+        self.0
+    }
+}
+
+/// A channel bound to a set of clients, messages sent to it are sent to all its members.
+/// Borrowed from the server with [`RenetServer::group_channel`][crate::RenetServer::group_channel].
+///
+/// Clients are removed from the group channels when they disconnect.
+pub struct GroupChannel<'a> {
+    pub(crate) channel_id: u8,
+    pub(crate) group: &'a mut ChannelGroup,
+    pub(crate) connections: &'a mut HashMap<ClientId, RenetClient>,
+}
+
+impl GroupChannel<'_> {
+    pub fn channel_id(&self) -> u8 {
+        self.channel_id
+    }
+
+    /// Returns the clients that are members of the group channel.
+    pub fn members(&self) -> &HashSet<ClientId> {
+        &self.group.clients
+    }
+
+    /// Adds a client to the group channel, returns false if it was already a member.
+    pub fn add_member(&mut self, client_id: ClientId) -> bool {
+        self.group.clients.insert(client_id)
+    }
+
+    /// Removes a client from the group channel, returns false if it was not a member.
+    pub fn remove_member(&mut self, client_id: ClientId) -> bool {
+        self.group.clients.remove(&client_id)
+    }
+
+    /// Sends a message to all the members, the message bytes are shared between them.
+    /// Members that are not connected are skipped.
+    pub fn send<B: Into<Bytes>>(&mut self, message: B) {
+        send_to_members(self.connections, &self.group.clients, self.channel_id, message.into());
+    }
+}
+
+// Bytes clones only bump the reference count, so every member shares the same buffer
+pub(crate) fn send_to_members(
+    connections: &mut HashMap<ClientId, RenetClient>,
+    members: &HashSet<ClientId>,
+    channel_id: u8,
+    message: Bytes,
+) {
+    for client_id in members {
+        if let Some(connection) = connections.get_mut(client_id) {
+            connection.send_message(channel_id, message.clone());
+        }
+    }
+}
@@ -0,0 +1,126 @@
+use std::time::Duration;
+
+use crate::packet::Payload;
+use crate::simulation::{take_arrived, NetworkConditions, SimulationRng};
+
+#[derive(Debug)]
+struct DelayedPacket {
+    deliver_at: Duration,
+    payload: Payload,
+}
+
+// Delays the packets of a connection, passes them through without conditions
+#[derive(Debug)]
+pub(crate) struct Conditioner {
+    conditions: Option<NetworkConditions>,
+    rng: SimulationRng,
+    outgoing: Vec<DelayedPacket>,
+    incoming: Vec<DelayedPacket>,
+}
+
+impl Conditioner {
+    pub fn new() -> Self {
+        Self {
+            conditions: None,
+            rng: SimulationRng::new(0x853c_49e6_748f_ea9b),
+            outgoing: Vec::new(),
+            incoming: Vec::new(),
+        }
+    }
+
+    pub fn conditions(&self) -> Option<NetworkConditions> {
+        self.conditions
+    }
+
+    // The packets already delayed keep their delivery time, or are delivered right away without conditions
+    pub fn set_conditions(&mut self, conditions: Option<NetworkConditions>) {
+        self.conditions = conditions;
+        if conditions.is_none() {
+            for packet in self.outgoing.iter_mut().chain(self.incoming.iter_mut()) {
+                packet.deliver_at = Duration::ZERO;
+            }
+        }
+    }
+
+    pub fn send(&mut self, payload: Payload, current_time: Duration) {
+        condition(&mut self.outgoing, payload, current_time, self.conditions, &mut self.rng);
+    }
+
+    pub fn receive(&mut self, payload: Payload, current_time: Duration) {
+        condition(&mut self.incoming, payload, current_time, self.conditions, &mut self.rng);
+    }
+
+    pub fn take_outgoing(&mut self, current_time: Duration) -> Vec<Payload> {
+        take_payloads(&mut self.outgoing, current_time)
+    }
+
+    pub fn take_incoming(&mut self, current_time: Duration) -> Vec<Payload> {
+        take_payloads(&mut self.incoming, current_time)
+    }
+}
+
+fn condition(
+    packets: &mut Vec<DelayedPacket>,
+    payload: Payload,
+    current_time: Duration,
+    conditions: Option<NetworkConditions>,
+    rng: &mut SimulationRng,
+) {
+    let delivery_times = match conditions {
+        Some(conditions) => rng.delivery_times(current_time, &conditions),
+        None => vec![Duration::ZERO],
+    };
+    for deliver_at in delivery_times {
+        packets.push(DelayedPacket {
+            deliver_at,
+            payload: payload.clone(),
+        });
+    }
+}
+
+fn take_payloads(packets: &mut Vec<DelayedPacket>, current_time: Duration) -> Vec<Payload> {
+    take_arrived(packets, current_time, |packet| packet.deliver_at)
+        .into_iter()
+        .map(|packet| packet.payload)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn delays_drops_and_duplicates() {
+        let mut conditioner = Conditioner::new();
+        conditioner.set_conditions(Some(NetworkConditions {
+            latency: Duration::from_millis(100),
+            ..Default::default()
+        }));
+        conditioner.send(vec![1], Duration::ZERO);
+        assert!(conditioner.take_outgoing(Duration::from_millis(99)).is_empty());
+        assert_eq!(conditioner.take_outgoing(Duration::from_millis(100)), vec![vec![1]]);
+
+        conditioner.set_conditions(Some(NetworkConditions {
+            loss_percent: 100.0,
+            ..Default::default()
+        }));
+        conditioner.receive(vec![2], Duration::ZERO);
+        assert!(conditioner.take_incoming(Duration::ZERO).is_empty());
+
+        conditioner.set_conditions(Some(NetworkConditions {
+            duplication_percent: 100.0,
+            ..Default::default()
+        }));
+        conditioner.receive(vec![3], Duration::ZERO);
+        assert_eq!(conditioner.take_incoming(Duration::ZERO), vec![vec![3], vec![3]]);
+
+        // Removing the conditions delivers the delayed packets
+        conditioner.set_conditions(Some(NetworkConditions {
+            latency: Duration::from_secs(1),
+            ..Default::default()
+        }));
+        conditioner.send(vec![4], Duration::ZERO);
+        conditioner.set_conditions(None);
+        assert_eq!(conditioner.take_outgoing(Duration::ZERO), vec![vec![4]]);
+    }
+}
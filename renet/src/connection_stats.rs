@@ -3,6 +3,8 @@ use std::time::Duration;
 const RESOLUTION: Duration = Duration::from_millis(300);
 const WINDOW: Duration = Duration::from_millis(6000);
 const SIZE: usize = (WINDOW.as_millis() / RESOLUTION.as_millis()) as usize;
+// Largest gap in the received sequences counted as lost packets, so a bogus sequence can't overflow the sums
+const MAX_SEQUENCE_GAP: u64 = u16::MAX as u64;
 
 #[derive(Debug, Default)]
 pub struct ConnectionStats {
@@ -10,6 +12,10 @@ pub struct ConnectionStats {
     packets_acked: [u64; SIZE],
     bytes_sent: [u64; SIZE],
     bytes_received: [u64; SIZE],
+    packets_received: [u64; SIZE],
+    // Packets sent by the other side, counted from the gaps in the received sequences
+    packets_expected: [u64; SIZE],
+    highest_received_sequence: Option<u64>,
     current_index: usize,
 }
 
@@ -20,6 +26,9 @@ impl ConnectionStats {
             packets_acked: [0; SIZE],
             bytes_sent: [0; SIZE],
             bytes_received: [0; SIZE],
+            packets_received: [0; SIZE],
+            packets_expected: [0; SIZE],
+            highest_received_sequence: None,
             current_index: 0,
         }
     }
@@ -36,6 +45,8 @@ impl ConnectionStats {
             self.bytes_sent[i] = 0;
             self.bytes_received[i] = 0;
             self.packets_acked[i] = 0;
+            self.packets_received[i] = 0;
+            self.packets_expected[i] = 0;
         }
     }
 
@@ -48,6 +59,18 @@ impl ConnectionStats {
         self.bytes_received[self.current_index] += bytes;
     }
 
+    pub fn received_sequence(&mut self, sequence: u64) {
+        self.packets_received[self.current_index] += 1;
+        let gap = match self.highest_received_sequence {
+            // Late or duplicated packet, already expected
+            Some(highest) if sequence <= highest => return,
+            Some(highest) => sequence - highest,
+            None => 1,
+        };
+        self.packets_expected[self.current_index] += gap.min(MAX_SEQUENCE_GAP);
+        self.highest_received_sequence = Some(sequence);
+    }
+
     pub fn acked_packet(&mut self, sent_at: Duration, current_time: Duration) {
         let delta = current_time - sent_at;
         if delta > WINDOW {
@@ -109,6 +132,74 @@ impl ConnectionStats {
 
         (total_packets_sent - total_packets_acked) / total_packets_sent
     }
+
+    pub fn receive_packet_loss(&self) -> f64 {
+        // Ignore the current resolution, late packets can still arrive
+        let total_packets_expected: u64 = self.packets_expected.iter().sum::<u64>() - self.packets_expected[self.current_index];
+        let total_packets_received: u64 = self.packets_received.iter().sum::<u64>() - self.packets_received[self.current_index];
+
+        if total_packets_expected == 0 {
+            return 0.0;
+        }
+
+        total_packets_expected.saturating_sub(total_packets_received) as f64 / total_packets_expected as f64
+    }
+}
+
+// Exponentially weighted moving average of the bytes sent and received per second
+#[derive(Debug, Default)]
+pub struct DataRateAverage {
+    smoothing: f64,
+    pending_bytes_sent: u64,
+    pending_bytes_received: u64,
+    bytes_sent_per_second: f64,
+    bytes_received_per_second: f64,
+}
+
+impl DataRateAverage {
+    pub fn new(smoothing: f64) -> Self {
+        Self {
+            smoothing,
+            ..Default::default()
+        }
+    }
+
+    pub fn set_smoothing(&mut self, smoothing: f64) {
+        self.smoothing = smoothing;
+    }
+
+    pub fn sent(&mut self, bytes: u64) {
+        self.pending_bytes_sent += bytes;
+    }
+
+    pub fn received(&mut self, bytes: u64) {
+        self.pending_bytes_received += bytes;
+    }
+
+    // The smoothing is the weight of one second of traffic,
+    // so the average changes at the same speed with any update rate
+    pub fn update(&mut self, elapsed: Duration) {
+        if elapsed.is_zero() {
+            return;
+        }
+
+        let elapsed = elapsed.as_secs_f64();
+        let weight = 1.0 - (1.0 - self.smoothing).powf(elapsed);
+        let sent = self.pending_bytes_sent as f64 / elapsed;
+        let received = self.pending_bytes_received as f64 / elapsed;
+        self.bytes_sent_per_second += (sent - self.bytes_sent_per_second) * weight;
+        self.bytes_received_per_second += (received - self.bytes_received_per_second) * weight;
+        self.pending_bytes_sent = 0;
+        self.pending_bytes_received = 0;
+    }
+
+    pub fn bytes_sent_per_second(&self) -> f64 {
+        self.bytes_sent_per_second
+    }
+
+    pub fn bytes_received_per_second(&self) -> f64 {
+        self.bytes_received_per_second
+    }
 }
 
 #[cfg(test)]
@@ -169,4 +260,48 @@ mod tests {
         assert_eq!(window.packets_acked, [3; SIZE]);
         assert_eq!(window.packet_loss(), 0.5);
     }
+
+    #[test]
+    fn receive_packet_loss() {
+        let mut current_time = Duration::ZERO;
+        let mut window = ConnectionStats::default();
+
+        // Receive every other packet, with one late packet that was already counted as lost
+        for sequence in (0..20).step_by(2) {
+            window.update(current_time);
+            window.received_sequence(sequence);
+            current_time += Duration::from_millis(300);
+        }
+        window.received_sequence(1);
+        window.update(current_time);
+        assert!((window.receive_packet_loss() - 8. / 19.).abs() < 1e-9);
+
+        // A bogus sequence is capped
+        window.received_sequence(u64::MAX);
+        window.update(current_time + RESOLUTION);
+        assert!(window.receive_packet_loss() < 1.0);
+    }
+
+    #[test]
+    fn data_rate_average() {
+        let mut average = DataRateAverage::new(0.5);
+        average.sent(1000);
+        average.update(Duration::from_secs(1));
+        assert_eq!(average.bytes_sent_per_second(), 500.);
+        assert_eq!(average.bytes_received_per_second(), 0.);
+
+        // Two updates of half a second weight the same as one of a second
+        for _ in 0..2 {
+            average.sent(500);
+            average.received(100);
+            average.update(Duration::from_millis(500));
+        }
+        assert!((average.bytes_sent_per_second() - 750.).abs() < 1e-9);
+        assert!((average.bytes_received_per_second() - 100.).abs() < 1e-9);
+
+        // No time elapsed, kept for the next update
+        average.sent(100);
+        average.update(Duration::ZERO);
+        assert!((average.bytes_sent_per_second() - 750.).abs() < 1e-9);
+    }
 }
@@ -21,6 +21,8 @@ pub enum DisconnectReason {
     SendChannelError { channel_id: u8, error: ChannelError },
     /// Error occurred in a receive channel
     ReceiveChannelError { channel_id: u8, error: ChannelError },
+    /// The application schema versions are not compatible, see [`SchemaHandshake`](crate::SchemaHandshake)
+    SchemaMismatch { local_version: u32, remote_version: u32 },
 }
 
 /// Possibles errors that can occur in a channel.
@@ -30,6 +32,8 @@ pub enum ChannelError {
     ReliableChannelMaxMemoryReached,
     /// Received an invalid slice message in the channel.
     InvalidSliceMessage,
+    /// Received a fragment out of order or with an invalid header in a chunked channel.
+    InvalidChunk,
 }
 
 impl fmt::Display for ChannelError {
@@ -39,6 +43,7 @@ impl fmt::Display for ChannelError {
         match *self {
             ReliableChannelMaxMemoryReached => write!(fmt, "reliable channel memory usage was exausted"),
             InvalidSliceMessage => write!(fmt, "received an invalid slice packet"),
+            InvalidChunk => write!(fmt, "received an invalid chunked message fragment"),
         }
     }
 }
@@ -56,12 +61,74 @@ impl fmt::Display for DisconnectReason {
             ReceivedInvalidChannelId(id) => write!(fmt, "received message with invalid channel {id}"),
             SendChannelError { channel_id, error } => write!(fmt, "send channel {channel_id} with error: {error}"),
             ReceiveChannelError { channel_id, error } => write!(fmt, "receive channel {channel_id} with error: {error}"),
+            SchemaMismatch {
+                local_version,
+                remote_version,
+            } => write!(
+                fmt,
+                "schema version {remote_version} is not compatible with version {local_version}"
+            ),
         }
     }
 }
 
 impl std::error::Error for ChannelError {}
 
+/// Possible errors when replacing the configuration of a channel.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ConfigError {
+    /// No channel exists with the id
+    ChannelNotFound(u8),
+    /// The new configuration has another channel id
+    ChannelIdMismatch { channel_id: u8, config_channel_id: u8 },
+    /// The delivery type can't change, the peers still receive the channel with the old type
+    ChannelTypeChanged { channel_id: u8 },
+    /// The chunk size or max message size of a chunked channel doesn't fit its memory, see [`ChannelConfig::is_valid`](crate::ChannelConfig::is_valid)
+    InvalidChunkConfig { channel_id: u8 },
+    /// The memory limit is above the one the clients use to receive the channel
+    MemoryLimitExceeded { channel_id: u8, max_memory_usage_bytes: usize },
+    /// The max message size of a chunked channel is above the one the clients use to receive the channel
+    MessageSizeExceeded { channel_id: u8, max_message_size: usize },
+    /// The chunk size of a chunked channel is above the one the clients use to receive the channel
+    ChunkSizeExceeded { channel_id: u8, chunk_size: usize },
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        use ConfigError::*;
+
+        match *self {
+            ChannelNotFound(id) => write!(fmt, "channel {id} not found"),
+            ChannelIdMismatch {
+                channel_id,
+                config_channel_id,
+            } => write!(fmt, "replacing channel {channel_id} with the config of channel {config_channel_id}"),
+            ChannelTypeChanged { channel_id } => write!(fmt, "the delivery type of channel {channel_id} can't change"),
+            InvalidChunkConfig { channel_id } => write!(fmt, "the chunk size of channel {channel_id} doesn't fit its memory"),
+            MemoryLimitExceeded {
+                channel_id,
+                max_memory_usage_bytes,
+            } => write!(
+                fmt,
+                "the memory limit of channel {channel_id} can't exceed the {max_memory_usage_bytes} bytes the clients receive"
+            ),
+            MessageSizeExceeded {
+                channel_id,
+                max_message_size,
+            } => write!(
+                fmt,
+                "the max message size of channel {channel_id} can't exceed the {max_message_size} bytes the clients receive"
+            ),
+            ChunkSizeExceeded { channel_id, chunk_size } => write!(
+                fmt,
+                "the chunk size of channel {channel_id} can't exceed the {chunk_size} bytes the clients receive"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
 #[derive(Debug)]
 pub struct ClientNotFound;
 
@@ -72,3 +139,65 @@ impl fmt::Display for ClientNotFound {
         write!(fmt, "client with given id was not found")
     }
 }
+
+/// Error type wrapping the errors of renet and its transports, so they can be propagated with `?`.
+///
+/// The transport crates convert their errors into [`RenetError::Transport`].
+#[derive(Debug)]
+pub enum RenetError {
+    Channel(ChannelError),
+    Config(ConfigError),
+    ClientNotFound(ClientNotFound),
+    Disconnected(DisconnectReason),
+    Transport(Box<dyn std::error::Error + Send + Sync + 'static>),
+}
+
+impl fmt::Display for RenetError {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        use RenetError::*;
+
+        match self {
+            Channel(err) => write!(fmt, "channel error: {err}"),
+            Config(err) => write!(fmt, "config error: {err}"),
+            ClientNotFound(err) => write!(fmt, "{err}"),
+            Disconnected(reason) => write!(fmt, "disconnected: {reason}"),
+            Transport(err) => write!(fmt, "transport error: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for RenetError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            RenetError::Channel(err) => Some(err),
+            RenetError::Config(err) => Some(err),
+            RenetError::ClientNotFound(err) => Some(err),
+            RenetError::Disconnected(_) => None,
+            RenetError::Transport(err) => Some(err.as_ref()),
+        }
+    }
+}
+
+impl From<ChannelError> for RenetError {
+    fn from(err: ChannelError) -> Self {
+        RenetError::Channel(err)
+    }
+}
+
+impl From<ConfigError> for RenetError {
+    fn from(err: ConfigError) -> Self {
+        RenetError::Config(err)
+    }
+}
+
+impl From<ClientNotFound> for RenetError {
+    fn from(err: ClientNotFound) -> Self {
+        RenetError::ClientNotFound(err)
+    }
+}
+
+impl From<DisconnectReason> for RenetError {
+    fn from(reason: DisconnectReason) -> Self {
+        RenetError::Disconnected(reason)
+    }
+}
@@ -1,15 +1,29 @@
 mod channel;
+mod channel_group;
+#[cfg(feature = "conditioner")]
+mod conditioner;
 mod connection_stats;
 mod error;
 mod packet;
 mod remote_connection;
+mod schema;
 mod server;
 
-pub use channel::{ChannelConfig, DefaultChannel, SendType};
-pub use error::{ChannelError, ClientNotFound, DisconnectReason};
-pub use remote_connection::{ConnectionConfig, NetworkInfo, RenetClient, RenetConnectionStatus};
-pub use server::{RenetServer, ServerEvent};
+pub mod simulation;
+
+pub use channel::{AckCallback, ChannelConfig, ChannelDef, ChannelType, DefaultChannel, Direction, SendType, MAX_CHUNKED_MESSAGE_SIZE};
+pub use channel_group::{ChannelGroup, GroupChannel, GroupChannelHandle, GroupId};
+pub use error::{ChannelError, ClientNotFound, ConfigError, DisconnectReason, RenetError};
+pub use packet::packet_channel_id;
+pub use remote_connection::{
+    ChannelNetworkInfo, ConnectionConfig, DataRate, NetworkInfo, RenetClient, RenetConnectionStatus, TransportStats,
+};
+pub use schema::{SchemaHandshake, SchemaStatus};
+pub use server::{GracefulShutdown, RenetServer, RenetServerReceive, RenetServerSend, ServerEvent, ServerTransport};
+pub use simulation::NetworkConditions;
 
 pub use bytes::Bytes;
 
+/// Identifier of a client in the server, chosen by the transport layer.
+/// No values are reserved, any `u64` can identify a client.
 pub type ClientId = u64;
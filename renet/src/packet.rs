@@ -92,6 +92,16 @@ impl Packet {
         }
     }
 
+    pub fn channel_id(&self) -> Option<u8> {
+        match self {
+            Packet::SmallReliable { channel_id, .. }
+            | Packet::SmallUnreliable { channel_id, .. }
+            | Packet::UnreliableSlice { channel_id, .. }
+            | Packet::ReliableSlice { channel_id, .. } => Some(*channel_id),
+            Packet::Ack { .. } => None,
+        }
+    }
+
     pub fn to_bytes(&self, b: &mut octets::OctetsMut) -> Result<usize, SerializationError> {
         let before = b.cap();
 
@@ -351,10 +361,46 @@ impl Packet {
     }
 }
 
+/// Returns the channel of a packet generated by the [`RenetServer`](crate::RenetServer) or [`RenetClient`](crate::RenetClient),
+/// None for packets that don't belong to a channel, like acks.
+///
+/// Useful for transports that want to handle packets differently depending on their channel.
+pub fn packet_channel_id(packet: &[u8]) -> Option<u8> {
+    let mut b = octets::Octets::with_slice(packet);
+    match b.get_u8().ok()? {
+        // SmallReliable, SmallUnreliable, ReliableSlice, UnreliableSlice
+        0..=3 => {
+            b.get_varint().ok()?;
+            b.get_u8().ok()
+        }
+        _ => None,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn channel_id_of_packet() {
+        let mut buffer = [0u8; 1300];
+        let packet = Packet::SmallUnreliable {
+            sequence: 300,
+            channel_id: 7,
+            messages: vec![vec![1, 2, 3].into()],
+        };
+        let len = packet.to_bytes(&mut octets::OctetsMut::with_slice(&mut buffer)).unwrap();
+        assert_eq!(packet_channel_id(&buffer[..len]), Some(7));
+
+        let packet = Packet::Ack {
+            sequence: 0,
+            ack_ranges: vec![0..1, 3..5],
+        };
+        let len = packet.to_bytes(&mut octets::OctetsMut::with_slice(&mut buffer)).unwrap();
+        assert_eq!(packet_channel_id(&buffer[..len]), None);
+        assert_eq!(packet_channel_id(&[]), None);
+    }
+
     #[test]
     fn serialize_small_reliable_packet() {
         let mut buffer = [0u8; 1300];
@@ -1,15 +1,29 @@
 use crate::channel::reliable::{ReceiveChannelReliable, SendChannelReliable};
 use crate::channel::unreliable::{ReceiveChannelUnreliable, SendChannelUnreliable};
-use crate::channel::{ChannelConfig, DefaultChannel, SendType};
-use crate::connection_stats::ConnectionStats;
+use crate::channel::{AckCallback, ChannelConfig, ChannelDef, ChannelType, DefaultChannel, Direction, SendType};
+#[cfg(feature = "conditioner")]
+use crate::conditioner::Conditioner;
+use crate::connection_stats::{ConnectionStats, DataRateAverage};
 use crate::error::DisconnectReason;
 use crate::packet::{Packet, Payload};
+use crate::schema::{SchemaHandshake, SchemaState, SchemaStatus};
+#[cfg(feature = "conditioner")]
+use crate::NetworkConditions;
 use bytes::Bytes;
 use octets::OctetsMut;
 
 use std::collections::{BTreeMap, HashMap};
 use std::ops::Range;
-use std::time::Duration;
+use std::time::{Duration, Instant};
+
+pub(crate) const DEFAULT_DATA_RATE_SMOOTHING: f64 = 0.5;
+
+pub(crate) fn assert_valid_data_rate_smoothing(smoothing: f64) {
+    assert!(
+        smoothing > 0.0 && smoothing <= 1.0,
+        "data rate smoothing must be in the range (0, 1], got {smoothing}"
+    );
+}
 
 /// Configuration for a renet connection and its channels.
 #[derive(Debug, Clone)]
@@ -61,15 +75,56 @@ enum ChannelOrder {
     Unreliable(u8),
 }
 
+/// Describes the traffic of a channel in a connection.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct ChannelNetworkInfo {
+    pub channel_id: u8,
+    pub bytes_sent_per_second: f64,
+    pub bytes_received_per_second: f64,
+    /// Bytes of the messages waiting to be sent, or to be acked in reliable channels.
+    pub buffered_bytes: usize,
+    /// Memory limit of the buffered bytes, zero for channels that only receive.
+    /// Reliable channels disconnect the connection when a message doesn't fit.
+    pub max_memory_usage_bytes: usize,
+    /// Bytes of the reliable messages sent and not acked yet, part of the buffered bytes.
+    pub bytes_in_flight: usize,
+}
+
 /// Describes the stats of a connection.
 pub struct NetworkInfo {
     /// Round-trip Time
     pub rtt: f64,
+    /// Fraction of the packets sent that were not acked.
     pub packet_loss: f64,
+    /// Fraction of the packets sent by the other side that were not received.
+    pub receive_packet_loss: f64,
+    /// Variation of the round-trip time, in seconds.
+    pub jitter: f64,
     pub bytes_sent_per_second: f64,
     pub bytes_received_per_second: f64,
 }
 
+/// Stats of a connection measured by its transport, below renet. Returned by the transports that can measure them,
+/// to compare them with the [`NetworkInfo`] of renet, the difference is the time spent in the buffers of the transport.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct TransportStats {
+    /// Round-trip time measured by the transport, in seconds.
+    pub rtt: f64,
+    /// Quality of the connection from 0 to 1, the fraction of the packets delivered.
+    pub connection_quality: f32,
+    /// The packets go through a relay instead of directly to the peer.
+    pub relayed: bool,
+    /// Bytes waiting to be sent in the buffers of the transport.
+    pub pending_bytes: usize,
+}
+
+/// Average bytes sent and received per second of a connection, see [`RenetClient::data_rate`].
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct DataRate {
+    pub send_bps: f64,
+    pub recv_bps: f64,
+}
+
 /// The connection status of a [`RenetClient`].
 #[derive(Debug)]
 pub enum RenetConnectionStatus {
@@ -79,7 +134,7 @@ pub enum RenetConnectionStatus {
 }
 
 #[derive(Debug)]
-#[cfg_attr(feature = "bevy", derive(bevy_ecs::resource::Resource))]
+#[cfg_attr(feature = "bevy", derive(bevy_ecs::resource::Resource, bevy_ecs::component::Component))]
 pub struct RenetClient {
     packet_sequence: u64,
     current_time: Duration,
@@ -90,10 +145,19 @@ pub struct RenetClient {
     receive_unreliable_channels: HashMap<u8, ReceiveChannelUnreliable>,
     send_reliable_channels: HashMap<u8, SendChannelReliable>,
     receive_reliable_channels: HashMap<u8, ReceiveChannelReliable>,
+    send_channel_types: HashMap<u8, ChannelType>,
     stats: ConnectionStats,
+    channel_stats: BTreeMap<u8, ConnectionStats>,
+    data_rate: DataRateAverage,
+    bytes_sent_total: u64,
+    bytes_received_total: u64,
     available_bytes_per_tick: u64,
     connection_status: RenetConnectionStatus,
     rtt: f64,
+    jitter: f64,
+    #[cfg(feature = "conditioner")]
+    conditioner: Option<Conditioner>,
+    schema: Option<SchemaState>,
 }
 
 impl Default for ConnectionConfig {
@@ -107,6 +171,64 @@ impl Default for ConnectionConfig {
     }
 }
 
+impl ConnectionConfig {
+    /// Creates a configuration from a list of channels, with the default `available_bytes_per_tick`.
+    /// The channels keep their order, which determines their priority.
+    ///
+    /// # Usage
+    /// ```
+    /// # use std::time::Duration;
+    /// # use renet::{ChannelConfig, ConnectionConfig, Direction, SendType};
+    /// let reliable = ChannelConfig {
+    ///     channel_id: 0,
+    ///     max_memory_usage_bytes: 5 * 1024 * 1024,
+    ///     send_type: SendType::ReliableOrdered {
+    ///         resend_time: Duration::from_millis(300),
+    ///     },
+    /// };
+    /// let unreliable = ChannelConfig {
+    ///     send_type: SendType::Unreliable,
+    ///     ..reliable.clone()
+    /// };
+    /// let config = ConnectionConfig::from_channels([
+    ///     (0, reliable, Direction::Bidirectional),
+    ///     (1, unreliable.clone(), Direction::Send),
+    ///     (1, unreliable, Direction::Receive),
+    /// ]);
+    /// assert_eq!(config.server_channels_config.len(), 2);
+    /// assert_eq!(config.client_channels_config.len(), 2);
+    /// ```
+    pub fn from_channels(channels: impl IntoIterator<Item = ChannelDef>) -> Self {
+        let mut server_channels_config = Vec::new();
+        let mut client_channels_config = Vec::new();
+        for (channel_id, mut channel_config, direction) in channels {
+            channel_config.channel_id = channel_id;
+            match direction {
+                Direction::Send => server_channels_config.push(channel_config),
+                Direction::Receive => client_channels_config.push(channel_config),
+                Direction::Bidirectional => {
+                    server_channels_config.push(channel_config.clone());
+                    client_channels_config.push(channel_config);
+                }
+            }
+        }
+
+        Self {
+            server_channels_config,
+            client_channels_config,
+            ..Default::default()
+        }
+    }
+}
+
+fn assert_valid_chunk_config(channel_config: &ChannelConfig) {
+    assert!(
+        channel_config.is_valid(),
+        "chunk size or max message size of chunked channel {} doesn't fit its max_memory_usage_bytes",
+        channel_config.channel_id
+    );
+}
+
 impl RenetClient {
     pub fn new(config: ConnectionConfig) -> Self {
         Self::from_channels(
@@ -148,11 +270,34 @@ impl RenetClient {
                     let old = send_reliable_channels.insert(channel_config.channel_id, channel);
                     assert!(old.is_none(), "already exists send channel {}", channel_config.channel_id);
 
+                    channel_send_order.push(ChannelOrder::Reliable(channel_config.channel_id));
+                }
+                SendType::ReliableChunked {
+                    resend_time,
+                    chunk_size,
+                    max_message_size,
+                } => {
+                    assert_valid_chunk_config(channel_config);
+                    let channel = SendChannelReliable::new_chunked(
+                        channel_config.channel_id,
+                        resend_time,
+                        channel_config.max_memory_usage_bytes,
+                        chunk_size,
+                        max_message_size,
+                    );
+                    let old = send_reliable_channels.insert(channel_config.channel_id, channel);
+                    assert!(old.is_none(), "already exists send channel {}", channel_config.channel_id);
+
                     channel_send_order.push(ChannelOrder::Reliable(channel_config.channel_id));
                 }
             }
         }
 
+        let send_channel_types = send_channels_config
+            .iter()
+            .map(|channel_config| (channel_config.channel_id, ChannelType::from(&channel_config.send_type)))
+            .collect();
+
         let mut receive_unreliable_channels = HashMap::new();
         let mut receive_reliable_channels = HashMap::new();
         for channel_config in receive_channels_config.iter() {
@@ -172,6 +317,12 @@ impl RenetClient {
                     let old = receive_reliable_channels.insert(channel_config.channel_id, channel);
                     assert!(old.is_none(), "already exists receive channel {}", channel_config.channel_id);
                 }
+                SendType::ReliableChunked { max_message_size, .. } => {
+                    assert_valid_chunk_config(channel_config);
+                    let channel = ReceiveChannelReliable::new_chunked(channel_config.max_memory_usage_bytes, max_message_size);
+                    let old = receive_reliable_channels.insert(channel_config.channel_id, channel);
+                    assert!(old.is_none(), "already exists receive channel {}", channel_config.channel_id);
+                }
             }
         }
 
@@ -185,10 +336,19 @@ impl RenetClient {
             receive_unreliable_channels,
             send_reliable_channels,
             receive_reliable_channels,
+            send_channel_types,
             stats: ConnectionStats::new(),
+            channel_stats: BTreeMap::new(),
+            data_rate: DataRateAverage::new(DEFAULT_DATA_RATE_SMOOTHING),
+            bytes_sent_total: 0,
+            bytes_received_total: 0,
             rtt: 0.0,
+            jitter: 0.0,
             available_bytes_per_tick,
             connection_status: RenetConnectionStatus::Connecting,
+            #[cfg(feature = "conditioner")]
+            conditioner: None,
+            schema: None,
         }
     }
 
@@ -202,6 +362,16 @@ impl RenetClient {
         self.stats.packet_loss()
     }
 
+    /// Returns the loss of the packets sent by the other side of the connection.
+    pub fn receive_packet_loss(&self) -> f64 {
+        self.stats.receive_packet_loss()
+    }
+
+    /// Returns the variation of the round-time trip for the connection.
+    pub fn jitter(&self) -> f64 {
+        self.jitter
+    }
+
     /// Returns the bytes sent per second in the connection.
     pub fn bytes_sent_per_sec(&self) -> f64 {
         self.stats.bytes_sent_per_second(self.current_time)
@@ -212,16 +382,89 @@ impl RenetClient {
         self.stats.bytes_received_per_second(self.current_time)
     }
 
+    /// Returns the total of bytes of the packets sent in the connection.
+    pub fn bytes_sent_total(&self) -> u64 {
+        self.bytes_sent_total
+    }
+
+    /// Returns the total of bytes of the packets received in the connection.
+    pub fn bytes_received_total(&self) -> u64 {
+        self.bytes_received_total
+    }
+
+    /// Returns the exponentially weighted moving average of the bytes sent and received per second,
+    /// smoothed by [`set_data_rate_smoothing`](Self::set_data_rate_smoothing). Unlike [`network_info`](Self::network_info),
+    /// which averages the last seconds, it reacts to changes depending on the smoothing.
+    pub fn data_rate(&self) -> DataRate {
+        DataRate {
+            send_bps: self.data_rate.bytes_sent_per_second(),
+            recv_bps: self.data_rate.bytes_received_per_second(),
+        }
+    }
+
+    /// Sets the smoothing factor of the average [`data_rate`](Self::data_rate): the weight of the last second of traffic
+    /// in the average, higher values react faster to changes. Default: 0.5
+    ///
+    /// # Panics
+    /// If the smoothing is not in the range (0, 1].
+    pub fn set_data_rate_smoothing(&mut self, smoothing: f64) {
+        assert_valid_data_rate_smoothing(smoothing);
+        self.data_rate.set_smoothing(smoothing);
+    }
+
     /// Returns all network informations for the connection.
     pub fn network_info(&self) -> NetworkInfo {
         NetworkInfo {
             rtt: self.rtt,
             packet_loss: self.stats.packet_loss(),
+            receive_packet_loss: self.stats.receive_packet_loss(),
+            jitter: self.jitter,
             bytes_sent_per_second: self.stats.bytes_sent_per_second(self.current_time),
             bytes_received_per_second: self.stats.bytes_received_per_second(self.current_time),
         }
     }
 
+    /// Returns the traffic of each channel of the connection, sorted by channel id.
+    /// The bytes per second count the packets of the channel, without the acks.
+    pub fn channels_network_info(&self) -> Vec<ChannelNetworkInfo> {
+        let mut channel_ids: Vec<u8> = self
+            .send_channel_types
+            .keys()
+            .chain(self.receive_reliable_channels.keys())
+            .chain(self.receive_unreliable_channels.keys())
+            .copied()
+            .collect();
+        channel_ids.sort_unstable();
+        channel_ids.dedup();
+
+        channel_ids
+            .into_iter()
+            .map(|channel_id| {
+                let (buffered_bytes, max_memory_usage_bytes, bytes_in_flight) =
+                    if let Some(channel) = self.send_reliable_channels.get(&channel_id) {
+                        (
+                            channel.memory_usage_bytes(),
+                            channel.memory_limit_bytes(),
+                            channel.bytes_in_flight(),
+                        )
+                    } else if let Some(channel) = self.send_unreliable_channels.get(&channel_id) {
+                        (channel.memory_usage_bytes(), channel.max_memory_usage_bytes(), 0)
+                    } else {
+                        (0, 0, 0)
+                    };
+                let stats = self.channel_stats.get(&channel_id);
+                ChannelNetworkInfo {
+                    channel_id,
+                    bytes_sent_per_second: stats.map_or(0.0, |stats| stats.bytes_sent_per_second(self.current_time)),
+                    bytes_received_per_second: stats.map_or(0.0, |stats| stats.bytes_received_per_second(self.current_time)),
+                    buffered_bytes,
+                    max_memory_usage_bytes,
+                    bytes_in_flight,
+                }
+            })
+            .collect()
+    }
+
     /// Returns whether the client is connected.
     #[inline]
     pub fn is_connected(&self) -> bool {
@@ -304,6 +547,58 @@ impl RenetClient {
         }
     }
 
+    /// Returns the delivery type of the channel used to send messages to the server.
+    /// Returns None if the channel doesn't exist.
+    pub fn channel_type<I: Into<u8>>(&self, channel_id: I) -> Option<ChannelType> {
+        self.send_channel_types.get(&channel_id.into()).copied()
+    }
+
+    // Updates a send channel in place, its type is checked by the server
+    pub(crate) fn replace_send_channel_config(&mut self, channel_config: &ChannelConfig) {
+        let channel_id = channel_config.channel_id;
+        match channel_config.send_type {
+            SendType::Unreliable => {
+                if let Some(channel) = self.send_unreliable_channels.get_mut(&channel_id) {
+                    channel.set_max_memory_usage_bytes(channel_config.max_memory_usage_bytes);
+                }
+            }
+            SendType::ReliableOrdered { resend_time } | SendType::ReliableUnordered { resend_time } => {
+                if let Some(channel) = self.send_reliable_channels.get_mut(&channel_id) {
+                    channel.set_config(resend_time, channel_config.max_memory_usage_bytes);
+                }
+            }
+            SendType::ReliableChunked {
+                resend_time,
+                chunk_size,
+                max_message_size,
+            } => {
+                if let Some(channel) = self.send_reliable_channels.get_mut(&channel_id) {
+                    channel.set_config(resend_time, channel_config.max_memory_usage_bytes);
+                    channel.set_chunk_config(chunk_size, max_message_size);
+                }
+            }
+        }
+    }
+
+    /// Returns the size in bytes of the largest message that can be sent over the channel.
+    /// Bigger messages are dropped by unreliable channels and disconnect reliable channels.
+    /// Chunked channels send messages up to their `max_message_size`.
+    /// Returns None if the channel doesn't exist.
+    pub fn channel_max_message_size<I: Into<u8>>(&self, channel_id: I) -> Option<usize> {
+        let channel_id = channel_id.into();
+        if let Some(reliable_channel) = self.send_reliable_channels.get(&channel_id) {
+            Some(reliable_channel.max_message_size())
+        } else {
+            // Unreliable messages that don't fit in a tick are never sent
+            let unreliable_channel = self.send_unreliable_channels.get(&channel_id)?;
+            Some(
+                unreliable_channel
+                    .max_memory_usage_bytes()
+                    .min(self.available_bytes_per_tick as usize),
+            )
+        }
+    }
+
     /// Checks if the channel can send a message with the given size in bytes.
     pub fn can_send_message<I: Into<u8>>(&self, channel_id: I, size_bytes: usize) -> bool {
         let channel_id = channel_id.into();
@@ -318,35 +613,146 @@ impl RenetClient {
 
     /// Send a message to the server over a channel.
     pub fn send_message<I: Into<u8>, B: Into<Bytes>>(&mut self, channel_id: I, message: B) {
-        if self.is_disconnected() {
-            return;
-        }
+        self.split_channels().1.send_message(channel_id.into(), message.into());
+    }
+
+    /// Send a message to the server over a reliable channel, `on_ack` is called when the server acks the message.
+    /// The callback is dropped without being called if the client disconnects before the ack arrives.
+    ///
+    /// # Panics
+    /// If the channel doesn't exist or is not reliable.
+    ///
+    /// # Usage
+    /// ```
+    /// # use std::sync::{atomic::{AtomicBool, Ordering}, Arc};
+    /// # use renet::{ConnectionConfig, DefaultChannel, RenetClient};
+    /// # let mut client = RenetClient::new(ConnectionConfig::default());
+    /// let delivered = Arc::new(AtomicBool::new(false));
+    /// let on_ack = delivered.clone();
+    /// client.send_message_with_ack_callback(
+    ///     DefaultChannel::ReliableOrdered,
+    ///     "ready",
+    ///     Box::new(move || on_ack.store(true, Ordering::Relaxed)),
+    /// );
+    /// ```
+    pub fn send_message_with_ack_callback<I: Into<u8>, B: Into<Bytes>>(&mut self, channel_id: I, message: B, on_ack: AckCallback) {
+        self.split_channels()
+            .1
+            .send_message_with_ack_callback(channel_id.into(), message.into(), on_ack);
+    }
 
+    /// Returns the number of messages of a reliable channel waiting to be acked by the server, including the ones not sent yet.
+    /// A growing count means the server is not receiving the messages or the acks are lost.
+    /// Returns None if the channel doesn't exist or is not reliable.
+    pub fn unacked_message_count<I: Into<u8>>(&self, channel_id: I) -> Option<usize> {
         let channel_id = channel_id.into();
-        if let Some(reliable_channel) = self.send_reliable_channels.get_mut(&channel_id) {
-            if let Err(error) = reliable_channel.send_message(message.into()) {
-                self.disconnect_with_reason(DisconnectReason::SendChannelError { channel_id, error });
-            }
-        } else if let Some(unreliable_channel) = self.send_unreliable_channels.get_mut(&channel_id) {
-            unreliable_channel.send_message(message.into());
-        } else {
-            panic!("Called 'send_message' with invalid channel {channel_id}");
+        self.send_reliable_channels
+            .get(&channel_id)
+            .map(|channel| channel.unacked_message_count())
+    }
+
+    /// Returns the id the next message sent on a reliable channel will have, to match it with
+    /// [`acked_messages`](Self::acked_messages). Returns None if the channel doesn't exist or is not reliable.
+    pub fn next_message_id<I: Into<u8>>(&self, channel_id: I) -> Option<u64> {
+        let channel_id = channel_id.into();
+        self.send_reliable_channels
+            .get(&channel_id)
+            .map(|channel| channel.next_message_id())
+    }
+
+    /// Returns the id the next reliable message sent on the channel will have on the wire, compare it with the
+    /// [`expected_recv_sequence`](Self::expected_recv_sequence) of the server to debug desyncs.
+    /// Unlike [`next_message_id`](Self::next_message_id), chunked channels count each fragment.
+    /// Returns None if the channel doesn't exist or is not reliable.
+    pub fn next_send_sequence<I: Into<u8>>(&self, channel_id: I) -> Option<u64> {
+        let channel_id = channel_id.into();
+        self.send_reliable_channels
+            .get(&channel_id)
+            .map(|channel| channel.next_reliable_message_id())
+    }
+
+    /// Returns the id of the next reliable message expected from the server on the channel,
+    /// all the messages before it were received. Returns None if the channel doesn't exist or is not reliable.
+    pub fn expected_recv_sequence<I: Into<u8>>(&self, channel_id: I) -> Option<u64> {
+        let channel_id = channel_id.into();
+        self.receive_reliable_channels
+            .get(&channel_id)
+            .map(|channel| channel.expected_message_id())
+    }
+
+    /// Enables or disables the tracking of the acked messages of all the reliable channels, returned by
+    /// [`acked_messages`](Self::acked_messages). Disabled by default, since the ids are stored until they are drained:
+    /// only enable it for the channels that are drained, see [`set_channel_ack_notifications`](Self::set_channel_ack_notifications).
+    pub fn set_ack_notifications(&mut self, enabled: bool) {
+        for channel in self.send_reliable_channels.values_mut() {
+            channel.set_ack_notifications(enabled);
         }
     }
 
+    /// Enables or disables the tracking of the acked messages of a reliable channel.
+    /// Does nothing if the channel doesn't exist or is not reliable.
+    pub fn set_channel_ack_notifications<I: Into<u8>>(&mut self, channel_id: I, enabled: bool) {
+        if let Some(channel) = self.send_reliable_channels.get_mut(&channel_id.into()) {
+            channel.set_ack_notifications(enabled);
+        }
+    }
+
+    /// Returns true if the acked messages of any channel are tracked, see [`set_ack_notifications`](Self::set_ack_notifications).
+    pub fn ack_notifications(&self) -> bool {
+        self.send_reliable_channels.values().any(|channel| channel.ack_notifications())
+    }
+
+    /// Returns true if the acked messages of the channel are tracked, see [`set_channel_ack_notifications`](Self::set_channel_ack_notifications).
+    pub fn channel_ack_notifications<I: Into<u8>>(&self, channel_id: I) -> bool {
+        self.send_reliable_channels
+            .get(&channel_id.into())
+            .is_some_and(|channel| channel.ack_notifications())
+    }
+
+    /// Drains the ids of the messages of a reliable channel acked by the server since the last call,
+    /// requires [`set_ack_notifications`](Self::set_ack_notifications) or [`set_channel_ack_notifications`](Self::set_channel_ack_notifications).
+    /// Empty if the channel doesn't exist or is not reliable.
+    pub fn acked_messages<I: Into<u8>>(&mut self, channel_id: I) -> impl Iterator<Item = u64> + '_ {
+        let channel_id = channel_id.into();
+        self.send_reliable_channels
+            .get_mut(&channel_id)
+            .into_iter()
+            .flat_map(|channel| channel.drain_acked_messages())
+    }
+
+    /// Removes and returns the oldest queued message of an unreliable channel, to make room for newer messages.
+    ///
+    /// Does nothing for reliable channels: their messages have an id as soon as they are queued,
+    /// removing one would leave a gap the receiver waits for forever.
+    pub fn drop_oldest_queued_message<I: Into<u8>>(&mut self, channel_id: I) -> Option<Bytes> {
+        let channel_id = channel_id.into();
+        self.send_unreliable_channels.get_mut(&channel_id)?.drop_oldest_message()
+    }
+
     /// Receive a message from the server over a channel.
     pub fn receive_message<I: Into<u8>>(&mut self, channel_id: I) -> Option<Bytes> {
+        self.poll_schema_handshake();
+        self.split_channels().0.receive_message(channel_id.into())
+    }
+
+    /// Receive a message from the server over a channel, with the time its packet arrived.
+    /// The time is only available if the packet was processed with [`process_packet_timestamped`](Self::process_packet_timestamped).
+    pub fn receive_message_with_timestamp<I: Into<u8>>(&mut self, channel_id: I) -> Option<(Bytes, Option<Instant>)> {
+        self.poll_schema_handshake();
         if self.is_disconnected() {
             return None;
         }
 
         let channel_id = channel_id.into();
+        if self.schema_holds(channel_id) {
+            return None;
+        }
         if let Some(reliable_channel) = self.receive_reliable_channels.get_mut(&channel_id) {
-            reliable_channel.receive_message()
+            reliable_channel.receive_message_with_timestamp()
         } else if let Some(unreliable_channel) = self.receive_unreliable_channels.get_mut(&channel_id) {
-            unreliable_channel.receive_message()
+            unreliable_channel.receive_message_with_timestamp()
         } else {
-            panic!("Called 'receive_message' with invalid channel {channel_id}");
+            panic!("Called 'receive_message_with_timestamp' with invalid channel {channel_id}");
         }
     }
 
@@ -355,6 +761,10 @@ impl RenetClient {
     pub fn update(&mut self, duration: Duration) {
         self.current_time += duration;
         self.stats.update(self.current_time);
+        self.data_rate.update(duration);
+        for stats in self.channel_stats.values_mut() {
+            stats.update(self.current_time);
+        }
 
         for unreliable_channel in self.receive_unreliable_channels.values_mut() {
             unreliable_channel.discard_incomplete_old_slices(self.current_time);
@@ -376,6 +786,113 @@ impl RenetClient {
         for sequence in lost_packets.iter() {
             self.sent_packets.remove(sequence);
         }
+
+        #[cfg(feature = "conditioner")]
+        if let Some(conditioner) = &mut self.conditioner {
+            for packet in conditioner.take_incoming(self.current_time) {
+                self.handle_packet(&packet, None);
+            }
+        }
+
+        self.poll_schema_handshake();
+    }
+
+    /// Applies artificial latency, jitter, loss and duplication to the packets sent and received, for testing.
+    /// The change applies right away to the next packets, and None delivers the delayed packets in the next update.
+    ///
+    /// The packets delayed on reception are processed without their arrival time.
+    #[cfg(feature = "conditioner")]
+    pub fn set_network_conditions(&mut self, conditions: Option<NetworkConditions>) {
+        self.conditioner.get_or_insert_with(Conditioner::new).set_conditions(conditions);
+    }
+
+    #[cfg(feature = "conditioner")]
+    pub fn network_conditions(&self) -> Option<NetworkConditions> {
+        self.conditioner.as_ref()?.conditions()
+    }
+
+    /// Sends the schema version of the client to the server, the messages of the other channels are held until the server
+    /// accepts it. See [`SchemaHandshake`].
+    ///
+    /// # Panics
+    /// If the handshake channel is not a reliable channel in both directions.
+    pub fn set_schema_handshake(&mut self, handshake: SchemaHandshake) {
+        self.start_schema_handshake(SchemaState::client(handshake));
+        if let Some(schema) = &self.schema {
+            let hello = schema.hello();
+            self.send_message(schema.channel_id(), hello);
+        }
+    }
+
+    /// Returns the state of the schema handshake, None if no handshake was set.
+    pub fn schema_status(&self) -> Option<SchemaStatus> {
+        self.schema.as_ref().map(|schema| schema.status())
+    }
+
+    /// Returns the schema version negotiated with the peer, None until the handshake completes.
+    pub fn negotiated_version(&self) -> Option<u32> {
+        match self.schema_status()? {
+            SchemaStatus::Accepted { version, .. } => Some(version),
+            _ => None,
+        }
+    }
+
+    /// Returns the features negotiated with the peer, None until the handshake completes.
+    pub fn negotiated_features(&self) -> Option<u64> {
+        match self.schema_status()? {
+            SchemaStatus::Accepted { features, .. } => Some(features),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn start_schema_handshake(&mut self, schema: SchemaState) {
+        let channel_id = schema.channel_id();
+        assert!(
+            self.send_reliable_channels.contains_key(&channel_id) && self.receive_reliable_channels.contains_key(&channel_id),
+            "schema handshake channel {channel_id} must be reliable in both directions"
+        );
+        self.schema = Some(schema);
+    }
+
+    // Processes the handshake messages received, and disconnects when the versions are not compatible
+    pub(crate) fn poll_schema_handshake(&mut self) {
+        let Some(schema) = &mut self.schema else {
+            return;
+        };
+        if schema.is_accepted() {
+            return;
+        }
+
+        let channel_id = schema.channel_id();
+        let mut replies = vec![];
+        if let Some(channel) = self.receive_reliable_channels.get_mut(&channel_id) {
+            while let Some(message) = channel.receive_message() {
+                replies.extend(schema.process_message(&message, self.current_time));
+            }
+        }
+        for reply in replies {
+            self.send_message(channel_id, reply);
+        }
+
+        let Some(schema) = &self.schema else {
+            return;
+        };
+        let rejection_acked = self.unacked_message_count(channel_id) == Some(0);
+        if let Some(reason) = schema.disconnect_reason(rejection_acked, self.current_time) {
+            self.disconnect_with_reason(reason);
+        }
+    }
+
+    // Reason of the disconnection if the handshake was rejected, even if the rejection was not acked yet
+    pub(crate) fn schema_mismatch(&self) -> Option<DisconnectReason> {
+        self.schema.as_ref()?.disconnect_reason(true, self.current_time)
+    }
+
+    fn schema_holds(&self, channel_id: u8) -> bool {
+        match &self.schema {
+            Some(schema) => schema.channel_id() == channel_id || !schema.is_accepted(),
+            None => false,
+        }
     }
 
     /// Process a packet received from the server.
@@ -383,11 +900,42 @@ impl RenetClient {
     /// <strong>Note:</strong> This should only be called by the transport layer.
     /// </p>
     pub fn process_packet(&mut self, packet: &[u8]) {
+        self.process_packet_internal(packet, None);
+    }
+
+    /// Process a packet received from the server, storing when it arrived for
+    /// [`receive_message_with_timestamp`](Self::receive_message_with_timestamp).
+    /// <p style="background:rgba(77,220,255,0.16);padding:0.5em;">
+    /// <strong>Note:</strong> This should only be called by the transport layer.
+    /// </p>
+    pub fn process_packet_timestamped(&mut self, packet: &[u8], arrived_at: Instant) {
+        self.process_packet_internal(packet, Some(arrived_at));
+    }
+
+    fn process_packet_internal(&mut self, packet: &[u8], arrived_at: Option<Instant>) {
+        #[cfg(feature = "conditioner")]
+        match &mut self.conditioner {
+            Some(conditioner) if conditioner.conditions().is_some() => {
+                conditioner.receive(packet.to_vec(), self.current_time);
+                for packet in conditioner.take_incoming(self.current_time) {
+                    self.handle_packet(&packet, None);
+                }
+            }
+            _ => self.handle_packet(packet, arrived_at),
+        }
+        #[cfg(not(feature = "conditioner"))]
+        self.handle_packet(packet, arrived_at);
+    }
+
+    fn handle_packet(&mut self, packet: &[u8], arrived_at: Option<Instant>) {
         if self.is_disconnected() {
             return;
         }
 
-        self.stats.received_packet(packet.len() as u64);
+        let packet_len = packet.len() as u64;
+        self.stats.received_packet(packet_len);
+        self.data_rate.received(packet_len);
+        self.bytes_received_total += packet_len;
         let mut octets = octets::Octets::with_slice(packet);
         let packet = match Packet::from_bytes(&mut octets) {
             Err(err) => {
@@ -398,6 +946,12 @@ impl RenetClient {
         };
 
         self.add_pending_ack(packet.sequence());
+        self.stats.received_sequence(packet.sequence());
+        if let Some(channel_id) = packet.channel_id() {
+            if self.receive_reliable_channels.contains_key(&channel_id) || self.receive_unreliable_channels.contains_key(&channel_id) {
+                self.channel_stats_mut(channel_id).received_packet(packet_len);
+            }
+        }
 
         match packet {
             Packet::SmallReliable { channel_id, messages, .. } => {
@@ -407,7 +961,7 @@ impl RenetClient {
                 };
 
                 for (message_id, message) in messages {
-                    if let Err(error) = channel.process_message(message, message_id) {
+                    if let Err(error) = channel.process_message(message, message_id, arrived_at) {
                         self.disconnect_with_reason(DisconnectReason::ReceiveChannelError { channel_id, error });
                         return;
                     }
@@ -420,7 +974,7 @@ impl RenetClient {
                 };
 
                 for message in messages {
-                    channel.process_message(message);
+                    channel.process_message(message, arrived_at);
                 }
             }
             Packet::ReliableSlice { channel_id, slice, .. } => {
@@ -429,7 +983,7 @@ impl RenetClient {
                     return;
                 };
 
-                if let Err(error) = channel.process_slice(slice) {
+                if let Err(error) = channel.process_slice(slice, arrived_at) {
                     self.disconnect_with_reason(DisconnectReason::ReceiveChannelError { channel_id, error });
                 }
             }
@@ -439,7 +993,7 @@ impl RenetClient {
                     return;
                 };
 
-                if let Err(error) = channel.process_slice(slice, self.current_time) {
+                if let Err(error) = channel.process_slice(slice, self.current_time, arrived_at) {
                     self.disconnect_with_reason(DisconnectReason::ReceiveChannelError { channel_id, error });
                 }
             }
@@ -462,6 +1016,7 @@ impl RenetClient {
                     if self.rtt < f64::EPSILON {
                         self.rtt = rtt;
                     } else {
+                        self.jitter = self.jitter * 0.75 + (self.rtt - rtt).abs() * 0.25;
                         self.rtt = self.rtt * 0.875 + rtt * 0.125;
                     }
 
@@ -495,6 +1050,8 @@ impl RenetClient {
     /// <strong>Note:</strong> This should only be called by the transport layer.
     /// </p>
     pub fn get_packets_to_send(&mut self) -> Vec<Payload> {
+        // Answer the handshake received since the last update in the same tick
+        self.poll_schema_handshake();
         let mut packets: Vec<Packet> = vec![];
         if self.is_disconnected() {
             return vec![];
@@ -605,14 +1162,36 @@ impl RenetClient {
             };
 
             bytes_sent += len as u64;
+            if let Some(channel_id) = packet.channel_id() {
+                self.channel_stats_mut(channel_id).sent_packets(1, len as u64);
+            }
             serialized_packets.push(buffer[..len].to_vec());
         }
 
         self.stats.sent_packets(serialized_packets.len() as u64, bytes_sent);
+        self.data_rate.sent(bytes_sent);
+        self.bytes_sent_total += bytes_sent;
+
+        #[cfg(feature = "conditioner")]
+        if let Some(conditioner) = &mut self.conditioner {
+            for packet in serialized_packets {
+                conditioner.send(packet, self.current_time);
+            }
+            return conditioner.take_outgoing(self.current_time);
+        }
 
         serialized_packets
     }
 
+    fn channel_stats_mut(&mut self, channel_id: u8) -> &mut ConnectionStats {
+        let current_time = self.current_time;
+        self.channel_stats.entry(channel_id).or_insert_with(|| {
+            let mut stats = ConnectionStats::new();
+            stats.update(current_time);
+            stats
+        })
+    }
+
     fn add_pending_ack(&mut self, sequence: u64) {
         if self.pending_acks.is_empty() {
             self.pending_acks.push(sequence..sequence + 1);
@@ -687,6 +1266,22 @@ impl RenetClient {
         }
     }
 
+    // Borrows the receive and send channels separately
+    pub(crate) fn split_channels(&mut self) -> (ConnectionReceive<'_>, ConnectionSend<'_>) {
+        let receive = ConnectionReceive {
+            disconnected: matches!(self.connection_status, RenetConnectionStatus::Disconnected { .. }),
+            schema: self.schema.as_ref().map(|schema| (schema.channel_id(), schema.is_accepted())),
+            reliable_channels: &mut self.receive_reliable_channels,
+            unreliable_channels: &mut self.receive_unreliable_channels,
+        };
+        let send = ConnectionSend {
+            connection_status: &mut self.connection_status,
+            reliable_channels: &mut self.send_reliable_channels,
+            unreliable_channels: &mut self.send_unreliable_channels,
+        };
+        (receive, send)
+    }
+
     pub(crate) fn disconnect_with_reason(&mut self, reason: DisconnectReason) {
         if !self.is_disconnected() {
             self.connection_status = RenetConnectionStatus::Disconnected { reason };
@@ -694,6 +1289,74 @@ impl RenetClient {
     }
 }
 
+pub(crate) struct ConnectionReceive<'a> {
+    disconnected: bool,
+    // Handshake channel and whether the handshake was accepted
+    schema: Option<(u8, bool)>,
+    reliable_channels: &'a mut HashMap<u8, ReceiveChannelReliable>,
+    unreliable_channels: &'a mut HashMap<u8, ReceiveChannelUnreliable>,
+}
+
+pub(crate) struct ConnectionSend<'a> {
+    connection_status: &'a mut RenetConnectionStatus,
+    reliable_channels: &'a mut HashMap<u8, SendChannelReliable>,
+    unreliable_channels: &'a mut HashMap<u8, SendChannelUnreliable>,
+}
+
+impl ConnectionReceive<'_> {
+    pub fn receive_message(&mut self, channel_id: u8) -> Option<Bytes> {
+        if self.disconnected {
+            return None;
+        }
+        if let Some((schema_channel_id, accepted)) = self.schema {
+            if schema_channel_id == channel_id || !accepted {
+                return None;
+            }
+        }
+
+        if let Some(reliable_channel) = self.reliable_channels.get_mut(&channel_id) {
+            reliable_channel.receive_message()
+        } else if let Some(unreliable_channel) = self.unreliable_channels.get_mut(&channel_id) {
+            unreliable_channel.receive_message()
+        } else {
+            panic!("Called 'receive_message' with invalid channel {channel_id}");
+        }
+    }
+}
+
+impl ConnectionSend<'_> {
+    pub fn send_message(&mut self, channel_id: u8, message: Bytes) {
+        if matches!(self.connection_status, RenetConnectionStatus::Disconnected { .. }) {
+            return;
+        }
+
+        if let Some(reliable_channel) = self.reliable_channels.get_mut(&channel_id) {
+            if let Err(error) = reliable_channel.send_message(message) {
+                let reason = DisconnectReason::SendChannelError { channel_id, error };
+                *self.connection_status = RenetConnectionStatus::Disconnected { reason };
+            }
+        } else if let Some(unreliable_channel) = self.unreliable_channels.get_mut(&channel_id) {
+            unreliable_channel.send_message(message);
+        } else {
+            panic!("Called 'send_message' with invalid channel {channel_id}");
+        }
+    }
+
+    pub fn send_message_with_ack_callback(&mut self, channel_id: u8, message: Bytes, on_ack: AckCallback) {
+        if matches!(self.connection_status, RenetConnectionStatus::Disconnected { .. }) {
+            return;
+        }
+
+        let Some(reliable_channel) = self.reliable_channels.get_mut(&channel_id) else {
+            panic!("Called 'send_message_with_ack_callback' with invalid or unreliable channel {channel_id}");
+        };
+        if let Err(error) = reliable_channel.send_message_with_ack_callback(message, on_ack) {
+            let reason = DisconnectReason::SendChannelError { channel_id, error };
+            *self.connection_status = RenetConnectionStatus::Disconnected { reason };
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -752,6 +1415,66 @@ mod tests {
         assert_eq!(connection.pending_acks, vec![]);
     }
 
+    #[test]
+    fn channel_type() {
+        let connection = RenetClient::new(ConnectionConfig::default());
+        assert_eq!(connection.channel_type(DefaultChannel::Unreliable), Some(ChannelType::Unreliable));
+        assert_eq!(
+            connection.channel_type(DefaultChannel::ReliableOrdered),
+            Some(ChannelType::ReliableOrdered)
+        );
+        assert_eq!(
+            connection.channel_type(DefaultChannel::ReliableUnordered),
+            Some(ChannelType::ReliableUnordered)
+        );
+        assert_eq!(connection.channel_type(42), None);
+    }
+
+    #[test]
+    fn channel_max_message_size() {
+        let config = ConnectionConfig {
+            available_bytes_per_tick: 1024 * 1024,
+            ..Default::default()
+        };
+        let connection = RenetClient::new(config);
+        assert_eq!(connection.channel_max_message_size(DefaultChannel::Unreliable), Some(1024 * 1024));
+        assert_eq!(
+            connection.channel_max_message_size(DefaultChannel::ReliableOrdered),
+            Some(5 * 1024 * 1024)
+        );
+        assert_eq!(connection.channel_max_message_size(42), None);
+    }
+
+    #[test]
+    fn drop_oldest_queued_message() {
+        let mut connection = RenetClient::new(ConnectionConfig::default());
+        connection.send_message(DefaultChannel::Unreliable, "first");
+        connection.send_message(DefaultChannel::Unreliable, "second");
+        connection.send_message(DefaultChannel::ReliableOrdered, "reliable");
+        let available_memory = connection.channel_available_memory(DefaultChannel::Unreliable);
+
+        assert_eq!(connection.drop_oldest_queued_message(DefaultChannel::Unreliable).unwrap(), "first");
+        assert_eq!(
+            connection.channel_available_memory(DefaultChannel::Unreliable),
+            available_memory + "first".len()
+        );
+        assert_eq!(connection.drop_oldest_queued_message(DefaultChannel::Unreliable).unwrap(), "second");
+        assert!(connection.drop_oldest_queued_message(DefaultChannel::Unreliable).is_none());
+        assert!(connection.drop_oldest_queued_message(DefaultChannel::ReliableOrdered).is_none());
+    }
+
+    #[test]
+    fn unacked_message_count() {
+        let mut connection = RenetClient::new(ConnectionConfig::default());
+        connection.send_message(DefaultChannel::ReliableOrdered, "first");
+        connection.send_message(DefaultChannel::ReliableOrdered, "second");
+
+        assert_eq!(connection.unacked_message_count(DefaultChannel::ReliableOrdered), Some(2));
+        assert_eq!(connection.unacked_message_count(DefaultChannel::ReliableUnordered), Some(0));
+        assert_eq!(connection.unacked_message_count(DefaultChannel::Unreliable), None);
+        assert_eq!(connection.unacked_message_count(42), None);
+    }
+
     #[test]
     fn discard_old_packets() {
         let mut connection = RenetClient::new(ConnectionConfig::default());
@@ -767,4 +1490,22 @@ mod tests {
         connection.update(Duration::from_secs(4));
         assert_eq!(connection.sent_packets.len(), 0);
     }
+
+    #[test]
+    fn config_from_channels() {
+        let config_of = |channel_id| DefaultChannel::config().remove(channel_id);
+        let config = ConnectionConfig::from_channels([
+            (5, config_of(2), Direction::Send),
+            (3, config_of(0), Direction::Bidirectional),
+            (4, config_of(1), Direction::Receive),
+        ]);
+
+        let ids = |channels: &[ChannelConfig]| channels.iter().map(|c| c.channel_id).collect::<Vec<_>>();
+        assert_eq!(ids(&config.server_channels_config), vec![5, 3]);
+        assert_eq!(ids(&config.client_channels_config), vec![3, 4]);
+        assert_eq!(
+            config.available_bytes_per_tick,
+            ConnectionConfig::default().available_bytes_per_tick
+        );
+    }
 }
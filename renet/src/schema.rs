@@ -0,0 +1,287 @@
+use std::{ops::RangeInclusive, time::Duration};
+
+use bytes::Bytes;
+
+use crate::{ChannelConfig, DisconnectReason, SendType};
+
+// Time the server waits for the rejection to be acked before disconnecting the client
+const REJECT_TIMEOUT: Duration = Duration::from_secs(1);
+
+const HELLO: u8 = 0;
+const ACCEPT: u8 = 1;
+const REJECT: u8 = 2;
+
+/// Application schema version exchanged by a client and the server when it connects,
+/// before the messages of the other channels are delivered.
+///
+/// The client sends its version and feature flags on a reserved reliable channel, and the server accepts it if the
+/// version is in its [`accept_versions`](Self::accept_versions) range. The negotiated version is the lowest of both,
+/// and the negotiated features the ones enabled on both sides. Rejected clients are disconnected with
+/// [`DisconnectReason::SchemaMismatch`] on both sides.
+///
+/// Until the handshake completes, receiving from the other channels returns None, their messages are held in the channels.
+/// Messages on the handshake channel are never returned. Add the channel with [`channel_config`](Self::channel_config)
+/// to both the server and client channels, and set the handshake in the client with
+/// [`RenetClient::set_schema_handshake`](crate::RenetClient::set_schema_handshake) and in the server with
+/// [`RenetServer::set_schema_handshake`](crate::RenetServer::set_schema_handshake) before the clients connect.
+///
+/// # Usage
+/// ```
+/// # use std::time::Duration;
+/// # use renet::{ConnectionConfig, DefaultChannel, RenetServer, SchemaHandshake, SchemaStatus};
+/// const SCHEMA_CHANNEL: u8 = 3;
+///
+/// let mut channels = DefaultChannel::config();
+/// channels.push(SchemaHandshake::channel_config(SCHEMA_CHANNEL));
+/// let connection_config = ConnectionConfig {
+///     server_channels_config: channels.clone(),
+///     client_channels_config: channels,
+///     ..Default::default()
+/// };
+///
+/// let mut server = RenetServer::new(connection_config);
+/// server.set_schema_handshake(Some(SchemaHandshake::new(SCHEMA_CHANNEL, 4).accept_versions(3..=4)));
+/// let mut client = server.new_local_client(1);
+/// client.set_schema_handshake(SchemaHandshake::new(SCHEMA_CHANNEL, 3));
+///
+/// // The hello is answered when the server sends its packets, and the answer processed in the next update of the client
+/// server.process_local_client(1, &mut client).unwrap();
+/// server.process_local_client(1, &mut client).unwrap();
+/// client.update(Duration::ZERO);
+/// assert_eq!(client.negotiated_version(), Some(3));
+/// assert_eq!(server.schema_status(1), Some(SchemaStatus::Accepted { version: 3, features: 0 }));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SchemaHandshake {
+    channel_id: u8,
+    version: u32,
+    features: u64,
+    accepted_versions: RangeInclusive<u32>,
+}
+
+/// State of the schema handshake of a connection, see [`SchemaHandshake`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SchemaStatus {
+    /// Waiting for the peer, the messages of the other channels are held.
+    Pending,
+    /// The versions are compatible, the messages of the other channels are delivered.
+    Accepted { version: u32, features: u64 },
+    /// The versions are not compatible, the connection is disconnected.
+    Rejected { remote_version: u32 },
+}
+
+impl SchemaHandshake {
+    /// Only peers with the same version are accepted by default, and no features are enabled.
+    pub fn new(channel_id: impl Into<u8>, version: u32) -> Self {
+        Self {
+            channel_id: channel_id.into(),
+            version,
+            features: 0,
+            accepted_versions: version..=version,
+        }
+    }
+
+    /// Bitset of the optional features supported, only the features of both sides are negotiated.
+    pub fn features(mut self, features: u64) -> Self {
+        self.features = features;
+        self
+    }
+
+    /// Versions of the clients accepted by the server, only used in the server.
+    pub fn accept_versions(mut self, versions: RangeInclusive<u32>) -> Self {
+        self.accepted_versions = versions;
+        self
+    }
+
+    pub fn channel_id(&self) -> u8 {
+        self.channel_id
+    }
+
+    pub fn version(&self) -> u32 {
+        self.version
+    }
+
+    /// Config of the handshake channel, add it to the server and client channels.
+    pub fn channel_config(channel_id: impl Into<u8>) -> ChannelConfig {
+        ChannelConfig {
+            channel_id: channel_id.into(),
+            max_memory_usage_bytes: 64 * 1024,
+            send_type: SendType::ReliableOrdered {
+                resend_time: Duration::from_millis(300),
+            },
+        }
+    }
+}
+
+// Handshake of a connection, the client side sends the hello and the server side answers it
+#[derive(Debug)]
+pub(crate) struct SchemaState {
+    handshake: SchemaHandshake,
+    is_server: bool,
+    status: SchemaStatus,
+    rejected_at: Option<Duration>,
+}
+
+impl SchemaState {
+    pub fn client(handshake: SchemaHandshake) -> Self {
+        Self {
+            handshake,
+            is_server: false,
+            status: SchemaStatus::Pending,
+            rejected_at: None,
+        }
+    }
+
+    pub fn server(handshake: SchemaHandshake) -> Self {
+        Self {
+            is_server: true,
+            ..Self::client(handshake)
+        }
+    }
+
+    pub fn channel_id(&self) -> u8 {
+        self.handshake.channel_id
+    }
+
+    pub fn status(&self) -> SchemaStatus {
+        self.status
+    }
+
+    pub fn is_accepted(&self) -> bool {
+        matches!(self.status, SchemaStatus::Accepted { .. })
+    }
+
+    pub fn hello(&self) -> Bytes {
+        encode(HELLO, self.handshake.version, Some(self.handshake.features))
+    }
+
+    // Returns the reply to send to the peer
+    pub fn process_message(&mut self, message: &[u8], now: Duration) -> Option<Bytes> {
+        if self.status != SchemaStatus::Pending {
+            return None;
+        }
+        let Some((kind, version, features)) = decode(message) else {
+            log::warn!("Received invalid schema handshake message");
+            return None;
+        };
+
+        match (self.is_server, kind) {
+            (true, HELLO) => {
+                if self.handshake.accepted_versions.contains(&version) {
+                    let version = version.min(self.handshake.version);
+                    let features = features & self.handshake.features;
+                    self.status = SchemaStatus::Accepted { version, features };
+                    Some(encode(ACCEPT, version, Some(features)))
+                } else {
+                    self.status = SchemaStatus::Rejected { remote_version: version };
+                    self.rejected_at = Some(now);
+                    Some(encode(REJECT, self.handshake.version, None))
+                }
+            }
+            (false, ACCEPT) => {
+                self.status = SchemaStatus::Accepted { version, features };
+                None
+            }
+            (false, REJECT) => {
+                self.status = SchemaStatus::Rejected { remote_version: version };
+                None
+            }
+            _ => {
+                log::warn!("Received unexpected schema handshake message {kind}");
+                None
+            }
+        }
+    }
+
+    // The client disconnects as soon as it's rejected, the server waits for the rejection to arrive
+    pub fn disconnect_reason(&self, rejection_acked: bool, now: Duration) -> Option<DisconnectReason> {
+        let SchemaStatus::Rejected { remote_version } = self.status else {
+            return None;
+        };
+        if self.is_server && !rejection_acked && self.rejected_at.is_some_and(|rejected_at| now - rejected_at < REJECT_TIMEOUT) {
+            return None;
+        }
+
+        Some(DisconnectReason::SchemaMismatch {
+            local_version: self.handshake.version,
+            remote_version,
+        })
+    }
+}
+
+// Kind (u8), version (u32) and the optional features (u64)
+fn encode(kind: u8, version: u32, features: Option<u64>) -> Bytes {
+    let mut message = Vec::with_capacity(13);
+    message.push(kind);
+    message.extend_from_slice(&version.to_le_bytes());
+    if let Some(features) = features {
+        message.extend_from_slice(&features.to_le_bytes());
+    }
+    message.into()
+}
+
+fn decode(message: &[u8]) -> Option<(u8, u32, u64)> {
+    let (&kind, rest) = message.split_first()?;
+    let version = u32::from_le_bytes(rest.get(0..4)?.try_into().ok()?);
+    let features = match rest.get(4..12) {
+        Some(features) => u64::from_le_bytes(features.try_into().ok()?),
+        None => 0,
+    };
+    Some((kind, version, features))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn negotiate_schema() {
+        let handshake = SchemaHandshake::new(0, 5).features(0b011).accept_versions(4..=6);
+        let mut server = SchemaState::server(handshake);
+        let mut client = SchemaState::client(SchemaHandshake::new(0, 4).features(0b110));
+
+        let reply = server.process_message(&client.hello(), Duration::ZERO).unwrap();
+        assert_eq!(
+            server.status(),
+            SchemaStatus::Accepted {
+                version: 4,
+                features: 0b010
+            }
+        );
+        assert_eq!(client.process_message(&reply, Duration::ZERO), None);
+        assert_eq!(client.status(), server.status());
+
+        // Ignored once the handshake completed
+        assert_eq!(server.process_message(&client.hello(), Duration::ZERO), None);
+        assert_eq!(server.disconnect_reason(false, Duration::ZERO), None);
+    }
+
+    #[test]
+    fn reject_schema() {
+        let mut server = SchemaState::server(SchemaHandshake::new(0, 2));
+        let mut client = SchemaState::client(SchemaHandshake::new(0, 1));
+
+        let reply = server.process_message(&client.hello(), Duration::ZERO).unwrap();
+        assert_eq!(server.status(), SchemaStatus::Rejected { remote_version: 1 });
+        let reason = DisconnectReason::SchemaMismatch {
+            local_version: 2,
+            remote_version: 1,
+        };
+        assert_eq!(server.disconnect_reason(false, Duration::ZERO), None);
+        assert_eq!(server.disconnect_reason(true, Duration::ZERO), Some(reason));
+        assert_eq!(server.disconnect_reason(false, REJECT_TIMEOUT), Some(reason));
+
+        client.process_message(&reply, Duration::ZERO);
+        assert_eq!(
+            client.disconnect_reason(false, Duration::ZERO),
+            Some(DisconnectReason::SchemaMismatch {
+                local_version: 1,
+                remote_version: 2,
+            })
+        );
+
+        let mut server = SchemaState::server(SchemaHandshake::new(0, 2));
+        assert_eq!(server.process_message(&[HELLO, 1], Duration::ZERO), None);
+        assert_eq!(server.status(), SchemaStatus::Pending);
+    }
+}
@@ -1,9 +1,17 @@
-use crate::error::{ClientNotFound, DisconnectReason};
+use crate::channel::{AckCallback, ChannelConfig, ChannelType, SendType};
+use crate::channel_group::{send_to_members, ChannelGroup, GroupChannel, GroupChannelHandle, GroupId};
+use crate::error::{ClientNotFound, ConfigError, DisconnectReason};
 use crate::packet::Payload;
-use crate::remote_connection::{ConnectionConfig, NetworkInfo, RenetClient};
+use crate::remote_connection::{
+    assert_valid_data_rate_smoothing, ChannelNetworkInfo, ConnectionConfig, ConnectionReceive, ConnectionSend, DataRate, NetworkInfo,
+    RenetClient, DEFAULT_DATA_RATE_SMOOTHING,
+};
+use crate::schema::{SchemaHandshake, SchemaState, SchemaStatus};
 use crate::ClientId;
-use std::collections::{HashMap, VecDeque};
-use std::time::Duration;
+#[cfg(feature = "conditioner")]
+use crate::NetworkConditions;
+use std::collections::{BTreeSet, HashMap, HashSet, VecDeque};
+use std::time::{Duration, Instant};
 
 use bytes::Bytes;
 
@@ -15,20 +23,84 @@ pub enum ServerEvent {
     ClientDisconnected { client_id: ClientId, reason: DisconnectReason },
 }
 
+/// Transport layer of a [`RenetServer`], used by the server methods that need to send packets immediately.
+pub trait ServerTransport {
+    /// Sends the packets of all connected clients.
+    fn send_packets(&mut self, server: &mut RenetServer);
+
+    /// Disconnects a client from the transport and removes its connection from the server.
+    fn disconnect(&mut self, client_id: ClientId, server: &mut RenetServer);
+}
+
+/// Shutdown of a server transport that lets the clients know the server is closing, instead of waiting for their timeout.
+pub trait GracefulShutdown {
+    /// Sends the pending packets, disconnects all clients and stops accepting new connections.
+    /// The connections are removed from the server.
+    fn begin_shutdown(&mut self, server: &mut RenetServer);
+
+    /// Returns true once the transport was shut down and all connections are closed,
+    /// the transport can then be dropped without losing the last packets.
+    fn is_shutdown_complete(&self) -> bool;
+}
+
 #[derive(Debug)]
 #[cfg_attr(feature = "bevy", derive(bevy_ecs::resource::Resource))]
 pub struct RenetServer {
     connections: HashMap<ClientId, RenetClient>,
     connection_config: ConnectionConfig,
+    // Configs of the server channels when the server was created, used by the clients to receive them
+    client_receive_configs: HashMap<u8, ChannelConfig>,
     events: VecDeque<ServerEvent>,
+    channel_groups: HashMap<GroupId, ChannelGroup>,
+    next_group_id: u64,
+    current_tick: u64,
+    last_receive_ticks: HashMap<ClientId, u64>,
+    // Bytes sent and received by the removed connections
+    removed_bytes_sent: u64,
+    removed_bytes_received: u64,
+    // Channels with ack notifications, applied to the new clients
+    ack_notification_channels: BTreeSet<u8>,
+    // Applied to the new clients
+    data_rate_smoothing: f64,
+    #[cfg(feature = "conditioner")]
+    network_conditions: Option<NetworkConditions>,
+    schema_handshake: Option<SchemaHandshake>,
+}
+
+/// The receive half of a [`RenetServer`], see [`RenetServer::split_borrow_mut`].
+pub struct RenetServerReceive<'a> {
+    connections: HashMap<ClientId, ConnectionReceive<'a>>,
+}
+
+/// The send half of a [`RenetServer`], see [`RenetServer::split_borrow_mut`].
+pub struct RenetServerSend<'a> {
+    connections: HashMap<ClientId, ConnectionSend<'a>>,
 }
 
 impl RenetServer {
     pub fn new(connection_config: ConnectionConfig) -> Self {
+        let client_receive_configs = connection_config
+            .server_channels_config
+            .iter()
+            .map(|channel_config| (channel_config.channel_id, channel_config.clone()))
+            .collect();
+
         Self {
             connections: HashMap::new(),
             connection_config,
+            client_receive_configs,
             events: VecDeque::new(),
+            channel_groups: HashMap::new(),
+            next_group_id: 0,
+            current_tick: 0,
+            last_receive_ticks: HashMap::new(),
+            removed_bytes_sent: 0,
+            removed_bytes_received: 0,
+            ack_notification_channels: BTreeSet::new(),
+            data_rate_smoothing: DEFAULT_DATA_RATE_SMOOTHING,
+            #[cfg(feature = "conditioner")]
+            network_conditions: None,
+            schema_handshake: None,
         }
     }
 
@@ -44,7 +116,19 @@ impl RenetServer {
         let mut connection = RenetClient::new_from_server(self.connection_config.clone());
         // Consider newly added connections as connected
         connection.set_connected();
+        for &channel_id in self.ack_notification_channels.iter() {
+            connection.set_channel_ack_notifications(channel_id, true);
+        }
+        connection.set_data_rate_smoothing(self.data_rate_smoothing);
+        #[cfg(feature = "conditioner")]
+        if self.network_conditions.is_some() {
+            connection.set_network_conditions(self.network_conditions);
+        }
+        if let Some(handshake) = &self.schema_handshake {
+            connection.start_schema_handshake(SchemaState::server(handshake.clone()));
+        }
         self.connections.insert(client_id, connection);
+        self.last_receive_ticks.insert(client_id, self.current_tick);
         self.events.push_back(ServerEvent::ClientConnected { client_id })
     }
 
@@ -99,6 +183,22 @@ impl RenetServer {
         }
     }
 
+    /// Returns the loss of the packets sent by the client or 0.0 if the client is not found
+    pub fn receive_packet_loss(&self, client_id: ClientId) -> f64 {
+        match self.connections.get(&client_id) {
+            Some(connection) => connection.receive_packet_loss(),
+            None => 0.0,
+        }
+    }
+
+    /// Returns the variation of the round-time trip for the client or 0.0 if the client is not found
+    pub fn jitter(&self, client_id: ClientId) -> f64 {
+        match self.connections.get(&client_id) {
+            Some(connection) => connection.jitter(),
+            None => 0.0,
+        }
+    }
+
     /// Returns the bytes sent per seconds for the client or 0.0 if the client is not found
     pub fn bytes_sent_per_sec(&self, client_id: ClientId) -> f64 {
         match self.connections.get(&client_id) {
@@ -115,6 +215,25 @@ impl RenetServer {
         }
     }
 
+    /// Returns the average bytes sent and received per second of a client, see [`RenetClient::data_rate`].
+    /// Returns None if the client is not found.
+    pub fn client_data_rate(&self, client_id: ClientId) -> Option<DataRate> {
+        self.connections.get(&client_id).map(|connection| connection.data_rate())
+    }
+
+    /// Sets the smoothing factor of the average data rate of the current and future clients,
+    /// see [`RenetClient::set_data_rate_smoothing`].
+    ///
+    /// # Panics
+    /// If the smoothing is not in the range (0, 1].
+    pub fn set_data_rate_smoothing(&mut self, smoothing: f64) {
+        assert_valid_data_rate_smoothing(smoothing);
+        self.data_rate_smoothing = smoothing;
+        for connection in self.connections.values_mut() {
+            connection.set_data_rate_smoothing(smoothing);
+        }
+    }
+
     /// Returns all network informations for the client
     pub fn network_info(&self, client_id: ClientId) -> Result<NetworkInfo, ClientNotFound> {
         match self.connections.get(&client_id) {
@@ -123,6 +242,14 @@ impl RenetServer {
         }
     }
 
+    /// Returns the traffic of each channel of the client, see [`RenetClient::channels_network_info`].
+    pub fn channels_network_info(&self, client_id: ClientId) -> Result<Vec<ChannelNetworkInfo>, ClientNotFound> {
+        match self.connections.get(&client_id) {
+            Some(connection) => Ok(connection.channels_network_info()),
+            None => Err(ClientNotFound),
+        }
+    }
+
     /// Removes a connection from the server, emits an disconnect server event.
     /// It does nothing if the client does not exits.
     /// <p style="background:rgba(77,220,255,0.16);padding:0.5em;">
@@ -130,7 +257,15 @@ impl RenetServer {
     /// </p>
     pub fn remove_connection(&mut self, client_id: ClientId) {
         if let Some(connection) = self.connections.remove(&client_id) {
-            let reason = connection.disconnect_reason().unwrap_or(DisconnectReason::Transport);
+            self.last_receive_ticks.remove(&client_id);
+            self.remove_from_channel_groups(client_id);
+            self.removed_bytes_sent += connection.bytes_sent_total();
+            self.removed_bytes_received += connection.bytes_received_total();
+            // Rejected clients may disconnect before the server disconnects them
+            let reason = connection
+                .disconnect_reason()
+                .or_else(|| connection.schema_mismatch())
+                .unwrap_or(DisconnectReason::Transport);
             self.events.push_back(ServerEvent::ClientDisconnected { client_id, reason });
         }
     }
@@ -142,6 +277,51 @@ impl RenetServer {
         }
     }
 
+    /// Sends a final message to a client and disconnects it in the same frame.
+    /// The packets are sent only once, so the message is lost if they are dropped,
+    /// use a reliable channel to keep it in order with the previous messages.
+    /// It does nothing if the client does not exist.
+    pub fn disconnect_client_with_payload<I: Into<u8>, B: Into<Bytes>>(
+        &mut self,
+        transport: &mut impl ServerTransport,
+        client_id: ClientId,
+        channel_id: I,
+        payload: B,
+    ) {
+        if !self.is_connected(client_id) {
+            return;
+        }
+
+        self.send_message(client_id, channel_id, payload);
+        transport.send_packets(self);
+        self.disconnect(client_id);
+        transport.disconnect(client_id, self);
+    }
+
+    /// Disconnects the clients that did not send any packet in the last `max_silent_ticks` calls to [`update`](Self::update),
+    /// also disconnecting them from the transport. Returns the disconnected clients.
+    pub fn disconnect_stale_clients(&mut self, transport: &mut impl ServerTransport, max_silent_ticks: u64) -> Vec<ClientId> {
+        let stale_clients: Vec<ClientId> = self
+            .last_receive_ticks
+            .iter()
+            .filter(|(_, &last_receive_tick)| self.current_tick - last_receive_tick > max_silent_ticks)
+            .map(|(&client_id, _)| client_id)
+            .filter(|&client_id| self.is_connected(client_id))
+            .collect();
+
+        for &client_id in stale_clients.iter() {
+            self.disconnect(client_id);
+            transport.disconnect(client_id, self);
+        }
+
+        stale_clients
+    }
+
+    /// Returns the number of times [`update`](Self::update) was called.
+    pub fn current_tick(&self) -> u64 {
+        self.current_tick
+    }
+
     /// Disconnects all client.
     pub fn disconnect_all(&mut self) {
         for connection in self.connections.values_mut() {
@@ -171,6 +351,93 @@ impl RenetServer {
         }
     }
 
+    /// Registers a new group of clients that messages can be sent to with [`Self::send_to_group`].
+    pub fn create_channel_group(&mut self, name: &str, clients: HashSet<ClientId>) -> GroupId {
+        self.insert_channel_group(ChannelGroup::new(name, clients, None))
+    }
+
+    /// Registers a channel bound to a set of clients, to send messages to all of them with [`GroupChannel::send`].
+    /// The group channel is also a [`ChannelGroup`], see [`GroupChannelHandle::group_id`].
+    ///
+    /// # Panics
+    /// If the channel doesn't exist.
+    ///
+    /// # Usage
+    /// ```
+    /// # use std::collections::HashSet;
+    /// # use renet::{ConnectionConfig, DefaultChannel, RenetServer};
+    /// # let mut server = RenetServer::new(ConnectionConfig::default());
+    /// let team = server.create_group_channel(DefaultChannel::ReliableOrdered.into(), HashSet::from([1, 2]));
+    /// if let Some(mut group_channel) = server.group_channel(team) {
+    ///     group_channel.send("round started");
+    /// }
+    /// ```
+    pub fn create_group_channel(&mut self, channel_id: u8, members: HashSet<ClientId>) -> GroupChannelHandle {
+        assert!(
+            self.channel_type(channel_id).is_some(),
+            "Called 'create_group_channel' with invalid channel {channel_id}"
+        );
+        GroupChannelHandle(self.insert_channel_group(ChannelGroup::new("", members, Some(channel_id))))
+    }
+
+    /// Removes a group channel, it does nothing if it does not exist.
+    pub fn destroy_group_channel(&mut self, handle: GroupChannelHandle) {
+        self.channel_groups.remove(&handle.0);
+    }
+
+    /// Borrows a group channel to send messages and update its members, None if it does not exist.
+    pub fn group_channel(&mut self, handle: GroupChannelHandle) -> Option<GroupChannel<'_>> {
+        let group = self.channel_groups.get_mut(&handle.0)?;
+        Some(GroupChannel {
+            channel_id: group.channel_id()?,
+            group,
+            connections: &mut self.connections,
+        })
+    }
+
+    fn insert_channel_group(&mut self, group: ChannelGroup) -> GroupId {
+        let group_id = GroupId(self.next_group_id);
+        self.next_group_id += 1;
+        self.channel_groups.insert(group_id, group);
+
+        group_id
+    }
+
+    /// Returns the group with the given id if it exists.
+    pub fn channel_group(&self, group_id: GroupId) -> Option<&ChannelGroup> {
+        self.channel_groups.get(&group_id)
+    }
+
+    /// Removes a group from the server, returning it if it existed.
+    pub fn remove_channel_group(&mut self, group_id: GroupId) -> Option<ChannelGroup> {
+        self.channel_groups.remove(&group_id)
+    }
+
+    /// Adds and removes clients from a group, it does nothing if the group does not exist.
+    pub fn update_group(&mut self, group_id: GroupId, add: &[ClientId], remove: &[ClientId]) {
+        match self.channel_groups.get_mut(&group_id) {
+            Some(group) => group.update(add, remove),
+            None => log::error!("Tried to update invalid channel group {:?}", group_id),
+        }
+    }
+
+    /// Send a message to all clients in a group over a channel.
+    /// Members of the group that are not connected are skipped.
+    pub fn send_to_group<I: Into<u8>, B: Into<Bytes>>(&mut self, group_id: GroupId, channel_id: I, message: B) {
+        let Some(group) = self.channel_groups.get(&group_id) else {
+            log::error!("Tried to send a message to invalid channel group {:?}", group_id);
+            return;
+        };
+
+        send_to_members(&mut self.connections, group.clients(), channel_id.into(), message.into());
+    }
+
+    fn remove_from_channel_groups(&mut self, client_id: ClientId) {
+        for group in self.channel_groups.values_mut() {
+            group.remove(client_id);
+        }
+    }
+
     /// Returns the available memory in bytes of a channel for the given client.
     /// Returns 0 if the client is not found.
     pub fn channel_available_memory<I: Into<u8>>(&self, client_id: ClientId, channel_id: I) -> usize {
@@ -180,6 +447,104 @@ impl RenetServer {
         }
     }
 
+    /// Returns the delivery type of the channel used to send messages to the clients.
+    /// Returns None if the channel doesn't exist.
+    pub fn channel_type<I: Into<u8>>(&self, channel_id: I) -> Option<ChannelType> {
+        let channel_id = channel_id.into();
+        self.connection_config
+            .server_channels_config
+            .iter()
+            .find(|channel_config| channel_config.channel_id == channel_id)
+            .map(|channel_config| ChannelType::from(&channel_config.send_type))
+    }
+
+    /// Replaces the configuration of a channel used to send messages to the clients, for the current and future clients.
+    /// Returns the old configuration.
+    ///
+    /// The messages already queued or in flight are kept and delivered, the new memory limit and resend time
+    /// apply to them and the next messages. The delivery type can't change, and the memory limit, chunk size and max message size
+    /// can't grow above the original ones, since the clients receive the channel with their own configuration.
+    pub fn replace_channel_config(&mut self, channel_id: u8, new_config: ChannelConfig) -> Result<ChannelConfig, ConfigError> {
+        if new_config.channel_id != channel_id {
+            return Err(ConfigError::ChannelIdMismatch {
+                channel_id,
+                config_channel_id: new_config.channel_id,
+            });
+        }
+        let channel_config = self
+            .connection_config
+            .server_channels_config
+            .iter_mut()
+            .find(|channel_config| channel_config.channel_id == channel_id)
+            .ok_or(ConfigError::ChannelNotFound(channel_id))?;
+        if ChannelType::from(&channel_config.send_type) != ChannelType::from(&new_config.send_type) {
+            return Err(ConfigError::ChannelTypeChanged { channel_id });
+        }
+        if !new_config.is_valid() {
+            return Err(ConfigError::InvalidChunkConfig { channel_id });
+        }
+        let client_config = &self.client_receive_configs[&channel_id];
+        if new_config.max_memory_usage_bytes > client_config.max_memory_usage_bytes {
+            return Err(ConfigError::MemoryLimitExceeded {
+                channel_id,
+                max_memory_usage_bytes: client_config.max_memory_usage_bytes,
+            });
+        }
+        if let (
+            SendType::ReliableChunked {
+                chunk_size,
+                max_message_size,
+                ..
+            },
+            SendType::ReliableChunked {
+                chunk_size: client_chunk_size,
+                max_message_size: client_max_message_size,
+                ..
+            },
+        ) = (&new_config.send_type, &client_config.send_type)
+        {
+            if max_message_size > client_max_message_size {
+                return Err(ConfigError::MessageSizeExceeded {
+                    channel_id,
+                    max_message_size: *client_max_message_size,
+                });
+            }
+            if chunk_size > client_chunk_size {
+                return Err(ConfigError::ChunkSizeExceeded {
+                    channel_id,
+                    chunk_size: *client_chunk_size,
+                });
+            }
+        }
+
+        for connection in self.connections.values_mut() {
+            connection.replace_send_channel_config(&new_config);
+        }
+
+        Ok(std::mem::replace(channel_config, new_config))
+    }
+
+    /// Returns the size in bytes of the largest message that can be sent to the clients over the channel.
+    /// Returns None if the channel doesn't exist, see [`RenetClient::channel_max_message_size`].
+    pub fn channel_max_message_size<I: Into<u8>>(&self, channel_id: I) -> Option<usize> {
+        let channel_id = channel_id.into();
+        let channel_config = self
+            .connection_config
+            .server_channels_config
+            .iter()
+            .find(|channel_config| channel_config.channel_id == channel_id)?;
+
+        match channel_config.send_type {
+            SendType::Unreliable => Some(
+                channel_config
+                    .max_memory_usage_bytes
+                    .min(self.connection_config.available_bytes_per_tick as usize),
+            ),
+            SendType::ReliableOrdered { .. } | SendType::ReliableUnordered { .. } => Some(channel_config.max_memory_usage_bytes),
+            SendType::ReliableChunked { max_message_size, .. } => Some(max_message_size),
+        }
+    }
+
     /// Checks if can send a message with the given size in bytes over a channel for the given client.
     /// Returns false if the client is not found.
     pub fn can_send_message<I: Into<u8>>(&self, client_id: ClientId, channel_id: I, size_bytes: usize) -> bool {
@@ -189,6 +554,135 @@ impl RenetServer {
         }
     }
 
+    /// Returns the number of messages of a reliable channel waiting to be acked by a client,
+    /// see [`RenetClient::unacked_message_count`]. Returns None if the client is not found.
+    pub fn unacked_message_count<I: Into<u8>>(&self, client_id: ClientId, channel_id: I) -> Option<usize> {
+        self.connections.get(&client_id)?.unacked_message_count(channel_id)
+    }
+
+    /// Applies artificial network conditions to the current and future clients, see [`RenetClient::set_network_conditions`].
+    #[cfg(feature = "conditioner")]
+    pub fn set_network_conditions(&mut self, conditions: Option<NetworkConditions>) {
+        self.network_conditions = conditions;
+        for connection in self.connections.values_mut() {
+            connection.set_network_conditions(conditions);
+        }
+    }
+
+    #[cfg(feature = "conditioner")]
+    pub fn network_conditions(&self) -> Option<NetworkConditions> {
+        self.network_conditions
+    }
+
+    /// Sets the schema handshake of the clients connected from now on, see [`SchemaHandshake`].
+    /// The messages of each client are held until its version is accepted, and the clients with a
+    /// version out of the accepted range are disconnected with [`DisconnectReason::SchemaMismatch`].
+    ///
+    /// # Panics
+    /// If the handshake channel is not a reliable channel in both directions.
+    pub fn set_schema_handshake(&mut self, handshake: Option<SchemaHandshake>) {
+        if let Some(handshake) = &handshake {
+            let channel_id = handshake.channel_id();
+            let is_reliable = |channels: &[ChannelConfig]| {
+                channels
+                    .iter()
+                    .any(|channel| channel.channel_id == channel_id && !matches!(channel.send_type, SendType::Unreliable))
+            };
+            assert!(
+                is_reliable(&self.connection_config.server_channels_config) && is_reliable(&self.connection_config.client_channels_config),
+                "schema handshake channel {channel_id} must be reliable in both directions"
+            );
+        }
+        self.schema_handshake = handshake;
+    }
+
+    pub fn schema_handshake(&self) -> Option<&SchemaHandshake> {
+        self.schema_handshake.as_ref()
+    }
+
+    /// Returns the state of the schema handshake with the client, None if the client doesn't exist
+    /// or connected without a handshake.
+    pub fn schema_status(&self, client_id: ClientId) -> Option<SchemaStatus> {
+        self.connections.get(&client_id)?.schema_status()
+    }
+
+    /// Returns the schema version negotiated with the client, None until the handshake completes.
+    pub fn negotiated_version(&self, client_id: ClientId) -> Option<u32> {
+        self.connections.get(&client_id)?.negotiated_version()
+    }
+
+    /// Returns the id the next message sent to a client on a reliable channel will have,
+    /// see [`RenetClient::next_message_id`]. Returns None if the client is not found.
+    pub fn next_message_id<I: Into<u8>>(&self, client_id: ClientId, channel_id: I) -> Option<u64> {
+        self.connections.get(&client_id)?.next_message_id(channel_id)
+    }
+
+    /// Returns the id the next reliable message sent to a client on the channel will have on the wire,
+    /// see [`RenetClient::next_send_sequence`]. Returns None if the client is not found.
+    pub fn next_send_sequence<I: Into<u8>>(&self, client_id: ClientId, channel_id: I) -> Option<u64> {
+        self.connections.get(&client_id)?.next_send_sequence(channel_id)
+    }
+
+    /// Returns the id of the next reliable message expected from a client on the channel,
+    /// see [`RenetClient::expected_recv_sequence`]. Returns None if the client is not found.
+    pub fn expected_recv_sequence<I: Into<u8>>(&self, client_id: ClientId, channel_id: I) -> Option<u64> {
+        self.connections.get(&client_id)?.expected_recv_sequence(channel_id)
+    }
+
+    /// Enables or disables the tracking of the acked messages of all the reliable channels for the current and future clients,
+    /// see [`RenetClient::set_ack_notifications`].
+    pub fn set_ack_notifications(&mut self, enabled: bool) {
+        let channel_ids: Vec<u8> = self
+            .connection_config
+            .server_channels_config
+            .iter()
+            .filter(|channel_config| !matches!(channel_config.send_type, SendType::Unreliable))
+            .map(|channel_config| channel_config.channel_id)
+            .collect();
+        for channel_id in channel_ids {
+            self.set_channel_ack_notifications(channel_id, enabled);
+        }
+    }
+
+    /// Enables or disables the tracking of the acked messages of a reliable channel for the current and future clients,
+    /// see [`RenetClient::set_channel_ack_notifications`].
+    pub fn set_channel_ack_notifications<I: Into<u8>>(&mut self, channel_id: I, enabled: bool) {
+        let channel_id = channel_id.into();
+        match enabled {
+            true => self.ack_notification_channels.insert(channel_id),
+            false => self.ack_notification_channels.remove(&channel_id),
+        };
+        for connection in self.connections.values_mut() {
+            connection.set_channel_ack_notifications(channel_id, enabled);
+        }
+    }
+
+    /// Returns true if the acked messages of any channel are tracked, see [`set_ack_notifications`](Self::set_ack_notifications).
+    pub fn ack_notifications(&self) -> bool {
+        !self.ack_notification_channels.is_empty()
+    }
+
+    /// Returns true if the acked messages of the channel are tracked, see [`set_channel_ack_notifications`](Self::set_channel_ack_notifications).
+    pub fn channel_ack_notifications<I: Into<u8>>(&self, channel_id: I) -> bool {
+        self.ack_notification_channels.contains(&channel_id.into())
+    }
+
+    /// Drains the ids of the messages of a reliable channel acked by a client since the last call,
+    /// see [`RenetClient::acked_messages`]. Empty if the client is not found.
+    pub fn acked_messages<I: Into<u8>>(&mut self, client_id: ClientId, channel_id: I) -> impl Iterator<Item = u64> + '_ {
+        let channel_id = channel_id.into();
+        self.connections
+            .get_mut(&client_id)
+            .into_iter()
+            .flat_map(move |connection| connection.acked_messages(channel_id))
+    }
+
+    /// Removes and returns the oldest queued message for a client of an unreliable channel,
+    /// see [`RenetClient::drop_oldest_queued_message`]. Returns None if the client is not found.
+    pub fn drop_oldest_queued_message<I: Into<u8>>(&mut self, client_id: ClientId, channel_id: I) -> Option<Bytes> {
+        self.connections.get_mut(&client_id)?.drop_oldest_queued_message(channel_id)
+    }
+
     /// Send a message to a client over a channel.
     pub fn send_message<I: Into<u8>, B: Into<Bytes>>(&mut self, client_id: ClientId, channel_id: I, message: B) {
         match self.connections.get_mut(&client_id) {
@@ -197,6 +691,21 @@ impl RenetServer {
         }
     }
 
+    /// Send a message to a client over a reliable channel, `on_ack` is called when the client acks the message,
+    /// see [`RenetClient::send_message_with_ack_callback`].
+    pub fn send_message_with_ack_callback<I: Into<u8>, B: Into<Bytes>>(
+        &mut self,
+        client_id: ClientId,
+        channel_id: I,
+        message: B,
+        on_ack: AckCallback,
+    ) {
+        match self.connections.get_mut(&client_id) {
+            Some(connection) => connection.send_message_with_ack_callback(channel_id, message, on_ack),
+            None => log::error!("Tried to send a message to invalid client {:?}", client_id),
+        }
+    }
+
     /// Receive a message from a client over a channel.
     pub fn receive_message<I: Into<u8>>(&mut self, client_id: ClientId, channel_id: I) -> Option<Bytes> {
         if let Some(connection) = self.connections.get_mut(&client_id) {
@@ -205,6 +714,69 @@ impl RenetServer {
         None
     }
 
+    /// Borrows the received messages and the send channels separately, to receive and send in the same scope.
+    ///
+    /// # Usage
+    /// ```
+    /// # use renet::{ConnectionConfig, DefaultChannel, RenetServer};
+    /// # let mut server = RenetServer::new(ConnectionConfig::default());
+    /// let (mut receive, mut send) = server.split_borrow_mut();
+    /// for client_id in receive.clients_id() {
+    ///     while let Some(message) = receive.receive_message(client_id, DefaultChannel::ReliableOrdered) {
+    ///         send.broadcast_message_except(client_id, DefaultChannel::ReliableOrdered, message);
+    ///     }
+    /// }
+    /// ```
+    pub fn split_borrow_mut(&mut self) -> (RenetServerReceive<'_>, RenetServerSend<'_>) {
+        let mut receive = HashMap::with_capacity(self.connections.len());
+        let mut send = HashMap::with_capacity(self.connections.len());
+        for (&client_id, connection) in self.connections.iter_mut() {
+            connection.poll_schema_handshake();
+            let (connection_receive, connection_send) = connection.split_channels();
+            receive.insert(client_id, connection_receive);
+            send.insert(client_id, connection_send);
+        }
+
+        (RenetServerReceive { connections: receive }, RenetServerSend { connections: send })
+    }
+
+    /// Receive all messages from all clients over all channels.
+    /// Yields `(client_id, channel_id, message)` for every buffered message.
+    ///
+    /// # Usage
+    /// ```
+    /// # use renet::{RenetServer, ConnectionConfig};
+    /// # let mut server = RenetServer::new(ConnectionConfig::default());
+    /// for (client_id, channel_id, message) in server.iter_receive_all() {
+    ///     println!("Received {} bytes from client {client_id} on channel {channel_id}", message.len());
+    /// }
+    /// ```
+    pub fn iter_receive_all(&mut self) -> impl Iterator<Item = (ClientId, u8, Bytes)> + '_ {
+        let channel_ids: Vec<u8> = self.connection_config.client_channels_config.iter().map(|c| c.channel_id).collect();
+        self.connections.iter_mut().flat_map(move |(&client_id, connection)| {
+            let mut channel_ids = channel_ids.clone().into_iter();
+            let mut current_channel = channel_ids.next();
+            std::iter::from_fn(move || {
+                while let Some(channel_id) = current_channel {
+                    if let Some(message) = connection.receive_message(channel_id) {
+                        return Some((client_id, channel_id, message));
+                    }
+                    current_channel = channel_ids.next();
+                }
+                None
+            })
+        })
+    }
+
+    /// Receive a message from a client over a channel, with the time its packet arrived.
+    /// The time is only available if the packet was processed with [`process_packet_from_timestamped`](Self::process_packet_from_timestamped).
+    pub fn receive_message_with_timestamp<I: Into<u8>>(&mut self, client_id: ClientId, channel_id: I) -> Option<(Bytes, Option<Instant>)> {
+        if let Some(connection) = self.connections.get_mut(&client_id) {
+            return connection.receive_message_with_timestamp(channel_id);
+        }
+        None
+    }
+
     /// Return ids for all connected clients (iterator)
     pub fn clients_id_iter(&self) -> impl Iterator<Item = ClientId> + '_ {
         self.connections.iter().filter(|(_, c)| c.is_connected()).map(|(id, _)| *id)
@@ -230,6 +802,21 @@ impl RenetServer {
         self.connections.iter().filter(|(_, c)| c.is_connected()).count()
     }
 
+    /// Returns the total of bytes of the packets sent to all clients, including the disconnected ones.
+    pub fn bytes_sent_total(&self) -> u64 {
+        self.removed_bytes_sent + self.connections.values().map(|c| c.bytes_sent_total()).sum::<u64>()
+    }
+
+    /// Returns the total of bytes of the packets received from all clients, including the disconnected ones.
+    pub fn bytes_received_total(&self) -> u64 {
+        self.removed_bytes_received + self.connections.values().map(|c| c.bytes_received_total()).sum::<u64>()
+    }
+
+    /// Returns whether the server has a connection for the client, connected or not.
+    pub fn has_client(&self, client_id: ClientId) -> bool {
+        self.connections.contains_key(&client_id)
+    }
+
     pub fn is_connected(&self, client_id: ClientId) -> bool {
         if let Some(connection) = self.connections.get(&client_id) {
             return connection.is_connected();
@@ -241,6 +828,7 @@ impl RenetServer {
     /// Advances the server by the duration.
     /// Should be called every tick
     pub fn update(&mut self, duration: Duration) {
+        self.current_tick += 1;
         for connection in self.connections.values_mut() {
             connection.update(duration);
         }
@@ -265,6 +853,28 @@ impl RenetServer {
         match self.connections.get_mut(&client_id) {
             Some(connection) => {
                 connection.process_packet(payload);
+                self.last_receive_ticks.insert(client_id, self.current_tick);
+                Ok(())
+            }
+            None => Err(ClientNotFound),
+        }
+    }
+
+    /// Process a packet received from the client, storing when it arrived for
+    /// [`receive_message_with_timestamp`](Self::receive_message_with_timestamp).
+    /// <p style="background:rgba(77,220,255,0.16);padding:0.5em;">
+    /// <strong>Note:</strong> This should only be called by the transport layer.
+    /// </p>
+    pub fn process_packet_from_timestamped(
+        &mut self,
+        payload: &[u8],
+        client_id: ClientId,
+        arrived_at: Instant,
+    ) -> Result<(), ClientNotFound> {
+        match self.connections.get_mut(&client_id) {
+            Some(connection) => {
+                connection.process_packet_timestamped(payload, arrived_at);
+                self.last_receive_ticks.insert(client_id, self.current_tick);
                 Ok(())
             }
             None => Err(ClientNotFound),
@@ -289,7 +899,11 @@ impl RenetServer {
         }
         client.disconnect();
 
-        if self.connections.remove(&client_id).is_some() {
+        if let Some(connection) = self.connections.remove(&client_id) {
+            self.last_receive_ticks.remove(&client_id);
+            self.remove_from_channel_groups(client_id);
+            self.removed_bytes_sent += connection.bytes_sent_total();
+            self.removed_bytes_received += connection.bytes_received_total();
             self.events.push_back(ServerEvent::ClientDisconnected {
                 client_id,
                 reason: DisconnectReason::DisconnectedByClient,
@@ -311,3 +925,48 @@ impl RenetServer {
         Ok(())
     }
 }
+
+impl RenetServerReceive<'_> {
+    pub fn clients_id(&self) -> Vec<ClientId> {
+        self.connections.keys().copied().collect()
+    }
+
+    /// Receive a message from a client over a channel, see [`RenetServer::receive_message`].
+    pub fn receive_message<I: Into<u8>>(&mut self, client_id: ClientId, channel_id: I) -> Option<Bytes> {
+        self.connections.get_mut(&client_id)?.receive_message(channel_id.into())
+    }
+}
+
+impl RenetServerSend<'_> {
+    pub fn clients_id(&self) -> Vec<ClientId> {
+        self.connections.keys().copied().collect()
+    }
+
+    /// Send a message to a client over a channel, see [`RenetServer::send_message`].
+    pub fn send_message<I: Into<u8>, B: Into<Bytes>>(&mut self, client_id: ClientId, channel_id: I, message: B) {
+        match self.connections.get_mut(&client_id) {
+            Some(connection) => connection.send_message(channel_id.into(), message.into()),
+            None => log::error!("Tried to send a message to invalid client {:?}", client_id),
+        }
+    }
+
+    /// Send a message to all clients over a channel.
+    pub fn broadcast_message<I: Into<u8>, B: Into<Bytes>>(&mut self, channel_id: I, message: B) {
+        let channel_id = channel_id.into();
+        let message = message.into();
+        for connection in self.connections.values_mut() {
+            connection.send_message(channel_id, message.clone());
+        }
+    }
+
+    /// Send a message to all clients, except the specified one, over a channel.
+    pub fn broadcast_message_except<I: Into<u8>, B: Into<Bytes>>(&mut self, except_id: ClientId, channel_id: I, message: B) {
+        let channel_id = channel_id.into();
+        let message = message.into();
+        for (&client_id, connection) in self.connections.iter_mut() {
+            if client_id != except_id {
+                connection.send_message(channel_id, message.clone());
+            }
+        }
+    }
+}
@@ -0,0 +1,576 @@
+//! In-memory transports with simulated latency, and a [`SimulatedHarness`] running a server with its clients, for deterministic tests of game protocols.
+//!
+//! # Usage
+//! ```
+//! # use std::time::Duration;
+//! # use renet::{ConnectionConfig, DefaultChannel, RenetClient, RenetServer};
+//! # use renet::simulation::{DelayDistribution, SimulatedClientTransport, SimulatedNetwork, SimulatedServerTransport};
+//! let mut network = SimulatedNetwork::new(DelayDistribution::Uniform {
+//!     min: Duration::from_millis(20),
+//!     max: Duration::from_millis(80),
+//! }, 42);
+//! let mut server = RenetServer::new(ConnectionConfig::default());
+//! let mut server_transport = SimulatedServerTransport::default();
+//! let mut client = RenetClient::new(ConnectionConfig::default());
+//! let mut client_transport = SimulatedClientTransport::new(0);
+//!
+//! client.send_message(DefaultChannel::ReliableOrdered, "hello");
+//! for _ in 0..10 {
+//!     let dt = Duration::from_millis(16);
+//!     network.tick(dt);
+//!     server.update(dt);
+//!     client.update(dt);
+//!     server_transport.update(&mut network, &mut server);
+//!     client_transport.update(&mut network, &mut client);
+//!     server_transport.send_packets(&mut network, &mut server);
+//!     client_transport.send_packets(&mut network, &mut client);
+//! }
+//!
+//! assert_eq!(server.receive_message(0, DefaultChannel::ReliableOrdered).unwrap(), "hello");
+//! ```
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::time::Duration;
+
+use crate::packet::Payload;
+use crate::{ClientId, ConnectionConfig, RenetClient, RenetServer, ServerTransport};
+
+/// Artificial network conditions applied to the packets, to test the game with a bad network.
+///
+/// Used by the links of a [`SimulatedNetwork`] and, with the `conditioner` feature, by the
+/// `set_network_conditions` of [`RenetClient`] and [`RenetServer`]. The conditions of a connection are applied
+/// to the packets sent and received, so the round trip time grows by twice the latency.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[cfg_attr(feature = "bevy", derive(bevy_ecs::resource::Resource))]
+pub struct NetworkConditions {
+    /// Delay added to each packet.
+    pub latency: Duration,
+    /// Maximum random delay added on top of the latency, the packets can arrive out of order.
+    pub jitter: Duration,
+    /// Percentage of packets dropped, from 0 to 100.
+    pub loss_percent: f32,
+    /// Percentage of packets delivered twice, from 0 to 100.
+    pub duplication_percent: f32,
+}
+
+/// Distribution of the artificial delay applied to each packet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DelayDistribution {
+    Constant(Duration),
+    /// Uniformly distributed between min and max (inclusive).
+    Uniform {
+        min: Duration,
+        max: Duration,
+    },
+}
+
+#[derive(Debug)]
+struct InFlightPacket {
+    client_id: ClientId,
+    deliver_at: Duration,
+    payload: Payload,
+}
+
+/// Shared network connecting the [`SimulatedServerTransport`] and the [`SimulatedClientTransport`]s.
+///
+/// Packets are delivered once their delay elapsed with [`tick`](Self::tick),
+/// packets with different delays can be delivered out of order.
+/// The delays are generated from the seed, so a simulation with the same inputs is always the same.
+#[derive(Debug)]
+pub struct SimulatedNetwork {
+    delay: DelayDistribution,
+    rng: SimulationRng,
+    current_time: Duration,
+    to_server: Vec<InFlightPacket>,
+    to_clients: Vec<InFlightPacket>,
+    server_inbox: VecDeque<(ClientId, Payload)>,
+    client_inboxes: HashMap<ClientId, VecDeque<Payload>>,
+    // Conditions of the links that don't use the default delay
+    link_conditions: HashMap<ClientId, NetworkConditions>,
+}
+
+impl SimulatedNetwork {
+    pub fn new(delay: DelayDistribution, seed: u64) -> Self {
+        Self {
+            delay,
+            rng: SimulationRng::new(seed),
+            current_time: Duration::ZERO,
+            to_server: Vec::new(),
+            to_clients: Vec::new(),
+            server_inbox: VecDeque::new(),
+            client_inboxes: HashMap::new(),
+            link_conditions: HashMap::new(),
+        }
+    }
+
+    /// Set the latency, jitter, loss and duplication of the packets between a client and the server, in both directions.
+    /// The jitter is uniformly distributed between zero and its value. Links without conditions use the default delay.
+    pub fn set_link_conditions(&mut self, client_id: ClientId, conditions: Option<NetworkConditions>) {
+        match conditions {
+            Some(conditions) => self.link_conditions.insert(client_id, conditions),
+            None => self.link_conditions.remove(&client_id),
+        };
+    }
+
+    pub fn link_conditions(&self, client_id: ClientId) -> Option<NetworkConditions> {
+        self.link_conditions.get(&client_id).copied()
+    }
+
+    /// Returns the time elapsed since the network was created.
+    pub fn current_time(&self) -> Duration {
+        self.current_time
+    }
+
+    /// Advances the network by the duration, moving the packets whose delay has elapsed into the receive queues.
+    pub fn tick(&mut self, dt: Duration) {
+        self.current_time += dt;
+        let current_time = self.current_time;
+
+        for packet in take_arrived(&mut self.to_server, current_time, |packet| packet.deliver_at) {
+            self.server_inbox.push_back((packet.client_id, packet.payload));
+        }
+        for packet in take_arrived(&mut self.to_clients, current_time, |packet| packet.deliver_at) {
+            self.client_inboxes.entry(packet.client_id).or_default().push_back(packet.payload);
+        }
+    }
+
+    /// Returns the number of packets not yet delivered.
+    pub fn in_flight(&self) -> usize {
+        self.to_server.len() + self.to_clients.len()
+    }
+
+    fn send_to_server(&mut self, client_id: ClientId, payload: Payload) {
+        let delivery_times = self.delivery_times(client_id);
+        push_in_flight(&mut self.to_server, client_id, payload, delivery_times);
+    }
+
+    fn send_to_client(&mut self, client_id: ClientId, payload: Payload) {
+        let delivery_times = self.delivery_times(client_id);
+        push_in_flight(&mut self.to_clients, client_id, payload, delivery_times);
+    }
+
+    fn delivery_times(&mut self, client_id: ClientId) -> Vec<Duration> {
+        match self.link_conditions.get(&client_id) {
+            Some(conditions) => self.rng.delivery_times(self.current_time, conditions),
+            None => vec![self.current_time + self.rng.random_delay(self.delay)],
+        }
+    }
+}
+
+// Random delays and losses of the simulated packets, generated from a seed.
+// Also used by the connections with artificial network conditions.
+#[derive(Debug)]
+pub(crate) struct SimulationRng {
+    state: u64,
+}
+
+impl SimulationRng {
+    pub fn new(seed: u64) -> Self {
+        // xorshift state must not be zero
+        Self { state: seed.max(1) }
+    }
+
+    // Empty when the packet is lost, two times when duplicated
+    pub fn delivery_times(&mut self, current_time: Duration, conditions: &NetworkConditions) -> Vec<Duration> {
+        if self.next_chance(conditions.loss_percent) {
+            return vec![];
+        }
+
+        let copies = if self.next_chance(conditions.duplication_percent) { 2 } else { 1 };
+        (0..copies)
+            .map(|_| {
+                let jitter = DelayDistribution::Uniform {
+                    min: Duration::ZERO,
+                    max: conditions.jitter,
+                };
+                current_time + conditions.latency + self.random_delay(jitter)
+            })
+            .collect()
+    }
+
+    pub fn random_delay(&mut self, delay: DelayDistribution) -> Duration {
+        match delay {
+            DelayDistribution::Constant(delay) => delay,
+            DelayDistribution::Uniform { min, max } => {
+                let range = max.saturating_sub(min).as_nanos() as u64;
+                if range == 0 {
+                    return min;
+                }
+                min + Duration::from_nanos(self.next_random() % (range + 1))
+            }
+        }
+    }
+
+    // True with the given percentage, from 0 to 100
+    fn next_chance(&mut self, percent: f32) -> bool {
+        percent > 0.0 && ((self.next_random() % 10_000) as f32) < percent * 100.
+    }
+
+    // xorshift64
+    fn next_random(&mut self) -> u64 {
+        let mut x = self.state;
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        self.state = x;
+        x
+    }
+}
+
+// One packet for each delivery time, the payload is only cloned for duplicates
+fn push_in_flight(packets: &mut Vec<InFlightPacket>, client_id: ClientId, payload: Payload, delivery_times: Vec<Duration>) {
+    let Some((last, duplicates)) = delivery_times.split_last() else {
+        return;
+    };
+    for deliver_at in duplicates {
+        packets.push(InFlightPacket {
+            client_id,
+            deliver_at: *deliver_at,
+            payload: payload.clone(),
+        });
+    }
+    packets.push(InFlightPacket {
+        client_id,
+        deliver_at: *last,
+        payload,
+    });
+}
+
+// Removes the packets that should be delivered by now, sorted by their delivery time.
+pub(crate) fn take_arrived<T>(packets: &mut Vec<T>, current_time: Duration, deliver_at: fn(&T) -> Duration) -> Vec<T> {
+    let (mut arrived, pending): (Vec<_>, Vec<_>) = std::mem::take(packets)
+        .into_iter()
+        .partition(|packet| deliver_at(packet) <= current_time);
+    *packets = pending;
+    arrived.sort_by_key(deliver_at);
+    arrived
+}
+
+/// Server transport over a [`SimulatedNetwork`].
+/// Clients are added to the server when their first packet arrives.
+///
+/// The packets sent through the [`ServerTransport`] trait, that has no access to the network,
+/// are queued and handed to the network on the next [`update`](Self::update) or [`send_packets`](Self::send_packets).
+#[derive(Debug, Default)]
+#[cfg_attr(feature = "bevy", derive(bevy_ecs::resource::Resource))]
+pub struct SimulatedServerTransport {
+    queued_packets: Vec<(ClientId, Payload)>,
+}
+
+impl SimulatedServerTransport {
+    /// Receive the packets delivered by the network.
+    pub fn update(&mut self, network: &mut SimulatedNetwork, server: &mut RenetServer) {
+        self.send_queued_packets(network);
+        while let Some((client_id, payload)) = network.server_inbox.pop_front() {
+            if !server.has_client(client_id) {
+                server.add_connection(client_id);
+            }
+            if let Err(e) = server.process_packet_from(&payload, client_id) {
+                log::error!("Error while processing payload for {}: {}", client_id, e);
+            }
+        }
+
+        for client_id in server.disconnections_id() {
+            server.remove_connection(client_id);
+        }
+    }
+
+    /// Send packets to connected clients through the network.
+    pub fn send_packets(&mut self, network: &mut SimulatedNetwork, server: &mut RenetServer) {
+        ServerTransport::send_packets(self, server);
+        self.send_queued_packets(network);
+    }
+
+    fn send_queued_packets(&mut self, network: &mut SimulatedNetwork) {
+        for (client_id, packet) in self.queued_packets.drain(..) {
+            network.send_to_client(client_id, packet);
+        }
+    }
+}
+
+impl ServerTransport for SimulatedServerTransport {
+    fn send_packets(&mut self, server: &mut RenetServer) {
+        // Sorted so the random delays are generated in the same order
+        let mut clients_id = server.clients_id();
+        clients_id.sort_unstable();
+        for client_id in clients_id {
+            let packets = server.get_packets_to_send(client_id).unwrap();
+            self.queued_packets.extend(packets.into_iter().map(|packet| (client_id, packet)));
+        }
+    }
+
+    // The simulated network has no disconnect packet, the client is only removed from the server
+    fn disconnect(&mut self, client_id: ClientId, server: &mut RenetServer) {
+        server.remove_connection(client_id);
+    }
+}
+
+/// Client transport over a [`SimulatedNetwork`].
+/// The client is considered connected as soon as it's created.
+#[derive(Debug)]
+#[cfg_attr(feature = "bevy", derive(bevy_ecs::resource::Resource))]
+pub struct SimulatedClientTransport {
+    client_id: ClientId,
+}
+
+impl SimulatedClientTransport {
+    pub fn new(client_id: ClientId) -> Self {
+        Self { client_id }
+    }
+
+    pub fn client_id(&self) -> ClientId {
+        self.client_id
+    }
+
+    /// Receive the packets delivered by the network.
+    pub fn update(&mut self, network: &mut SimulatedNetwork, client: &mut RenetClient) {
+        if client.is_disconnected() {
+            return;
+        }
+        client.set_connected();
+
+        if let Some(inbox) = network.client_inboxes.get_mut(&self.client_id) {
+            while let Some(payload) = inbox.pop_front() {
+                client.process_packet(&payload);
+            }
+        }
+    }
+
+    /// Send packets to the server through the network.
+    pub fn send_packets(&mut self, network: &mut SimulatedNetwork, client: &mut RenetClient) {
+        if client.is_disconnected() {
+            return;
+        }
+
+        for packet in client.get_packets_to_send() {
+            network.send_to_server(self.client_id, packet);
+        }
+    }
+}
+
+/// Transport connecting the server and the clients of a [`SimulatedHarness`].
+///
+/// [`SimulatedLinks`] sends the packets through a [`SimulatedNetwork`], other implementations run the same tests
+/// over a real transport, like the netcode loopback transport of `renet_netcode`.
+pub trait HarnessTransport {
+    /// Adds the transport of a client, called for each client when the harness is created.
+    fn add_client(&mut self, client_id: ClientId);
+
+    /// Advances the transport by the duration and receives the packets of the server and the clients.
+    fn update(&mut self, dt: Duration, server: &mut RenetServer, clients: &mut BTreeMap<ClientId, RenetClient>);
+
+    /// Sends the packets of the server and the clients.
+    fn send_packets(&mut self, server: &mut RenetServer, clients: &mut BTreeMap<ClientId, RenetClient>);
+}
+
+/// The default [`HarnessTransport`], the server and the clients are connected through a [`SimulatedNetwork`].
+#[derive(Debug)]
+pub struct SimulatedLinks {
+    network: SimulatedNetwork,
+    server_transport: SimulatedServerTransport,
+    client_transports: BTreeMap<ClientId, SimulatedClientTransport>,
+}
+
+impl SimulatedLinks {
+    pub fn new(network: SimulatedNetwork) -> Self {
+        Self {
+            network,
+            server_transport: SimulatedServerTransport::default(),
+            client_transports: BTreeMap::new(),
+        }
+    }
+
+    pub fn network(&self) -> &SimulatedNetwork {
+        &self.network
+    }
+
+    pub fn network_mut(&mut self) -> &mut SimulatedNetwork {
+        &mut self.network
+    }
+}
+
+impl HarnessTransport for SimulatedLinks {
+    fn add_client(&mut self, client_id: ClientId) {
+        self.client_transports.insert(client_id, SimulatedClientTransport::new(client_id));
+    }
+
+    fn update(&mut self, dt: Duration, server: &mut RenetServer, clients: &mut BTreeMap<ClientId, RenetClient>) {
+        self.network.tick(dt);
+        self.server_transport.update(&mut self.network, server);
+        for (client_id, client) in clients.iter_mut() {
+            if let Some(transport) = self.client_transports.get_mut(client_id) {
+                transport.update(&mut self.network, client);
+            }
+        }
+    }
+
+    fn send_packets(&mut self, server: &mut RenetServer, clients: &mut BTreeMap<ClientId, RenetClient>) {
+        self.server_transport.send_packets(&mut self.network, server);
+        for (client_id, client) in clients.iter_mut() {
+            if let Some(transport) = self.client_transports.get_mut(client_id) {
+                transport.send_packets(&mut self.network, client);
+            }
+        }
+    }
+}
+
+/// Server and clients connected through a [`SimulatedNetwork`], advanced together with a virtual clock.
+///
+/// Without threads or sockets, a test with the same seed and inputs always runs the same way.
+/// The clients have the ids from 0 to the number of clients, use [`set_link_conditions`](Self::set_link_conditions)
+/// to simulate a bad network for some of them.
+///
+/// The same tests can run over a real transport with [`with_transport`](Self::with_transport),
+/// the packets then take real time to arrive so the runs are no longer deterministic.
+///
+/// # Usage
+/// ```
+/// # use std::time::Duration;
+/// # use renet::{ConnectionConfig, DefaultChannel, NetworkConditions};
+/// # use renet::simulation::{DelayDistribution, SimulatedHarness};
+/// let mut harness = SimulatedHarness::new(ConnectionConfig::default(), 2, DelayDistribution::Constant(Duration::from_millis(30)), 42);
+/// harness.set_link_conditions(1, Some(NetworkConditions {
+///     latency: Duration::from_millis(50),
+///     loss_percent: 20.,
+///     ..Default::default()
+/// }));
+///
+/// harness.client_mut(1).unwrap().send_message(DefaultChannel::ReliableOrdered, "ready");
+/// assert!(harness.advance_until_delivered(Duration::from_millis(16), Duration::from_secs(5)));
+/// assert_eq!(harness.server_mut().receive_message(1, DefaultChannel::ReliableOrdered).unwrap(), "ready");
+/// ```
+#[derive(Debug)]
+pub struct SimulatedHarness<T: HarnessTransport = SimulatedLinks> {
+    transport: T,
+    server: RenetServer,
+    clients: BTreeMap<ClientId, RenetClient>,
+    elapsed: Duration,
+}
+
+impl SimulatedHarness {
+    /// Creates the server and the clients, the clients connect to the server with the first [`advance`](Self::advance).
+    pub fn new(connection_config: ConnectionConfig, num_clients: usize, delay: DelayDistribution, seed: u64) -> Self {
+        let links = SimulatedLinks::new(SimulatedNetwork::new(delay, seed));
+        Self::with_transport(connection_config, num_clients, links)
+    }
+
+    pub fn network(&self) -> &SimulatedNetwork {
+        self.transport.network()
+    }
+
+    pub fn network_mut(&mut self) -> &mut SimulatedNetwork {
+        self.transport.network_mut()
+    }
+
+    /// See [`SimulatedNetwork::set_link_conditions`].
+    pub fn set_link_conditions(&mut self, client_id: ClientId, conditions: Option<NetworkConditions>) {
+        self.transport.network_mut().set_link_conditions(client_id, conditions);
+    }
+}
+
+impl<T: HarnessTransport> SimulatedHarness<T> {
+    /// Creates the server and the clients connected through the transport,
+    /// the clients connect to the server with the first [`advance`](Self::advance).
+    pub fn with_transport(connection_config: ConnectionConfig, num_clients: usize, mut transport: T) -> Self {
+        let clients = (0..num_clients as ClientId)
+            .map(|client_id| {
+                transport.add_client(client_id);
+                (client_id, RenetClient::new(connection_config.clone()))
+            })
+            .collect();
+
+        Self {
+            transport,
+            server: RenetServer::new(connection_config),
+            clients,
+            elapsed: Duration::ZERO,
+        }
+    }
+
+    pub fn server(&self) -> &RenetServer {
+        &self.server
+    }
+
+    pub fn server_mut(&mut self) -> &mut RenetServer {
+        &mut self.server
+    }
+
+    pub fn client(&self, client_id: ClientId) -> Option<&RenetClient> {
+        self.clients.get(&client_id)
+    }
+
+    pub fn client_mut(&mut self, client_id: ClientId) -> Option<&mut RenetClient> {
+        self.clients.get_mut(&client_id)
+    }
+
+    pub fn clients_id(&self) -> Vec<ClientId> {
+        self.clients.keys().copied().collect()
+    }
+
+    pub fn transport(&self) -> &T {
+        &self.transport
+    }
+
+    pub fn transport_mut(&mut self) -> &mut T {
+        &mut self.transport
+    }
+
+    /// Returns the time of the virtual clock.
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed
+    }
+
+    /// Advances the transport, the server and the clients by the duration, receiving and then sending their packets.
+    /// Messages sent between two calls are sent in the same step.
+    pub fn advance(&mut self, dt: Duration) {
+        self.elapsed += dt;
+        self.server.update(dt);
+        for client in self.clients.values_mut() {
+            client.update(dt);
+        }
+        self.transport.update(dt, &mut self.server, &mut self.clients);
+        self.transport.send_packets(&mut self.server, &mut self.clients);
+    }
+
+    /// Advances in steps of `step` until `duration` has elapsed.
+    ///
+    /// # Panics
+    /// If the step is zero.
+    pub fn advance_for(&mut self, duration: Duration, step: Duration) {
+        assert!(!step.is_zero(), "the harness can't advance in steps of zero");
+        let end = self.elapsed() + duration;
+        while self.elapsed() < end {
+            self.advance(step.min(end - self.elapsed()));
+        }
+    }
+
+    /// Returns true when the reliable messages sent by the server and the clients were all acked,
+    /// and the other messages were all sent.
+    pub fn all_reliable_messages_delivered(&self) -> bool {
+        let server_channels = self
+            .server
+            .clients_id_iter()
+            .filter_map(|client_id| self.server.channels_network_info(client_id).ok())
+            .flatten();
+        let client_channels = self.clients.values().flat_map(|client| client.channels_network_info());
+        server_channels.chain(client_channels).all(|info| info.buffered_bytes == 0)
+    }
+
+    /// Advances in steps of `step` until [`all_reliable_messages_delivered`](Self::all_reliable_messages_delivered),
+    /// returns false if they were not delivered before the timeout.
+    ///
+    /// # Panics
+    /// If the step is zero.
+    pub fn advance_until_delivered(&mut self, step: Duration, timeout: Duration) -> bool {
+        assert!(!step.is_zero(), "the harness can't advance in steps of zero");
+        let end = self.elapsed() + timeout;
+        loop {
+            self.advance(step);
+            if self.all_reliable_messages_delivered() {
+                return true;
+            }
+            if self.elapsed() >= end {
+                return false;
+            }
+        }
+    }
+}
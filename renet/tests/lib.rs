@@ -1,5 +1,18 @@
+use std::{
+    collections::HashSet,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
 use bytes::Bytes;
-use renet::{ClientId, ConnectionConfig, DefaultChannel, DisconnectReason, RenetClient, RenetServer, ServerEvent};
+use renet_netcode::NetcodeLoopback;
+
+use renet::simulation::{DelayDistribution, SimulatedClientTransport, SimulatedHarness, SimulatedNetwork, SimulatedServerTransport};
+use renet::{
+    ChannelConfig, ChannelNetworkInfo, ClientId, ConfigError, ConnectionConfig, DataRate, DefaultChannel, DisconnectReason,
+    NetworkConditions, RenetClient, RenetError, RenetServer, SchemaHandshake, SchemaStatus, SendType, ServerEvent, ServerTransport,
+    MAX_CHUNKED_MESSAGE_SIZE,
+};
 
 pub fn init_log() {
     let _ = env_logger::builder().is_test(true).try_init();
@@ -102,3 +115,950 @@ fn test_local_client() {
             }
     );
 }
+
+#[test]
+fn test_group_channel() {
+    init_log();
+    let mut server = RenetServer::new(ConnectionConfig::default());
+
+    let mut client_a = server.new_local_client(0);
+    let mut client_b = server.new_local_client(1);
+    let mut client_c = server.new_local_client(2);
+
+    let handle = server.create_group_channel(DefaultChannel::ReliableOrdered.into(), HashSet::from([0, 1]));
+    let mut group_channel = server.group_channel(handle).unwrap();
+    assert_eq!(group_channel.channel_id(), u8::from(DefaultChannel::ReliableOrdered));
+    group_channel.send("first");
+    assert!(group_channel.remove_member(0));
+    assert!(group_channel.add_member(2));
+    group_channel.send("second");
+
+    server.process_local_client(0, &mut client_a).unwrap();
+    server.process_local_client(1, &mut client_b).unwrap();
+    server.process_local_client(2, &mut client_c).unwrap();
+    assert_eq!(client_a.receive_message(DefaultChannel::ReliableOrdered).unwrap(), "first");
+    assert!(client_a.receive_message(DefaultChannel::ReliableOrdered).is_none());
+    assert_eq!(client_b.receive_message(DefaultChannel::ReliableOrdered).unwrap(), "first");
+    assert_eq!(client_b.receive_message(DefaultChannel::ReliableOrdered).unwrap(), "second");
+    assert_eq!(client_c.receive_message(DefaultChannel::ReliableOrdered).unwrap(), "second");
+
+    // Disconnected clients are removed from all groups
+    let group = server.create_channel_group("all", HashSet::from([1, 2]));
+    server.disconnect_local_client(1, &mut client_b);
+    server.remove_connection(2);
+    assert!(server.group_channel(handle).unwrap().members().is_empty());
+    assert!(server.channel_group(group).unwrap().clients().is_empty());
+
+    server.destroy_group_channel(handle);
+    assert!(server.group_channel(handle).is_none());
+    assert!(server.channel_group(handle.group_id()).is_none());
+}
+
+#[test]
+fn test_send_to_channel_group() {
+    init_log();
+    let mut server = RenetServer::new(ConnectionConfig::default());
+
+    let mut client_a = server.new_local_client(0);
+    let mut client_b = server.new_local_client(1);
+    let mut client_c = server.new_local_client(2);
+
+    let group = server.create_channel_group("team", HashSet::from([0, 1]));
+    assert_eq!(server.channel_group(group).unwrap().name(), "team");
+
+    server.send_to_group(group, DefaultChannel::ReliableOrdered, Bytes::from("first"));
+    server.update_group(group, &[2], &[0]);
+    assert!(!server.channel_group(group).unwrap().contains(0));
+    server.send_to_group(group, DefaultChannel::ReliableOrdered, Bytes::from("second"));
+
+    server.process_local_client(0, &mut client_a).unwrap();
+    server.process_local_client(1, &mut client_b).unwrap();
+    server.process_local_client(2, &mut client_c).unwrap();
+
+    assert_eq!(client_a.receive_message(DefaultChannel::ReliableOrdered).unwrap(), "first");
+    assert!(client_a.receive_message(DefaultChannel::ReliableOrdered).is_none());
+
+    assert_eq!(client_b.receive_message(DefaultChannel::ReliableOrdered).unwrap(), "first");
+    assert_eq!(client_b.receive_message(DefaultChannel::ReliableOrdered).unwrap(), "second");
+
+    assert_eq!(client_c.receive_message(DefaultChannel::ReliableOrdered).unwrap(), "second");
+    assert!(client_c.receive_message(DefaultChannel::ReliableOrdered).is_none());
+}
+
+#[test]
+fn test_iter_receive_all() {
+    init_log();
+    let mut server = RenetServer::new(ConnectionConfig::default());
+
+    let mut client_a = server.new_local_client(0);
+    let mut client_b = server.new_local_client(1);
+
+    client_a.send_message(DefaultChannel::ReliableOrdered, Bytes::from("a1"));
+    client_a.send_message(DefaultChannel::Unreliable, Bytes::from("a2"));
+    client_b.send_message(DefaultChannel::ReliableUnordered, Bytes::from("b1"));
+
+    server.process_local_client(0, &mut client_a).unwrap();
+    server.process_local_client(1, &mut client_b).unwrap();
+
+    let mut messages: Vec<(u64, u8, Bytes)> = server.iter_receive_all().collect();
+    messages.sort();
+    assert_eq!(
+        messages,
+        vec![
+            (0, DefaultChannel::Unreliable.into(), Bytes::from("a2")),
+            (0, DefaultChannel::ReliableOrdered.into(), Bytes::from("a1")),
+            (1, DefaultChannel::ReliableUnordered.into(), Bytes::from("b1")),
+        ]
+    );
+    assert_eq!(server.iter_receive_all().count(), 0);
+}
+
+#[test]
+fn test_disconnect_stale_clients() {
+    init_log();
+    let mut server = RenetServer::new(ConnectionConfig::default());
+
+    let mut client_a = server.new_local_client(0);
+    let client_b = server.new_local_client(1);
+    let mut transport = LocalTransport {
+        client_id: 1,
+        client: client_b,
+    };
+
+    for _ in 0..3 {
+        server.update(Duration::from_millis(16));
+        client_a.update(Duration::from_millis(16));
+        client_a.send_message(DefaultChannel::Unreliable, Bytes::from("alive"));
+        server.process_local_client(0, &mut client_a).unwrap();
+    }
+
+    assert!(server.disconnect_stale_clients(&mut transport, 3).is_empty());
+    assert_eq!(server.disconnect_stale_clients(&mut transport, 2), vec![1]);
+    assert!(server.is_connected(0));
+    // The transport removed the connection
+    assert!(!server.clients_id().contains(&1));
+    let events: Vec<_> = std::iter::from_fn(|| server.get_event()).collect();
+    assert!(events.contains(&ServerEvent::ClientDisconnected {
+        client_id: 1,
+        reason: DisconnectReason::DisconnectedByServer
+    }));
+}
+
+#[test]
+fn test_unacked_message_count() {
+    init_log();
+    let mut server = RenetServer::new(ConnectionConfig::default());
+    let mut client = server.new_local_client(0);
+
+    server.send_message(0, DefaultChannel::ReliableOrdered, Bytes::from("first"));
+    server.send_message(0, DefaultChannel::ReliableOrdered, Bytes::from("second"));
+    assert_eq!(server.unacked_message_count(0, DefaultChannel::ReliableOrdered), Some(2));
+    assert_eq!(server.unacked_message_count(1, DefaultChannel::ReliableOrdered), None);
+
+    // The client acks the messages when answering
+    server.process_local_client(0, &mut client).unwrap();
+    assert_eq!(server.unacked_message_count(0, DefaultChannel::ReliableOrdered), Some(0));
+}
+
+#[test]
+fn test_ack_notifications() {
+    init_log();
+    let mut server = RenetServer::new(ConnectionConfig::default());
+    server.set_ack_notifications(true);
+    let mut client = server.new_local_client(0);
+
+    assert_eq!(server.next_message_id(0, DefaultChannel::ReliableOrdered), Some(0));
+    assert_eq!(server.next_message_id(0, DefaultChannel::Unreliable), None);
+    server.send_message(0, DefaultChannel::ReliableOrdered, Bytes::from("first"));
+    server.send_message(0, DefaultChannel::ReliableOrdered, Bytes::from("second"));
+    assert_eq!(server.next_message_id(0, DefaultChannel::ReliableOrdered), Some(2));
+    assert_eq!(server.acked_messages(0, DefaultChannel::ReliableOrdered).count(), 0);
+
+    server.process_local_client(0, &mut client).unwrap();
+    let mut acked: Vec<u64> = server.acked_messages(0, DefaultChannel::ReliableOrdered).collect();
+    acked.sort();
+    assert_eq!(acked, vec![0, 1]);
+    assert_eq!(server.acked_messages(0, DefaultChannel::ReliableOrdered).count(), 0);
+
+    // Not tracked when disabled
+    server.set_ack_notifications(false);
+    server.send_message(0, DefaultChannel::ReliableOrdered, Bytes::from("third"));
+    server.process_local_client(0, &mut client).unwrap();
+    assert_eq!(server.acked_messages(0, DefaultChannel::ReliableOrdered).count(), 0);
+}
+
+#[test]
+fn test_channel_ack_notifications() {
+    init_log();
+    let mut server = RenetServer::new(ConnectionConfig::default());
+    let mut client = server.new_local_client(0);
+    server.set_channel_ack_notifications(DefaultChannel::ReliableOrdered, true);
+    assert!(server.ack_notifications());
+    assert!(server.channel_ack_notifications(DefaultChannel::ReliableOrdered));
+    assert!(!server.channel_ack_notifications(DefaultChannel::ReliableUnordered));
+
+    server.send_message(0, DefaultChannel::ReliableOrdered, Bytes::from("tracked"));
+    server.send_message(0, DefaultChannel::ReliableUnordered, Bytes::from("untracked"));
+    server.process_local_client(0, &mut client).unwrap();
+    assert_eq!(
+        server.acked_messages(0, DefaultChannel::ReliableOrdered).collect::<Vec<_>>(),
+        vec![0]
+    );
+    assert_eq!(server.acked_messages(0, DefaultChannel::ReliableUnordered).count(), 0);
+
+    // New clients track the same channels
+    let mut new_client = server.new_local_client(1);
+    server.send_message(1, DefaultChannel::ReliableOrdered, Bytes::from("tracked"));
+    server.send_message(1, DefaultChannel::ReliableUnordered, Bytes::from("untracked"));
+    server.process_local_client(1, &mut new_client).unwrap();
+    assert_eq!(
+        server.acked_messages(1, DefaultChannel::ReliableOrdered).collect::<Vec<_>>(),
+        vec![0]
+    );
+    assert_eq!(server.acked_messages(1, DefaultChannel::ReliableUnordered).count(), 0);
+
+    server.set_channel_ack_notifications(DefaultChannel::ReliableOrdered, false);
+    assert!(!server.ack_notifications());
+}
+
+#[test]
+fn test_message_sequences() {
+    init_log();
+    let mut server = RenetServer::new(ConnectionConfig::default());
+    let mut client = server.new_local_client(0);
+
+    for message in ["first", "second", "third"] {
+        server.send_message(0, DefaultChannel::ReliableOrdered, message);
+    }
+    client.send_message(DefaultChannel::ReliableUnordered, "hello");
+    assert_eq!(server.next_send_sequence(0, DefaultChannel::ReliableOrdered), Some(3));
+    assert_eq!(client.expected_recv_sequence(DefaultChannel::ReliableOrdered), Some(0));
+    assert_eq!(client.next_send_sequence(DefaultChannel::ReliableUnordered), Some(1));
+
+    server.process_local_client(0, &mut client).unwrap();
+    assert_eq!(client.expected_recv_sequence(DefaultChannel::ReliableOrdered), Some(3));
+    assert_eq!(server.expected_recv_sequence(0, DefaultChannel::ReliableUnordered), Some(1));
+
+    assert_eq!(server.expected_recv_sequence(0, DefaultChannel::Unreliable), None);
+    assert_eq!(server.next_send_sequence(1, DefaultChannel::ReliableOrdered), None);
+}
+
+#[test]
+fn test_schema_handshake() {
+    init_log();
+    const SCHEMA_CHANNEL: u8 = 3;
+    let mut channels = DefaultChannel::config();
+    channels.push(SchemaHandshake::channel_config(SCHEMA_CHANNEL));
+    let connection_config = ConnectionConfig {
+        server_channels_config: channels.clone(),
+        client_channels_config: channels,
+        ..Default::default()
+    };
+    let mut server = RenetServer::new(connection_config);
+    server.set_schema_handshake(Some(SchemaHandshake::new(SCHEMA_CHANNEL, 4).features(0b11).accept_versions(3..=4)));
+
+    let mut client = server.new_local_client(0);
+    client.set_schema_handshake(SchemaHandshake::new(SCHEMA_CHANNEL, 3).features(0b110));
+    server.send_message(0, DefaultChannel::ReliableOrdered, "from server");
+    client.send_message(DefaultChannel::ReliableOrdered, "from client");
+    server.process_local_client(0, &mut client).unwrap();
+
+    // The client messages are delivered after its hello is accepted, the server ones are held until the answer arrives
+    assert_eq!(server.schema_status(0), Some(SchemaStatus::Pending));
+    assert_eq!(
+        server.receive_message(0, DefaultChannel::ReliableOrdered),
+        Some(Bytes::from("from client"))
+    );
+    assert_eq!(server.negotiated_version(0), Some(3));
+    assert_eq!(client.receive_message(DefaultChannel::ReliableOrdered), None);
+    assert_eq!(client.schema_status(), Some(SchemaStatus::Pending));
+
+    server.process_local_client(0, &mut client).unwrap();
+    assert_eq!(
+        client.receive_message(DefaultChannel::ReliableOrdered),
+        Some(Bytes::from("from server"))
+    );
+    assert_eq!(client.negotiated_version(), Some(3));
+    assert_eq!(client.negotiated_features(), Some(0b10));
+    assert_eq!(client.receive_message(SCHEMA_CHANNEL), None);
+
+    let mut old_client = server.new_local_client(1);
+    old_client.set_schema_handshake(SchemaHandshake::new(SCHEMA_CHANNEL, 2));
+    old_client.send_message(DefaultChannel::ReliableOrdered, "from old client");
+    server.process_local_client(1, &mut old_client).unwrap();
+    assert_eq!(server.receive_message(1, DefaultChannel::ReliableOrdered), None);
+    assert_eq!(server.schema_status(1), Some(SchemaStatus::Rejected { remote_version: 2 }));
+
+    server.process_local_client(1, &mut old_client).unwrap();
+    old_client.update(Duration::ZERO);
+    assert_eq!(
+        old_client.disconnect_reason(),
+        Some(DisconnectReason::SchemaMismatch {
+            local_version: 2,
+            remote_version: 4
+        })
+    );
+
+    // The client disconnected without acking the rejection
+    server.update(Duration::from_secs(1));
+    assert_eq!(
+        server.disconnect_reason(1),
+        Some(DisconnectReason::SchemaMismatch {
+            local_version: 4,
+            remote_version: 2
+        })
+    );
+    assert!(server.is_connected(0));
+}
+
+#[test]
+fn test_ack_callback() {
+    init_log();
+    let mut server = RenetServer::new(ConnectionConfig::default());
+    let mut client = server.new_local_client(0);
+
+    let acked = Arc::new(Mutex::new(Vec::new()));
+    for (i, message) in [Bytes::from("small"), Bytes::from(vec![7; 3000])].into_iter().enumerate() {
+        let acked = acked.clone();
+        server.send_message_with_ack_callback(
+            0,
+            DefaultChannel::ReliableOrdered,
+            message,
+            Box::new(move || acked.lock().unwrap().push(i)),
+        );
+    }
+    server.send_message(0, DefaultChannel::ReliableOrdered, Bytes::from("no callback"));
+    assert!(acked.lock().unwrap().is_empty());
+
+    server.process_local_client(0, &mut client).unwrap();
+    let mut acked = acked.lock().unwrap().clone();
+    acked.sort();
+    // Called once, for both the small and the sliced message
+    assert_eq!(acked, vec![0, 1]);
+}
+
+#[test]
+fn test_chunked_channel() {
+    init_log();
+    let channel = ChannelConfig {
+        channel_id: 0,
+        max_memory_usage_bytes: 10_000,
+        send_type: SendType::ReliableChunked {
+            resend_time: Duration::from_millis(300),
+            chunk_size: 4096,
+            max_message_size: 1024 * 1024,
+        },
+    };
+    let config = ConnectionConfig {
+        server_channels_config: vec![channel.clone()],
+        client_channels_config: vec![channel],
+        ..Default::default()
+    };
+    let mut server = RenetServer::new(config);
+    let mut client = server.new_local_client(0);
+    assert_eq!(server.channel_max_message_size(0), Some(1024 * 1024));
+
+    // Bigger than the memory of the channel, sent as the fragments are acked
+    let large_message: Bytes = (0..200_000u32).map(|i| i as u8).collect::<Vec<u8>>().into();
+    let acked = Arc::new(Mutex::new(false));
+    let on_ack = acked.clone();
+    server.send_message_with_ack_callback(0, 0, large_message.clone(), Box::new(move || *on_ack.lock().unwrap() = true));
+    server.send_message(0, 0, Bytes::from("after"));
+
+    let mut received = vec![];
+    for _ in 0..100 {
+        server.process_local_client(0, &mut client).unwrap();
+        while let Some(message) = client.receive_message(0) {
+            received.push(message);
+        }
+        if received.len() == 2 {
+            break;
+        }
+    }
+    assert!(client.is_connected());
+    assert_eq!(received, vec![large_message, Bytes::from("after")]);
+    assert!(*acked.lock().unwrap());
+    assert_eq!(server.unacked_message_count(0, 0), Some(0));
+}
+
+#[test]
+fn test_chunked_channel_config() {
+    let channel = ChannelConfig {
+        channel_id: 0,
+        max_memory_usage_bytes: 10_000,
+        send_type: SendType::ReliableChunked {
+            resend_time: Duration::from_millis(300),
+            chunk_size: 4096,
+            max_message_size: 1024 * 1024,
+        },
+    };
+    assert!(channel.is_valid());
+    let mut server = RenetServer::new(ConnectionConfig {
+        server_channels_config: vec![channel.clone()],
+        client_channels_config: vec![channel.clone()],
+        ..Default::default()
+    });
+
+    // The fragments would not fit in the memory of the receiving channel
+    let fragment_too_big = ChannelConfig {
+        max_memory_usage_bytes: 4096,
+        ..channel.clone()
+    };
+    assert!(!fragment_too_big.is_valid());
+    assert_eq!(
+        server.replace_channel_config(0, fragment_too_big).unwrap_err(),
+        ConfigError::InvalidChunkConfig { channel_id: 0 }
+    );
+
+    let message_too_big = ChannelConfig {
+        send_type: SendType::ReliableChunked {
+            resend_time: Duration::from_millis(300),
+            chunk_size: 4096,
+            max_message_size: MAX_CHUNKED_MESSAGE_SIZE + 1,
+        },
+        ..channel
+    };
+    assert!(!message_too_big.is_valid());
+    assert_eq!(
+        server.replace_channel_config(0, message_too_big).unwrap_err(),
+        ConfigError::InvalidChunkConfig { channel_id: 0 }
+    );
+
+    // The clients receive the channel with the original sizes
+    let chunked = |chunk_size, max_message_size| ChannelConfig {
+        send_type: SendType::ReliableChunked {
+            resend_time: Duration::from_millis(300),
+            chunk_size,
+            max_message_size,
+        },
+        ..channel.clone()
+    };
+    assert_eq!(
+        server.replace_channel_config(0, chunked(4096, 2 * 1024 * 1024)).unwrap_err(),
+        ConfigError::MessageSizeExceeded {
+            channel_id: 0,
+            max_message_size: 1024 * 1024
+        }
+    );
+    assert_eq!(
+        server.replace_channel_config(0, chunked(5000, 1024 * 1024)).unwrap_err(),
+        ConfigError::ChunkSizeExceeded {
+            channel_id: 0,
+            chunk_size: 4096
+        }
+    );
+    server.replace_channel_config(0, chunked(1024, 512 * 1024)).unwrap();
+    assert_eq!(server.channel_max_message_size(0), Some(512 * 1024));
+}
+
+#[test]
+#[should_panic(expected = "chunked channel 0")]
+fn test_chunked_channel_invalid_config() {
+    let channel = ChannelConfig {
+        channel_id: 0,
+        max_memory_usage_bytes: 4096,
+        send_type: SendType::ReliableChunked {
+            resend_time: Duration::from_millis(300),
+            chunk_size: 4096,
+            max_message_size: 1024 * 1024,
+        },
+    };
+    RenetClient::new(ConnectionConfig {
+        server_channels_config: vec![channel.clone()],
+        client_channels_config: vec![channel],
+        ..Default::default()
+    });
+}
+
+#[test]
+fn test_client_data_rate() {
+    init_log();
+    let mut server = RenetServer::new(ConnectionConfig::default());
+    let mut client = server.new_local_client(0);
+    assert_eq!(server.client_data_rate(0), Some(DataRate::default()));
+    assert_eq!(server.client_data_rate(1), None);
+
+    for _ in 0..10 {
+        server.send_message(0, DefaultChannel::Unreliable, vec![0; 500]);
+        client.send_message(DefaultChannel::Unreliable, vec![0; 100]);
+        server.process_local_client(0, &mut client).unwrap();
+        server.update(Duration::from_millis(100));
+        client.update(Duration::from_millis(100));
+    }
+
+    let server_rate = server.client_data_rate(0).unwrap();
+    assert!(server_rate.send_bps > server_rate.recv_bps);
+    assert!(server_rate.recv_bps > 0.0);
+    // The client receives what the server sends
+    assert_eq!(client.data_rate().recv_bps, server_rate.send_bps);
+}
+
+#[test]
+fn test_data_rate_smoothing() {
+    init_log();
+    let mut server = RenetServer::new(ConnectionConfig::default());
+    server.set_data_rate_smoothing(1.0);
+    let mut client = server.new_local_client(0);
+    client.set_data_rate_smoothing(1.0);
+
+    // Without smoothing the average is the traffic of the last update
+    server.send_message(0, DefaultChannel::Unreliable, vec![0; 500]);
+    server.process_local_client(0, &mut client).unwrap();
+    server.update(Duration::from_secs(1));
+    client.update(Duration::from_secs(1));
+    let sent = server.client_data_rate(0).unwrap().send_bps;
+    assert!(sent > 500.0);
+    assert_eq!(client.data_rate().recv_bps, sent);
+
+    server.update(Duration::from_secs(1));
+    assert_eq!(server.client_data_rate(0).unwrap().send_bps, 0.0);
+}
+
+#[test]
+#[should_panic(expected = "data rate smoothing")]
+fn test_invalid_data_rate_smoothing() {
+    RenetServer::new(ConnectionConfig::default()).set_data_rate_smoothing(f64::NAN);
+}
+
+#[test]
+#[should_panic(expected = "data rate smoothing")]
+fn test_zero_data_rate_smoothing() {
+    RenetClient::new(ConnectionConfig::default()).set_data_rate_smoothing(0.0);
+}
+
+#[test]
+fn test_has_client() {
+    init_log();
+    let mut server = RenetServer::new(ConnectionConfig::default());
+    assert!(!server.has_client(0));
+
+    server.add_connection(0);
+    assert!(server.has_client(0));
+    assert!(!server.has_client(1));
+
+    // Disconnected clients are kept until they are removed
+    server.disconnect(0);
+    assert!(!server.is_connected(0));
+    assert!(server.has_client(0));
+    server.remove_connection(0);
+    assert!(!server.has_client(0));
+}
+
+#[test]
+fn test_replace_channel_config() {
+    init_log();
+    let mut server = RenetServer::new(ConnectionConfig::default());
+    let mut client = server.new_local_client(0);
+    let channel_id: u8 = DefaultChannel::ReliableOrdered.into();
+
+    server.send_message(0, channel_id, Bytes::from("before"));
+    let new_config = ChannelConfig {
+        channel_id,
+        max_memory_usage_bytes: 1024,
+        send_type: SendType::ReliableOrdered {
+            resend_time: Duration::from_millis(100),
+        },
+    };
+    let old_config = server.replace_channel_config(channel_id, new_config.clone()).unwrap();
+    assert_eq!(old_config.max_memory_usage_bytes, 5 * 1024 * 1024);
+    assert_eq!(server.channel_max_message_size(channel_id), Some(1024));
+    assert_eq!(server.channel_available_memory(0, channel_id), 1024 - "before".len());
+
+    // The queued message is still delivered
+    server.send_message(0, channel_id, Bytes::from("after"));
+    server.process_local_client(0, &mut client).unwrap();
+    assert_eq!(client.receive_message(channel_id), Some(Bytes::from("before")));
+    assert_eq!(client.receive_message(channel_id), Some(Bytes::from("after")));
+
+    // Future clients use the new config
+    let _new_client = server.new_local_client(1);
+    assert_eq!(server.channel_available_memory(1, channel_id), 1024);
+
+    // The limit can grow back up to the one the clients receive with
+    let client_config = ChannelConfig {
+        max_memory_usage_bytes: old_config.max_memory_usage_bytes,
+        ..new_config.clone()
+    };
+    server.replace_channel_config(channel_id, client_config).unwrap();
+    assert_eq!(
+        server
+            .replace_channel_config(
+                channel_id,
+                ChannelConfig {
+                    max_memory_usage_bytes: old_config.max_memory_usage_bytes + 1,
+                    ..new_config.clone()
+                }
+            )
+            .unwrap_err(),
+        ConfigError::MemoryLimitExceeded {
+            channel_id,
+            max_memory_usage_bytes: old_config.max_memory_usage_bytes
+        }
+    );
+
+    assert_eq!(
+        server
+            .replace_channel_config(
+                7,
+                ChannelConfig {
+                    channel_id: 7,
+                    ..new_config.clone()
+                }
+            )
+            .unwrap_err(),
+        ConfigError::ChannelNotFound(7)
+    );
+    assert_eq!(
+        server
+            .replace_channel_config(
+                channel_id,
+                ChannelConfig {
+                    send_type: SendType::Unreliable,
+                    ..new_config
+                }
+            )
+            .unwrap_err(),
+        ConfigError::ChannelTypeChanged { channel_id }
+    );
+}
+
+#[test]
+#[cfg(feature = "conditioner")]
+fn test_network_conditions() {
+    init_log();
+    let mut server = RenetServer::new(ConnectionConfig::default());
+    server.set_network_conditions(Some(NetworkConditions {
+        latency: Duration::from_millis(100),
+        ..Default::default()
+    }));
+    let mut client = server.new_local_client(0);
+
+    server.send_message(0, DefaultChannel::ReliableOrdered, Bytes::from("delayed"));
+    server.process_local_client(0, &mut client).unwrap();
+    assert_eq!(client.receive_message(DefaultChannel::ReliableOrdered), None);
+
+    server.update(Duration::from_millis(100));
+    server.process_local_client(0, &mut client).unwrap();
+    assert_eq!(
+        client.receive_message(DefaultChannel::ReliableOrdered),
+        Some(Bytes::from("delayed"))
+    );
+
+    // Without conditions the packets are sent right away
+    server.set_network_conditions(None);
+    server.send_message(0, DefaultChannel::ReliableOrdered, Bytes::from("direct"));
+    server.process_local_client(0, &mut client).unwrap();
+    assert_eq!(client.receive_message(DefaultChannel::ReliableOrdered), Some(Bytes::from("direct")));
+}
+
+#[test]
+#[cfg(feature = "conditioner")]
+fn test_receive_packet_loss() {
+    init_log();
+    let mut server = RenetServer::new(ConnectionConfig::default());
+    server.set_network_conditions(Some(NetworkConditions {
+        loss_percent: 50.,
+        ..Default::default()
+    }));
+    let mut client = server.new_local_client(0);
+
+    for _ in 0..200 {
+        server.send_message(0, DefaultChannel::Unreliable, vec![0; 10]);
+        client.send_message(DefaultChannel::Unreliable, vec![0; 10]);
+        server.update(Duration::from_millis(16));
+        client.update(Duration::from_millis(16));
+        server.process_local_client(0, &mut client).unwrap();
+    }
+
+    // The conditions of the server drop the packets it sends and receives
+    assert!((0.3..0.7).contains(&client.receive_packet_loss()));
+    assert!((0.3..0.7).contains(&server.receive_packet_loss(0)));
+    assert_eq!(client.network_info().receive_packet_loss, client.receive_packet_loss());
+}
+
+#[test]
+fn test_split_borrow_mut() {
+    init_log();
+    let mut server = RenetServer::new(ConnectionConfig::default());
+    let mut client_a = server.new_local_client(0);
+    let mut client_b = server.new_local_client(1);
+
+    client_a.send_message(DefaultChannel::ReliableOrdered, Bytes::from("relayed"));
+    server.process_local_client(0, &mut client_a).unwrap();
+
+    let (mut receive, mut send) = server.split_borrow_mut();
+    while let Some(message) = receive.receive_message(0, DefaultChannel::ReliableOrdered) {
+        send.broadcast_message_except(0, DefaultChannel::ReliableOrdered, message);
+    }
+    assert_eq!(receive.receive_message(2, DefaultChannel::ReliableOrdered), None);
+
+    server.process_local_client(0, &mut client_a).unwrap();
+    server.process_local_client(1, &mut client_b).unwrap();
+    assert_eq!(client_a.receive_message(DefaultChannel::ReliableOrdered), None);
+    assert_eq!(
+        client_b.receive_message(DefaultChannel::ReliableOrdered),
+        Some(Bytes::from("relayed"))
+    );
+}
+
+// Delivers the packets directly to a single client
+struct LocalTransport {
+    client_id: ClientId,
+    client: RenetClient,
+}
+
+impl ServerTransport for LocalTransport {
+    fn send_packets(&mut self, server: &mut RenetServer) {
+        for packet in server.get_packets_to_send(self.client_id).unwrap() {
+            self.client.process_packet(&packet);
+        }
+    }
+
+    fn disconnect(&mut self, client_id: ClientId, server: &mut RenetServer) {
+        server.remove_connection(client_id);
+    }
+}
+
+#[test]
+fn test_disconnect_client_with_payload() {
+    init_log();
+    let mut server = RenetServer::new(ConnectionConfig::default());
+    let client = server.new_local_client(0);
+    assert_eq!(server.get_event(), Some(ServerEvent::ClientConnected { client_id: 0 }));
+    let mut transport = LocalTransport { client_id: 0, client };
+
+    server.disconnect_client_with_payload(&mut transport, 0, DefaultChannel::ReliableOrdered, Bytes::from("goodbye"));
+    assert_eq!(
+        transport.client.receive_message(DefaultChannel::ReliableOrdered),
+        Some(Bytes::from("goodbye"))
+    );
+    assert!(!server.is_connected(0));
+    assert_eq!(
+        server.get_event(),
+        Some(ServerEvent::ClientDisconnected {
+            client_id: 0,
+            reason: DisconnectReason::DisconnectedByServer
+        })
+    );
+
+    // Unknown clients are ignored
+    server.disconnect_client_with_payload(&mut transport, 1, DefaultChannel::ReliableOrdered, Bytes::from("goodbye"));
+    assert_eq!(server.get_event(), None);
+}
+
+#[test]
+fn test_simulated_network_reordering() {
+    init_log();
+    let delay = DelayDistribution::Uniform {
+        min: Duration::from_millis(10),
+        max: Duration::from_millis(100),
+    };
+    let mut network = SimulatedNetwork::new(delay, 7);
+    let mut server = RenetServer::new(ConnectionConfig::default());
+    let mut server_transport = SimulatedServerTransport::default();
+    let mut client = RenetClient::new(ConnectionConfig::default());
+    let mut client_transport = SimulatedClientTransport::new(3);
+
+    let mut received = vec![];
+    for tick in 0..200u64 {
+        let dt = Duration::from_millis(16);
+        network.tick(dt);
+        server.update(dt);
+        client.update(dt);
+        server_transport.update(&mut network, &mut server);
+        client_transport.update(&mut network, &mut client);
+
+        if tick < 50 {
+            client.send_message(DefaultChannel::ReliableOrdered, tick.to_le_bytes().to_vec());
+        }
+        while let Some(message) = server.receive_message(3, DefaultChannel::ReliableOrdered) {
+            received.push(u64::from_le_bytes(message[..].try_into().unwrap()));
+        }
+
+        server_transport.send_packets(&mut network, &mut server);
+        client_transport.send_packets(&mut network, &mut client);
+    }
+
+    assert_eq!(received, (0..50).collect::<Vec<u64>>());
+}
+
+// Returns the time all messages were delivered, and the messages received by the server
+fn run_harness(seed: u64) -> (Duration, Vec<(ClientId, u64)>) {
+    let delay = DelayDistribution::Uniform {
+        min: Duration::from_millis(10),
+        max: Duration::from_millis(50),
+    };
+    let mut harness = SimulatedHarness::new(ConnectionConfig::default(), 3, delay, seed);
+    harness.set_link_conditions(
+        2,
+        Some(NetworkConditions {
+            latency: Duration::from_millis(40),
+            jitter: Duration::from_millis(30),
+            loss_percent: 25.,
+            duplication_percent: 10.,
+        }),
+    );
+
+    for client_id in harness.clients_id() {
+        for i in 0..20u64 {
+            let client = harness.client_mut(client_id).unwrap();
+            client.send_message(DefaultChannel::ReliableOrdered, i.to_le_bytes().to_vec());
+        }
+    }
+    assert!(!harness.all_reliable_messages_delivered());
+    assert!(harness.advance_until_delivered(Duration::from_millis(16), Duration::from_secs(10)));
+    assert_eq!(harness.server().connected_clients(), 3);
+
+    let mut received = vec![];
+    for client_id in harness.clients_id() {
+        while let Some(message) = harness.server_mut().receive_message(client_id, DefaultChannel::ReliableOrdered) {
+            received.push((client_id, u64::from_le_bytes(message[..].try_into().unwrap())));
+        }
+    }
+    (harness.elapsed(), received)
+}
+
+#[test]
+fn test_simulated_harness() {
+    init_log();
+    let (elapsed, received) = run_harness(11);
+    let expected: Vec<(ClientId, u64)> = (0..3).flat_map(|client_id| (0..20).map(move |i| (client_id, i))).collect();
+    assert_eq!(received, expected);
+
+    // Same seed, same simulation
+    assert_eq!(run_harness(11).0, elapsed);
+
+    let mut harness = SimulatedHarness::new(ConnectionConfig::default(), 1, DelayDistribution::Constant(Duration::ZERO), 1);
+    harness.advance_for(Duration::from_millis(100), Duration::from_millis(30));
+    assert_eq!(harness.elapsed(), Duration::from_millis(100));
+}
+
+#[test]
+fn test_netcode_loopback_harness() {
+    init_log();
+    let transport = NetcodeLoopback::new(7, 2).unwrap();
+    let mut harness = SimulatedHarness::with_transport(ConnectionConfig::default(), 2, transport);
+
+    for client_id in harness.clients_id() {
+        let client = harness.client_mut(client_id).unwrap();
+        client.send_message(DefaultChannel::ReliableOrdered, client_id.to_le_bytes().to_vec());
+    }
+    assert!(harness.advance_until_delivered(Duration::from_millis(16), Duration::from_secs(10)));
+    assert_eq!(harness.server().connected_clients(), 2);
+
+    for client_id in harness.clients_id() {
+        let message = harness
+            .server_mut()
+            .receive_message(client_id, DefaultChannel::ReliableOrdered)
+            .unwrap();
+        assert_eq!(message, client_id.to_le_bytes().to_vec());
+    }
+}
+
+#[test]
+#[should_panic(expected = "steps of zero")]
+fn test_harness_zero_step() {
+    let mut harness = SimulatedHarness::new(ConnectionConfig::default(), 1, DelayDistribution::Constant(Duration::ZERO), 1);
+    harness.advance_until_delivered(Duration::ZERO, Duration::from_secs(1));
+}
+
+#[test]
+fn test_receive_message_with_timestamp() {
+    init_log();
+    let mut server = RenetServer::new(ConnectionConfig::default());
+    let mut client = server.new_local_client(0);
+
+    client.send_message(DefaultChannel::ReliableOrdered, Bytes::from("timestamped"));
+    client.send_message(DefaultChannel::Unreliable, Bytes::from("timestamped"));
+    let arrived_at = Instant::now();
+    for packet in client.get_packets_to_send() {
+        server.process_packet_from_timestamped(&packet, 0, arrived_at).unwrap();
+    }
+
+    client.send_message(DefaultChannel::ReliableOrdered, Bytes::from("untimestamped"));
+    server.process_local_client(0, &mut client).unwrap();
+
+    assert_eq!(
+        server.receive_message_with_timestamp(0, DefaultChannel::Unreliable),
+        Some((Bytes::from("timestamped"), Some(arrived_at)))
+    );
+    assert_eq!(
+        server.receive_message_with_timestamp(0, DefaultChannel::ReliableOrdered),
+        Some((Bytes::from("timestamped"), Some(arrived_at)))
+    );
+    assert_eq!(
+        server.receive_message_with_timestamp(0, DefaultChannel::ReliableOrdered),
+        Some((Bytes::from("untimestamped"), None))
+    );
+}
+
+#[test]
+fn test_renet_error_propagation() {
+    fn replace_and_process(server: &mut RenetServer, client: &mut RenetClient, channel_id: u8) -> Result<(), RenetError> {
+        let mut config = DefaultChannel::config().remove(0);
+        config.channel_id = channel_id;
+        server.replace_channel_config(channel_id, config)?;
+        server.process_local_client(0, client)?;
+        Ok(())
+    }
+
+    let mut server = RenetServer::new(ConnectionConfig::default());
+    let mut client = server.new_local_client(1);
+    let err = replace_and_process(&mut server, &mut client, 9).unwrap_err();
+    assert!(matches!(err, RenetError::Config(ConfigError::ChannelNotFound(9))));
+    let err = replace_and_process(&mut server, &mut client, 0).unwrap_err();
+    assert!(matches!(err, RenetError::ClientNotFound(_)));
+}
+
+#[test]
+fn test_bytes_total() {
+    let mut server = RenetServer::new(ConnectionConfig::default());
+    let mut client = server.new_local_client(0);
+
+    server.send_message(0, DefaultChannel::ReliableOrdered, vec![0; 100]);
+    client.send_message(DefaultChannel::ReliableOrdered, vec![0; 50]);
+    server.process_local_client(0, &mut client).unwrap();
+    assert!(server.bytes_sent_total() > 100);
+    assert!(server.bytes_received_total() > 50);
+    assert_eq!(server.bytes_sent_total(), client.bytes_received_total());
+    assert_eq!(server.bytes_received_total(), client.bytes_sent_total());
+
+    // Kept after the client is removed
+    server.disconnect_local_client(0, &mut client);
+    assert_eq!(server.bytes_sent_total(), client.bytes_received_total());
+    assert_eq!(server.bytes_received_total(), client.bytes_sent_total());
+}
+
+#[test]
+fn test_channels_network_info() {
+    let mut server = RenetServer::new(ConnectionConfig::default());
+    let mut client = server.new_local_client(0);
+    let channel_id: u8 = DefaultChannel::ReliableOrdered.into();
+    let channel_info = |infos: Vec<ChannelNetworkInfo>| infos.into_iter().find(|info| info.channel_id == channel_id).unwrap();
+
+    server.send_message(0, channel_id, vec![0; 100]);
+    let infos = server.channels_network_info(0).unwrap();
+    assert_eq!(infos.iter().map(|info| info.channel_id).collect::<Vec<_>>(), vec![0, 1, 2]);
+    let info = channel_info(infos);
+    assert_eq!(info.buffered_bytes, 100);
+    assert_eq!(info.bytes_in_flight, 0);
+    assert_eq!(
+        info.max_memory_usage_bytes,
+        DefaultChannel::config()[channel_id as usize].max_memory_usage_bytes
+    );
+
+    server.update(Duration::from_millis(100));
+    client.update(Duration::from_millis(100));
+    server.process_local_client(0, &mut client).unwrap();
+    // The client acked the message while processing
+    let server_info = channel_info(server.channels_network_info(0).unwrap());
+    assert_eq!(server_info.buffered_bytes, 0);
+    assert!(server_info.bytes_sent_per_second > 0.0);
+    assert!(channel_info(client.channels_network_info()).bytes_received_per_second > 0.0);
+    assert_eq!(server.channels_network_info(0).unwrap()[0].bytes_sent_per_second, 0.0);
+
+    // Sent but not acked yet
+    server.send_message(0, channel_id, vec![0; 100]);
+    server.get_packets_to_send(0).unwrap();
+    assert_eq!(channel_info(server.channels_network_info(0).unwrap()).bytes_in_flight, 100);
+}
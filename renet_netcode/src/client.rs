@@ -6,12 +6,12 @@ use std::{
 
 use renetcode::{ClientAuthentication, DisconnectReason, NetcodeClient, NetcodeError, NETCODE_MAX_PACKET_BYTES};
 
-use renet::{ClientId, RenetClient};
+use renet::{Bytes, ClientId, RenetClient};
 
 use super::NetcodeTransportError;
 
 #[derive(Debug)]
-#[cfg_attr(feature = "bevy", derive(bevy_ecs::resource::Resource))]
+#[cfg_attr(feature = "bevy", derive(bevy_ecs::resource::Resource, bevy_ecs::component::Component))]
 pub struct NetcodeClientTransport {
     socket: UdpSocket,
     netcode_client: NetcodeClient,
@@ -30,6 +30,16 @@ impl NetcodeClientTransport {
         })
     }
 
+    /// Restarts the connection with new authentication, keeping the socket.
+    /// Useful to retry with a new connect token when the previous one expired.
+    pub fn reconnect(&mut self, authentication: ClientAuthentication) -> Result<(), NetcodeError> {
+        let connect_timeout = self.netcode_client.connect_timeout();
+        self.netcode_client = NetcodeClient::new(self.netcode_client.current_time(), authentication)?;
+        self.netcode_client.set_connect_timeout(connect_timeout);
+
+        Ok(())
+    }
+
     pub fn addr(&self) -> io::Result<SocketAddr> {
         self.socket.local_addr()
     }
@@ -38,6 +48,18 @@ impl NetcodeClientTransport {
         self.netcode_client.client_id()
     }
 
+    /// Limits the total time to connect, including the retries with the next server addresses of the connect token.
+    /// Kept on [`reconnect`](Self::reconnect).
+    pub fn set_connect_timeout(&mut self, connect_timeout: Option<Duration>) {
+        self.netcode_client.set_connect_timeout(connect_timeout);
+    }
+
+    /// Returns the time left before the client gives up connecting, None once connected or disconnected.
+    /// Useful for the progress of a connecting screen.
+    pub fn time_remaining_to_connect(&self) -> Option<Duration> {
+        self.netcode_client.time_remaining_to_connect()
+    }
+
     /// Returns the duration since the client last received a packet.
     /// Usefull to detect timeouts.
     pub fn time_since_last_received_packet(&self) -> Duration {
@@ -67,6 +89,12 @@ impl NetcodeClientTransport {
         self.netcode_client.disconnect_reason()
     }
 
+    /// Returns the reason sent by the server with [`NetcodeServerTransport::disconnect_client_with_reason`](crate::NetcodeServerTransport::disconnect_client_with_reason),
+    /// None if the client disconnected itself, the connection was lost or the server gave no reason.
+    pub fn disconnect_payload(&self) -> Option<Bytes> {
+        self.netcode_client.disconnect_payload().map(Bytes::copy_from_slice)
+    }
+
     /// Send packets to the server.
     /// Should be called every tick
     pub fn send_packets(&mut self, connection: &mut RenetClient) -> Result<(), NetcodeTransportError> {
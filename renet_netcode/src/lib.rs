@@ -1,14 +1,17 @@
-use std::{error::Error, fmt};
+use std::{error::Error, fmt, io};
 
 mod client;
+mod loopback;
 mod server;
 
 pub use client::*;
+pub use loopback::*;
 pub use server::*;
 
 pub use renetcode::{
-    generate_random_bytes, ClientAuthentication, ConnectToken, DisconnectReason as NetcodeDisconnectReason, NetcodeError,
-    ServerAuthentication, ServerConfig, TokenGenerationError, NETCODE_KEY_BYTES, NETCODE_USER_DATA_BYTES,
+    generate_random_bytes, ClientAuthentication, ConnectToken, DisconnectReason as NetcodeDisconnectReason, NetcodeError, NetcodeStats,
+    ServerAuthentication, ServerConfig, TokenGenerationError, NETCODE_KEY_BYTES, NETCODE_MAX_DISCONNECT_REASON_BYTES,
+    NETCODE_USER_DATA_BYTES,
 };
 
 #[derive(Debug)]
@@ -19,7 +22,37 @@ pub enum NetcodeTransportError {
     IO(std::io::Error),
 }
 
-impl Error for NetcodeTransportError {}
+impl NetcodeTransportError {
+    /// Returns true if the transport is still usable after this error.
+    ///
+    /// Unrecoverable errors mean the client was disconnected or the socket failed,
+    /// and the transport must be recreated to connect again.
+    pub fn is_recoverable(&self) -> bool {
+        match self {
+            NetcodeTransportError::Netcode(NetcodeError::Disconnected(_)) => false,
+            NetcodeTransportError::Netcode(_) => true,
+            NetcodeTransportError::Renet(_) => false,
+            NetcodeTransportError::IO(e) => matches!(
+                e.kind(),
+                io::ErrorKind::WouldBlock
+                    | io::ErrorKind::Interrupted
+                    | io::ErrorKind::TimedOut
+                    | io::ErrorKind::ConnectionReset
+                    | io::ErrorKind::ConnectionRefused
+            ),
+        }
+    }
+}
+
+impl Error for NetcodeTransportError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            NetcodeTransportError::Netcode(err) => Some(err),
+            NetcodeTransportError::Renet(_) => None,
+            NetcodeTransportError::IO(err) => Some(err),
+        }
+    }
+}
 
 impl fmt::Display for NetcodeTransportError {
     fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
@@ -54,3 +87,9 @@ impl From<std::io::Error> for NetcodeTransportError {
         NetcodeTransportError::IO(inner)
     }
 }
+
+impl From<NetcodeTransportError> for renet::RenetError {
+    fn from(inner: NetcodeTransportError) -> Self {
+        renet::RenetError::Transport(Box::new(inner))
+    }
+}
@@ -0,0 +1,102 @@
+use std::{
+    collections::BTreeMap,
+    io,
+    net::{SocketAddr, UdpSocket},
+    time::Duration,
+};
+
+use renet::{simulation::HarnessTransport, ClientId, RenetClient, RenetServer};
+use renetcode::{ClientAuthentication, ServerAuthentication, ServerConfig};
+
+use crate::{NetcodeClientTransport, NetcodeServerTransport};
+
+/// Netcode transports over loopback sockets, to run the tests of a [`SimulatedHarness`](renet::simulation::SimulatedHarness)
+/// against the real netcode transport with [`with_transport`](renet::simulation::SimulatedHarness::with_transport).
+///
+/// The clients connect with unsecure authentication. The packets take real time to arrive,
+/// so advance the harness with a timeout large enough for the handshake.
+///
+/// # Panics
+/// Creating the harness panics if the socket of a client can't be bound.
+#[derive(Debug)]
+pub struct NetcodeLoopback {
+    protocol_id: u64,
+    server_addr: SocketAddr,
+    server_transport: NetcodeServerTransport,
+    client_transports: BTreeMap<ClientId, NetcodeClientTransport>,
+    current_time: Duration,
+}
+
+impl NetcodeLoopback {
+    /// Binds the server to a free loopback port.
+    pub fn new(protocol_id: u64, max_clients: usize) -> io::Result<Self> {
+        let socket = UdpSocket::bind("127.0.0.1:0")?;
+        let server_addr = socket.local_addr()?;
+        let server_config = ServerConfig {
+            current_time: Duration::ZERO,
+            max_clients,
+            protocol_id,
+            public_addresses: vec![server_addr],
+            authentication: ServerAuthentication::Unsecure,
+        };
+        let server_transport = NetcodeServerTransport::new(server_config, socket)?;
+
+        Ok(Self {
+            protocol_id,
+            server_addr,
+            server_transport,
+            client_transports: BTreeMap::new(),
+            current_time: Duration::ZERO,
+        })
+    }
+
+    pub fn server_transport(&self) -> &NetcodeServerTransport {
+        &self.server_transport
+    }
+
+    pub fn client_transport(&self, client_id: ClientId) -> Option<&NetcodeClientTransport> {
+        self.client_transports.get(&client_id)
+    }
+}
+
+impl HarnessTransport for NetcodeLoopback {
+    fn add_client(&mut self, client_id: ClientId) {
+        let socket = UdpSocket::bind("127.0.0.1:0").expect("failed to bind a loopback socket");
+        let authentication = ClientAuthentication::Unsecure {
+            protocol_id: self.protocol_id,
+            client_id,
+            server_addr: self.server_addr,
+            user_data: None,
+        };
+        let transport =
+            NetcodeClientTransport::new(self.current_time, authentication, socket).expect("failed to create the client transport");
+        self.client_transports.insert(client_id, transport);
+    }
+
+    fn update(&mut self, dt: Duration, server: &mut RenetServer, clients: &mut BTreeMap<ClientId, RenetClient>) {
+        self.current_time += dt;
+        if let Err(e) = self.server_transport.update(dt, server) {
+            log::error!("Failed to update the loopback server transport: {e}");
+        }
+        for (client_id, client) in clients.iter_mut() {
+            if let Some(transport) = self.client_transports.get_mut(client_id) {
+                if let Err(e) = transport.update(dt, client) {
+                    log::error!("Failed to update the loopback transport of client {client_id}: {e}");
+                }
+            }
+        }
+    }
+
+    fn send_packets(&mut self, server: &mut RenetServer, clients: &mut BTreeMap<ClientId, RenetClient>) {
+        if let Err(e) = self.server_transport.send_packets(server) {
+            log::error!("Failed to send the packets of the loopback server transport: {e}");
+        }
+        for (client_id, client) in clients.iter_mut() {
+            if let Some(transport) = self.client_transports.get_mut(client_id) {
+                if let Err(e) = transport.send_packets(client) {
+                    log::error!("Failed to send the packets of loopback client {client_id}: {e}");
+                }
+            }
+        }
+    }
+}
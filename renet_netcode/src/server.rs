@@ -1,13 +1,14 @@
 use std::{
+    collections::HashMap,
     io,
     net::{SocketAddr, UdpSocket},
-    time::Duration,
+    time::{Duration, Instant},
 };
 
-use renetcode::{NetcodeServer, ServerConfig, ServerResult, NETCODE_MAX_PACKET_BYTES, NETCODE_USER_DATA_BYTES};
+use renetcode::{NetcodeError, NetcodeServer, NetcodeStats, ServerConfig, ServerResult, NETCODE_MAX_PACKET_BYTES, NETCODE_USER_DATA_BYTES};
 
-use renet::ClientId;
-use renet::RenetServer;
+use renet::{packet_channel_id, ClientId};
+use renet::{GracefulShutdown, RenetServer, ServerTransport};
 
 use super::NetcodeTransportError;
 
@@ -17,18 +18,39 @@ pub struct NetcodeServerTransport {
     socket: UdpSocket,
     netcode_server: NetcodeServer,
     buffer: [u8; NETCODE_MAX_PACKET_BYTES],
+    channel_dscp: HashMap<u8, u8>,
+    current_tos: u32,
+    bound_addr: SocketAddr,
+    connected_at: HashMap<ClientId, Instant>,
+    is_shutdown: bool,
 }
 
 impl NetcodeServerTransport {
-    pub fn new(server_config: ServerConfig, socket: UdpSocket) -> Result<Self, std::io::Error> {
+    /// Creates the transport from an already bound socket.
+    ///
+    /// The socket can be bound to port 0 to let the OS pick a free port,
+    /// public addresses with port 0 are then replaced with the bound port, see [`bound_addr`](Self::bound_addr).
+    pub fn new(mut server_config: ServerConfig, socket: UdpSocket) -> Result<Self, std::io::Error> {
         socket.set_nonblocking(true)?;
 
+        let bound_addr = socket.local_addr()?;
+        for address in server_config.public_addresses.iter_mut() {
+            if address.port() == 0 {
+                address.set_port(bound_addr.port());
+            }
+        }
+
         let netcode_server = NetcodeServer::new(server_config);
 
         Ok(Self {
             socket,
+            bound_addr,
+            connected_at: HashMap::new(),
             netcode_server,
             buffer: [0; NETCODE_MAX_PACKET_BYTES],
+            channel_dscp: HashMap::new(),
+            current_tos: 0,
+            is_shutdown: false,
         })
     }
 
@@ -37,6 +59,24 @@ impl NetcodeServerTransport {
         self.netcode_server.addresses()
     }
 
+    /// Returns the address the server socket is bound to, with the actual port when it was bound to port 0.
+    /// Unlike [`addresses`](Self::addresses) it can be an unspecified address like `0.0.0.0`.
+    pub fn server_address(&self) -> io::Result<SocketAddr> {
+        self.socket.local_addr()
+    }
+
+    /// Returns the address the server socket was bound to when the transport was created,
+    /// with the port picked by the OS when it was bound to port 0.
+    pub fn bound_addr(&self) -> SocketAddr {
+        self.bound_addr
+    }
+
+    /// Returns all the addresses the server is listening on.
+    /// The transport has a single socket, bind it to `[::]` to listen on IPv4 and IPv6.
+    pub fn listen_addresses(&self) -> io::Result<Vec<SocketAddr>> {
+        Ok(vec![self.server_address()?])
+    }
+
     /// Returns the maximum number of clients that can be connected.
     pub fn max_clients(&self) -> usize {
         self.netcode_server.max_clients()
@@ -72,25 +112,62 @@ impl NetcodeServerTransport {
     pub fn disconnect_all(&mut self, server: &mut RenetServer) {
         for client_id in self.netcode_server.clients_id() {
             let server_result = self.netcode_server.disconnect(client_id);
-            handle_server_result(server_result, &self.socket, server);
+            handle_server_result(server_result, &self.socket, &mut self.connected_at, server);
         }
     }
 
+    /// Disconnects a client sending a reason that it can read with
+    /// [`NetcodeClientTransport::disconnect_payload`](crate::NetcodeClientTransport::disconnect_payload).
+    /// The reason is truncated to [`NETCODE_MAX_DISCONNECT_REASON_BYTES`](crate::NETCODE_MAX_DISCONNECT_REASON_BYTES).
+    pub fn disconnect_client_with_reason(&mut self, client_id: ClientId, reason: &[u8], server: &mut RenetServer) {
+        let server_result = self.netcode_server.disconnect_with_reason(client_id, reason);
+        handle_server_result(server_result, &self.socket, &mut self.connected_at, server);
+    }
+
+    /// Revokes the connect token used by the client, so it can't be used to connect again until it expires.
+    /// Use with [`disconnect_client_with_reason`](Self::disconnect_client_with_reason) to ban a connected client.
+    pub fn revoke_token_for_client_id(&mut self, client_id: ClientId) -> Result<(), NetcodeError> {
+        self.netcode_server.revoke_token_for_client_id(client_id)
+    }
+
+    /// Set the DSCP value (0-63) used to mark the packets of each channel, for network QoS.
+    /// Packets from channels not in the map, and packets from the netcode protocol, are not marked.
+    ///
+    /// Only supported for IPv4 sockets.
+    pub fn set_channel_dscp(&mut self, channel_dscp: HashMap<u8, u8>) {
+        self.channel_dscp = channel_dscp;
+    }
+
+    /// Returns the counters of the netcode protocol events handled by the server.
+    pub fn stats(&self) -> NetcodeStats {
+        self.netcode_server.stats()
+    }
+
     /// Returns the duration since the connected client last received a packet.
     /// Usefull to detect users that are timing out.
     pub fn time_since_last_received_packet(&self, client_id: ClientId) -> Option<Duration> {
         self.netcode_server.time_since_last_received_packet(client_id)
     }
 
+    /// Returns when the client completed the netcode handshake, None if the client is not connected.
+    /// This is the instant the transport accepted the client, before the [`RenetServer`] emits its connected event.
+    pub fn connected_since(&self, client_id: ClientId) -> Option<Instant> {
+        self.connected_at.get(&client_id).copied()
+    }
+
     /// Advances the transport by the duration, and receive packets from the network.
+    /// Does nothing after [`begin_shutdown`](GracefulShutdown::begin_shutdown).
     pub fn update(&mut self, duration: Duration, server: &mut RenetServer) -> Result<(), NetcodeTransportError> {
+        if self.is_shutdown {
+            return Ok(());
+        }
         self.netcode_server.update(duration);
 
         loop {
             match self.socket.recv_from(&mut self.buffer) {
                 Ok((len, addr)) => {
                     let server_result = self.netcode_server.process_packet(addr, &mut self.buffer[..len]);
-                    handle_server_result(server_result, &self.socket, server);
+                    handle_server_result(server_result, &self.socket, &mut self.connected_at, server);
                 }
                 Err(ref e) if e.kind() == io::ErrorKind::WouldBlock => break,
                 Err(ref e) if e.kind() == io::ErrorKind::Interrupted => break,
@@ -101,26 +178,35 @@ impl NetcodeServerTransport {
 
         for client_id in self.netcode_server.clients_id() {
             let server_result = self.netcode_server.update_client(client_id);
-            handle_server_result(server_result, &self.socket, server);
+            handle_server_result(server_result, &self.socket, &mut self.connected_at, server);
         }
 
         for disconnection_id in server.disconnections_id() {
             let server_result = self.netcode_server.disconnect(disconnection_id);
-            handle_server_result(server_result, &self.socket, server);
+            handle_server_result(server_result, &self.socket, &mut self.connected_at, server);
         }
 
         Ok(())
     }
 
     /// Send packets to connected clients.
-    pub fn send_packets(&mut self, server: &mut RenetServer) {
+    ///
+    /// Packets are still sent to the other clients when sending to one of them fails,
+    /// the last socket error is returned.
+    pub fn send_packets(&mut self, server: &mut RenetServer) -> Result<(), NetcodeTransportError> {
+        if self.is_shutdown {
+            return Ok(());
+        }
+        let mut result = Ok(());
         'clients: for client_id in server.clients_id() {
             let packets = server.get_packets_to_send(client_id).unwrap();
             for packet in packets {
+                self.mark_packet(&packet);
                 match self.netcode_server.generate_payload_packet(client_id, &packet) {
                     Ok((addr, payload)) => {
                         if let Err(e) = self.socket.send_to(payload, addr) {
                             log::error!("Failed to send packet to client {client_id} ({addr}): {e}");
+                            result = Err(e.into());
                             continue 'clients;
                         }
                     }
@@ -131,10 +217,69 @@ impl NetcodeServerTransport {
                 }
             }
         }
+
+        self.mark_packet(&[]);
+
+        result
+    }
+
+    // Set the type of service of the socket from the DSCP of the packet channel.
+    fn mark_packet(&mut self, packet: &[u8]) {
+        if self.channel_dscp.is_empty() && self.current_tos == 0 {
+            return;
+        }
+
+        let dscp = packet_channel_id(packet).and_then(|channel_id| self.channel_dscp.get(&channel_id));
+        let tos = dscp.map(|dscp| (*dscp as u32 & 0x3F) << 2).unwrap_or(0);
+        if tos == self.current_tos {
+            return;
+        }
+
+        self.current_tos = tos;
+        #[cfg(not(target_family = "wasm"))]
+        if let Err(e) = socket2::SockRef::from(&self.socket).set_tos_v4(tos) {
+            log::error!("Failed to set the socket type of service: {e}");
+        }
+    }
+}
+
+impl ServerTransport for NetcodeServerTransport {
+    // Socket errors are already logged for each client
+    fn send_packets(&mut self, server: &mut RenetServer) {
+        let _ = NetcodeServerTransport::send_packets(self, server);
+    }
+
+    fn disconnect(&mut self, client_id: ClientId, server: &mut RenetServer) {
+        let server_result = self.netcode_server.disconnect(client_id);
+        handle_server_result(server_result, &self.socket, &mut self.connected_at, server);
+    }
+}
+
+// Netcode has no acknowledgment of the disconnection, the disconnect packets are sent right away
+// so the shutdown completes as soon as it begins.
+impl GracefulShutdown for NetcodeServerTransport {
+    fn begin_shutdown(&mut self, server: &mut RenetServer) {
+        if self.is_shutdown {
+            return;
+        }
+        if let Err(e) = NetcodeServerTransport::send_packets(self, server) {
+            log::error!("Failed to send the last packets: {e}");
+        }
+        self.disconnect_all(server);
+        self.is_shutdown = true;
+    }
+
+    fn is_shutdown_complete(&self) -> bool {
+        self.is_shutdown
     }
 }
 
-fn handle_server_result(server_result: ServerResult, socket: &UdpSocket, reliable_server: &mut RenetServer) {
+fn handle_server_result(
+    server_result: ServerResult,
+    socket: &UdpSocket,
+    connected_at: &mut HashMap<ClientId, Instant>,
+    reliable_server: &mut RenetServer,
+) {
     let send_packet = |packet: &[u8], addr: SocketAddr| {
         if let Err(err) = socket.send_to(packet, addr) {
             log::error!("Failed to send packet to {addr}: {err}");
@@ -157,10 +302,12 @@ fn handle_server_result(server_result: ServerResult, socket: &UdpSocket, reliabl
             addr,
             payload,
         } => {
+            connected_at.insert(client_id, Instant::now());
             reliable_server.add_connection(client_id);
             send_packet(payload, addr);
         }
         ServerResult::ClientDisconnected { client_id, addr, payload } => {
+            connected_at.remove(&client_id);
             reliable_server.remove_connection(client_id);
             if let Some(payload) = payload {
                 send_packet(payload, addr);
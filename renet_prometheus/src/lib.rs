@@ -0,0 +1,239 @@
+use std::{
+    collections::BTreeMap,
+    fmt::{self, Write},
+};
+
+use renet::{ClientId, DisconnectReason, RenetServer, ServerEvent};
+
+#[cfg(feature = "netcode")]
+use renet_netcode::{NetcodeServerTransport, NetcodeStats};
+
+#[derive(Debug, Clone, Copy, Default)]
+struct ClientMetrics {
+    rtt: f64,
+    packet_loss: f64,
+}
+
+/// In-memory registry of the metrics of a [`RenetServer`], rendered in the Prometheus text exposition format.
+///
+/// Call [`update`](Self::update) every frame and forward the server events to [`record_event`](Self::record_event),
+/// then serve [`render`](Self::render) from the HTTP stack of the game.
+#[derive(Debug, Default)]
+pub struct RenetMetrics {
+    client_labels: bool,
+    connected_clients: usize,
+    clients: BTreeMap<ClientId, ClientMetrics>,
+    sent_bytes: u64,
+    received_bytes: u64,
+    connections: u64,
+    disconnections: BTreeMap<&'static str, u64>,
+    #[cfg(feature = "netcode")]
+    netcode: Option<NetcodeStats>,
+}
+
+impl RenetMetrics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Exports the RTT and packet loss of each client, labeled with its id. Disabled by default,
+    /// since every client creates new time series in Prometheus.
+    pub fn set_client_labels(&mut self, enabled: bool) {
+        self.client_labels = enabled;
+        if !enabled {
+            self.clients.clear();
+        }
+    }
+
+    pub fn client_labels(&self) -> bool {
+        self.client_labels
+    }
+
+    /// Updates the metrics from the server.
+    /// The bytes counters are the exact totals of the server, including the disconnected clients.
+    pub fn update(&mut self, server: &RenetServer) {
+        self.connected_clients = server.connected_clients();
+        self.sent_bytes = server.bytes_sent_total();
+        self.received_bytes = server.bytes_received_total();
+        self.clients.clear();
+        for client_id in server.clients_id_iter() {
+            let Ok(network_info) = server.network_info(client_id) else {
+                continue;
+            };
+            if self.client_labels {
+                let metrics = ClientMetrics {
+                    rtt: network_info.rtt,
+                    packet_loss: network_info.packet_loss,
+                };
+                self.clients.insert(client_id, metrics);
+            }
+        }
+    }
+
+    /// Counts the connections and the disconnections by reason.
+    pub fn record_event(&mut self, event: &ServerEvent) {
+        match event {
+            ServerEvent::ClientConnected { .. } => self.connections += 1,
+            ServerEvent::ClientDisconnected { client_id, reason } => {
+                *self.disconnections.entry(reason_label(reason)).or_default() += 1;
+                self.clients.remove(client_id);
+            }
+        }
+    }
+
+    /// Updates the handshake stats of the netcode transport.
+    #[cfg(feature = "netcode")]
+    pub fn update_netcode(&mut self, transport: &NetcodeServerTransport) {
+        self.netcode = Some(transport.stats());
+    }
+
+    /// Renders the metrics in the Prometheus text exposition format.
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        // Writing to a String never fails
+        let _ = self.write_metrics(&mut out);
+        out
+    }
+
+    fn write_metrics(&self, out: &mut String) -> fmt::Result {
+        write_header(out, "renet_connected_clients", "gauge", "Number of connected clients.")?;
+        writeln!(out, "renet_connected_clients {}", self.connected_clients)?;
+
+        write_header(out, "renet_sent_bytes_total", "counter", "Bytes sent to the clients.")?;
+        writeln!(out, "renet_sent_bytes_total {}", self.sent_bytes)?;
+
+        write_header(out, "renet_received_bytes_total", "counter", "Bytes received from the clients.")?;
+        writeln!(out, "renet_received_bytes_total {}", self.received_bytes)?;
+
+        write_header(out, "renet_connections_total", "counter", "Clients connected to the server.")?;
+        writeln!(out, "renet_connections_total {}", self.connections)?;
+
+        write_header(
+            out,
+            "renet_disconnections_total",
+            "counter",
+            "Clients disconnected from the server by reason.",
+        )?;
+        for (reason, count) in self.disconnections.iter() {
+            writeln!(out, "renet_disconnections_total{{reason=\"{reason}\"}} {count}")?;
+        }
+
+        if self.client_labels {
+            write_header(out, "renet_client_rtt_seconds", "gauge", "Round-trip time of each client.")?;
+            for (client_id, metrics) in self.clients.iter() {
+                writeln!(out, "renet_client_rtt_seconds{{client_id=\"{client_id}\"}} {}", metrics.rtt)?;
+            }
+
+            write_header(
+                out,
+                "renet_client_packet_loss_ratio",
+                "gauge",
+                "Packet loss of each client, from 0 to 1.",
+            )?;
+            for (client_id, metrics) in self.clients.iter() {
+                writeln!(
+                    out,
+                    "renet_client_packet_loss_ratio{{client_id=\"{client_id}\"}} {}",
+                    metrics.packet_loss
+                )?;
+            }
+        }
+
+        #[cfg(feature = "netcode")]
+        if let Some(stats) = &self.netcode {
+            let counters = [
+                (
+                    "renet_netcode_tokens_validated_total",
+                    "Connection requests with a valid connect token.",
+                    stats.tokens_validated,
+                ),
+                (
+                    "renet_netcode_tokens_rejected_total",
+                    "Connection requests with an invalid connect token.",
+                    stats.tokens_rejected,
+                ),
+                (
+                    "renet_netcode_handshakes_completed_total",
+                    "Clients that completed the handshake.",
+                    stats.handshakes_completed,
+                ),
+                (
+                    "renet_netcode_handshakes_timed_out_total",
+                    "Clients that did not complete the handshake in time.",
+                    stats.handshakes_timed_out,
+                ),
+                (
+                    "renet_netcode_replay_attacks_rejected_total",
+                    "Packets discarded as replayed.",
+                    stats.replay_attacks_rejected,
+                ),
+            ];
+            for (name, help, value) in counters {
+                write_header(out, name, "counter", help)?;
+                writeln!(out, "{name} {value}")?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn write_header(out: &mut String, name: &str, metric_type: &str, help: &str) -> fmt::Result {
+    writeln!(out, "# HELP {name} {help}")?;
+    writeln!(out, "# TYPE {name} {metric_type}")
+}
+
+fn reason_label(reason: &DisconnectReason) -> &'static str {
+    match reason {
+        DisconnectReason::Transport => "transport",
+        DisconnectReason::DisconnectedByClient => "disconnected_by_client",
+        DisconnectReason::DisconnectedByServer => "disconnected_by_server",
+        DisconnectReason::PacketSerialization(_) => "packet_serialization",
+        DisconnectReason::PacketDeserialization(_) => "packet_deserialization",
+        DisconnectReason::ReceivedInvalidChannelId(_) => "invalid_channel_id",
+        DisconnectReason::SendChannelError { .. } => "send_channel_error",
+        DisconnectReason::ReceiveChannelError { .. } => "receive_channel_error",
+        DisconnectReason::SchemaMismatch { .. } => "schema_mismatch",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use renet::{ConnectionConfig, DefaultChannel};
+
+    use super::*;
+
+    #[test]
+    fn render_metrics() {
+        let mut server = RenetServer::new(ConnectionConfig::default());
+        let mut client = server.new_local_client(1);
+        let mut disconnected_client = server.new_local_client(2);
+        server.broadcast_message(DefaultChannel::ReliableOrdered, vec![0; 100]);
+        server.process_local_client(1, &mut client).unwrap();
+        server.process_local_client(2, &mut disconnected_client).unwrap();
+        let sent_bytes = server.bytes_sent_total();
+        assert!(sent_bytes > 0);
+        server.disconnect(2);
+        server.remove_connection(2);
+
+        let mut metrics = RenetMetrics::new();
+        metrics.update(&server);
+        while let Some(event) = server.get_event() {
+            metrics.record_event(&event);
+        }
+
+        let text = metrics.render();
+        assert!(text.contains("# TYPE renet_connected_clients gauge\nrenet_connected_clients 1\n"));
+        assert!(text.contains("renet_connections_total 2\n"));
+        // The bytes of the disconnected client are kept
+        assert!(text.contains(&format!("renet_sent_bytes_total {sent_bytes}\n")));
+        assert!(text.contains("renet_disconnections_total{reason=\"disconnected_by_server\"} 1\n"));
+        assert!(!text.contains("client_id"));
+
+        metrics.set_client_labels(true);
+        metrics.update(&server);
+        let text = metrics.render();
+        assert!(text.contains("renet_client_rtt_seconds{client_id=\"1\"} 0\n"));
+        assert!(!text.contains("client_id=\"2\""));
+    }
+}
@@ -0,0 +1,5 @@
+mod packet;
+mod server;
+
+pub use packet::*;
+pub use server::*;
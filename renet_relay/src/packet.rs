@@ -0,0 +1,109 @@
+/// Size of the token that authenticates the parties with the relay.
+pub const RELAY_AUTH_TOKEN_BYTES: usize = 32;
+/// Maximum size of a relay packet, header included.
+pub const RELAY_MAX_PACKET_BYTES: usize = 1500;
+/// Size of the header of a [`RelayPacket::Data`].
+pub const RELAY_DATA_HEADER_BYTES: usize = 1 + TUNNEL_ID_BYTES;
+
+const TUNNEL_ID_BYTES: usize = 16;
+
+const PACKET_BIND: u8 = 0;
+const PACKET_BOUND: u8 = 1;
+const PACKET_DATA: u8 = 2;
+
+/// Identifies a tunnel between two parties, issued to both by an orchestrator. Usually the bytes of a UUID.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TunnelId(pub [u8; TUNNEL_ID_BYTES]);
+
+/// Packets exchanged between the parties and the [`RelayServer`](crate::RelayServer).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RelayPacket<'a> {
+    /// Sent by a party to join a tunnel, and as keep alive.
+    Bind {
+        tunnel_id: TunnelId,
+        auth_token: [u8; RELAY_AUTH_TOKEN_BYTES],
+    },
+    /// Sent by the relay when a party joined the tunnel.
+    Bound { tunnel_id: TunnelId },
+    /// Payload forwarded to the other party of the tunnel.
+    Data { tunnel_id: TunnelId, payload: &'a [u8] },
+}
+
+impl<'a> RelayPacket<'a> {
+    pub fn tunnel_id(&self) -> TunnelId {
+        match self {
+            RelayPacket::Bind { tunnel_id, .. } | RelayPacket::Bound { tunnel_id } | RelayPacket::Data { tunnel_id, .. } => *tunnel_id,
+        }
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(RELAY_DATA_HEADER_BYTES);
+        match self {
+            RelayPacket::Bind { tunnel_id, auth_token } => {
+                bytes.push(PACKET_BIND);
+                bytes.extend_from_slice(&tunnel_id.0);
+                bytes.extend_from_slice(auth_token);
+            }
+            RelayPacket::Bound { tunnel_id } => {
+                bytes.push(PACKET_BOUND);
+                bytes.extend_from_slice(&tunnel_id.0);
+            }
+            RelayPacket::Data { tunnel_id, payload } => {
+                bytes.push(PACKET_DATA);
+                bytes.extend_from_slice(&tunnel_id.0);
+                bytes.extend_from_slice(payload);
+            }
+        }
+        bytes
+    }
+
+    /// Returns None if the packet is invalid.
+    pub fn from_bytes(bytes: &'a [u8]) -> Option<Self> {
+        let (&packet_type, bytes) = bytes.split_first()?;
+        let tunnel_id = TunnelId(bytes.get(..TUNNEL_ID_BYTES)?.try_into().ok()?);
+        let bytes = &bytes[TUNNEL_ID_BYTES..];
+        match packet_type {
+            PACKET_BIND => Some(RelayPacket::Bind {
+                tunnel_id,
+                auth_token: bytes.try_into().ok()?,
+            }),
+            PACKET_BOUND if bytes.is_empty() => Some(RelayPacket::Bound { tunnel_id }),
+            PACKET_DATA => Some(RelayPacket::Data { tunnel_id, payload: bytes }),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn serialize_packets() {
+        let tunnel_id = TunnelId([7; TUNNEL_ID_BYTES]);
+        let packets = [
+            RelayPacket::Bind {
+                tunnel_id,
+                auth_token: [3; RELAY_AUTH_TOKEN_BYTES],
+            },
+            RelayPacket::Bound { tunnel_id },
+            RelayPacket::Data {
+                tunnel_id,
+                payload: b"payload",
+            },
+        ];
+
+        for packet in packets {
+            let bytes = packet.to_bytes();
+            assert_eq!(RelayPacket::from_bytes(&bytes), Some(packet));
+        }
+    }
+
+    #[test]
+    fn invalid_packets() {
+        assert_eq!(RelayPacket::from_bytes(&[]), None);
+        assert_eq!(RelayPacket::from_bytes(&[PACKET_DATA, 1, 2]), None);
+        assert_eq!(RelayPacket::from_bytes(&[PACKET_BIND; 1 + TUNNEL_ID_BYTES + 3]), None);
+        assert_eq!(RelayPacket::from_bytes(&[42; 1 + TUNNEL_ID_BYTES]), None);
+    }
+}
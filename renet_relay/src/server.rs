@@ -0,0 +1,283 @@
+use std::{
+    collections::HashMap,
+    io,
+    net::{SocketAddr, UdpSocket},
+    time::Duration,
+};
+
+use crate::{RelayPacket, TunnelId, RELAY_AUTH_TOKEN_BYTES, RELAY_MAX_PACKET_BYTES};
+
+/// A party that didn't send any packet for this long leaves its tunnel.
+pub const RELAY_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone)]
+pub struct RelayConfig {
+    /// Address the relay socket is bound to.
+    pub listen_addr: SocketAddr,
+    /// Maximum number of tunnels at the same time, new tunnels are refused above it.
+    pub max_tunnels: usize,
+    /// Token the parties must send to bind to a tunnel, shared with the orchestrator.
+    pub auth_token: [u8; RELAY_AUTH_TOKEN_BYTES],
+}
+
+/// Forwards the packets between the two parties of each tunnel, see [`RelayPacket`].
+///
+/// The payloads are forwarded as they are, the relay never reads them.
+/// Invalid packets, packets with a wrong auth token and binds to a full tunnel are ignored without answer.
+#[derive(Debug)]
+pub struct RelayServer {
+    socket: UdpSocket,
+    max_tunnels: usize,
+    auth_token: [u8; RELAY_AUTH_TOKEN_BYTES],
+    tunnels: HashMap<TunnelId, Tunnel>,
+    current_time: Duration,
+    buffer: [u8; RELAY_MAX_PACKET_BYTES],
+}
+
+#[derive(Debug, Default)]
+struct Tunnel {
+    parties: Vec<Party>,
+}
+
+#[derive(Debug)]
+struct Party {
+    addr: SocketAddr,
+    last_received: Duration,
+}
+
+impl RelayServer {
+    pub fn new(config: RelayConfig) -> Result<Self, io::Error> {
+        let socket = UdpSocket::bind(config.listen_addr)?;
+        socket.set_nonblocking(true)?;
+
+        Ok(Self {
+            socket,
+            max_tunnels: config.max_tunnels,
+            auth_token: config.auth_token,
+            tunnels: HashMap::new(),
+            current_time: Duration::ZERO,
+            buffer: [0; RELAY_MAX_PACKET_BYTES],
+        })
+    }
+
+    /// Returns the address the relay socket is bound to, with the actual port when it was bound to port 0.
+    pub fn local_addr(&self) -> Result<SocketAddr, io::Error> {
+        self.socket.local_addr()
+    }
+
+    /// Returns the number of tunnels with at least one party.
+    pub fn tunnels(&self) -> usize {
+        self.tunnels.len()
+    }
+
+    /// Returns true if both parties are bound to the tunnel.
+    pub fn is_tunnel_open(&self, tunnel_id: TunnelId) -> bool {
+        self.tunnels.get(&tunnel_id).is_some_and(|tunnel| tunnel.parties.len() == 2)
+    }
+
+    /// Removes a tunnel, for example when the orchestrator ended the match. The parties can bind to it again.
+    pub fn close_tunnel(&mut self, tunnel_id: TunnelId) {
+        self.tunnels.remove(&tunnel_id);
+    }
+
+    /// Receives and forwards the packets, and removes the parties that timed out.
+    pub fn update(&mut self, duration: Duration) -> Result<(), io::Error> {
+        self.current_time += duration;
+
+        loop {
+            match self.socket.recv_from(&mut self.buffer) {
+                Ok((len, addr)) => self.process_packet(len, addr),
+                Err(ref e) if e.kind() == io::ErrorKind::WouldBlock => break,
+                // Sent to a party that closed its socket
+                Err(ref e) if e.kind() == io::ErrorKind::ConnectionReset => continue,
+                Err(e) => return Err(e),
+            };
+        }
+
+        let current_time = self.current_time;
+        self.tunnels.retain(|_, tunnel| {
+            tunnel
+                .parties
+                .retain(|party| current_time.saturating_sub(party.last_received) < RELAY_TIMEOUT);
+            !tunnel.parties.is_empty()
+        });
+
+        Ok(())
+    }
+
+    fn process_packet(&mut self, len: usize, addr: SocketAddr) {
+        let Some(packet) = RelayPacket::from_bytes(&self.buffer[..len]) else {
+            return;
+        };
+
+        match packet {
+            RelayPacket::Bind { tunnel_id, auth_token } => {
+                if !tokens_match(&auth_token, &self.auth_token) {
+                    return;
+                }
+                if !self.tunnels.contains_key(&tunnel_id) && self.tunnels.len() >= self.max_tunnels {
+                    return;
+                }
+
+                let tunnel = self.tunnels.entry(tunnel_id).or_default();
+                if let Some(party) = tunnel.parties.iter_mut().find(|party| party.addr == addr) {
+                    party.last_received = self.current_time;
+                } else if tunnel.parties.len() < 2 {
+                    tunnel.parties.push(Party {
+                        addr,
+                        last_received: self.current_time,
+                    });
+                } else {
+                    return;
+                }
+
+                let bound = RelayPacket::Bound { tunnel_id }.to_bytes();
+                if let Err(e) = self.socket.send_to(&bound, addr) {
+                    log::error!("Failed to send bound packet to {addr}: {e}");
+                }
+            }
+            RelayPacket::Data { tunnel_id, .. } => {
+                let Some(tunnel) = self.tunnels.get_mut(&tunnel_id) else {
+                    return;
+                };
+                let Some(party) = tunnel.parties.iter_mut().find(|party| party.addr == addr) else {
+                    return;
+                };
+                party.last_received = self.current_time;
+
+                // The packet is forwarded with its header, so the other party knows the tunnel
+                if let Some(peer) = tunnel.parties.iter().find(|party| party.addr != addr) {
+                    if let Err(e) = self.socket.send_to(&self.buffer[..len], peer.addr) {
+                        log::error!("Failed to forward packet to {}: {e}", peer.addr);
+                    }
+                }
+            }
+            RelayPacket::Bound { .. } => {}
+        }
+    }
+}
+
+// Compares every byte, so the response time doesn't tell how much of a guessed token is right
+fn tokens_match(a: &[u8; RELAY_AUTH_TOKEN_BYTES], b: &[u8; RELAY_AUTH_TOKEN_BYTES]) -> bool {
+    let diff = a.iter().zip(b.iter()).fold(0u8, |diff, (a, b)| diff | (a ^ b));
+    std::hint::black_box(diff) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const AUTH_TOKEN: [u8; RELAY_AUTH_TOKEN_BYTES] = [9; RELAY_AUTH_TOKEN_BYTES];
+    const TUNNEL: TunnelId = TunnelId([1; 16]);
+
+    fn new_relay(max_tunnels: usize) -> RelayServer {
+        RelayServer::new(RelayConfig {
+            listen_addr: "127.0.0.1:0".parse().unwrap(),
+            max_tunnels,
+            auth_token: AUTH_TOKEN,
+        })
+        .unwrap()
+    }
+
+    fn new_party(relay: &RelayServer) -> UdpSocket {
+        let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+        socket.connect(relay.local_addr().unwrap()).unwrap();
+        socket.set_read_timeout(Some(Duration::from_millis(100))).unwrap();
+        socket
+    }
+
+    fn bind(party: &UdpSocket, tunnel_id: TunnelId, auth_token: [u8; RELAY_AUTH_TOKEN_BYTES]) {
+        party.send(&RelayPacket::Bind { tunnel_id, auth_token }.to_bytes()).unwrap();
+    }
+
+    fn receive(party: &UdpSocket) -> Option<Vec<u8>> {
+        let mut buffer = [0; RELAY_MAX_PACKET_BYTES];
+        let len = party.recv(&mut buffer).ok()?;
+        Some(buffer[..len].to_vec())
+    }
+
+    // Loopback packets are delivered right away, but give the relay a few tries
+    fn update(relay: &mut RelayServer, duration: Duration) {
+        for _ in 0..3 {
+            relay.update(duration).unwrap();
+            std::thread::sleep(Duration::from_millis(5));
+        }
+    }
+
+    #[test]
+    fn forward_between_parties() {
+        let mut relay = new_relay(8);
+        let party_a = new_party(&relay);
+        let party_b = new_party(&relay);
+
+        bind(&party_a, TUNNEL, AUTH_TOKEN);
+        bind(&party_b, TUNNEL, AUTH_TOKEN);
+        update(&mut relay, Duration::ZERO);
+        let bound = RelayPacket::Bound { tunnel_id: TUNNEL }.to_bytes();
+        assert_eq!(receive(&party_a), Some(bound.clone()));
+        assert_eq!(receive(&party_b), Some(bound));
+        assert!(relay.is_tunnel_open(TUNNEL));
+
+        let data = RelayPacket::Data {
+            tunnel_id: TUNNEL,
+            payload: b"hello",
+        }
+        .to_bytes();
+        party_a.send(&data).unwrap();
+        update(&mut relay, Duration::ZERO);
+        assert_eq!(receive(&party_b), Some(data));
+    }
+
+    #[test]
+    fn compare_tokens() {
+        let mut token = AUTH_TOKEN;
+        assert!(tokens_match(&token, &AUTH_TOKEN));
+        token[RELAY_AUTH_TOKEN_BYTES - 1] ^= 1;
+        assert!(!tokens_match(&token, &AUTH_TOKEN));
+    }
+
+    #[test]
+    fn ignore_invalid_binds() {
+        let mut relay = new_relay(1);
+        let party_a = new_party(&relay);
+        let party_b = new_party(&relay);
+        let party_c = new_party(&relay);
+
+        bind(&party_a, TUNNEL, [0; RELAY_AUTH_TOKEN_BYTES]);
+        update(&mut relay, Duration::ZERO);
+        assert_eq!(receive(&party_a), None);
+        assert_eq!(relay.tunnels(), 0);
+
+        // Full tunnel, and over the maximum of tunnels
+        bind(&party_a, TUNNEL, AUTH_TOKEN);
+        bind(&party_b, TUNNEL, AUTH_TOKEN);
+        update(&mut relay, Duration::ZERO);
+        bind(&party_c, TUNNEL, AUTH_TOKEN);
+        bind(&party_c, TunnelId([2; 16]), AUTH_TOKEN);
+        update(&mut relay, Duration::ZERO);
+        assert_eq!(receive(&party_c), None);
+        assert_eq!(relay.tunnels(), 1);
+    }
+
+    #[test]
+    fn parties_timeout() {
+        let mut relay = new_relay(8);
+        let party_a = new_party(&relay);
+        let party_b = new_party(&relay);
+        bind(&party_a, TUNNEL, AUTH_TOKEN);
+        bind(&party_b, TUNNEL, AUTH_TOKEN);
+        update(&mut relay, Duration::ZERO);
+        assert!(relay.is_tunnel_open(TUNNEL));
+
+        // Only the party A keeps the tunnel alive
+        relay.update(RELAY_TIMEOUT / 2).unwrap();
+        bind(&party_a, TUNNEL, AUTH_TOKEN);
+        update(&mut relay, Duration::ZERO);
+        relay.update(RELAY_TIMEOUT / 2).unwrap();
+        assert!(!relay.is_tunnel_open(TUNNEL));
+        assert_eq!(relay.tunnels(), 1);
+
+        relay.update(RELAY_TIMEOUT).unwrap();
+        assert_eq!(relay.tunnels(), 0);
+    }
+}
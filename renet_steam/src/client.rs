@@ -1,8 +1,15 @@
-use std::net::SocketAddr;
+use std::{
+    net::SocketAddr,
+    time::{Duration, Instant},
+};
 
 use super::MAX_MESSAGE_BATCH_SIZE;
+use crate::{
+    fragment::{Fragmenter, Reassembler},
+    security, stats, SecurityInfo,
+};
 use log::info;
-use renet::RenetClient;
+use renet::{ConnectionConfig, RenetClient, TransportStats};
 use steamworks::{
     networking_sockets::{InvalidHandle, NetConnection, NetworkingSockets},
     networking_types::{NetConnectionEnd, NetworkingConnectionState, NetworkingIdentity, SendFlags},
@@ -10,14 +17,59 @@ use steamworks::{
 };
 
 enum ConnectionState {
-    Connected { connection: NetConnection<ClientManager> },
-    Disconnected { end_reason: NetConnectionEnd },
+    Connected {
+        connection: NetConnection<ClientManager>,
+    },
+    Disconnected {
+        end_reason: NetConnectionEnd,
+        end_message: Option<String>,
+    },
+}
+
+#[derive(Clone, Copy)]
+enum ConnectionTarget {
+    P2p(SteamId),
+    Ip(SocketAddr),
+}
+
+/// How the transport should reconnect when the connection fails, see [`SteamClientTransport::enable_auto_reconnect`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReconnectPolicy {
+    /// Maximum reconnection attempts before giving up.
+    pub max_attempts: u32,
+    /// Delay before the first attempt, doubled on each following attempt.
+    pub backoff: Duration,
+}
+
+/// The status of the automatic reconnection.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReconnectStatus {
+    /// The transport is not reconnecting.
+    Idle,
+    /// The connection failed and the transport is trying to reconnect.
+    Reconnecting { attempt: u32, max_attempts: u32 },
+    /// All the reconnection attempts failed.
+    GaveUp,
+}
+
+struct Reconnect {
+    policy: ReconnectPolicy,
+    // The client is recreated on each redial, the server sees a new connection
+    connection_config: ConnectionConfig,
+    attempts: u32,
+    next_attempt: Option<Instant>,
+    status: ReconnectStatus,
 }
 
 #[cfg_attr(feature = "bevy", derive(bevy_ecs::resource::Resource))]
 pub struct SteamClientTransport {
     networking_sockets: NetworkingSockets<ClientManager>,
     state: ConnectionState,
+    target: ConnectionTarget,
+    reconnect: Option<Reconnect>,
+    fragmenter: Fragmenter,
+    reassembler: Reassembler,
+    close_on_drop: bool,
 }
 
 impl SteamClientTransport {
@@ -29,6 +81,11 @@ impl SteamClientTransport {
         Ok(Self {
             networking_sockets,
             state: ConnectionState::Connected { connection },
+            target: ConnectionTarget::P2p(*steam_id),
+            reconnect: None,
+            fragmenter: Fragmenter::default(),
+            reassembler: Reassembler::default(),
+            close_on_drop: true,
         })
     }
 
@@ -40,6 +97,11 @@ impl SteamClientTransport {
         Ok(Self {
             networking_sockets,
             state: ConnectionState::Connected { connection },
+            target: ConnectionTarget::Ip(socket_addr),
+            reconnect: None,
+            fragmenter: Fragmenter::default(),
+            reassembler: Reassembler::default(),
+            close_on_drop: true,
         })
     }
 
@@ -94,6 +156,138 @@ impl SteamClientTransport {
         None
     }
 
+    /// Returns the message describing why the connection was closed, like the reason given by the server when kicking the client.
+    pub fn disconnect_message(&self) -> Option<String> {
+        let connection = match &self.state {
+            ConnectionState::Connected { connection } => connection,
+            ConnectionState::Disconnected { end_message, .. } => {
+                return end_message.clone();
+            }
+        };
+
+        let info = self.networking_sockets.get_connection_info(connection).ok()?;
+        info.end_reason()?;
+        security::end_debug_message(&info)
+    }
+
+    /// Returns the encryption and identity status of the connection with the server.
+    pub fn connection_security(&self) -> Option<SecurityInfo> {
+        let ConnectionState::Connected { connection } = &self.state else {
+            return None;
+        };
+        let info = self.networking_sockets.get_connection_info(connection).ok()?;
+
+        Some(SecurityInfo::from_connection_info(&info))
+    }
+
+    /// Returns the ping, connection quality, relay route and pending bytes of the connection measured by steam,
+    /// to show them with the renet stats. None if not connected.
+    pub fn transport_stats(&self) -> Option<TransportStats> {
+        let ConnectionState::Connected { connection } = &self.state else {
+            return None;
+        };
+        stats::transport_stats(&self.networking_sockets, connection)
+    }
+
+    /// Enables redialing the server when the connection fails, for example due to a relay failure.
+    /// Connections closed by the server or by [`disconnect`](Self::disconnect) are not reconnected.
+    ///
+    /// The session doesn't survive a redial: the server sees a new connection with new channels, so the [`RenetClient`]
+    /// is replaced by a new one created with the connection config, and kept as connecting while reconnecting.
+    /// The messages not delivered before the connection failed are lost.
+    pub fn enable_auto_reconnect(&mut self, policy: ReconnectPolicy, connection_config: ConnectionConfig) {
+        self.reconnect = Some(Reconnect {
+            policy,
+            connection_config,
+            attempts: 0,
+            next_attempt: None,
+            status: ReconnectStatus::Idle,
+        });
+    }
+
+    pub fn disable_auto_reconnect(&mut self) {
+        self.reconnect = None;
+    }
+
+    /// Returns the status of the automatic reconnection, [`ReconnectStatus::Idle`] if it's not enabled.
+    pub fn reconnect_status(&self) -> ReconnectStatus {
+        match &self.reconnect {
+            Some(reconnect) => reconnect.status,
+            None => ReconnectStatus::Idle,
+        }
+    }
+
+    // Returns true while the transport is reconnecting, the client is recreated when redialing.
+    fn try_reconnect(&mut self, client: &mut RenetClient) -> bool {
+        let state = self.connection_state();
+        let Some(reconnect) = &mut self.reconnect else {
+            return false;
+        };
+
+        let connection_failed = state == NetworkingConnectionState::ProblemDetectedLocally;
+        let reconnecting = matches!(reconnect.status, ReconnectStatus::Reconnecting { .. });
+        let redial_failed = reconnecting && state == NetworkingConnectionState::None;
+        if !connection_failed && !redial_failed {
+            if reconnecting {
+                reconnect.status = ReconnectStatus::GaveUp;
+            }
+            return false;
+        }
+
+        if reconnect.attempts >= reconnect.policy.max_attempts {
+            reconnect.status = ReconnectStatus::GaveUp;
+            return false;
+        }
+
+        let now = Instant::now();
+        match reconnect.next_attempt {
+            None => {
+                let backoff = reconnect.policy.backoff.saturating_mul(2u32.saturating_pow(reconnect.attempts));
+                reconnect.next_attempt = Some(now + backoff);
+                reconnect.status = ReconnectStatus::Reconnecting {
+                    attempt: reconnect.attempts + 1,
+                    max_attempts: reconnect.policy.max_attempts,
+                };
+                return true;
+            }
+            Some(next_attempt) if now < next_attempt => return true,
+            Some(_) => {}
+        }
+
+        reconnect.attempts += 1;
+        reconnect.next_attempt = None;
+        info!("Reconnecting, attempt {} of {}", reconnect.attempts, reconnect.policy.max_attempts);
+        let connection_config = reconnect.connection_config.clone();
+
+        if let ConnectionState::Connected { connection } = std::mem::replace(
+            &mut self.state,
+            ConnectionState::Disconnected {
+                end_reason: NetConnectionEnd::AppGeneric,
+                end_message: None,
+            },
+        ) {
+            connection.close(NetConnectionEnd::AppGeneric, Some("Client reconnecting"), false);
+        }
+
+        let connection = match self.target {
+            ConnectionTarget::P2p(steam_id) => self
+                .networking_sockets
+                .connect_p2p(NetworkingIdentity::new_steam_id(steam_id), 0, vec![]),
+            ConnectionTarget::Ip(socket_addr) => self.networking_sockets.connect_by_ip_address(socket_addr, vec![]),
+        };
+        self.fragmenter = Fragmenter::default();
+        self.reassembler = Reassembler::default();
+        match connection {
+            Ok(connection) => {
+                self.state = ConnectionState::Connected { connection };
+                reset_client(client, connection_config);
+            }
+            Err(InvalidHandle) => log::error!("Failed to reconnect: invalid handle"),
+        }
+
+        true
+    }
+
     pub fn client_id(&self, steam_client: &steamworks::Client<ClientManager>) -> u64 {
         steam_client.user().steam_id().raw()
     }
@@ -104,8 +298,10 @@ impl SteamClientTransport {
             return;
         }
 
+        self.reconnect = None;
         let disconnect_state = ConnectionState::Disconnected {
             end_reason: NetConnectionEnd::AppGeneric,
+            end_message: Some("Client disconnected".to_string()),
         };
         let old_state = std::mem::replace(&mut self.state, disconnect_state);
         if let ConnectionState::Connected { connection } = old_state {
@@ -113,27 +309,44 @@ impl SteamClientTransport {
         }
     }
 
+    /// Set whether the connection should be flushed and closed when the transport is dropped, enabled by default.
+    ///
+    /// Disable this if the transport may be dropped after the steamworks [`Client`](steamworks::Client) was shut down,
+    /// in that case the connection is leaked instead of calling into the dead steam interface.
+    pub fn set_close_on_drop(&mut self, close_on_drop: bool) {
+        self.close_on_drop = close_on_drop;
+    }
+
     pub fn update(&mut self, client: &mut RenetClient) {
         if self.is_disconnected() {
+            if !client.is_disconnected() && self.try_reconnect(client) {
+                client.set_connecting();
+                return;
+            }
+
             info!("Mark DC called!");
             // Mark the client as disconnected if an error occured in the transport layer
             client.disconnect_due_to_transport();
 
             if let ConnectionState::Connected { connection } = &self.state {
-                let end_reason = self
-                    .networking_sockets
-                    .get_connection_info(connection)
-                    .map(|info| info.end_reason())
-                    .unwrap_or_default()
+                let info = self.networking_sockets.get_connection_info(connection).ok();
+                let end_reason = info
+                    .as_ref()
+                    .and_then(|info| info.end_reason())
                     .unwrap_or(NetConnectionEnd::AppGeneric);
+                let end_message = info.as_ref().and_then(security::end_debug_message);
 
-                self.state = ConnectionState::Disconnected { end_reason };
+                self.state = ConnectionState::Disconnected { end_reason, end_message };
             }
 
             return;
         };
 
         if self.is_connected() {
+            if let Some(reconnect) = &mut self.reconnect {
+                reconnect.attempts = 0;
+                reconnect.status = ReconnectStatus::Idle;
+            }
             client.set_connected();
         } else if self.is_connecting() {
             client.set_connecting();
@@ -145,7 +358,9 @@ impl SteamClientTransport {
 
         if let Ok(messages) = connection.receive_messages(MAX_MESSAGE_BATCH_SIZE) {
             messages.iter().for_each(|message| {
-                client.process_packet(message.data());
+                if let Some(packet) = self.reassembler.receive(message.data()) {
+                    client.process_packet(&packet);
+                }
             });
         }
     }
@@ -164,9 +379,81 @@ impl SteamClientTransport {
         };
         let packets = client.get_packets_to_send();
         for packet in packets {
-            connection.send_message(&packet, SendFlags::UNRELIABLE)?;
+            // Packets above the steam unreliable limit are split in fragments
+            for message in self.fragmenter.split(&packet) {
+                connection.send_message(&message, SendFlags::UNRELIABLE)?;
+            }
         }
 
         connection.flush_messages()
     }
 }
+
+impl Drop for SteamClientTransport {
+    fn drop(&mut self) {
+        let disconnect_state = ConnectionState::Disconnected {
+            end_reason: NetConnectionEnd::AppGeneric,
+            end_message: None,
+        };
+        let ConnectionState::Connected { connection } = std::mem::replace(&mut self.state, disconnect_state) else {
+            return;
+        };
+
+        if !self.close_on_drop {
+            // Dropping the connection would also call into steam to close it
+            std::mem::forget(connection);
+            return;
+        }
+
+        let _ = connection.flush_messages();
+        connection.close(NetConnectionEnd::AppGeneric, Some("Client quit"), true);
+    }
+}
+
+// The server starts fresh channels for the new connection, the message ids of the old client would not match them
+fn reset_client(client: &mut RenetClient, connection_config: ConnectionConfig) {
+    *client = RenetClient::new(connection_config);
+    client.set_connecting();
+}
+
+#[cfg(test)]
+mod tests {
+    use renet::{DefaultChannel, RenetServer};
+
+    use super::*;
+
+    fn exchange(server: &mut RenetServer, client: &mut RenetClient) {
+        for packet in server.get_packets_to_send(1).unwrap() {
+            client.process_packet(&packet);
+        }
+        for packet in client.get_packets_to_send() {
+            server.process_packet_from(&packet, 1).unwrap();
+        }
+    }
+
+    #[test]
+    fn reset_client_after_redial() {
+        let mut server = RenetServer::new(ConnectionConfig::default());
+        let mut client = RenetClient::new(ConnectionConfig::default());
+        client.set_connected();
+        server.add_connection(1);
+        server.send_message(1, DefaultChannel::ReliableOrdered, "before");
+        client.send_message(DefaultChannel::ReliableOrdered, "before");
+        exchange(&mut server, &mut client);
+        assert_eq!(client.receive_message(DefaultChannel::ReliableOrdered).unwrap(), "before");
+        assert_eq!(server.receive_message(1, DefaultChannel::ReliableOrdered).unwrap(), "before");
+
+        // The server sees the redial as a new connection
+        server.remove_connection(1);
+        server.add_connection(1);
+        reset_client(&mut client, ConnectionConfig::default());
+        assert!(client.is_connecting());
+        client.set_connected();
+
+        server.send_message(1, DefaultChannel::ReliableOrdered, "after");
+        client.send_message(DefaultChannel::ReliableOrdered, "after");
+        exchange(&mut server, &mut client);
+        assert_eq!(client.receive_message(DefaultChannel::ReliableOrdered).unwrap(), "after");
+        assert_eq!(server.receive_message(1, DefaultChannel::ReliableOrdered).unwrap(), "after");
+    }
+}
@@ -0,0 +1,74 @@
+use std::fmt;
+
+use renet::{Bytes, ClientId, RenetServer};
+use steamworks::SteamId;
+
+/// Extension methods for a [`ClientId`], in the steam transport it's always the Steam64 ID of the client.
+pub trait ClientIdSteamExt {
+    /// Returns the [`SteamId`] of the client.
+    fn as_steam_id(&self) -> SteamId;
+
+    /// Returns a value that formats the client as a SteamID instead of a raw 64 bit number.
+    /// Formats as `STEAM_0:1:xxxxxxx`, or as `[U:1:xxxxxxx]` with the alternate flag (`{:#}`).
+    fn display_as_steam_id(&self) -> impl fmt::Display;
+}
+
+impl ClientIdSteamExt for ClientId {
+    fn as_steam_id(&self) -> SteamId {
+        SteamId::from_raw(*self)
+    }
+
+    fn display_as_steam_id(&self) -> impl fmt::Display {
+        SteamIdDisplay(self.as_steam_id())
+    }
+}
+
+/// Extension methods for a [`RenetServer`] used with the steam transport, to address clients by their [`SteamId`].
+pub trait RenetServerSteamExt {
+    /// Receive a message from a client over a channel.
+    fn receive_message_by_steam_id<I: Into<u8>>(&mut self, steam_id: SteamId, channel_id: I) -> Option<Bytes>;
+}
+
+impl RenetServerSteamExt for RenetServer {
+    fn receive_message_by_steam_id<I: Into<u8>>(&mut self, steam_id: SteamId, channel_id: I) -> Option<Bytes> {
+        // The steam transport adds the connections with the raw steam id as the client id
+        self.receive_message(steam_id.raw(), channel_id)
+    }
+}
+
+struct SteamIdDisplay(SteamId);
+
+impl fmt::Display for SteamIdDisplay {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if f.alternate() {
+            write!(f, "[U:1:{}]", self.0.account_id().raw())
+        } else {
+            write!(f, "{}", self.0.steamid32())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use renet::{ConnectionConfig, DefaultChannel};
+
+    use super::*;
+
+    #[test]
+    fn receive_message_by_steam_id() {
+        let steam_id = SteamId::from_raw(76561197960287930);
+        let mut server = RenetServer::new(ConnectionConfig::default());
+        let mut client = server.new_local_client(steam_id.raw());
+
+        client.send_message(DefaultChannel::ReliableOrdered, "hello");
+        server.process_local_client(steam_id.raw(), &mut client).unwrap();
+
+        let message = server
+            .receive_message_by_steam_id(steam_id, DefaultChannel::ReliableOrdered)
+            .unwrap();
+        assert_eq!(message, "hello");
+        assert!(server
+            .receive_message_by_steam_id(SteamId::from_raw(1), DefaultChannel::ReliableOrdered)
+            .is_none());
+    }
+}
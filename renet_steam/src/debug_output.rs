@@ -0,0 +1,81 @@
+use std::ffi::{c_char, CStr};
+
+use steamworks_sys as sys;
+
+const LOG_TARGET: &str = "renet_steam::steam_networking";
+
+/// Verbosity of the steam networking debug output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SteamDebugOutputLevel {
+    Bug,
+    Error,
+    Important,
+    Warning,
+    Msg,
+    /// Includes route selection, relay picks and retry attempts.
+    Verbose,
+    Debug,
+    Everything,
+}
+
+impl SteamDebugOutputLevel {
+    fn to_sys(self) -> sys::ESteamNetworkingSocketsDebugOutputType {
+        use sys::ESteamNetworkingSocketsDebugOutputType::*;
+        match self {
+            SteamDebugOutputLevel::Bug => k_ESteamNetworkingSocketsDebugOutputType_Bug,
+            SteamDebugOutputLevel::Error => k_ESteamNetworkingSocketsDebugOutputType_Error,
+            SteamDebugOutputLevel::Important => k_ESteamNetworkingSocketsDebugOutputType_Important,
+            SteamDebugOutputLevel::Warning => k_ESteamNetworkingSocketsDebugOutputType_Warning,
+            SteamDebugOutputLevel::Msg => k_ESteamNetworkingSocketsDebugOutputType_Msg,
+            SteamDebugOutputLevel::Verbose => k_ESteamNetworkingSocketsDebugOutputType_Verbose,
+            SteamDebugOutputLevel::Debug => k_ESteamNetworkingSocketsDebugOutputType_Debug,
+            SteamDebugOutputLevel::Everything => k_ESteamNetworkingSocketsDebugOutputType_Everything,
+        }
+    }
+}
+
+/// Forwards the steam networking debug output into `log`, with the target `renet_steam::steam_networking`.
+/// Messages about a connection are prefixed by steam with its description (`[#handle P2P steamid:...]`).
+///
+/// Applies to both transports, steam must be initialized before calling it.
+/// The [`Verbose`](SteamDebugOutputLevel::Verbose) level and above produce a lot of output from the
+/// steam networking thread and have a noticeable performance cost, use them only while debugging.
+pub fn install_debug_output(level: SteamDebugOutputLevel) {
+    set_debug_output(level.to_sys(), Some(forward_debug_output));
+}
+
+/// Removes the debug output installed with [`install_debug_output`].
+pub fn remove_debug_output() {
+    set_debug_output(
+        sys::ESteamNetworkingSocketsDebugOutputType::k_ESteamNetworkingSocketsDebugOutputType_None,
+        None,
+    );
+}
+
+fn set_debug_output(level: sys::ESteamNetworkingSocketsDebugOutputType, function: sys::FSteamNetworkingSocketsDebugOutput) {
+    unsafe {
+        let utils = sys::SteamAPI_SteamNetworkingUtils_SteamAPI_v004();
+        if utils.is_null() {
+            log::error!("Failed to set steam networking debug output: steam is not initialized");
+            return;
+        }
+        sys::SteamAPI_ISteamNetworkingUtils_SetDebugOutputFunction(utils, level, function);
+    }
+}
+
+unsafe extern "C" fn forward_debug_output(output_type: sys::ESteamNetworkingSocketsDebugOutputType, message: *const c_char) {
+    use sys::ESteamNetworkingSocketsDebugOutputType::*;
+
+    if message.is_null() {
+        return;
+    }
+    let message = CStr::from_ptr(message).to_string_lossy();
+    let level = match output_type {
+        k_ESteamNetworkingSocketsDebugOutputType_Bug | k_ESteamNetworkingSocketsDebugOutputType_Error => log::Level::Error,
+        k_ESteamNetworkingSocketsDebugOutputType_Important | k_ESteamNetworkingSocketsDebugOutputType_Warning => log::Level::Warn,
+        k_ESteamNetworkingSocketsDebugOutputType_Msg => log::Level::Info,
+        k_ESteamNetworkingSocketsDebugOutputType_Verbose => log::Level::Debug,
+        _ => log::Level::Trace,
+    };
+    log::log!(target: LOG_TARGET, level, "{}", message);
+}
@@ -0,0 +1,175 @@
+use std::{borrow::Cow, collections::VecDeque};
+
+/// Largest packet sent as a single steam message, above the biggest packet renet generates
+/// (a 1200 bytes slice with its headers), so only oversized packets are fragmented.
+pub(crate) const MAX_UNRELIABLE_MESSAGE_SIZE: usize = 1300;
+
+// renet packets never start with this byte, it marks a fragment of an oversized packet.
+const FRAGMENT_MARKER: u8 = 0xFF;
+// marker + message id (u16) + fragment index (u8) + fragment count (u8)
+const FRAGMENT_HEADER_SIZE: usize = 5;
+const MAX_FRAGMENT_PAYLOAD: usize = MAX_UNRELIABLE_MESSAGE_SIZE - FRAGMENT_HEADER_SIZE;
+const MAX_PENDING_MESSAGES: usize = 16;
+
+/// Splits renet packets that are too big for a single steam unreliable message.
+#[derive(Debug, Default)]
+pub(crate) struct Fragmenter {
+    next_message_id: u16,
+}
+
+impl Fragmenter {
+    /// Returns the steam messages to send for the packet.
+    /// Small packets are sent as they are, oversized packets are split in fragments.
+    pub(crate) fn split<'a>(&mut self, packet: &'a [u8]) -> Vec<Cow<'a, [u8]>> {
+        if packet.len() <= MAX_UNRELIABLE_MESSAGE_SIZE {
+            return vec![Cow::Borrowed(packet)];
+        }
+
+        let chunks = packet.chunks(MAX_FRAGMENT_PAYLOAD);
+        if chunks.len() > u8::MAX as usize {
+            log::error!("Packet of {} bytes is too big to be fragmented, it was dropped", packet.len());
+            return vec![];
+        }
+
+        let message_id = self.next_message_id;
+        self.next_message_id = self.next_message_id.wrapping_add(1);
+
+        let fragment_count = chunks.len() as u8;
+        chunks
+            .enumerate()
+            .map(|(index, chunk)| {
+                let mut fragment = Vec::with_capacity(FRAGMENT_HEADER_SIZE + chunk.len());
+                fragment.push(FRAGMENT_MARKER);
+                fragment.extend_from_slice(&message_id.to_le_bytes());
+                fragment.push(index as u8);
+                fragment.push(fragment_count);
+                fragment.extend_from_slice(chunk);
+                Cow::Owned(fragment)
+            })
+            .collect()
+    }
+}
+
+/// Fragmentation state of a single connection, message ids are per connection.
+#[derive(Debug, Default)]
+pub(crate) struct ConnectionFragments {
+    pub(crate) fragmenter: Fragmenter,
+    pub(crate) reassembler: Reassembler,
+}
+
+#[derive(Debug)]
+struct PendingMessage {
+    message_id: u16,
+    fragments: Vec<Option<Vec<u8>>>,
+    received: usize,
+}
+
+/// Rejoins the fragments created by a [`Fragmenter`].
+/// Only the latest messages are kept, incomplete older messages are discarded.
+#[derive(Debug, Default)]
+pub(crate) struct Reassembler {
+    pending: VecDeque<PendingMessage>,
+}
+
+impl Reassembler {
+    /// Returns the renet packet once it's complete.
+    pub(crate) fn receive<'a>(&mut self, message: &'a [u8]) -> Option<Cow<'a, [u8]>> {
+        if message.first() != Some(&FRAGMENT_MARKER) {
+            return Some(Cow::Borrowed(message));
+        }
+
+        if message.len() <= FRAGMENT_HEADER_SIZE {
+            log::error!("Received invalid packet fragment");
+            return None;
+        }
+
+        let message_id = u16::from_le_bytes([message[1], message[2]]);
+        let index = message[3] as usize;
+        let fragment_count = message[4] as usize;
+        if index >= fragment_count {
+            log::error!("Received invalid packet fragment");
+            return None;
+        }
+
+        let position = match self.pending.iter().position(|p| p.message_id == message_id) {
+            Some(position) => position,
+            None => {
+                if self.pending.len() >= MAX_PENDING_MESSAGES {
+                    self.pending.pop_front();
+                }
+                self.pending.push_back(PendingMessage {
+                    message_id,
+                    fragments: vec![None; fragment_count],
+                    received: 0,
+                });
+                self.pending.len() - 1
+            }
+        };
+
+        let pending = &mut self.pending[position];
+        if pending.fragments.len() != fragment_count {
+            log::error!("Received invalid packet fragment");
+            return None;
+        }
+        if pending.fragments[index].is_none() {
+            pending.fragments[index] = Some(message[FRAGMENT_HEADER_SIZE..].to_vec());
+            pending.received += 1;
+        }
+
+        if pending.received < fragment_count {
+            return None;
+        }
+
+        let pending = self.pending.remove(position).unwrap();
+        let packet = pending.fragments.into_iter().flatten().flatten().collect();
+        Some(Cow::Owned(packet))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn small_packet_is_not_fragmented() {
+        let mut fragmenter = Fragmenter::default();
+        let mut reassembler = Reassembler::default();
+        let packet = vec![1u8; MAX_UNRELIABLE_MESSAGE_SIZE];
+
+        let messages = fragmenter.split(&packet);
+        assert_eq!(messages.len(), 1);
+        assert_eq!(reassembler.receive(&messages[0]).unwrap(), &packet[..]);
+    }
+
+    #[test]
+    fn renet_packets_are_not_fragmented() {
+        let mut client = renet::RenetClient::new(renet::ConnectionConfig::default());
+        client.send_message(renet::DefaultChannel::ReliableOrdered, vec![7u8; 5000]);
+        client.send_message(renet::DefaultChannel::Unreliable, vec![7u8; 1000]);
+        client.send_message(renet::DefaultChannel::Unreliable, vec![7u8; 1000]);
+
+        let mut fragmenter = Fragmenter::default();
+        let packets = client.get_packets_to_send();
+        assert!(packets.len() > 1);
+        for packet in packets {
+            assert_eq!(fragmenter.split(&packet).len(), 1);
+        }
+    }
+
+    #[test]
+    fn oversized_packet_out_of_order() {
+        let mut fragmenter = Fragmenter::default();
+        let mut reassembler = Reassembler::default();
+        let packet: Vec<u8> = (0..3000).map(|i| i as u8).collect();
+
+        let messages = fragmenter.split(&packet);
+        assert_eq!(messages.len(), 3);
+        assert!(messages.iter().all(|m| m.len() <= MAX_UNRELIABLE_MESSAGE_SIZE));
+
+        assert!(reassembler.receive(&messages[2]).is_none());
+        assert!(reassembler.receive(&messages[0]).is_none());
+        assert!(reassembler.receive(&messages[0]).is_none());
+        assert_eq!(reassembler.receive(&messages[1]).unwrap(), &packet[..]);
+        assert!(reassembler.pending.is_empty());
+    }
+}
@@ -1,10 +1,27 @@
 const MAX_MESSAGE_BATCH_SIZE: usize = 512;
 
 mod client;
+mod client_id;
+mod debug_output;
+mod fragment;
+mod security;
 mod server;
+mod server_browser;
+mod signaling;
+mod stats;
+mod voice;
 
-pub use client::SteamClientTransport;
-pub use server::{AccessPermission, SteamServerConfig, SteamServerSocketOptions, SteamServerTransport};
+pub use client::{ReconnectPolicy, ReconnectStatus, SteamClientTransport};
+pub use client_id::{ClientIdSteamExt, RenetServerSteamExt};
+pub use debug_output::{install_debug_output, remove_debug_output, SteamDebugOutputLevel};
+pub use security::SecurityInfo;
+pub use server::{
+    AccessPermission, AdoptError, ClientSteamServerTransport, ConnectionAttempt, ConnectionDecision, SteamServerBuildError,
+    SteamServerBuilder, SteamServerConfig, SteamServerSocketOptions, SteamServerTransport, SteamTransportEvent,
+};
+pub use server_browser::{SteamServerBrowser, SteamServerBrowserInfo};
+pub use signaling::CustomSignaling;
+pub use voice::{VoiceFrame, VoiceReceiver, VoiceSender};
 
 #[doc(hidden)]
 pub use steamworks;
@@ -0,0 +1,98 @@
+use std::{
+    ffi::{c_char, CStr},
+    mem::{align_of, offset_of, size_of},
+};
+
+use steamworks::networking_types::NetConnectionInfo;
+use steamworks_sys as sys;
+
+/// Encryption and identity status of a steam connection.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SecurityInfo {
+    /// The connection is end-to-end encrypted.
+    pub encrypted: bool,
+    /// The remote identity was validated by steam.
+    pub identity_verified: bool,
+    /// The connection is routed through the steam relay network.
+    pub relayed: bool,
+}
+
+// steamworks doesn't expose the connection flags nor the connection handles to query them from steam,
+// NetConnectionInfo only wraps the raw connection info. Fail to build if it ever holds anything else.
+const _: () = assert!(
+    size_of::<NetConnectionInfo>() == size_of::<sys::SteamNetConnectionInfo_t>()
+        && align_of::<NetConnectionInfo>() == align_of::<sys::SteamNetConnectionInfo_t>()
+);
+
+// Reads the flags field of the raw connection info, the only field of NetConnectionInfo and so at offset 0.
+fn connection_flags(info: &NetConnectionInfo) -> i32 {
+    let info = (info as *const NetConnectionInfo).cast::<u8>();
+    // SAFETY: the field fills the whole NetConnectionInfo (checked above), m_nFlags is an initialized i32 of it.
+    unsafe {
+        info.add(offset_of!(sys::SteamNetConnectionInfo_t, m_nFlags))
+            .cast::<i32>()
+            .read_unaligned()
+    }
+}
+
+// Reads the debug message of a closed connection, it holds the reason given when the connection was closed.
+pub(crate) fn end_debug_message(info: &NetConnectionInfo) -> Option<String> {
+    let info = (info as *const NetConnectionInfo).cast::<u8>();
+    // SAFETY: same as connection_flags, m_szEndDebug is an initialized char array of it.
+    let message = unsafe {
+        info.add(offset_of!(sys::SteamNetConnectionInfo_t, m_szEndDebug))
+            .cast::<[c_char; sys::k_cchSteamNetworkingMaxConnectionCloseReason as usize]>()
+            .read_unaligned()
+    };
+    let message = message.map(|c| c as u8);
+    let message = CStr::from_bytes_until_nul(&message).ok()?.to_string_lossy();
+    (!message.is_empty()).then(|| message.into_owned())
+}
+
+impl SecurityInfo {
+    pub(crate) fn from_connection_info(info: &NetConnectionInfo) -> Self {
+        let flags = connection_flags(info);
+
+        Self {
+            encrypted: flags & sys::k_nSteamNetworkConnectionInfoFlags_Unencrypted == 0,
+            identity_verified: flags & sys::k_nSteamNetworkConnectionInfoFlags_Unauthenticated == 0,
+            relayed: flags & sys::k_nSteamNetworkConnectionInfoFlags_Relayed != 0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn read_connection_flags() {
+        // SAFETY: the raw connection info is plain data, zeroed is a valid value
+        let mut raw: sys::SteamNetConnectionInfo_t = unsafe { std::mem::zeroed() };
+        raw.m_nUserData = -1;
+        raw.m_nFlags = sys::k_nSteamNetworkConnectionInfoFlags_Relayed | sys::k_nSteamNetworkConnectionInfoFlags_Unencrypted;
+        let info = NetConnectionInfo::from(raw);
+        assert_eq!(info.user_data(), -1);
+        assert_eq!(end_debug_message(&info), None);
+
+        assert_eq!(
+            SecurityInfo::from_connection_info(&info),
+            SecurityInfo {
+                encrypted: false,
+                identity_verified: true,
+                relayed: true,
+            }
+        );
+    }
+
+    #[test]
+    fn read_end_debug_message() {
+        // SAFETY: the raw connection info is plain data, zeroed is a valid value
+        let mut raw: sys::SteamNetConnectionInfo_t = unsafe { std::mem::zeroed() };
+        for (c, b) in raw.m_szEndDebug.iter_mut().zip(b"Kicked: banned") {
+            *c = *b as c_char;
+        }
+        let info = NetConnectionInfo::from(raw);
+        assert_eq!(end_debug_message(&info).as_deref(), Some("Kicked: banned"));
+    }
+}
@@ -1,23 +1,42 @@
 use std::{
     collections::{HashMap, HashSet},
+    fmt,
     net::SocketAddr,
+    panic::{self, AssertUnwindSafe},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
 };
 
-use renet::{ClientId, RenetServer};
+use renet::{Bytes, ClientId, GracefulShutdown, RenetServer, ServerTransport, TransportStats};
 use steamworks::{
     networking_sockets::{InvalidHandle, ListenSocket, NetConnection},
-    networking_types::{ListenSocketEvent, NetConnectionEnd, NetworkingConfigEntry, SendFlags},
-    Client, ClientManager, FriendFlags, Friends, LobbyId, Manager, Matchmaking, SteamId,
+    networking_types::{
+        ListenSocketEvent, NetConnectionEnd, NetworkingAvailability, NetworkingConfigEntry, NetworkingConnectionState, NetworkingIdentity,
+        SendFlags,
+    },
+    Client, ClientManager, FriendFlags, LobbyId, Manager, SteamId,
 };
+use steamworks_sys as sys;
 
 use super::MAX_MESSAGE_BATCH_SIZE;
+use crate::{fragment::ConnectionFragments, signaling, stats, CustomSignaling, SecurityInfo};
+
+// Connections closed with flush are closed without waiting after this.
+const DRAIN_TIMEOUT: Duration = Duration::from_secs(5);
+
+struct DrainingConnection<Manager> {
+    connection: NetConnection<Manager>,
+    reason: String,
+    started: Instant,
+}
 
 pub enum AccessPermission {
     /// Everyone can connect
     Public,
     /// No one can connect
     Private,
-    /// Only friends from the host can connect
+    /// Only friends from the host can connect,
+    /// immediate friends by default, see [`SteamServerTransport::set_friend_flags`]
     FriendsOnly,
     /// Only user from this list can connect
     InList(HashSet<SteamId>),
@@ -25,6 +44,31 @@ pub enum AccessPermission {
     InLobby(LobbyId),
 }
 
+/// Client asking to connect, passed to the validator set with [`SteamServerTransport::set_connection_validator`].
+#[derive(Debug, Clone)]
+pub struct ConnectionAttempt {
+    pub steam_id: SteamId,
+    /// Identity of the client, as sent in the connection request.
+    pub identity: NetworkingIdentity,
+    /// User data of the connection when the request was received, -1 if none was set.
+    pub user_data: i64,
+}
+
+/// Result of the validator set with [`SteamServerTransport::set_connection_validator`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConnectionDecision {
+    Accept,
+    /// Rejects the connection, the reason is sent to the client as the debug message of the connection end.
+    /// Steam limits the reason to 127 bytes, longer reasons are truncated and NUL characters are replaced.
+    Reject(String),
+}
+
+impl ConnectionDecision {
+    pub fn reject(reason: impl Into<String>) -> Self {
+        Self::Reject(reason.into())
+    }
+}
+
 pub struct SteamServerConfig {
     pub max_clients: usize,
     pub access_permission: AccessPermission,
@@ -33,17 +77,125 @@ pub struct SteamServerConfig {
 #[cfg_attr(feature = "bevy", derive(bevy_ecs::resource::Resource))]
 pub struct SteamServerTransport<Manager = ClientManager> {
     listen_socket: Vec<ListenSocket<Manager>>,
-    matchmaking: Matchmaking<Manager>,
-    friends: Friends<Manager>,
+    client: Client<Manager>,
     max_clients: usize,
     access_permission: AccessPermission,
+    friend_flags: FriendFlags,
     connections: HashMap<ClientId, NetConnection<Manager>>,
+    is_shutdown: bool,
+    connections_paused: bool,
+    fragments: HashMap<ClientId, ConnectionFragments>,
+    disconnect_end_reasons: HashMap<ClientId, NetConnectionEnd>,
+    draining: Vec<DrainingConnection<Manager>>,
+    // Per client idle timeout, and when the client last sent a message
+    connection_timeouts: HashMap<ClientId, (Duration, Instant)>,
+    callbacks: ConnectCallbacks,
+    connection_validator: Option<Mutex<ConnectionValidator>>,
+    signaling: Option<Arc<dyn CustomSignaling>>,
+}
+
+/// Change in the transport returned by [`SteamServerTransport::poll`],
+/// applied to the server with [`SteamServerTransport::apply_events`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SteamTransportEvent {
+    ClientConnected {
+        client_id: ClientId,
+    },
+    /// The client disconnected, was disconnected by steam or timed out.
+    ClientDisconnected {
+        client_id: ClientId,
+    },
+    /// A complete renet packet received from the client.
+    Packet {
+        client_id: ClientId,
+        packet: Bytes,
+    },
+}
+
+type ConnectCallback = Box<dyn Fn(SteamId, ClientId) + Send + 'static>;
+type AcceptDataCallback = Box<dyn Fn(SteamId) -> Option<Bytes> + Send + 'static>;
+type ConnectionValidator = Box<dyn Fn(&ConnectionAttempt) -> ConnectionDecision + Send + 'static>;
+
+// Called when a connection is added to the server
+#[derive(Default)]
+struct ConnectCallbacks {
+    // Mutex so the transport stays Sync, it's only accessed mutably
+    on_connect: Option<Mutex<ConnectCallback>>,
+    accept_data: Option<(u8, Mutex<AcceptDataCallback>)>,
+}
+
+impl ConnectCallbacks {
+    fn client_connected(&mut self, server: &mut RenetServer, client_id: ClientId) {
+        let steam_id = SteamId::from_raw(client_id);
+        server.add_connection(client_id);
+        if let Some((channel_id, accept_data)) = self.accept_data.as_mut() {
+            let accept_data = accept_data.get_mut().unwrap_or_else(|e| e.into_inner());
+            if let Some(data) = accept_data(steam_id) {
+                server.send_message(client_id, *channel_id, data);
+            }
+        }
+        if let Some(on_connect) = self.on_connect.as_mut() {
+            let on_connect = on_connect.get_mut().unwrap_or_else(|e| e.into_inner());
+            on_connect(steam_id, client_id);
+        }
+    }
+}
+
+// Where the received connections and packets go: update applies them right away to the server,
+// poll buffers them for apply_events.
+enum EventSink<'a> {
+    Server(&'a mut RenetServer),
+    Buffer(&'a mut Vec<SteamTransportEvent>),
+}
+
+impl EventSink<'_> {
+    fn connected(&mut self, client_id: ClientId, callbacks: &mut ConnectCallbacks) {
+        match self {
+            EventSink::Server(server) => callbacks.client_connected(server, client_id),
+            EventSink::Buffer(events) => events.push(SteamTransportEvent::ClientConnected { client_id }),
+        }
+    }
+
+    fn disconnected(&mut self, client_id: ClientId) {
+        match self {
+            EventSink::Server(server) => server.remove_connection(client_id),
+            EventSink::Buffer(events) => events.push(SteamTransportEvent::ClientDisconnected { client_id }),
+        }
+    }
+
+    // The packet is only copied when it's buffered
+    fn packet(&mut self, client_id: ClientId, packet: &[u8]) {
+        match self {
+            EventSink::Server(server) => {
+                if let Err(e) = server.process_packet_from(packet, client_id) {
+                    log::error!("Error while processing payload for {}: {}", client_id, e);
+                }
+            }
+            EventSink::Buffer(events) => events.push(SteamTransportEvent::Packet {
+                client_id,
+                packet: Bytes::copy_from_slice(packet),
+            }),
+        }
+    }
+
+    fn apply(&mut self, event: SteamTransportEvent, callbacks: &mut ConnectCallbacks) {
+        match event {
+            SteamTransportEvent::ClientConnected { client_id } => self.connected(client_id, callbacks),
+            SteamTransportEvent::ClientDisconnected { client_id } => self.disconnected(client_id),
+            SteamTransportEvent::Packet { client_id, packet } => self.packet(client_id, &packet),
+        }
+    }
 }
 
+/// Steam server transport running on a steam client (listen server).
+/// The steam game server API can't be used yet, steamworks doesn't expose its networking sockets.
+pub type ClientSteamServerTransport = SteamServerTransport<ClientManager>;
+
 pub struct SteamServerSocketOptions {
     p2p: bool,
     socket_addr: Option<SocketAddr>,
     configs: Vec<NetworkingConfigEntry>,
+    signaling: Option<Arc<dyn CustomSignaling>>,
 }
 
 impl Default for SteamServerSocketOptions {
@@ -58,6 +210,7 @@ impl SteamServerSocketOptions {
             p2p: true,
             socket_addr: None,
             configs: vec![],
+            signaling: None,
         }
     }
 
@@ -66,6 +219,7 @@ impl SteamServerSocketOptions {
             p2p: false,
             socket_addr: Some(socket_addr),
             configs: vec![],
+            signaling: None,
         }
     }
 
@@ -83,6 +237,152 @@ impl SteamServerSocketOptions {
         self.configs.push(config_option);
         self
     }
+
+    /// Exchange the P2P connection signals with the clients through the game's own signaling service,
+    /// instead of the steam signaling service. Enables the p2p listen socket, the connections requested
+    /// through the signaling are accepted on it. Received signals are passed in with [`SteamServerTransport::receive_signal`].
+    ///
+    /// The route of the connections is still negotiated by steam, disable the relay with the
+    /// `P2PTransportSDRPenalty` and `P2PTransportICEEnable` configs to only use the self hosted route.
+    pub fn with_custom_signaling(mut self, signaling: Arc<dyn CustomSignaling>) -> Self {
+        self.p2p = true;
+        self.signaling = Some(signaling);
+        self
+    }
+}
+
+/// Error when building a [`SteamServerTransport`] with the [`SteamServerBuilder`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SteamServerBuildError {
+    /// Neither p2p nor an address was configured.
+    NoListenSocket,
+    /// Steam failed to create a listen socket.
+    InvalidHandle,
+}
+
+impl std::error::Error for SteamServerBuildError {}
+
+impl fmt::Display for SteamServerBuildError {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            SteamServerBuildError::NoListenSocket => write!(fmt, "no p2p or address listen socket configured"),
+            SteamServerBuildError::InvalidHandle => write!(fmt, "failed to create listen socket"),
+        }
+    }
+}
+
+/// Error when adding a connection with [`SteamServerTransport::adopt_connection`] or [`SteamServerTransport::reconnect_client`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AdoptError {
+    /// The connection is closed, or its remote is not identified by a steam id or not the expected one.
+    InvalidConnection,
+    /// A client with the same steam id is already connected.
+    AlreadyConnected(ClientId),
+    /// The server was shut down.
+    Shutdown,
+}
+
+impl std::error::Error for AdoptError {}
+
+impl fmt::Display for AdoptError {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            AdoptError::InvalidConnection => write!(fmt, "connection is closed or has no remote steam id"),
+            AdoptError::AlreadyConnected(client_id) => write!(fmt, "client {} is already connected", client_id),
+            AdoptError::Shutdown => write!(fmt, "server was shut down"),
+        }
+    }
+}
+
+impl From<InvalidHandle> for SteamServerBuildError {
+    fn from(_: InvalidHandle) -> Self {
+        SteamServerBuildError::InvalidHandle
+    }
+}
+
+/// Builder for the [`SteamServerTransport`].
+///
+/// # Usage
+/// ```no_run
+/// # use renet_steam::{AccessPermission, SteamServerBuilder};
+/// # let (client, single) = renet_steam::steamworks::Client::init_app(480).unwrap();
+/// let transport = SteamServerBuilder::new(&client)
+///     .max_clients(16)
+///     .access(AccessPermission::FriendsOnly)
+///     .p2p()
+///     .ip("127.0.0.1:5000".parse().unwrap())
+///     .build()
+///     .unwrap();
+/// ```
+pub struct SteamServerBuilder<'a, Manager = ClientManager> {
+    client: &'a Client<Manager>,
+    max_clients: usize,
+    access_permission: AccessPermission,
+    socket_options: SteamServerSocketOptions,
+}
+
+impl<'a, T: Manager + 'static> SteamServerBuilder<'a, T> {
+    /// Creates a builder for a public server with 64 max clients and no listen sockets.
+    pub fn new(client: &'a Client<T>) -> Self {
+        Self {
+            client,
+            max_clients: 64,
+            access_permission: AccessPermission::Public,
+            socket_options: SteamServerSocketOptions {
+                p2p: false,
+                socket_addr: None,
+                configs: vec![],
+                signaling: None,
+            },
+        }
+    }
+
+    pub fn max_clients(mut self, max_clients: usize) -> Self {
+        self.max_clients = max_clients;
+        self
+    }
+
+    pub fn access(mut self, access_permission: AccessPermission) -> Self {
+        self.access_permission = access_permission;
+        self
+    }
+
+    /// Accept connections to the steam id of the server.
+    pub fn p2p(mut self) -> Self {
+        self.socket_options.p2p = true;
+        self
+    }
+
+    /// Accept connections to the ip address.
+    pub fn ip(mut self, socket_addr: SocketAddr) -> Self {
+        self.socket_options.socket_addr = Some(socket_addr);
+        self
+    }
+
+    /// Add a config option to the listen sockets.
+    pub fn config(mut self, config_option: NetworkingConfigEntry) -> Self {
+        self.socket_options.configs.push(config_option);
+        self
+    }
+
+    /// Exchange the connection signals through the game's signaling service, see [`SteamServerSocketOptions::with_custom_signaling`].
+    pub fn custom_signaling(mut self, signaling: Arc<dyn CustomSignaling>) -> Self {
+        self.socket_options = self.socket_options.with_custom_signaling(signaling);
+        self
+    }
+
+    /// Creates the transport, at least one of [`p2p`](Self::p2p) or [`ip`](Self::ip) must be configured.
+    pub fn build(self) -> Result<SteamServerTransport<T>, SteamServerBuildError> {
+        if !self.socket_options.p2p && self.socket_options.socket_addr.is_none() {
+            return Err(SteamServerBuildError::NoListenSocket);
+        }
+
+        let config = SteamServerConfig {
+            max_clients: self.max_clients,
+            access_permission: self.access_permission,
+        };
+        Ok(SteamServerTransport::new(self.client, config, self.socket_options)?)
+    }
 }
 
 impl<T: Manager + 'static> SteamServerTransport<T> {
@@ -98,69 +398,509 @@ impl<T: Manager + 'static> SteamServerTransport<T> {
             listen_socket.push(networking.create_listen_socket_ip(addr, options.clone())?);
         }
 
-        let matchmaking = client.matchmaking();
-        let friends = client.friends();
-
         Ok(Self {
             listen_socket,
-            matchmaking,
-            friends,
+            client: client.clone(),
             max_clients: config.max_clients,
             access_permission: config.access_permission,
+            friend_flags: FriendFlags::IMMEDIATE,
             connections: HashMap::new(),
+            is_shutdown: false,
+            connections_paused: false,
+            fragments: HashMap::new(),
+            disconnect_end_reasons: HashMap::new(),
+            draining: Vec::new(),
+            connection_timeouts: HashMap::new(),
+            callbacks: ConnectCallbacks::default(),
+            connection_validator: None,
+            signaling: socket_options.signaling,
         })
     }
 
+    /// Sets a callback invoked in [`update`](Self::update) when a client connects,
+    /// after it was added to the [`RenetServer`].
+    pub fn on_connect(mut self, f: impl Fn(SteamId, ClientId) + Send + 'static) -> Self {
+        self.callbacks.on_connect = Some(Mutex::new(Box::new(f)));
+        self
+    }
+
+    /// Sets a callback that returns the data sent to each client as soon as its connection is accepted,
+    /// for handshake data like the server config or a session key.
+    ///
+    /// Steam can't attach data to the accept response, so the data is queued as a message on the given channel
+    /// when the client connects, and is sent with the first packets to the client without waiting for a request.
+    /// Return None to send nothing to a client.
+    pub fn accept_with_data<I: Into<u8>>(mut self, channel_id: I, f: impl Fn(SteamId) -> Option<Bytes> + Send + 'static) -> Self {
+        self.callbacks.accept_data = Some((channel_id.into(), Mutex::new(Box::new(f))));
+        self
+    }
+
+    /// Sets a steam networking config value in the global scope, for the options that can change after the socket was created.
+    /// The options of [`SteamServerSocketOptions::with_config`] only apply when the socket is created.
+    ///
+    /// The global values apply to the connections that don't override them. Returns false if steam refused the value.
+    pub fn set_global_config(&self, entry: NetworkingConfigEntry) -> bool {
+        let value: sys::SteamNetworkingConfigValue_t = entry.into();
+        unsafe {
+            let utils = sys::SteamAPI_SteamNetworkingUtils_SteamAPI_v004();
+            if utils.is_null() {
+                return false;
+            }
+
+            sys::SteamAPI_ISteamNetworkingUtils_SetConfigValueStruct(
+                utils,
+                &value,
+                sys::ESteamNetworkingConfigScope::k_ESteamNetworkingConfig_Global,
+                0,
+            )
+        }
+    }
+
     pub fn max_clients(&self) -> usize {
         self.max_clients
     }
 
+    pub(crate) fn connected_clients(&self) -> usize {
+        self.connections.len()
+    }
+
+    pub(crate) fn client_ids(&self) -> impl Iterator<Item = ClientId> + '_ {
+        self.connections.keys().copied()
+    }
+
     /// Update the access permission to the server,
     /// this change only applies to new connections.
     pub fn set_access_permissions(&mut self, access_permission: AccessPermission) {
         self.access_permission = access_permission;
     }
 
+    /// Sets a validator called for each connection request permitted by the access permission,
+    /// for checks done when the client connects like a ban list or an invite token.
+    ///
+    /// Connections rejected by the validator are closed with [`NetConnectionEnd::AppGeneric`] and the returned reason.
+    /// A panic in the validator is caught and rejects the connection. Replaces the previous validator,
+    /// the change applies to the requests received in the next [`update`](Self::update).
+    pub fn set_connection_validator(&mut self, f: impl Fn(&ConnectionAttempt) -> ConnectionDecision + Send + 'static) {
+        self.connection_validator = Some(Mutex::new(Box::new(f)));
+    }
+
+    /// Removes the validator, the connections permitted by the access permission are accepted.
+    pub fn clear_connection_validator(&mut self) {
+        self.connection_validator = None;
+    }
+
+    /// Sets the relationships with the host accepted by [`AccessPermission::FriendsOnly`],
+    /// for example `FriendFlags::IMMEDIATE | FriendFlags::CLAN_MEMBER`. Defaults to `FriendFlags::IMMEDIATE`.
+    /// This change only applies to new connections.
+    pub fn set_friend_flags(&mut self, flags: FriendFlags) {
+        self.friend_flags = flags;
+    }
+
+    /// Reject new connections without changing the access permission, for example while loading a map.
+    /// Existing connections are kept.
+    pub fn refuse_new_connections(&mut self) {
+        self.connections_paused = true;
+    }
+
+    /// Accept new connections again after [`refuse_new_connections`](Self::refuse_new_connections).
+    pub fn allow_new_connections(&mut self) {
+        self.connections_paused = false;
+    }
+
+    /// Returns whether new connections are being refused.
+    pub fn connections_paused(&self) -> bool {
+        self.connections_paused
+    }
+
+    /// Update the lobby that users must be in to connect to the server,
+    /// if the access permission is not [`AccessPermission::InLobby`] it will be changed to it.
+    /// Existing connections are kept, this change only applies to new connections.
+    pub fn set_lobby(&mut self, new_lobby: LobbyId) {
+        match &mut self.access_permission {
+            AccessPermission::InLobby(lobby) => *lobby = new_lobby,
+            access_permission => *access_permission = AccessPermission::InLobby(new_lobby),
+        }
+    }
+
+    /// Returns the lobby users must be in to connect, if the access permission is [`AccessPermission::InLobby`].
+    pub fn current_lobby(&self) -> Option<LobbyId> {
+        match &self.access_permission {
+            AccessPermission::InLobby(lobby) => Some(*lobby),
+            _ => None,
+        }
+    }
+
+    /// Returns the `(local, remote)` identities of the connection with the client.
+    ///
+    /// The remote identity is the one the peer authenticated with. When the connection is routed
+    /// through the steam relay network, the remote address is the relay node and not the peer,
+    /// so prefer the identities over addresses when logging.
+    pub fn connection_endpoints(&self, client_id: ClientId) -> Option<(NetworkingIdentity, NetworkingIdentity)> {
+        let connection = self.connections.get(&client_id)?;
+        let info = self.client.networking_sockets().get_connection_info(connection).ok()?;
+        let remote = info.identity_remote()?;
+        let local = NetworkingIdentity::new_steam_id(self.client.user().steam_id());
+
+        Some((local, remote))
+    }
+
+    /// Returns the encryption and identity status of the connection with the client.
+    pub fn connection_security(&self, client_id: ClientId) -> Option<SecurityInfo> {
+        let connection = self.connections.get(&client_id)?;
+        let info = self.client.networking_sockets().get_connection_info(connection).ok()?;
+
+        Some(SecurityInfo::from_connection_info(&info))
+    }
+
+    /// Returns the ping, connection quality, relay route and pending bytes of a client measured by steam,
+    /// to show them with the renet stats. None if the client is not connected.
+    pub fn transport_stats(&self, client_id: ClientId) -> Option<TransportStats> {
+        let connection = self.connections.get(&client_id)?;
+        stats::transport_stats(&self.client.networking_sockets(), connection)
+    }
+
+    /// Passes a signal received from a client through the custom signaling to steam.
+    /// Returns false if the transport has no custom signaling or steam could not process the signal.
+    pub fn receive_signal(&self, signal: &[u8]) -> bool {
+        match &self.signaling {
+            Some(signaling) => signaling::receive_signal(signaling, signal),
+            None => false,
+        }
+    }
+
+    /// Stores an opaque value on the steam connection of the client, for example a session or entity id.
+    /// Returns false if the client is not connected.
+    ///
+    /// Steam uses -1 for connections without user data, so don't store it.
+    pub fn set_user_data(&self, client_id: ClientId, data: i64) -> bool {
+        self.connections
+            .get(&client_id)
+            .is_some_and(|connection| connection.set_connection_user_data(data).is_ok())
+    }
+
+    /// Disconnects the client when it sends nothing for `timeout_ms`, checked in [`update`](Self::update).
+    /// Returns false if the client is not connected.
+    ///
+    /// steamworks doesn't expose the connection handles to change the steam timeout per connection,
+    /// so the steam timeout still applies. To give some clients a longer timeout, raise the steam one with
+    /// [`set_global_config`](Self::set_global_config) and `TimeoutConnected`, and set a shorter timeout for the others.
+    pub fn set_connection_timeout(&mut self, client_id: ClientId, timeout_ms: u32) -> bool {
+        if !self.connections.contains_key(&client_id) {
+            return false;
+        }
+
+        let timeout = Duration::from_millis(timeout_ms as u64);
+        let last_received = self
+            .connection_timeouts
+            .get(&client_id)
+            .map_or_else(Instant::now, |(_, last)| *last);
+        self.connection_timeouts.insert(client_id, (timeout, last_received));
+        true
+    }
+
+    /// Returns the value stored with [`set_user_data`](Self::set_user_data),
+    /// None if the client is not connected or no value was stored.
+    pub fn user_data(&self, client_id: ClientId) -> Option<i64> {
+        self.connections.get(&client_id)?.connection_user_data().ok()
+    }
+
+    /// Returns true if the steam relay network can be used to reach the server.
+    ///
+    /// Relay access is initialized on demand by steam, call
+    /// [`init_relay_network_access`](steamworks::networking_utils::NetworkingUtils::init_relay_network_access)
+    /// at startup to have the status ready when it's needed.
+    pub fn is_relay_available(&self) -> bool {
+        self.client.networking_utils().relay_network_status() == Ok(NetworkingAvailability::Current)
+    }
+
+    /// Returns the estimated latency in milliseconds to the nearest data center of the steam relay network,
+    /// or None if the relay network is not available or the latency was not measured yet.
+    pub fn relay_latency_ms(&self) -> Option<u32> {
+        if !self.is_relay_available() {
+            return None;
+        }
+
+        unsafe {
+            let utils = sys::SteamAPI_SteamNetworkingUtils_SteamAPI_v004();
+            if utils.is_null() {
+                return None;
+            }
+
+            let pop_count = sys::SteamAPI_ISteamNetworkingUtils_GetPOPCount(utils);
+            if pop_count <= 0 {
+                return None;
+            }
+            let mut pops: Vec<sys::SteamNetworkingPOPID> = vec![0; pop_count as usize];
+            let pop_count = sys::SteamAPI_ISteamNetworkingUtils_GetPOPList(utils, pops.as_mut_ptr(), pop_count);
+            pops.truncate(pop_count.max(0) as usize);
+
+            pops.into_iter()
+                .map(|pop| sys::SteamAPI_ISteamNetworkingUtils_GetPingToDataCenter(utils, pop, std::ptr::null_mut()))
+                // Negative values mean the ping is not known
+                .filter(|&ping| ping >= 0)
+                .min()
+                .map(|ping| ping as u32)
+        }
+    }
+
+    /// Adds an existing connection to the server, for example the connections of the previous host after a host migration.
+    /// The client id is the steam id of the remote, and the [`RenetServer`] sends a `ClientConnected` event for it.
+    ///
+    /// The channels of the client start from a clean state, messages not yet delivered by the previous server are lost.
+    /// The connection is closed if it can't be adopted.
+    pub fn adopt_connection(&mut self, connection: NetConnection<T>, server: &mut RenetServer) -> Result<ClientId, AdoptError> {
+        self.add_existing_connection(connection, None, server)
+    }
+
+    /// Adds the new connection of a client that recently disconnected, with the same client id so its state on the server can be kept.
+    /// The remote of the connection must be `steam_id`.
+    ///
+    /// Returns [`AdoptError::AlreadyConnected`] if the previous connection of the client was not disconnected yet.
+    /// The channels of the client start from a clean state, and the connection is closed if it can't be added.
+    pub fn reconnect_client(
+        &mut self,
+        steam_id: SteamId,
+        new_connection: NetConnection<T>,
+        server: &mut RenetServer,
+    ) -> Result<ClientId, AdoptError> {
+        self.add_existing_connection(new_connection, Some(steam_id), server)
+    }
+
+    fn add_existing_connection(
+        &mut self,
+        connection: NetConnection<T>,
+        expected_steam_id: Option<SteamId>,
+        server: &mut RenetServer,
+    ) -> Result<ClientId, AdoptError> {
+        if self.is_shutdown {
+            return Err(AdoptError::Shutdown);
+        }
+
+        let info = self
+            .client
+            .networking_sockets()
+            .get_connection_info(&connection)
+            .map_err(|_| AdoptError::InvalidConnection)?;
+        let connected = matches!(
+            info.state(),
+            Ok(NetworkingConnectionState::Connecting | NetworkingConnectionState::FindingRoute | NetworkingConnectionState::Connected)
+        );
+        let Some(steam_id) = info.identity_remote().and_then(|identity| identity.steam_id()) else {
+            return Err(AdoptError::InvalidConnection);
+        };
+        if !connected || expected_steam_id.is_some_and(|expected| expected != steam_id) {
+            return Err(AdoptError::InvalidConnection);
+        }
+
+        let client_id = steam_id.raw();
+        if self.connections.contains_key(&client_id) || server.has_client(client_id) {
+            return Err(AdoptError::AlreadyConnected(client_id));
+        }
+
+        server.add_connection(client_id);
+        self.fragments.remove(&client_id);
+        self.disconnect_end_reasons.remove(&client_id);
+        self.connection_timeouts.remove(&client_id);
+        self.connections.insert(client_id, connection);
+        Ok(client_id)
+    }
+
     /// Disconnects a client from the server.
     pub fn disconnect_client(&mut self, client_id: ClientId, server: &mut RenetServer, flush_last_packets: bool) {
-        if let Some((_key, value)) = self.connections.remove_entry(&client_id) {
-            let _ = value.close(NetConnectionEnd::AppGeneric, Some("Client was kicked"), flush_last_packets);
+        self.fragments.remove(&client_id);
+        if let Some(connection) = self.connections.remove(&client_id) {
+            self.close_connection(connection, "Client was kicked", flush_last_packets);
         }
         server.remove_connection(client_id);
     }
 
+    /// Receives all the packets buffered in the connection of a client, until its queue is empty.
+    /// Useful before kicking a client, to still handle its last messages like a final save command:
+    /// process the packets with [`RenetServer::process_packet_from`], read the messages from the server
+    /// and then call [`disconnect_client`](Self::disconnect_client).
+    /// Returns an empty Vec if the client is not connected.
+    pub fn drain_all_messages_from(&mut self, client_id: ClientId) -> Vec<Bytes> {
+        let mut packets = Vec::new();
+        let Some(connection) = self.connections.get_mut(&client_id) else {
+            return packets;
+        };
+        let reassembler = &mut self.fragments.entry(client_id).or_default().reassembler;
+        while let Ok(messages) = connection.receive_messages(MAX_MESSAGE_BATCH_SIZE) {
+            if messages.is_empty() {
+                break;
+            }
+            if let Some((_, last_received)) = self.connection_timeouts.get_mut(&client_id) {
+                *last_received = Instant::now();
+            }
+            for message in messages.iter() {
+                if let Some(packet) = reassembler.receive(message.data()) {
+                    packets.push(Bytes::copy_from_slice(&packet));
+                }
+            }
+        }
+        packets
+    }
+
     /// Disconnects all active clients including the host client from the server.
     pub fn disconnect_all(&mut self, server: &mut RenetServer, flush_last_packets: bool) {
         let keys = self.connections.keys().cloned().collect::<Vec<ClientId>>();
         for client_id in keys {
-            let _ = self.connections.remove_entry(&client_id).unwrap().1.close(
-                NetConnectionEnd::AppGeneric,
-                Some("Client was kicked"),
-                flush_last_packets,
-            );
+            self.fragments.remove(&client_id);
+            let connection = self.connections.remove(&client_id).unwrap();
+            self.close_connection(connection, "Client was kicked", flush_last_packets);
             server.remove_connection(client_id);
         }
     }
 
+    /// Shuts down the server, all connections are closed and the listen sockets stop accepting new connections.
+    /// After this, [`update`](Self::update) only finishes closing the flushed connections and [`send_packets`](Self::send_packets) does nothing.
+    /// The transport can be safely dropped at any time, when flushing wait until [`pending_disconnects`](Self::pending_disconnects) is zero
+    /// so the last packets are delivered.
+    pub fn shutdown(&mut self, server: &mut RenetServer, notice: Option<&str>, flush_last_packets: bool) {
+        if self.is_shutdown {
+            return;
+        }
+
+        let notice = notice.unwrap_or("Server shutting down");
+        self.fragments.clear();
+        let connections: Vec<_> = self.connections.drain().collect();
+        for (client_id, connection) in connections {
+            self.close_connection(connection, notice, flush_last_packets);
+            server.remove_connection(client_id);
+        }
+
+        // Dropping the listen sockets closes them, and also the accepted connections that are still draining
+        if self.draining.is_empty() {
+            self.listen_socket.clear();
+        }
+        self.is_shutdown = true;
+    }
+
+    /// Returns the number of connections closed with flush that are still sending their last packets.
+    pub fn pending_disconnects(&self) -> usize {
+        self.draining.len()
+    }
+
+    fn close_connection(&mut self, connection: NetConnection<T>, reason: &str, flush_last_packets: bool) {
+        let reason = close_reason(reason);
+        if !flush_last_packets {
+            connection.close(NetConnectionEnd::AppGeneric, Some(&reason), false);
+            return;
+        }
+
+        let _ = connection.flush_messages();
+        self.draining.push(DrainingConnection {
+            connection,
+            reason,
+            started: Instant::now(),
+        });
+    }
+
+    // Closes the draining connections that sent all their pending packets.
+    fn update_draining(&mut self) {
+        let networking_sockets = self.client.networking_sockets();
+        let mut index = 0;
+        while index < self.draining.len() {
+            let draining = &self.draining[index];
+            let drained = match networking_sockets.get_realtime_connection_status(&draining.connection, 0) {
+                Ok((status, _)) => {
+                    status.pending_unreliable() == 0 && status.pending_reliable() == 0 && status.sent_unacked_reliable() == 0
+                }
+                Err(_) => true,
+            };
+
+            if drained || draining.started.elapsed() >= DRAIN_TIMEOUT {
+                let draining = self.draining.swap_remove(index);
+                draining
+                    .connection
+                    .close(NetConnectionEnd::AppGeneric, Some(&draining.reason), false);
+            } else {
+                index += 1;
+            }
+        }
+
+        if self.is_shutdown && self.draining.is_empty() {
+            self.listen_socket.clear();
+        }
+    }
+
+    // The listen sockets stay open until the flushed connections are drained, refuse the clients connecting meanwhile
+    fn reject_connecting(&self) {
+        for listen_socket in self.listen_socket.iter() {
+            while let Some(event) = listen_socket.try_receive_event() {
+                if let ListenSocketEvent::Connecting(event) = event {
+                    event.reject(NetConnectionEnd::AppGeneric, Some("Server shutting down"));
+                }
+            }
+        }
+    }
+
+    /// Returns whether the server was shut down with [`shutdown`](Self::shutdown).
+    pub fn is_shutdown(&self) -> bool {
+        self.is_shutdown
+    }
+
+    /// Returns the steam end reason of a client that was disconnected by steam during the last [`update`](Self::update) or [`poll`](Self::poll).
+    pub fn disconnect_end_reason(&self, client_id: ClientId) -> Option<NetConnectionEnd> {
+        self.disconnect_end_reasons.get(&client_id).copied()
+    }
+
     /// Update server connections, and receive packets from the network.
+    /// Same as [`poll`](Self::poll) followed by [`apply_events`](Self::apply_events).
     pub fn update(&mut self, server: &mut RenetServer) {
+        self.receive(&mut EventSink::Server(server));
+    }
+
+    /// Receives the connections, disconnections and packets from the network without accessing the [`RenetServer`],
+    /// they are applied to it later with [`apply_events`](Self::apply_events).
+    ///
+    /// New connections are refused when the transport has [`max_clients`](Self::max_clients) connections,
+    /// including the connections not applied to the server yet.
+    pub fn poll(&mut self) -> Vec<SteamTransportEvent> {
+        let mut events = Vec::new();
+        self.receive(&mut EventSink::Buffer(&mut events));
+        events
+    }
+
+    fn receive(&mut self, sink: &mut EventSink) {
+        self.disconnect_end_reasons.clear();
+        self.update_draining();
+        if self.is_shutdown {
+            self.reject_connecting();
+            return;
+        }
+
         for listen_socket in self.listen_socket.iter() {
             while let Some(event) = listen_socket.try_receive_event() {
                 match event {
                     ListenSocketEvent::Connected(event) => {
                         if let Some(steam_id) = event.remote().steam_id() {
-                            server.add_connection(steam_id.raw());
-                            self.connections.insert(steam_id.raw(), event.take_connection());
+                            let connection = event.take_connection();
+                            if let Ok(info) = self.client.networking_sockets().get_connection_info(&connection) {
+                                if !SecurityInfo::from_connection_info(&info).encrypted {
+                                    log::warn!("Accepted unencrypted connection from {steam_id:?}");
+                                }
+                            }
+                            self.connection_timeouts.remove(&steam_id.raw());
+                            self.connections.insert(steam_id.raw(), connection);
+                            sink.connected(steam_id.raw(), &mut self.callbacks);
                         }
                     }
                     ListenSocketEvent::Disconnected(event) => {
                         if let Some(steam_id) = event.remote().steam_id() {
-                            server.remove_connection(steam_id.raw());
                             self.connections.remove(&steam_id.raw());
+                            self.fragments.remove(&steam_id.raw());
+                            self.disconnect_end_reasons.insert(steam_id.raw(), event.end_reason());
+                            sink.disconnected(steam_id.raw());
                         }
                     }
                     ListenSocketEvent::Connecting(event) => {
-                        if server.connected_clients() >= self.max_clients {
+                        if self.connections_paused {
+                            event.reject(NetConnectionEnd::AppGeneric, Some("Server not accepting connections"));
+                            continue;
+                        }
+
+                        if self.connected_clients() >= self.max_clients {
                             event.reject(NetConnectionEnd::AppGeneric, Some("Too many clients"));
                             continue;
                         }
@@ -174,22 +914,35 @@ impl<T: Manager + 'static> SteamServerTransport<T> {
                             AccessPermission::Public => true,
                             AccessPermission::Private => false,
                             AccessPermission::FriendsOnly => {
-                                let friend = self.friends.get_friend(steam_id);
-                                friend.has_friend(FriendFlags::IMMEDIATE)
+                                let friend = self.client.friends().get_friend(steam_id);
+                                friend.has_friend(self.friend_flags)
                             }
                             AccessPermission::InList(list) => list.contains(&steam_id),
                             AccessPermission::InLobby(lobby) => {
-                                let users_in_lobby = self.matchmaking.lobby_members(*lobby);
+                                let users_in_lobby = self.client.matchmaking().lobby_members(*lobby);
                                 users_in_lobby.contains(&steam_id)
                             }
                         };
 
-                        if permitted {
-                            if let Err(e) = event.accept() {
-                                log::error!("Failed to accept connection from {steam_id:?}: {e}");
-                            }
-                        } else {
+                        if !permitted {
                             event.reject(NetConnectionEnd::AppGeneric, Some("Not allowed"));
+                            continue;
+                        }
+
+                        let attempt = ConnectionAttempt {
+                            steam_id,
+                            identity: event.remote(),
+                            user_data: event.user_data(),
+                        };
+                        match validate_connection(&mut self.connection_validator, &attempt) {
+                            ConnectionDecision::Accept => {
+                                if let Err(e) = event.accept() {
+                                    log::error!("Failed to accept connection from {steam_id:?}: {e}");
+                                }
+                            }
+                            ConnectionDecision::Reject(reason) => {
+                                event.reject(NetConnectionEnd::AppGeneric, Some(&close_reason(&reason)));
+                            }
                         }
                     }
                 }
@@ -199,28 +952,73 @@ impl<T: Manager + 'static> SteamServerTransport<T> {
         for (client_id, connection) in self.connections.iter_mut() {
             // TODO this allocates on the side of steamworks.rs and should be avoided, PR needed
             if let Ok(messages) = connection.receive_messages(MAX_MESSAGE_BATCH_SIZE) {
+                if let Some((_, last_received)) = self.connection_timeouts.get_mut(client_id).filter(|_| !messages.is_empty()) {
+                    *last_received = Instant::now();
+                }
+                let reassembler = &mut self.fragments.entry(*client_id).or_default().reassembler;
                 messages.iter().for_each(|message| {
-                    if let Err(e) = server.process_packet_from(message.data(), *client_id) {
-                        log::error!("Error while processing payload for {}: {}", client_id, e);
+                    let Some(packet) = reassembler.receive(message.data()) else {
+                        return;
                     };
+                    sink.packet(*client_id, &packet);
                 });
             }
         }
+
+        self.disconnect_timed_out(sink);
+    }
+
+    /// Applies the events returned by [`poll`](Self::poll) to the server, in order.
+    /// Also sends the data of [`accept_with_data`](Self::accept_with_data)
+    /// and calls the [`on_connect`](Self::on_connect) callback of the new connections.
+    pub fn apply_events(&mut self, events: Vec<SteamTransportEvent>, server: &mut RenetServer) {
+        let mut sink = EventSink::Server(server);
+        for event in events {
+            sink.apply(event, &mut self.callbacks);
+        }
+    }
+
+    fn disconnect_timed_out(&mut self, sink: &mut EventSink) {
+        let connections = &self.connections;
+        self.connection_timeouts.retain(|client_id, _| connections.contains_key(client_id));
+        let timed_out: Vec<ClientId> = self
+            .connection_timeouts
+            .iter()
+            .filter(|(_, (timeout, last_received))| last_received.elapsed() >= *timeout)
+            .map(|(client_id, _)| *client_id)
+            .collect();
+
+        for client_id in timed_out {
+            self.connection_timeouts.remove(&client_id);
+            self.fragments.remove(&client_id);
+            if let Some(connection) = self.connections.remove(&client_id) {
+                self.close_connection(connection, "Connection timed out", false);
+            }
+            sink.disconnected(client_id);
+        }
     }
 
     /// Send packets to connected clients.
     pub fn send_packets(&mut self, server: &mut RenetServer) {
+        if self.is_shutdown {
+            return;
+        }
+
         'clients: for client_id in server.clients_id() {
             let Some(connection) = self.connections.get(&client_id) else {
                 log::error!("Error while sending packet: connection not found");
                 continue;
             };
             let packets = server.get_packets_to_send(client_id).unwrap();
+            let fragmenter = &mut self.fragments.entry(client_id).or_default().fragmenter;
             // TODO: while this works fine we should probaly use the send_messages function from the listen_socket
             for packet in packets {
-                if let Err(e) = connection.send_message(&packet, SendFlags::UNRELIABLE) {
-                    log::error!("Failed to send packet to client {client_id}: {e}");
-                    continue 'clients;
+                // Packets above the steam unreliable limit are split in fragments
+                for message in fragmenter.split(&packet) {
+                    if let Err(e) = connection.send_message(&message, SendFlags::UNRELIABLE) {
+                        log::error!("Failed to send packet to client {client_id}: {e}");
+                        continue 'clients;
+                    }
                 }
             }
 
@@ -230,3 +1028,216 @@ impl<T: Manager + 'static> SteamServerTransport<T> {
         }
     }
 }
+
+impl<T: Manager + 'static> ServerTransport for SteamServerTransport<T> {
+    fn send_packets(&mut self, server: &mut RenetServer) {
+        SteamServerTransport::send_packets(self, server);
+    }
+
+    // Flushes the packets sent before the disconnection
+    fn disconnect(&mut self, client_id: ClientId, server: &mut RenetServer) {
+        self.disconnect_client(client_id, server, true);
+    }
+}
+
+// The flushed connections are closed by update, the shutdown completes when they delivered their last packets
+impl<T: Manager + 'static> GracefulShutdown for SteamServerTransport<T> {
+    fn begin_shutdown(&mut self, server: &mut RenetServer) {
+        self.send_packets(server);
+        self.shutdown(server, None, true);
+    }
+
+    fn is_shutdown_complete(&self) -> bool {
+        self.is_shutdown && self.draining.is_empty()
+    }
+}
+
+// steamworks panics on a close reason with a NUL byte, and steam keeps 127 bytes of it
+fn close_reason(reason: &str) -> String {
+    let mut reason = reason.replace('\0', "\u{FFFD}");
+    let max_len = sys::k_cchSteamNetworkingMaxConnectionCloseReason as usize - 1;
+    if reason.len() > max_len {
+        let mut len = max_len;
+        while !reason.is_char_boundary(len) {
+            len -= 1;
+        }
+        reason.truncate(len);
+    }
+    reason
+}
+
+// A panic in the validator rejects the connection instead of unwinding through the transport
+fn validate_connection(validator: &mut Option<Mutex<ConnectionValidator>>, attempt: &ConnectionAttempt) -> ConnectionDecision {
+    let Some(validator) = validator.as_mut() else {
+        return ConnectionDecision::Accept;
+    };
+    let validator = validator.get_mut().unwrap_or_else(|e| e.into_inner());
+    match panic::catch_unwind(AssertUnwindSafe(|| validator(attempt))) {
+        Ok(decision) => decision,
+        Err(_) => {
+            log::error!("Connection validator panicked, rejecting connection from {:?}", attempt.steam_id);
+            ConnectionDecision::reject("Connection validation failed")
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use renet::{ConnectionConfig, DefaultChannel, RenetClient};
+
+    use super::*;
+
+    fn assert_send_sync<T: Send + Sync + 'static>() {}
+
+    #[test]
+    fn client_manager_transport_is_send_sync() {
+        assert_send_sync::<ClientSteamServerTransport>();
+        #[cfg(feature = "bevy")]
+        assert_resource::<ClientSteamServerTransport>();
+    }
+
+    fn attempt(steam_id: u64) -> ConnectionAttempt {
+        let steam_id = SteamId::from_raw(steam_id);
+        ConnectionAttempt {
+            steam_id,
+            identity: NetworkingIdentity::new_steam_id(steam_id),
+            user_data: -1,
+        }
+    }
+
+    #[test]
+    fn validate_connections() {
+        let mut validator: Option<Mutex<ConnectionValidator>> = None;
+        assert_eq!(validate_connection(&mut validator, &attempt(1)), ConnectionDecision::Accept);
+
+        let banned: HashSet<SteamId> = [SteamId::from_raw(2)].into();
+        validator = Some(Mutex::new(Box::new(move |attempt: &ConnectionAttempt| {
+            if banned.contains(&attempt.steam_id) {
+                ConnectionDecision::reject("Banned")
+            } else {
+                ConnectionDecision::Accept
+            }
+        })));
+        assert_eq!(validate_connection(&mut validator, &attempt(1)), ConnectionDecision::Accept);
+        assert_eq!(
+            validate_connection(&mut validator, &attempt(2)),
+            ConnectionDecision::reject("Banned")
+        );
+
+        // Swapped between two updates
+        validator = Some(Mutex::new(Box::new(|_: &ConnectionAttempt| {
+            ConnectionDecision::reject("Server full")
+        })));
+        assert_eq!(
+            validate_connection(&mut validator, &attempt(1)),
+            ConnectionDecision::reject("Server full")
+        );
+    }
+
+    #[test]
+    fn validator_panic_rejects_connection() {
+        let mut validator: Option<Mutex<ConnectionValidator>> =
+            Some(Mutex::new(Box::new(|_: &ConnectionAttempt| panic!("Backend unreachable"))));
+        assert_eq!(
+            validate_connection(&mut validator, &attempt(1)),
+            ConnectionDecision::reject("Connection validation failed")
+        );
+        // Still usable after the panic
+        assert_eq!(
+            validate_connection(&mut validator, &attempt(1)),
+            ConnectionDecision::reject("Connection validation failed")
+        );
+    }
+
+    fn connect_callbacks(connected: Arc<Mutex<Vec<ClientId>>>) -> ConnectCallbacks {
+        ConnectCallbacks {
+            on_connect: Some(Mutex::new(Box::new(move |_, client_id| connected.lock().unwrap().push(client_id)))),
+            accept_data: Some((
+                DefaultChannel::ReliableOrdered.into(),
+                Mutex::new(Box::new(|_| Some(Bytes::from("welcome")))),
+            )),
+        }
+    }
+
+    #[test]
+    fn polled_events_are_applied() {
+        let mut client = RenetClient::new(ConnectionConfig::default());
+        client.send_message(DefaultChannel::ReliableOrdered, "hello");
+        let packets = client.get_packets_to_send();
+        let connected = Arc::new(Mutex::new(Vec::new()));
+        let mut callbacks = connect_callbacks(connected.clone());
+
+        // Buffered by poll, without touching the server
+        let mut events = Vec::new();
+        let mut sink = EventSink::Buffer(&mut events);
+        sink.connected(1, &mut callbacks);
+        for packet in packets.iter() {
+            sink.packet(1, packet);
+        }
+        sink.disconnected(2);
+        assert!(connected.lock().unwrap().is_empty());
+        assert_eq!(events.len(), packets.len() + 2);
+        assert_eq!(events[0], SteamTransportEvent::ClientConnected { client_id: 1 });
+        assert_eq!(
+            events[1],
+            SteamTransportEvent::Packet {
+                client_id: 1,
+                packet: Bytes::from(packets[0].clone())
+            }
+        );
+        assert_eq!(events.last(), Some(&SteamTransportEvent::ClientDisconnected { client_id: 2 }));
+
+        let mut server = RenetServer::new(ConnectionConfig::default());
+        server.add_connection(2);
+        let mut sink = EventSink::Server(&mut server);
+        for event in events {
+            sink.apply(event, &mut callbacks);
+        }
+        assert!(server.has_client(1));
+        assert!(!server.has_client(2));
+        assert_eq!(*connected.lock().unwrap(), vec![1]);
+        assert_eq!(server.receive_message(1, DefaultChannel::ReliableOrdered).unwrap(), "hello");
+
+        // The accept data is queued for the new client
+        for packet in server.get_packets_to_send(1).unwrap() {
+            client.process_packet(&packet);
+        }
+        assert_eq!(client.receive_message(DefaultChannel::ReliableOrdered).unwrap(), "welcome");
+    }
+
+    #[test]
+    fn updated_events_are_applied_right_away() {
+        let mut client = RenetClient::new(ConnectionConfig::default());
+        client.send_message(DefaultChannel::ReliableOrdered, "hello");
+        let connected = Arc::new(Mutex::new(Vec::new()));
+        let mut callbacks = connect_callbacks(connected.clone());
+
+        let mut server = RenetServer::new(ConnectionConfig::default());
+        let mut sink = EventSink::Server(&mut server);
+        sink.connected(1, &mut callbacks);
+        for packet in client.get_packets_to_send() {
+            sink.packet(1, &packet);
+        }
+        assert_eq!(*connected.lock().unwrap(), vec![1]);
+        assert_eq!(server.receive_message(1, DefaultChannel::ReliableOrdered).unwrap(), "hello");
+
+        EventSink::Server(&mut server).disconnected(1);
+        assert!(!server.has_client(1));
+    }
+
+    #[test]
+    fn close_reasons() {
+        assert_eq!(close_reason("Banned"), "Banned");
+        // Would panic when closing the connection
+        assert_eq!(close_reason("Ban\0ned"), "Ban\u{FFFD}ned");
+        assert!(std::ffi::CString::new(close_reason("\0")).is_ok());
+
+        let long_reason = "é".repeat(100);
+        let reason = close_reason(&long_reason);
+        assert_eq!(reason.len(), 126);
+        assert!(long_reason.starts_with(&reason));
+    }
+
+    #[cfg(feature = "bevy")]
+    fn assert_resource<T: bevy_ecs::resource::Resource>() {}
+}
@@ -0,0 +1,227 @@
+use std::{
+    collections::{HashMap, HashSet},
+    ffi::{CString, NulError},
+};
+
+use renet::ClientId;
+use steamworks::{Manager, Server};
+use steamworks_sys as sys;
+
+use crate::SteamServerTransport;
+
+/// Information about the server displayed in the Steam server browser.
+pub struct SteamServerBrowserInfo {
+    pub product: String,
+    pub game_description: String,
+    pub server_name: String,
+    pub map_name: String,
+    pub dedicated: bool,
+}
+
+impl SteamServerBrowserInfo {
+    // steamworks panics on nul bytes, check all the strings before registering anything
+    fn validate(&self) -> Result<(), NulError> {
+        for value in [&self.product, &self.game_description, &self.server_name, &self.map_name] {
+            CString::new(value.as_str())?;
+        }
+        Ok(())
+    }
+}
+
+/// Registers a standalone server in the Steam server browser / matchmaking server list.
+///
+/// The [`Server`] must be initialized with a [`ServerMode`](steamworks::ServerMode) that lists the server,
+/// and be logged on (for example with [`Server::log_on_anonymous`]).
+#[cfg_attr(feature = "bevy", derive(bevy_ecs::resource::Resource))]
+pub struct SteamServerBrowser {
+    server: Server,
+    players: PlayerList,
+    max_players: usize,
+    registered: bool,
+}
+
+impl SteamServerBrowser {
+    /// Registers the server in the Steam server browser.
+    /// Returns an error if any of the info strings contains a nul byte.
+    pub fn new(server: Server, info: SteamServerBrowserInfo) -> Result<Self, NulError> {
+        info.validate()?;
+
+        server.set_product(&info.product);
+        server.set_game_description(&info.game_description);
+        server.set_map_name(&info.map_name);
+        server.set_dedicated_server(info.dedicated);
+
+        let browser = Self {
+            server,
+            players: PlayerList::default(),
+            max_players: 0,
+            registered: true,
+        };
+        browser.set_server_name(&info.server_name)?;
+        browser.server.enable_heartbeats(true);
+
+        Ok(browser)
+    }
+
+    /// Set the name of the server as it will appear in the server browser.
+    /// Returns an error if the name contains a nul byte.
+    pub fn set_server_name(&self, server_name: &str) -> Result<(), NulError> {
+        // Server::set_server_name from steamworks sets the map name instead, so we call it directly
+        let server_name = CString::new(server_name)?;
+        unsafe {
+            sys::SteamAPI_ISteamGameServer_SetServerName(game_server(), server_name.as_ptr());
+        }
+        Ok(())
+    }
+
+    /// Set the name of the map to report in the server browser.
+    /// Returns an error if the name contains a nul byte.
+    pub fn set_map_name(&self, map_name: &str) -> Result<(), NulError> {
+        CString::new(map_name)?;
+        self.server.set_map_name(map_name);
+        Ok(())
+    }
+
+    /// Returns whether the server is still listed in the server browser.
+    pub fn is_registered(&self) -> bool {
+        self.registered
+    }
+
+    /// Keeps the players in sync with the transport connections,
+    /// unregisters the server when the transport was shut down.
+    pub fn update<T: Manager + 'static>(&mut self, transport: &SteamServerTransport<T>) {
+        if !self.registered {
+            return;
+        }
+
+        if transport.is_shutdown() {
+            self.unregister();
+            return;
+        }
+
+        if self.max_players != transport.max_clients() {
+            self.max_players = transport.max_clients();
+            self.server.set_max_players(self.max_players as i32);
+        }
+
+        // renet clients don't authenticate with the game server, so each one is listed
+        // as an unauthenticated user connection to be counted as a player.
+        let clients: HashSet<ClientId> = transport.client_ids().collect();
+        let (joined, left) = self.players.diff(&clients);
+        for client_id in left {
+            if let Some(user) = self.players.remove(client_id) {
+                unsafe {
+                    sys::SteamAPI_ISteamGameServer_SendUserDisconnect_DEPRECATED(game_server(), user);
+                }
+            }
+        }
+        for client_id in joined {
+            let user = unsafe { sys::SteamAPI_ISteamGameServer_CreateUnauthenticatedUserConnection(game_server()) };
+            self.players.insert(client_id, user);
+        }
+    }
+
+    /// Removes the server from the server browser and logs off from steam.
+    pub fn unregister(&mut self) {
+        if !self.registered {
+            return;
+        }
+
+        for user in self.players.drain() {
+            unsafe {
+                sys::SteamAPI_ISteamGameServer_SendUserDisconnect_DEPRECATED(game_server(), user);
+            }
+        }
+        self.server.enable_heartbeats(false);
+        unsafe {
+            sys::SteamAPI_ISteamGameServer_LogOff(game_server());
+        }
+        self.registered = false;
+    }
+}
+
+// Same as the SteamGameServer() accessor from the steam headers,
+// uses the interface version of the bindings instead of a versioned flat accessor.
+fn game_server() -> *mut sys::ISteamGameServer {
+    unsafe {
+        sys::SteamInternal_FindOrCreateGameServerInterface(
+            sys::SteamGameServer_GetHSteamUser(),
+            sys::STEAMGAMESERVER_INTERFACE_VERSION.as_ptr().cast(),
+        )
+        .cast()
+    }
+}
+
+/// Steam user listed for each connected client.
+#[derive(Debug, Default)]
+struct PlayerList {
+    users: HashMap<ClientId, u64>,
+}
+
+impl PlayerList {
+    /// Returns the clients that joined and the clients that left.
+    fn diff(&self, clients: &HashSet<ClientId>) -> (Vec<ClientId>, Vec<ClientId>) {
+        let joined = clients
+            .iter()
+            .filter(|client_id| !self.users.contains_key(client_id))
+            .copied()
+            .collect();
+        let left = self
+            .users
+            .keys()
+            .filter(|client_id| !clients.contains(client_id))
+            .copied()
+            .collect();
+        (joined, left)
+    }
+
+    fn insert(&mut self, client_id: ClientId, user: u64) {
+        self.users.insert(client_id, user);
+    }
+
+    fn remove(&mut self, client_id: ClientId) -> Option<u64> {
+        self.users.remove(&client_id)
+    }
+
+    fn drain(&mut self) -> impl Iterator<Item = u64> + '_ {
+        self.users.drain().map(|(_, user)| user)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn player_list_diff() {
+        let mut players = PlayerList::default();
+        let (joined, left) = players.diff(&HashSet::from([1]));
+        assert_eq!(joined, vec![1]);
+        assert!(left.is_empty());
+
+        players.insert(1, 100);
+        players.insert(2, 200);
+        let (joined, left) = players.diff(&HashSet::from([1, 3]));
+        assert_eq!(joined, vec![3]);
+        assert_eq!(left, vec![2]);
+
+        assert_eq!(players.remove(2), Some(200));
+        assert_eq!(players.drain().collect::<Vec<_>>(), vec![100]);
+        assert!(players.users.is_empty());
+    }
+
+    #[test]
+    fn info_with_nul_byte() {
+        let mut info = SteamServerBrowserInfo {
+            product: "renet".to_string(),
+            game_description: "Demo".to_string(),
+            server_name: "Server".to_string(),
+            map_name: "Map".to_string(),
+            dedicated: true,
+        };
+        assert!(info.validate().is_ok());
+
+        info.server_name = "Ser\0ver".to_string();
+        assert!(info.validate().is_err());
+    }
+}
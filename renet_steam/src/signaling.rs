@@ -0,0 +1,217 @@
+use std::{
+    ffi::c_void,
+    os::raw::c_int,
+    panic::{catch_unwind, AssertUnwindSafe},
+    ptr,
+    sync::Arc,
+};
+
+use steamworks::networking_types::NetworkingIdentity;
+use steamworks_sys as sys;
+
+/// Signaling service used to exchange the P2P connection signals with the clients,
+/// for example a websocket server of the game, instead of the steam signaling service.
+///
+/// The transport calls [`send_signal`](Self::send_signal) when steam needs to send a signal to a client.
+/// The signals received from the clients must be passed to [`SteamServerTransport::receive_signal`](crate::SteamServerTransport::receive_signal).
+pub trait CustomSignaling: Send + Sync {
+    /// Sends an opaque signal to the peer. Returns false if the signal could not be sent,
+    /// steam retries the signals it needs.
+    fn send_signal(&self, peer: &NetworkingIdentity, signal: &[u8]) -> bool;
+}
+
+// Steam uses the signaling objects as C++ interfaces: a pointer to the table of virtual methods,
+// in declaration order, with the object as the first argument.
+// On 32 bit windows the virtual methods use the thiscall convention.
+macro_rules! signaling_interfaces {
+    ($abi:literal) => {
+        #[repr(C)]
+        struct RecvContextVtable {
+            on_connect_request: unsafe extern $abi fn(
+                *mut RecvContext,
+                sys::HSteamNetConnection,
+                *const sys::SteamNetworkingIdentity,
+                c_int,
+            ) -> *mut ConnectionSignaling,
+            send_rejection_signal: unsafe extern $abi fn(*mut RecvContext, *const sys::SteamNetworkingIdentity, *const c_void, c_int),
+        }
+
+        #[repr(C)]
+        struct ConnectionSignalingVtable {
+            send_signal: unsafe extern $abi fn(
+                *mut ConnectionSignaling,
+                sys::HSteamNetConnection,
+                *const sys::SteamNetConnectionInfo_t,
+                *const c_void,
+                c_int,
+            ) -> bool,
+            release: unsafe extern $abi fn(*mut ConnectionSignaling),
+        }
+
+        // Connection requests are not accepted here, they are accepted or rejected
+        // by the transport like the requests received from the steam signaling.
+        unsafe extern $abi fn on_connect_request(
+            context: *mut RecvContext,
+            _connection: sys::HSteamNetConnection,
+            _identity: *const sys::SteamNetworkingIdentity,
+            _local_virtual_port: c_int,
+        ) -> *mut ConnectionSignaling {
+            // A null signaling rejects the connection
+            catch_panic(ptr::null_mut(), || {
+                let signaling = (*context).signaling.clone();
+                Box::into_raw(Box::new(ConnectionSignaling {
+                    vtable: &CONNECTION_SIGNALING_VTABLE,
+                    signaling,
+                }))
+            })
+        }
+
+        unsafe extern $abi fn send_rejection_signal(
+            context: *mut RecvContext,
+            identity: *const sys::SteamNetworkingIdentity,
+            signal: *const c_void,
+            size: c_int,
+        ) {
+            catch_panic(false, || {
+                let peer = NetworkingIdentity::from(*identity);
+                (*context).signaling.send_signal(&peer, signal_bytes(signal, size))
+            });
+        }
+
+        unsafe extern $abi fn send_signal(
+            signaling: *mut ConnectionSignaling,
+            _connection: sys::HSteamNetConnection,
+            info: *const sys::SteamNetConnectionInfo_t,
+            signal: *const c_void,
+            size: c_int,
+        ) -> bool {
+            catch_panic(false, || {
+                let peer = NetworkingIdentity::from((*info).m_identityRemote);
+                (*signaling).signaling.send_signal(&peer, signal_bytes(signal, size))
+            })
+        }
+
+        unsafe extern $abi fn release(signaling: *mut ConnectionSignaling) {
+            drop(Box::from_raw(signaling));
+        }
+    };
+}
+
+#[cfg(all(windows, target_arch = "x86"))]
+signaling_interfaces!("thiscall");
+#[cfg(not(all(windows, target_arch = "x86")))]
+signaling_interfaces!("C");
+
+static RECV_CONTEXT_VTABLE: RecvContextVtable = RecvContextVtable {
+    on_connect_request,
+    send_rejection_signal,
+};
+
+static CONNECTION_SIGNALING_VTABLE: ConnectionSignalingVtable = ConnectionSignalingVtable { send_signal, release };
+
+// ISteamNetworkingSignalingRecvContext, only used while steam processes a received signal
+#[repr(C)]
+struct RecvContext {
+    vtable: &'static RecvContextVtable,
+    signaling: Arc<dyn CustomSignaling>,
+}
+
+// ISteamNetworkingConnectionSignaling of a connection, released by steam when the connection is closed
+#[repr(C)]
+struct ConnectionSignaling {
+    vtable: &'static ConnectionSignalingVtable,
+    signaling: Arc<dyn CustomSignaling>,
+}
+
+// Unwinding out of the callbacks into steam is undefined behavior, a panic of the signaling is returned as a failure
+fn catch_panic<R>(failure: R, f: impl FnOnce() -> R) -> R {
+    catch_unwind(AssertUnwindSafe(f)).unwrap_or_else(|_| {
+        log::error!("Custom signaling panicked in a steam callback");
+        failure
+    })
+}
+
+unsafe fn signal_bytes<'a>(signal: *const c_void, size: c_int) -> &'a [u8] {
+    if signal.is_null() || size <= 0 {
+        return &[];
+    }
+    std::slice::from_raw_parts(signal.cast::<u8>(), size as usize)
+}
+
+// Passes a signal received from a client to steam, new connections use the signaling to reply.
+pub(crate) fn receive_signal(signaling: &Arc<dyn CustomSignaling>, signal: &[u8]) -> bool {
+    // SAFETY: the steam api is initialized while a steamworks client exists, and the transport keeps one.
+    let sockets = unsafe { sys::SteamAPI_SteamNetworkingSockets_SteamAPI_v012() };
+    if sockets.is_null() {
+        return false;
+    }
+
+    let mut context = RecvContext {
+        vtable: &RECV_CONTEXT_VTABLE,
+        signaling: signaling.clone(),
+    };
+    // SAFETY: the context outlives the call, steam doesn't keep it after returning.
+    unsafe {
+        sys::SteamAPI_ISteamNetworkingSockets_ReceivedP2PCustomSignal(
+            sockets,
+            signal.as_ptr().cast(),
+            signal.len() as c_int,
+            (&mut context as *mut RecvContext).cast(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::mem::{offset_of, size_of};
+
+    use super::*;
+
+    struct PanicSignaling;
+
+    impl CustomSignaling for PanicSignaling {
+        fn send_signal(&self, _peer: &NetworkingIdentity, _signal: &[u8]) -> bool {
+            panic!("signaling failed");
+        }
+    }
+
+    #[test]
+    fn interfaces_layout() {
+        // Steam reads the virtual methods from the vtable pointer at the start of the objects
+        let pointer = size_of::<usize>();
+        assert_eq!(size_of::<RecvContextVtable>(), 2 * pointer);
+        assert_eq!(offset_of!(RecvContextVtable, on_connect_request), 0);
+        assert_eq!(offset_of!(RecvContextVtable, send_rejection_signal), pointer);
+        assert_eq!(size_of::<ConnectionSignalingVtable>(), 2 * pointer);
+        assert_eq!(offset_of!(ConnectionSignalingVtable, send_signal), 0);
+        assert_eq!(offset_of!(ConnectionSignalingVtable, release), pointer);
+
+        assert_eq!(offset_of!(RecvContext, vtable), 0);
+        assert_eq!(offset_of!(ConnectionSignaling, vtable), 0);
+        assert_eq!(size_of::<RecvContext>(), 3 * pointer);
+        assert_eq!(size_of::<ConnectionSignaling>(), 3 * pointer);
+    }
+
+    #[test]
+    fn signaling_panic_is_caught() {
+        let signaling = Box::into_raw(Box::new(ConnectionSignaling {
+            vtable: &CONNECTION_SIGNALING_VTABLE,
+            signaling: Arc::new(PanicSignaling),
+        }));
+        // SAFETY: the raw connection info is plain data, zeroed is a valid value
+        let info: sys::SteamNetConnectionInfo_t = unsafe { std::mem::zeroed() };
+        let signal = [1u8, 2, 3];
+
+        unsafe {
+            let vtable = (*signaling).vtable;
+            assert!(!(vtable.send_signal)(
+                signaling,
+                0,
+                &info,
+                signal.as_ptr().cast(),
+                signal.len() as c_int
+            ));
+            (vtable.release)(signaling);
+        }
+    }
+}
@@ -0,0 +1,20 @@
+use renet::TransportStats;
+use steamworks::networking_sockets::{NetConnection, NetworkingSockets};
+
+use crate::SecurityInfo;
+
+// None if steam doesn't know the connection
+pub(crate) fn transport_stats<M: 'static>(
+    networking_sockets: &NetworkingSockets<M>,
+    connection: &NetConnection<M>,
+) -> Option<TransportStats> {
+    let (status, _) = networking_sockets.get_realtime_connection_status(connection, 0).ok()?;
+    let info = networking_sockets.get_connection_info(connection).ok()?;
+    // Steam uses negative values when they are not measured yet
+    Some(TransportStats {
+        rtt: status.ping().max(0) as f64 / 1000.,
+        connection_quality: status.connection_quality_local().clamp(0., 1.),
+        relayed: SecurityInfo::from_connection_info(&info).relayed,
+        pending_bytes: (status.pending_unreliable().max(0) + status.pending_reliable().max(0)) as usize,
+    })
+}
@@ -0,0 +1,282 @@
+use std::collections::HashMap;
+
+use bytes::{Buf, BufMut, Bytes, BytesMut};
+use renet::{ClientId, RenetClient, RenetServer};
+use steamworks_sys as sys;
+
+const VOICE_BUFFER_SIZE: usize = 8 * 1024;
+const DECOMPRESS_BUFFER_SIZE: usize = 22 * 1024;
+
+/// Captures the local user voice with the steam voice API and sends the compressed frames to the server.
+///
+/// Each frame is prefixed with a sequence number, so the receivers can discard frames
+/// that arrived out of order and detect lost frames.
+/// The steam client must be initialized before using it.
+#[cfg_attr(feature = "bevy", derive(bevy_ecs::resource::Resource))]
+pub struct VoiceSender {
+    recording: bool,
+    sequence: u16,
+    buffer: Vec<u8>,
+}
+
+/// A compressed voice frame received from a client.
+#[derive(Debug, Clone)]
+pub struct VoiceFrame {
+    /// The client that recorded the frame.
+    pub client_id: ClientId,
+    pub sequence: u16,
+    /// How many frames from this client were lost before this one,
+    /// playback should fill the gap with silence.
+    pub lost_frames: u16,
+    /// The voice data compressed by steam, use [`VoiceReceiver::decompress`] to get the audio samples.
+    pub compressed: Bytes,
+}
+
+/// Receives voice frames sent with [`VoiceSender`] and keeps the per-user decompression state.
+///
+/// The steam client must be initialized before using it.
+#[cfg_attr(feature = "bevy", derive(bevy_ecs::resource::Resource))]
+pub struct VoiceReceiver {
+    last_sequences: HashMap<ClientId, u16>,
+    buffer: Vec<u8>,
+}
+
+fn steam_user() -> Option<*mut sys::ISteamUser> {
+    let user = unsafe { sys::SteamAPI_SteamUser_v023() };
+    if user.is_null() {
+        return None;
+    }
+
+    Some(user)
+}
+
+impl Default for VoiceSender {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl VoiceSender {
+    pub fn new() -> Self {
+        Self {
+            recording: false,
+            sequence: 0,
+            buffer: vec![0; VOICE_BUFFER_SIZE],
+        }
+    }
+
+    /// Starts recording the user voice.
+    pub fn start_recording(&mut self) {
+        let Some(user) = steam_user() else {
+            log::error!("Failed to start voice recording: steam is not initialized");
+            return;
+        };
+        unsafe { sys::SteamAPI_ISteamUser_StartVoiceRecording(user) };
+        self.recording = true;
+    }
+
+    /// Stops recording the user voice.
+    /// Steam keeps providing voice data for a short time after this, so keep calling [`poll_and_send`](Self::poll_and_send).
+    pub fn stop_recording(&mut self) {
+        let Some(user) = steam_user() else {
+            return;
+        };
+        unsafe { sys::SteamAPI_ISteamUser_StopVoiceRecording(user) };
+        self.recording = false;
+    }
+
+    pub fn is_recording(&self) -> bool {
+        self.recording
+    }
+
+    /// Sends all the available voice data to the server, should be called every frame.
+    /// The channel should be unreliable, since late voice frames are discarded by the receivers.
+    pub fn poll_and_send<I: Into<u8>>(&mut self, client: &mut RenetClient, channel_id: I) {
+        let Some(user) = steam_user() else {
+            return;
+        };
+
+        let channel_id = channel_id.into();
+        loop {
+            let mut available: u32 = 0;
+            let result = unsafe { sys::SteamAPI_ISteamUser_GetAvailableVoice(user, &mut available, std::ptr::null_mut(), 0) };
+            if result != sys::EVoiceResult::k_EVoiceResultOK || available == 0 {
+                if !matches!(
+                    result,
+                    sys::EVoiceResult::k_EVoiceResultOK
+                        | sys::EVoiceResult::k_EVoiceResultNoData
+                        | sys::EVoiceResult::k_EVoiceResultNotRecording
+                ) {
+                    log::error!("Failed to get available voice: {:?}", result);
+                }
+                return;
+            }
+
+            if self.buffer.len() < available as usize {
+                self.buffer.resize(available as usize, 0);
+            }
+
+            let mut written: u32 = 0;
+            let result = unsafe {
+                sys::SteamAPI_ISteamUser_GetVoice(
+                    user,
+                    true,
+                    self.buffer.as_mut_ptr() as *mut _,
+                    self.buffer.len() as u32,
+                    &mut written,
+                    false,
+                    std::ptr::null_mut(),
+                    0,
+                    std::ptr::null_mut(),
+                    0,
+                )
+            };
+            if result != sys::EVoiceResult::k_EVoiceResultOK {
+                log::error!("Failed to get voice: {:?}", result);
+                return;
+            }
+
+            let mut message = BytesMut::with_capacity(2 + written as usize);
+            message.put_u16_le(self.sequence);
+            message.put_slice(&self.buffer[..written as usize]);
+            client.send_message(channel_id, message.freeze());
+            self.sequence = self.sequence.wrapping_add(1);
+        }
+    }
+}
+
+impl Drop for VoiceSender {
+    fn drop(&mut self) {
+        if self.recording {
+            self.stop_recording();
+        }
+    }
+}
+
+impl Default for VoiceReceiver {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl VoiceReceiver {
+    pub fn new() -> Self {
+        Self {
+            last_sequences: HashMap::new(),
+            buffer: vec![0; DECOMPRESS_BUFFER_SIZE],
+        }
+    }
+
+    /// Receives the voice frames sent by the clients to the server.
+    pub fn receive_from_clients<I: Into<u8>>(&mut self, server: &mut RenetServer, channel_id: I) -> Vec<VoiceFrame> {
+        let channel_id = channel_id.into();
+        let mut frames = vec![];
+        for client_id in server.clients_id() {
+            while let Some(mut message) = server.receive_message(client_id, channel_id) {
+                if message.len() < 2 {
+                    log::error!("Invalid voice message from client {}", client_id);
+                    continue;
+                }
+                let sequence = message.get_u16_le();
+                if let Some(frame) = self.accept_frame(client_id, sequence, message) {
+                    frames.push(frame);
+                }
+            }
+        }
+
+        frames
+    }
+
+    /// Receives the voice frames relayed by the server with [`relay`](Self::relay).
+    pub fn receive_from_server<I: Into<u8>>(&mut self, client: &mut RenetClient, channel_id: I) -> Vec<VoiceFrame> {
+        let channel_id = channel_id.into();
+        let mut frames = vec![];
+        while let Some(mut message) = client.receive_message(channel_id) {
+            if message.len() < 10 {
+                log::error!("Invalid voice message from server");
+                continue;
+            }
+            let client_id = message.get_u64_le();
+            let sequence = message.get_u16_le();
+            if let Some(frame) = self.accept_frame(client_id, sequence, message) {
+                frames.push(frame);
+            }
+        }
+
+        frames
+    }
+
+    /// Relays a frame received from a client to all the other clients.
+    pub fn relay<I: Into<u8>>(server: &mut RenetServer, channel_id: I, frame: &VoiceFrame) {
+        let mut message = BytesMut::with_capacity(10 + frame.compressed.len());
+        message.put_u64_le(frame.client_id);
+        message.put_u16_le(frame.sequence);
+        message.put_slice(&frame.compressed);
+        server.broadcast_message_except(frame.client_id, channel_id, message.freeze());
+    }
+
+    /// Decompresses the frame into 16-bit mono PCM samples at the given sample rate.
+    /// Returns None if the frame could not be decompressed.
+    pub fn decompress(&mut self, frame: &VoiceFrame, sample_rate: u32) -> Option<&[u8]> {
+        let user = steam_user()?;
+        loop {
+            let mut written: u32 = 0;
+            let result = unsafe {
+                sys::SteamAPI_ISteamUser_DecompressVoice(
+                    user,
+                    frame.compressed.as_ptr() as *const _,
+                    frame.compressed.len() as u32,
+                    self.buffer.as_mut_ptr() as *mut _,
+                    self.buffer.len() as u32,
+                    &mut written,
+                    sample_rate,
+                )
+            };
+            match result {
+                sys::EVoiceResult::k_EVoiceResultOK => return Some(&self.buffer[..written as usize]),
+                sys::EVoiceResult::k_EVoiceResultBufferTooSmall if written as usize > self.buffer.len() => {
+                    self.buffer.resize(written as usize, 0);
+                }
+                result => {
+                    log::error!("Failed to decompress voice from client {}: {:?}", frame.client_id, result);
+                    return None;
+                }
+            }
+        }
+    }
+
+    /// Returns the sample rate that steam decompresses voice with the best quality.
+    pub fn optimal_sample_rate(&self) -> u32 {
+        match steam_user() {
+            Some(user) => unsafe { sys::SteamAPI_ISteamUser_GetVoiceOptimalSampleRate(user) },
+            None => 0,
+        }
+    }
+
+    /// Removes the voice state of a client, should be called when the client disconnects.
+    pub fn remove_client(&mut self, client_id: ClientId) {
+        self.last_sequences.remove(&client_id);
+    }
+
+    fn accept_frame(&mut self, client_id: ClientId, sequence: u16, compressed: Bytes) -> Option<VoiceFrame> {
+        let lost_frames = match self.last_sequences.get(&client_id) {
+            Some(&last_sequence) => {
+                let diff = sequence.wrapping_sub(last_sequence);
+                // Duplicated or older than the last frame received
+                if diff == 0 || diff > u16::MAX / 2 {
+                    return None;
+                }
+                diff - 1
+            }
+            None => 0,
+        };
+        self.last_sequences.insert(client_id, sequence);
+
+        Some(VoiceFrame {
+            client_id,
+            sequence,
+            lost_frames,
+            compressed,
+        })
+    }
+}
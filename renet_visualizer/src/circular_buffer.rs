@@ -2,6 +2,7 @@
 pub struct CircularBuffer<const N: usize, T> {
     pub(crate) queue: [T; N],
     cursor: usize,
+    len: usize,
 }
 
 impl<const N: usize, T: Default + Copy> Default for CircularBuffer<N, T> {
@@ -9,6 +10,7 @@ impl<const N: usize, T: Default + Copy> Default for CircularBuffer<N, T> {
         Self {
             queue: [T::default(); N],
             cursor: 0,
+            len: 0,
         }
     }
 }
@@ -17,6 +19,20 @@ impl<const N: usize, T: Default + Copy> CircularBuffer<N, T> {
     pub fn push(&mut self, value: T) {
         self.queue[self.cursor] = value;
         self.cursor = (self.cursor + 1) % N;
+        self.len = (self.len + 1).min(N);
+    }
+
+    pub fn last(&self) -> T {
+        self.queue[(self.cursor + N - 1) % N]
+    }
+
+    /// Replaces the newest value, or pushes it if the buffer is empty.
+    pub fn set_last(&mut self, value: T) {
+        if self.len == 0 {
+            self.push(value);
+        } else {
+            self.queue[(self.cursor + N - 1) % N] = value;
+        }
     }
 
     pub fn as_vec(&self) -> Vec<T> {
@@ -27,6 +43,13 @@ impl<const N: usize, T: Default + Copy> CircularBuffer<N, T> {
 
         vec
     }
+
+    /// Returns only the pushed values, from the oldest to the newest.
+    pub fn values(&self) -> Vec<T> {
+        let mut vec = self.as_vec();
+        vec.drain(..N - self.len);
+        vec
+    }
 }
 
 #[cfg(test)]
@@ -37,8 +60,11 @@ mod tests {
     fn usage() {
         let mut buffer: CircularBuffer<3, usize> = CircularBuffer::default();
         assert_eq!(buffer.as_vec(), vec![0, 0, 0]);
+        assert!(buffer.values().is_empty());
 
         buffer.push(1);
+        assert_eq!(buffer.values(), vec![1]);
+
         buffer.push(2);
         buffer.push(3);
         assert_eq!(buffer.as_vec(), vec![1, 2, 3]);
@@ -46,5 +72,10 @@ mod tests {
         buffer.push(4);
         buffer.push(5);
         assert_eq!(buffer.as_vec(), vec![3, 4, 5]);
+        assert_eq!(buffer.values(), vec![3, 4, 5]);
+        assert_eq!(buffer.last(), 5);
+
+        buffer.set_last(6);
+        assert_eq!(buffer.values(), vec![3, 4, 6]);
     }
 }
@@ -0,0 +1,295 @@
+use std::{collections::BTreeMap, fmt::Write, time::Duration};
+
+use renet::ClientId;
+
+use crate::{circular_buffer::CircularBuffer, sample::Sample, RenetClientVisualizer, RenetServerVisualizer};
+
+/// Format of the metrics exported by the visualizers.
+///
+/// The exports contain every sample in the history of the graphs, timestamped with the seconds since the unix epoch.
+/// The value is the average of the samples aggregated in a point, with their minimum and maximum,
+/// see [`RenetVisualizerStyle::sample_interval`](crate::RenetVisualizerStyle::sample_interval).
+/// - `Csv`: one row per sample with the columns `timestamp,channel,metric,value,min,max`,
+///   the channel is empty for the metrics of the whole connection.
+///   The server export adds a `client_id` column at the start.
+/// - `Json`: an object with an array of `[timestamp, value, min, max]` per metric and a `channels` object keyed by the channel id.
+///   The server export is an object keyed by the client id.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Csv,
+    Json,
+}
+
+impl<const N: usize> RenetClientVisualizer<N> {
+    /// Exports the history of all the metrics, including the channels ones.
+    ///
+    /// # Usage
+    /// ```
+    /// # use renet_visualizer::{ExportFormat, RenetClientVisualizer};
+    /// # let visualizer = RenetClientVisualizer::<5>::default();
+    /// let csv = visualizer.export(ExportFormat::Csv);
+    /// // Save it to a file, send it to a server, ...
+    /// ```
+    pub fn export(&self, format: ExportFormat) -> Vec<u8> {
+        let mut out = String::new();
+        match format {
+            ExportFormat::Csv => {
+                out.push_str("timestamp,channel,metric,value,min,max\n");
+                self.write_csv_rows(&mut out, "");
+            }
+            ExportFormat::Json => self.write_json(&mut out),
+        }
+        out.into_bytes()
+    }
+
+    /// Draws a button that exports the metrics and calls `on_export` with the bytes when clicked.
+    #[cfg(feature = "egui")]
+    pub fn draw_export_button(&self, ui: &mut egui::Ui, format: ExportFormat, on_export: impl FnOnce(Vec<u8>)) {
+        if ui.button("Export").clicked() {
+            on_export(self.export(format));
+        }
+    }
+
+    fn metrics(&self) -> Vec<(&'static str, &CircularBuffer<N, Sample>)> {
+        let mut metrics = vec![
+            ("rtt_ms", &self.rtt),
+            ("packet_loss", &self.packet_loss),
+            ("sent_kbps", &self.sent_bandwidth_kbps),
+            ("received_kbps", &self.received_bandwidth_kbps),
+        ];
+        if self.direction_stats {
+            metrics.push(("upstream_packet_loss", &self.upstream_packet_loss));
+            metrics.push(("downstream_packet_loss", &self.downstream_packet_loss));
+            metrics.push(("jitter_ms", &self.jitter));
+        }
+        if self.transport_stats.is_some() {
+            metrics.push(("transport_rtt_ms", &self.transport_rtt));
+            metrics.push(("transport_pending_bytes", &self.transport_pending_bytes));
+        }
+        metrics
+    }
+
+    // Each row is prefixed with the given columns, used by the server for the client id
+    fn write_csv_rows(&self, out: &mut String, prefix: &str) {
+        let times = self.sample_times.values();
+        for (metric, values) in self.metrics() {
+            for (time, sample) in times.iter().zip(values.values()) {
+                let _ = writeln!(out, "{prefix}{:.3},,{metric},{}", time.as_secs_f64(), csv_sample(sample));
+            }
+        }
+
+        for (channel_id, graphs) in self.channels.iter() {
+            let times = graphs.sample_times.values();
+            for (metric, values) in graphs.metrics() {
+                for (time, sample) in times.iter().zip(values.values()) {
+                    let _ = writeln!(
+                        out,
+                        "{prefix}{:.3},{channel_id},{metric},{}",
+                        time.as_secs_f64(),
+                        csv_sample(sample)
+                    );
+                }
+            }
+        }
+    }
+
+    fn write_json(&self, out: &mut String) {
+        out.push('{');
+        let times = self.sample_times.values();
+        for (metric, values) in self.metrics() {
+            let _ = write!(out, "\"{metric}\":");
+            write_json_samples(out, &times, &values.values());
+            out.push(',');
+        }
+
+        out.push_str("\"channels\":{");
+        for (i, (channel_id, graphs)) in self.channels.iter().enumerate() {
+            if i > 0 {
+                out.push(',');
+            }
+            let _ = write!(out, "\"{channel_id}\":{{\"name\":");
+            match self.channel_names.get(channel_id) {
+                Some(name) => write_json_string(out, name),
+                None => out.push_str("null"),
+            }
+            let times = graphs.sample_times.values();
+            for (metric, values) in graphs.metrics() {
+                let _ = write!(out, ",\"{metric}\":");
+                write_json_samples(out, &times, &values.values());
+            }
+            out.push('}');
+        }
+        out.push_str("}}");
+    }
+}
+
+impl<const N: usize> crate::ChannelGraphs<N> {
+    fn metrics(&self) -> [(&'static str, &CircularBuffer<N, Sample>); 4] {
+        [
+            ("sent_kbps", &self.sent_bandwidth_kbps),
+            ("received_kbps", &self.received_bandwidth_kbps),
+            ("buffered_bytes", &self.buffered_bytes),
+            ("bytes_in_flight", &self.bytes_in_flight),
+        ]
+    }
+}
+
+impl<const N: usize> RenetServerVisualizer<N> {
+    /// Exports the history of the metrics of all clients in a single file, keyed by the client id.
+    /// See [`RenetClientVisualizer::export`] for the metrics of each client.
+    pub fn export(&self, format: ExportFormat) -> Vec<u8> {
+        // Sorted so the exports are deterministic
+        let clients: BTreeMap<&ClientId, &RenetClientVisualizer<N>> = self.clients.iter().collect();
+        let mut out = String::new();
+        match format {
+            ExportFormat::Csv => {
+                out.push_str("client_id,timestamp,channel,metric,value,min,max\n");
+                for (client_id, client) in clients {
+                    client.write_csv_rows(&mut out, &format!("{client_id},"));
+                }
+            }
+            ExportFormat::Json => {
+                out.push('{');
+                for (i, (client_id, client)) in clients.into_iter().enumerate() {
+                    if i > 0 {
+                        out.push(',');
+                    }
+                    let _ = write!(out, "\"{client_id}\":");
+                    client.write_json(&mut out);
+                }
+                out.push('}');
+            }
+        }
+        out.into_bytes()
+    }
+
+    /// Exports the history of the metrics of a single client, returns None if the client is not tracked.
+    pub fn export_client(&self, client_id: ClientId, format: ExportFormat) -> Option<Vec<u8>> {
+        self.clients.get(&client_id).map(|client| client.export(format))
+    }
+
+    /// Draws a button that exports the metrics of all clients and calls `on_export` with the bytes when clicked.
+    #[cfg(feature = "egui")]
+    pub fn draw_export_button(&self, ui: &mut egui::Ui, format: ExportFormat, on_export: impl FnOnce(Vec<u8>)) {
+        if ui.button("Export").clicked() {
+            on_export(self.export(format));
+        }
+    }
+}
+
+fn csv_sample(sample: Sample) -> String {
+    let value = |value: f32| if value.is_finite() { value.to_string() } else { String::new() };
+    format!("{},{},{}", value(sample.avg), value(sample.min), value(sample.max))
+}
+
+fn write_json_samples(out: &mut String, times: &[Duration], samples: &[Sample]) {
+    // JSON has no representation for NaN or infinity
+    let value = |value: f32| if value.is_finite() { value.to_string() } else { "null".to_string() };
+    out.push('[');
+    for (i, (time, sample)) in times.iter().zip(samples).enumerate() {
+        if i > 0 {
+            out.push(',');
+        }
+        let _ = write!(
+            out,
+            "[{:.3},{},{},{}]",
+            time.as_secs_f64(),
+            value(sample.avg),
+            value(sample.min),
+            value(sample.max)
+        );
+    }
+    out.push(']');
+}
+
+fn write_json_string(out: &mut String, value: &str) {
+    out.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            c if (c as u32) < 0x20 => {
+                let _ = write!(out, "\\u{:04x}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+#[cfg(test)]
+mod tests {
+    use renet::{ChannelNetworkInfo, NetworkInfo};
+
+    use super::*;
+
+    fn client_visualizer() -> RenetClientVisualizer<4> {
+        let mut visualizer = RenetClientVisualizer::<4>::default();
+        visualizer.set_channel_name(1, "chat \"lobby\"");
+        visualizer.add_network_info(NetworkInfo {
+            rtt: 0.05,
+            packet_loss: 0.25,
+            receive_packet_loss: 0.,
+            jitter: 0.002,
+            bytes_sent_per_second: 1000.,
+            bytes_received_per_second: 500.,
+        });
+        visualizer.add_channel_network_info(ChannelNetworkInfo {
+            channel_id: 1,
+            bytes_sent_per_second: 250.,
+            bytes_received_per_second: 0.,
+            buffered_bytes: 12,
+            ..Default::default()
+        });
+        visualizer
+    }
+
+    #[test]
+    fn export_csv() {
+        let visualizer = client_visualizer();
+        let csv = String::from_utf8(visualizer.export(ExportFormat::Csv)).unwrap();
+        let rows: Vec<Vec<&str>> = csv.lines().map(|line| line.split(',').collect()).collect();
+
+        assert_eq!(rows[0], vec!["timestamp", "channel", "metric", "value", "min", "max"]);
+        // Only the pushed samples are exported: 7 connection metrics and 4 channel metrics
+        assert_eq!(rows.len(), 1 + 7 + 4);
+        assert_eq!(rows[1][1..], ["", "rtt_ms", "50", "50", "50"]);
+        assert_eq!(rows[3][1..], ["", "sent_kbps", "8", "8", "8"]);
+        assert_eq!(rows[7][1..], ["", "jitter_ms", "2", "2", "2"]);
+        assert_eq!(rows[10][1..], ["1", "buffered_bytes", "12", "12", "12"]);
+        assert!(rows[1][0].parse::<f64>().unwrap() > 0.);
+    }
+
+    #[test]
+    fn export_json() {
+        let visualizer = client_visualizer();
+        let json = String::from_utf8(visualizer.export(ExportFormat::Json)).unwrap();
+
+        assert!(json.starts_with("{\"rtt_ms\":[["));
+        assert!(json.contains(",0.25,0.25,0.25]],\"sent_kbps\""));
+        assert!(json.contains("\"channels\":{\"1\":{\"name\":\"chat \\\"lobby\\\"\",\"sent_kbps\":[["));
+        assert!(json.contains(",12,12,12]],\"bytes_in_flight\":[["));
+        assert!(json.ends_with(",0,0,0]]}}}"));
+    }
+
+    #[test]
+    fn export_server() {
+        let mut visualizer = RenetServerVisualizer::<4>::default();
+        visualizer.add_client(7);
+        visualizer.add_client(3);
+        visualizer.clients.insert(5, client_visualizer());
+
+        let json = String::from_utf8(visualizer.export(ExportFormat::Json)).unwrap();
+        assert!(json.starts_with("{\"3\":{\"rtt_ms\":[]"));
+        assert!(json.contains(",\"5\":{\"rtt_ms\":[["));
+        assert!(json.contains("]]}}},\"7\":{"));
+
+        let csv = String::from_utf8(visualizer.export(ExportFormat::Csv)).unwrap();
+        assert!(csv.starts_with("client_id,timestamp,channel,metric,value,min,max\n5,"));
+        assert_eq!(csv.lines().count(), 1 + 7 + 4);
+
+        let client_csv = visualizer.clients[&5].export(ExportFormat::Csv);
+        assert_eq!(visualizer.export_client(5, ExportFormat::Csv), Some(client_csv));
+        assert_eq!(visualizer.export_client(9, ExportFormat::Csv), None);
+    }
+}
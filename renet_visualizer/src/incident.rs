@@ -0,0 +1,302 @@
+use std::time::SystemTime;
+
+#[cfg(feature = "egui")]
+use egui::RichText;
+use renet::ClientId;
+
+use crate::{RenetClientVisualizer, RenetServerVisualizer};
+
+// Oldest incidents are dropped past this count, so a flapping metric can't grow the list forever
+const MAX_INCIDENTS: usize = 1000;
+
+/// Metric of the connection that can exceed a threshold.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum IncidentMetric {
+    /// Round-trip time in milliseconds.
+    Rtt,
+    PacketLoss,
+    SentKbps,
+    ReceivedKbps,
+}
+
+impl IncidentMetric {
+    pub(crate) fn label(&self) -> &'static str {
+        match self {
+            IncidentMetric::Rtt => "RTT",
+            IncidentMetric::PacketLoss => "Packet Loss",
+            IncidentMetric::SentKbps => "Sent Kbitps",
+            IncidentMetric::ReceivedKbps => "Received Kbitps",
+        }
+    }
+
+    pub(crate) fn format(&self, value: f32) -> String {
+        match self {
+            IncidentMetric::Rtt => format!("{value:.0} ms"),
+            IncidentMetric::PacketLoss => format!("{:.1}%", value * 100.),
+            IncidentMetric::SentKbps | IncidentMetric::ReceivedKbps => format!("{value:.1}"),
+        }
+    }
+}
+
+/// Values above which a metric is drawn with the alert color and an [`Incident`] is recorded, None disables it.
+/// All disabled by default.
+///
+/// # Usage
+/// ```
+/// # use renet_visualizer::{RenetVisualizerStyle, Thresholds};
+/// let style = RenetVisualizerStyle {
+///     thresholds: Thresholds {
+///         rtt_ms: Some(150.),
+///         packet_loss: Some(0.02),
+///         ..Default::default()
+///     },
+///     ..Default::default()
+/// };
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Thresholds {
+    pub rtt_ms: Option<f32>,
+    /// Fraction of the packets lost, 0.02 is 2%.
+    pub packet_loss: Option<f32>,
+    pub sent_kbps: Option<f32>,
+    pub received_kbps: Option<f32>,
+}
+
+impl Thresholds {
+    pub(crate) fn get(&self, metric: IncidentMetric) -> Option<f32> {
+        match metric {
+            IncidentMetric::Rtt => self.rtt_ms,
+            IncidentMetric::PacketLoss => self.packet_loss,
+            IncidentMetric::SentKbps => self.sent_kbps,
+            IncidentMetric::ReceivedKbps => self.received_kbps,
+        }
+    }
+}
+
+/// A period where a metric was above its threshold.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Incident {
+    pub metric: IncidentMetric,
+    /// Highest value of the metric during the incident.
+    pub peak: f32,
+    pub start: SystemTime,
+    /// None while the metric is still above the threshold.
+    pub end: Option<SystemTime>,
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct Incidents {
+    incidents: Vec<Incident>,
+}
+
+impl Incidents {
+    pub fn add_values(&mut self, thresholds: &Thresholds, values: [(IncidentMetric, f32); 4], now: SystemTime) {
+        for (metric, value) in values {
+            let ongoing = self
+                .incidents
+                .iter_mut()
+                .rev()
+                .find(|incident| incident.metric == metric && incident.end.is_none());
+            let exceeded = thresholds.get(metric).is_some_and(|threshold| value > threshold);
+            match (ongoing, exceeded) {
+                (Some(incident), true) => incident.peak = incident.peak.max(value),
+                (Some(incident), false) => incident.end = Some(now),
+                (None, true) => {
+                    if self.incidents.len() >= MAX_INCIDENTS {
+                        self.incidents.remove(0);
+                    }
+                    self.incidents.push(Incident {
+                        metric,
+                        peak: value,
+                        start: now,
+                        end: None,
+                    });
+                }
+                (None, false) => {}
+            }
+        }
+    }
+
+    // Ends the ongoing incidents, when the client is removed
+    pub fn end_all(&mut self, now: SystemTime) {
+        for incident in self.incidents.iter_mut().filter(|incident| incident.end.is_none()) {
+            incident.end = Some(now);
+        }
+    }
+
+    pub fn as_slice(&self) -> &[Incident] {
+        &self.incidents
+    }
+
+    pub fn clear(&mut self) {
+        self.incidents.clear();
+    }
+}
+
+impl<const N: usize> RenetClientVisualizer<N> {
+    /// Returns the incidents recorded when a metric exceeded its [`Thresholds`], from the oldest to the newest.
+    pub fn incidents(&self) -> &[Incident] {
+        self.incidents.as_slice()
+    }
+
+    /// Removes all the recorded incidents.
+    pub fn clear_incidents(&mut self) {
+        self.incidents.clear();
+    }
+
+    /// Draws the list of incidents with a button to clear them.
+    #[cfg(feature = "egui")]
+    pub fn draw_incidents(&mut self, ui: &mut egui::Ui) {
+        if ui.button("Clear").clicked() {
+            self.clear_incidents();
+        }
+        let text_color = self.style.text_color;
+        let rows = self.incidents().iter().rev().map(|incident| (None, *incident));
+        incidents_grid(ui, "renet_client_incidents", text_color, false, rows);
+    }
+}
+
+impl<const N: usize> RenetServerVisualizer<N> {
+    /// Returns the incidents of all clients sorted by their start, including the ones of removed clients.
+    pub fn incidents(&self) -> Vec<(ClientId, Incident)> {
+        let mut incidents: Vec<(ClientId, Incident)> = self.removed_incidents.clone();
+        for (client_id, client) in self.clients.iter() {
+            incidents.extend(client.incidents().iter().map(|incident| (*client_id, *incident)));
+        }
+        incidents.sort_by_key(|(_, incident)| incident.start);
+        incidents
+    }
+
+    /// Returns the incidents of a connected client, None if the client is not tracked.
+    pub fn client_incidents(&self, client_id: ClientId) -> Option<&[Incident]> {
+        self.clients.get(&client_id).map(|client| client.incidents())
+    }
+
+    /// Removes the incidents of all clients.
+    pub fn clear_incidents(&mut self) {
+        self.removed_incidents.clear();
+        for client in self.clients.values_mut() {
+            client.clear_incidents();
+        }
+    }
+
+    // Keeps the incidents of a removed client, to log them at the end of a session
+    pub(crate) fn keep_incidents(&mut self, client_id: ClientId, client: &mut RenetClientVisualizer<N>) {
+        client.incidents.end_all(SystemTime::now());
+        let incidents = client.incidents().iter().map(|incident| (client_id, *incident));
+        self.removed_incidents.extend(incidents);
+        if self.removed_incidents.len() > MAX_INCIDENTS {
+            let excess = self.removed_incidents.len() - MAX_INCIDENTS;
+            self.removed_incidents.drain(..excess);
+        }
+    }
+
+    /// Draws the list of incidents of all clients with a button to clear them.
+    #[cfg(feature = "egui")]
+    pub fn draw_incidents(&mut self, ui: &mut egui::Ui) {
+        if ui.button("Clear").clicked() {
+            self.clear_incidents();
+        }
+        let rows = self
+            .incidents()
+            .into_iter()
+            .rev()
+            .map(|(client_id, incident)| (Some(client_id), incident));
+        incidents_grid(ui, "renet_server_incidents", self.style.text_color, true, rows);
+    }
+}
+
+// Newest incidents first, with the client column when drawn by the server
+#[cfg(feature = "egui")]
+fn incidents_grid(
+    ui: &mut egui::Ui,
+    id: &str,
+    text_color: egui::Color32,
+    with_clients: bool,
+    rows: impl Iterator<Item = (Option<ClientId>, Incident)>,
+) {
+    let now = SystemTime::now();
+    let ago = |time: SystemTime| format!("{:.1}s ago", now.duration_since(time).unwrap_or_default().as_secs_f32());
+
+    egui::ScrollArea::vertical().id_salt(id).max_height(150.).show(ui, |ui| {
+        egui::Grid::new(id).striped(true).show(ui, |ui| {
+            let mut headers = vec!["Metric", "Peak", "Start", "End"];
+            if with_clients {
+                headers.insert(0, "Client");
+            }
+            for header in headers {
+                ui.label(RichText::new(header).strong().color(text_color));
+            }
+            ui.end_row();
+
+            for (client_id, incident) in rows {
+                if let Some(client_id) = client_id {
+                    ui.label(client_id.to_string());
+                }
+                ui.label(incident.metric.label());
+                ui.label(incident.metric.format(incident.peak));
+                ui.label(ago(incident.start));
+                ui.label(incident.end.map_or("ongoing".to_string(), ago));
+                ui.end_row();
+            }
+        });
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    #[test]
+    fn record_incidents() {
+        let thresholds = Thresholds {
+            rtt_ms: Some(150.),
+            packet_loss: Some(0.02),
+            ..Default::default()
+        };
+        let mut incidents = Incidents::default();
+        let mut add = |rtt: f32, packet_loss: f32, secs: u64| {
+            let values = [
+                (IncidentMetric::Rtt, rtt),
+                (IncidentMetric::PacketLoss, packet_loss),
+                (IncidentMetric::SentKbps, 1000.),
+                (IncidentMetric::ReceivedKbps, 1000.),
+            ];
+            incidents.add_values(&thresholds, values, SystemTime::UNIX_EPOCH + Duration::from_secs(secs));
+        };
+
+        add(100., 0., 0);
+        add(200., 0., 1);
+        add(300., 0.05, 2);
+        add(180., 0.01, 3);
+        add(100., 0.03, 4);
+
+        let at = |secs: u64| SystemTime::UNIX_EPOCH + Duration::from_secs(secs);
+        let expected = [
+            Incident {
+                metric: IncidentMetric::Rtt,
+                peak: 300.,
+                start: at(1),
+                end: Some(at(4)),
+            },
+            Incident {
+                metric: IncidentMetric::PacketLoss,
+                peak: 0.05,
+                start: at(2),
+                end: Some(at(3)),
+            },
+            Incident {
+                metric: IncidentMetric::PacketLoss,
+                peak: 0.03,
+                start: at(4),
+                end: None,
+            },
+        ];
+        assert_eq!(incidents.as_slice(), expected);
+
+        incidents.end_all(at(5));
+        assert_eq!(incidents.as_slice()[2].end, Some(at(5)));
+    }
+}
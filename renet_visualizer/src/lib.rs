@@ -1,61 +1,184 @@
-use std::collections::HashMap;
-
-use egui::{
-    epaint::{PathShape, RectShape},
-    pos2, remap, vec2, Color32, CornerRadius, Rect, Rgba, RichText, Sense, Shape, Stroke, StrokeKind, TextStyle, Vec2, WidgetText,
+use std::{
+    collections::{BTreeMap, HashMap, VecDeque},
+    time::{Duration, Instant, SystemTime},
 };
 
-use renet::{ClientId, NetworkInfo, RenetServer};
+#[cfg(feature = "egui")]
+use egui::{Color32, Stroke};
+
+use renet::{ChannelNetworkInfo, ClientId, NetworkInfo, RenetServer, TransportStats};
 
 use circular_buffer::CircularBuffer;
+use incident::Incidents;
+use recording::RecordedEvent;
+use sample::{push_values, Bucket, Sample};
+
+pub use export::ExportFormat;
+pub use incident::{Incident, IncidentMetric, Thresholds};
+pub use recording::{Recording, RecordingError, RecordingPlayer};
+#[cfg(feature = "egui")]
+pub use widgets::CompactStyle;
 
 mod circular_buffer;
+mod export;
+mod incident;
+mod recording;
+mod sample;
+mod text;
+#[cfg(feature = "egui")]
+mod widgets;
+
+// Fraction of the memory limit of a channel above which its buffered bytes are highlighted
+const CHANNEL_MEMORY_ALERT: f32 = 0.8;
 
 /// Egui visualizer for the renet client. Draws graphs with metrics:
 /// RTT, Packet Loss, Kbitps Sent/Received.
 ///
-/// N: determines how many values are shown in the graph.
+/// N: determines how many values are kept in the history of the graphs.
 /// 200 is a good value, if updated at 60 fps the graphs would hold 3 seconds of data.
+/// For longer histories aggregate the samples with [`RenetVisualizerStyle::sample_interval`].
+///
+/// Scroll over a graph to zoom in the history and drag it to pan, double click resets it.
+/// Hovering a graph shows the value and age of the closest sample.
 #[cfg_attr(feature = "bevy", derive(bevy_ecs::resource::Resource))]
 pub struct RenetClientVisualizer<const N: usize> {
-    rtt: CircularBuffer<N, f32>,
-    sent_bandwidth_kbps: CircularBuffer<N, f32>,
-    received_bandwidth_kbps: CircularBuffer<N, f32>,
-    packet_loss: CircularBuffer<N, f32>,
+    rtt: CircularBuffer<N, Sample>,
+    sent_bandwidth_kbps: CircularBuffer<N, Sample>,
+    received_bandwidth_kbps: CircularBuffer<N, Sample>,
+    packet_loss: CircularBuffer<N, Sample>,
+    upstream_packet_loss: CircularBuffer<N, Sample>,
+    downstream_packet_loss: CircularBuffer<N, Sample>,
+    jitter: CircularBuffer<N, Sample>,
+    transport_rtt: CircularBuffer<N, Sample>,
+    transport_pending_bytes: CircularBuffer<N, Sample>,
+    // Time since the unix epoch of each sample, the start of the bucket when aggregated
+    sample_times: CircularBuffer<N, Duration>,
+    bucket: Option<Bucket<9>>,
+    // In the server the packets sent by the client are the received ones
+    server_side: bool,
+    // False when the latest sample has no receive loss and jitter, replayed from an older recording
+    direction_stats: bool,
+    // Latest stats of the transport
+    transport_stats: Option<TransportStats>,
+    // Stats added since the last network info, taken by the next sample
+    pending_transport_stats: Option<TransportStats>,
+    channels: BTreeMap<u8, ChannelGraphs<N>>,
+    channel_names: HashMap<u8, String>,
+    created_at: Instant,
+    // Time since the unix epoch when the graphs were paused
+    paused_at: Option<Duration>,
+    // Samples added while paused, pushed to the graphs when resumed so the frozen ones are not evicted
+    held_samples: VecDeque<HeldSample>,
+    // Keeps the zoom and pan of the graphs apart from the ones of other visualizers
+    #[cfg(feature = "egui")]
+    graph_id: u64,
+    incidents: Incidents,
+    recording: Option<Recording>,
     style: RenetVisualizerStyle,
 }
 
+enum HeldSample {
+    Network {
+        values: [f32; 9],
+        direction_stats: bool,
+        time: Duration,
+    },
+    Channel {
+        channel_id: u8,
+        max_memory_usage_bytes: usize,
+        values: [f32; 4],
+        time: Duration,
+    },
+}
+
+#[derive(Default)]
+struct ChannelGraphs<const N: usize> {
+    sent_bandwidth_kbps: CircularBuffer<N, Sample>,
+    received_bandwidth_kbps: CircularBuffer<N, Sample>,
+    buffered_bytes: CircularBuffer<N, Sample>,
+    bytes_in_flight: CircularBuffer<N, Sample>,
+    // Latest memory limit of the channel, zero when unknown
+    max_memory_usage_bytes: usize,
+    sample_times: CircularBuffer<N, Duration>,
+    bucket: Option<Bucket<4>>,
+}
+
 /// Egui visualizer for the renet server. Draws graphs for each connected client with metrics:
 /// RTT, Packet Loss, Kbitps Sent/Received.
 ///
-/// N: determines how many values are shown in the graph.
+/// N: determines how many values are kept in the history of the graphs.
 /// 200 is a good value, if updated at 60 fps the graphs would hold 3 seconds of data.
+/// For longer histories aggregate the samples with [`RenetVisualizerStyle::sample_interval`].
+///
+/// The graphs can be zoomed and paused like the ones of the [`RenetClientVisualizer`].
 #[cfg_attr(feature = "bevy", derive(bevy_ecs::resource::Resource))]
 pub struct RenetServerVisualizer<const N: usize> {
+    #[cfg(feature = "egui")]
     show_all_clients: bool,
     selected_client: Option<ClientId>,
     clients: HashMap<ClientId, RenetClientVisualizer<N>>,
+    channel_names: HashMap<u8, String>,
+    overview: Overview,
+    paused_at: Option<Duration>,
+    removed_incidents: Vec<(ClientId, Incident)>,
+    recording: Option<Recording>,
     style: RenetVisualizerStyle,
 }
 
-/// Style configuration for the visualizer. Customize size, color and line width.
+/// Column of the server overview table, see [`RenetServerVisualizer::draw_overview`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OverviewColumn {
+    #[default]
+    ClientId,
+    Rtt,
+    PacketLoss,
+    SentKbps,
+    ReceivedKbps,
+    BufferedBytes,
+    Uptime,
+}
+
+/// Latest metrics of a client, a row of the server overview table.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OverviewRow {
+    pub client_id: ClientId,
+    /// Round-trip time in milliseconds.
+    pub rtt: f32,
+    pub packet_loss: f32,
+    pub sent_kbps: f32,
+    pub received_kbps: f32,
+    /// Bytes buffered in all the channels, only known when the channels info is added.
+    pub buffered_bytes: f32,
+    pub uptime: Duration,
+}
+
+#[derive(Debug, Default)]
+struct Overview {
+    sort_column: OverviewColumn,
+    descending: bool,
+    filter: String,
+    expanded_client: Option<ClientId>,
+}
+
+/// Style configuration for the visualizer. Customize size, color, line width and sampling.
 #[derive(Debug, Clone)]
 pub struct RenetVisualizerStyle {
     pub width: f32,
     pub height: f32,
+    #[cfg(feature = "egui")]
     pub text_color: Color32,
+    #[cfg(feature = "egui")]
     pub rectangle_stroke: Stroke,
+    #[cfg(feature = "egui")]
     pub line_stroke: Stroke,
-}
-
-enum TopValue {
-    SuggestedValues([f32; 5]),
-    MaxValue { multiplicated: f32 },
-}
-
-enum TextFormat {
-    Percentage,
-    Normal,
+    /// Samples added in this interval are aggregated in a single point, drawn with its average, minimum and maximum.
+    /// The history of the graphs lasts N * `sample_interval`, see `set_history_duration` in the visualizers.
+    /// Default: zero, each sample is a point.
+    pub sample_interval: Duration,
+    /// Metrics above their threshold are drawn with the `alert_color` and recorded as incidents.
+    pub thresholds: Thresholds,
+    #[cfg(feature = "egui")]
+    pub alert_color: Color32,
 }
 
 impl Default for RenetVisualizerStyle {
@@ -63,9 +186,16 @@ impl Default for RenetVisualizerStyle {
         Self {
             width: 200.,
             height: 100.,
+            #[cfg(feature = "egui")]
             text_color: Color32::WHITE,
+            #[cfg(feature = "egui")]
             rectangle_stroke: Stroke::new(1., Color32::WHITE),
+            #[cfg(feature = "egui")]
             line_stroke: Stroke::new(1., Color32::WHITE),
+            sample_interval: Duration::ZERO,
+            thresholds: Thresholds::default(),
+            #[cfg(feature = "egui")]
+            alert_color: Color32::RED,
         }
     }
 }
@@ -89,6 +219,29 @@ impl<const N: usize> RenetClientVisualizer<N> {
             sent_bandwidth_kbps: CircularBuffer::default(),
             received_bandwidth_kbps: CircularBuffer::default(),
             packet_loss: CircularBuffer::default(),
+            upstream_packet_loss: CircularBuffer::default(),
+            downstream_packet_loss: CircularBuffer::default(),
+            jitter: CircularBuffer::default(),
+            transport_rtt: CircularBuffer::default(),
+            transport_pending_bytes: CircularBuffer::default(),
+            sample_times: CircularBuffer::default(),
+            bucket: None,
+            server_side: false,
+            direction_stats: true,
+            transport_stats: None,
+            pending_transport_stats: None,
+            channels: BTreeMap::new(),
+            channel_names: HashMap::new(),
+            created_at: Instant::now(),
+            paused_at: None,
+            held_samples: VecDeque::new(),
+            #[cfg(feature = "egui")]
+            graph_id: {
+                static NEXT_GRAPH_ID: std::sync::atomic::AtomicU64 = std::sync::atomic::AtomicU64::new(0);
+                NEXT_GRAPH_ID.fetch_add(1, std::sync::atomic::Ordering::Relaxed)
+            },
+            incidents: Incidents::default(),
+            recording: None,
             style,
         }
     }
@@ -107,89 +260,224 @@ impl<const N: usize> RenetClientVisualizer<N> {
     /// visualizer.add_network_info(client.network_info());
     /// ```
     pub fn add_network_info(&mut self, network_info: NetworkInfo) {
-        self.rtt.push((network_info.rtt * 1000.) as f32);
-        self.sent_bandwidth_kbps
-            .push((network_info.bytes_sent_per_second * 8. / 1000.) as f32);
-        self.received_bandwidth_kbps
-            .push((network_info.bytes_received_per_second * 8. / 1000.) as f32);
-        self.packet_loss.push(network_info.packet_loss as f32);
-    }
-
-    /// Renders a new window with all the graphs metrics drawn.
-    pub fn show_window(&self, ctx: &egui::Context) {
-        egui::Window::new("Client Network Info")
-            .resizable(false)
-            .collapsible(true)
-            .show(ctx, |ui| {
-                ui.horizontal(|ui| {
-                    self.draw_all(ui);
-                });
-            });
-    }
-
-    /// Draws only the Received Kilobits Per Second metric.
-    pub fn draw_received_kbps(&self, ui: &mut egui::Ui) {
-        show_graph(
-            ui,
-            &self.style,
-            "Received Kbitps",
-            TextFormat::Normal,
-            TopValue::MaxValue { multiplicated: 1.5 },
-            self.received_bandwidth_kbps.as_vec(),
-        );
-    }
-
-    /// Draws only the Sent Kilobits Per Second metric.
-    pub fn draw_sent_kbps(&self, ui: &mut egui::Ui) {
-        show_graph(
-            ui,
-            &self.style,
-            "Sent Kbitps",
-            TextFormat::Normal,
-            TopValue::MaxValue { multiplicated: 1.5 },
-            self.sent_bandwidth_kbps.as_vec(),
-        );
-    }
-
-    /// Draws only the Packet Loss metric.
-    pub fn draw_packet_loss(&self, ui: &mut egui::Ui) {
-        show_graph(
-            ui,
-            &self.style,
-            "Packet Loss",
-            TextFormat::Percentage,
-            TopValue::SuggestedValues([0.05, 0.1, 0.25, 0.5, 1.]),
-            self.packet_loss.as_vec(),
-        );
-    }
-
-    /// Draws only the Round Time Trip metric.
-    pub fn draw_rtt(&self, ui: &mut egui::Ui) {
-        show_graph(
-            ui,
-            &self.style,
-            "Round Time Trip (ms)",
-            TextFormat::Normal,
-            TopValue::SuggestedValues([32., 64., 128., 256., 512.]),
-            self.rtt.as_vec(),
-        );
-    }
-
-    /// Draw all metrics without a window or layout.
-    pub fn draw_all(&self, ui: &mut egui::Ui) {
-        self.draw_received_kbps(ui);
-        self.draw_sent_kbps(ui);
-        self.draw_rtt(ui);
-        self.draw_packet_loss(ui);
+        let time = unix_time();
+        self.record(time, RecordedEvent::network_info(None, &network_info));
+        self.push_network_info(&network_info, true, time);
+    }
+
+    /// Add the stats measured by the transport, like the ones of `SteamClientTransport::transport_stats` in renet_steam.
+    /// They are sampled with the next [`add_network_info`](Self::add_network_info), call it before it every time the client updates.
+    /// Samples without new stats are left as gaps in the transport graphs.
+    ///
+    /// The RTT of the transport is drawn with the RTT of renet, the difference is the time spent in the buffers of the transport.
+    pub fn add_transport_stats(&mut self, transport_stats: TransportStats) {
+        self.transport_stats = Some(transport_stats);
+        self.pending_transport_stats = Some(transport_stats);
+    }
+
+    /// Returns the latest stats added with [`add_transport_stats`](Self::add_transport_stats).
+    pub fn transport_stats(&self) -> Option<TransportStats> {
+        self.transport_stats
+    }
+
+    // Time is the duration since the unix epoch when the sample was added,
+    // without direction stats the receive loss and jitter of the info are ignored
+    fn push_network_info(&mut self, network_info: &NetworkInfo, direction_stats: bool, time: Duration) {
+        let (upstream_packet_loss, downstream_packet_loss) = match self.server_side {
+            true => (network_info.receive_packet_loss, network_info.packet_loss),
+            false => (network_info.packet_loss, network_info.receive_packet_loss),
+        };
+        // Missing samples are NaN, drawn as gaps instead of zeros
+        let (transport_rtt, transport_pending_bytes) = match self.pending_transport_stats.take() {
+            Some(transport_stats) => ((transport_stats.rtt * 1000.) as f32, transport_stats.pending_bytes as f32),
+            None => (f32::NAN, f32::NAN),
+        };
+        let values = [
+            (network_info.rtt * 1000.) as f32,
+            (network_info.bytes_sent_per_second * 8. / 1000.) as f32,
+            (network_info.bytes_received_per_second * 8. / 1000.) as f32,
+            network_info.packet_loss as f32,
+            upstream_packet_loss as f32,
+            downstream_packet_loss as f32,
+            (network_info.jitter * 1000.) as f32,
+            transport_rtt,
+            transport_pending_bytes,
+        ];
+        match self.paused_at {
+            Some(_) => self.hold_sample(HeldSample::Network {
+                values,
+                direction_stats,
+                time,
+            }),
+            None => self.push_network_values(values, direction_stats, time),
+        }
+
+        let [rtt, sent_kbps, received_kbps, packet_loss, ..] = values;
+        let values = [
+            (IncidentMetric::Rtt, rtt),
+            (IncidentMetric::SentKbps, sent_kbps),
+            (IncidentMetric::ReceivedKbps, received_kbps),
+            (IncidentMetric::PacketLoss, packet_loss),
+        ];
+        self.incidents
+            .add_values(&self.style.thresholds, values, SystemTime::UNIX_EPOCH + time);
+    }
+
+    fn push_network_values(&mut self, values: [f32; 9], direction_stats: bool, time: Duration) {
+        self.direction_stats = direction_stats;
+        let metrics = [
+            &mut self.rtt,
+            &mut self.sent_bandwidth_kbps,
+            &mut self.received_bandwidth_kbps,
+            &mut self.packet_loss,
+            &mut self.upstream_packet_loss,
+            &mut self.downstream_packet_loss,
+            &mut self.jitter,
+            &mut self.transport_rtt,
+            &mut self.transport_pending_bytes,
+        ];
+        let interval = self.style.sample_interval;
+        push_values(&mut self.sample_times, metrics, &mut self.bucket, values, time, interval);
+    }
+
+    /// Add the traffic of a channel from the client, to draw the graphs of each channel.
+    /// Should be called for each channel every time the client updates.
+    ///
+    /// # Usage
+    /// ```
+    /// # use renet::{RenetClient, ConnectionConfig};
+    /// # use renet_visualizer::RenetClientVisualizer;
+    /// # let client = RenetClient::new(ConnectionConfig::default());
+    /// # let mut visualizer = RenetClientVisualizer::<5>::new(Default::default());
+    /// for channel_info in client.channels_network_info() {
+    ///     visualizer.add_channel_network_info(channel_info);
+    /// }
+    /// ```
+    pub fn add_channel_network_info(&mut self, channel_info: ChannelNetworkInfo) {
+        let time = unix_time();
+        self.record(time, RecordedEvent::ChannelInfo(None, channel_info));
+        self.push_channel_network_info(channel_info, time);
+    }
+
+    fn push_channel_network_info(&mut self, channel_info: ChannelNetworkInfo, time: Duration) {
+        let values = [
+            (channel_info.bytes_sent_per_second * 8. / 1000.) as f32,
+            (channel_info.bytes_received_per_second * 8. / 1000.) as f32,
+            channel_info.buffered_bytes as f32,
+            channel_info.bytes_in_flight as f32,
+        ];
+        let sample = HeldSample::Channel {
+            channel_id: channel_info.channel_id,
+            max_memory_usage_bytes: channel_info.max_memory_usage_bytes,
+            values,
+            time,
+        };
+        match self.paused_at {
+            Some(_) => self.hold_sample(sample),
+            None => self.push_held_sample(sample),
+        }
+    }
+
+    fn push_channel_values(&mut self, channel_id: u8, max_memory_usage_bytes: usize, values: [f32; 4], time: Duration) {
+        let graphs = self.channels.entry(channel_id).or_default();
+        graphs.max_memory_usage_bytes = max_memory_usage_bytes;
+        let metrics = [
+            &mut graphs.sent_bandwidth_kbps,
+            &mut graphs.received_bandwidth_kbps,
+            &mut graphs.buffered_bytes,
+            &mut graphs.bytes_in_flight,
+        ];
+        let interval = self.style.sample_interval;
+        push_values(&mut graphs.sample_times, metrics, &mut graphs.bucket, values, time, interval);
+    }
+
+    fn hold_sample(&mut self, sample: HeldSample) {
+        // Only the newest N samples of each graph would be kept when resumed
+        let max_held = N * (self.channels.len() + 1);
+        if self.held_samples.len() >= max_held {
+            self.held_samples.pop_front();
+        }
+        self.held_samples.push_back(sample);
+    }
+
+    fn push_held_sample(&mut self, sample: HeldSample) {
+        match sample {
+            HeldSample::Network {
+                values,
+                direction_stats,
+                time,
+            } => self.push_network_values(values, direction_stats, time),
+            HeldSample::Channel {
+                channel_id,
+                max_memory_usage_bytes,
+                values,
+                time,
+            } => self.push_channel_values(channel_id, max_memory_usage_bytes, values, time),
+        }
+    }
+
+    fn metric_values(&self, metric: IncidentMetric) -> &CircularBuffer<N, Sample> {
+        match metric {
+            IncidentMetric::Rtt => &self.rtt,
+            IncidentMetric::PacketLoss => &self.packet_loss,
+            IncidentMetric::SentKbps => &self.sent_bandwidth_kbps,
+            IncidentMetric::ReceivedKbps => &self.received_bandwidth_kbps,
+        }
+    }
+
+    /// Sets the label of a channel in the graphs.
+    pub fn set_channel_name<I: Into<u8>>(&mut self, channel_id: I, name: impl Into<String>) {
+        self.channel_names.insert(channel_id.into(), name.into());
+    }
+
+    /// Freezes the graphs on the samples shown when paused. The samples added while paused are held
+    /// and added to the history when resumed, so the frozen ones are not evicted from it.
+    /// A paused graph can still be zoomed and panned over the older samples.
+    pub fn set_paused(&mut self, paused: bool) {
+        match (paused, self.paused_at) {
+            (true, None) => self.set_paused_at(Some(unix_time())),
+            (false, Some(_)) => self.set_paused_at(None),
+            _ => {}
+        }
+    }
+
+    fn set_paused_at(&mut self, paused_at: Option<Duration>) {
+        self.paused_at = paused_at;
+        if paused_at.is_none() {
+            while let Some(sample) = self.held_samples.pop_front() {
+                self.push_held_sample(sample);
+            }
+        }
+    }
+
+    /// Changes the [`RenetVisualizerStyle::sample_interval`], the points already in the history are kept.
+    pub fn set_sample_interval(&mut self, sample_interval: Duration) {
+        self.style.sample_interval = sample_interval;
+    }
+
+    /// Sets the sample interval so the history of the graphs lasts the duration.
+    pub fn set_history_duration(&mut self, duration: Duration) {
+        self.set_sample_interval(duration / N as u32);
+    }
+
+    /// Returns true if the graphs are paused, see [`set_paused`](Self::set_paused).
+    pub fn is_paused(&self) -> bool {
+        self.paused_at.is_some()
     }
 }
 
 impl<const N: usize> RenetServerVisualizer<N> {
     pub fn new(style: RenetVisualizerStyle) -> Self {
         Self {
+            #[cfg(feature = "egui")]
             show_all_clients: false,
             selected_client: None,
             clients: HashMap::new(),
+            channel_names: HashMap::new(),
+            overview: Overview::default(),
+            paused_at: None,
+            removed_incidents: Vec::new(),
+            recording: None,
             style,
         }
     }
@@ -214,7 +502,52 @@ impl<const N: usize> RenetServerVisualizer<N> {
     /// }
     /// ```
     pub fn add_client(&mut self, client_id: ClientId) {
-        self.clients.insert(client_id, RenetClientVisualizer::new(self.style.clone()));
+        let mut client = RenetClientVisualizer::new(self.style.clone());
+        client.server_side = true;
+        client.channel_names = self.channel_names.clone();
+        client.paused_at = self.paused_at;
+        self.clients.insert(client_id, client);
+        self.record(unix_time(), RecordedEvent::ClientAdded(client_id));
+    }
+
+    /// Sets the label of a channel in the graphs of all clients.
+    pub fn set_channel_name<I: Into<u8>>(&mut self, channel_id: I, name: impl Into<String>) {
+        let channel_id = channel_id.into();
+        let name = name.into();
+        for client in self.clients.values_mut() {
+            client.set_channel_name(channel_id, name.clone());
+        }
+        self.channel_names.insert(channel_id, name);
+    }
+
+    /// Pauses the graphs of all clients, see [`RenetClientVisualizer::set_paused`].
+    pub fn set_paused(&mut self, paused: bool) {
+        match (paused, self.paused_at) {
+            (true, None) => self.paused_at = Some(unix_time()),
+            (false, Some(_)) => self.paused_at = None,
+            _ => return,
+        }
+        for client in self.clients.values_mut() {
+            client.set_paused_at(self.paused_at);
+        }
+    }
+
+    /// Returns true if the graphs are paused, see [`set_paused`](Self::set_paused).
+    pub fn is_paused(&self) -> bool {
+        self.paused_at.is_some()
+    }
+
+    /// Changes the [`RenetVisualizerStyle::sample_interval`] of all clients, the points already in the history are kept.
+    pub fn set_sample_interval(&mut self, sample_interval: Duration) {
+        self.style.sample_interval = sample_interval;
+        for client in self.clients.values_mut() {
+            client.set_sample_interval(sample_interval);
+        }
+    }
+
+    /// Sets the sample interval of all clients so the history of the graphs lasts the duration.
+    pub fn set_history_duration(&mut self, duration: Duration) {
+        self.set_sample_interval(duration / N as u32);
     }
 
     /// Remove a client from the visualizer. Should be called whenever a client
@@ -237,12 +570,31 @@ impl<const N: usize> RenetServerVisualizer<N> {
     /// }
     /// ```
     pub fn remove_client(&mut self, client_id: ClientId) {
-        self.clients.remove(&client_id);
+        if let Some(mut client) = self.clients.remove(&client_id) {
+            self.keep_incidents(client_id, &mut client);
+            self.record(unix_time(), RecordedEvent::ClientRemoved(client_id));
+        }
     }
 
-    fn add_network_info(&mut self, client_id: ClientId, network_info: NetworkInfo) {
+    /// Add the stats measured by the transport for a client, like the ones of `SteamServerTransport::transport_stats`
+    /// in renet_steam. Call it before [`update`](Self::update), see [`RenetClientVisualizer::add_transport_stats`].
+    pub fn add_transport_stats(&mut self, client_id: ClientId, transport_stats: TransportStats) {
         if let Some(client) = self.clients.get_mut(&client_id) {
-            client.add_network_info(network_info);
+            client.add_transport_stats(transport_stats);
+        }
+    }
+
+    fn add_network_info(&mut self, client_id: ClientId, network_info: NetworkInfo, time: Duration) {
+        if let Some(client) = self.clients.get_mut(&client_id) {
+            client.push_network_info(&network_info, true, time);
+            self.record(time, RecordedEvent::network_info(Some(client_id), &network_info));
+        }
+    }
+
+    fn add_channel_network_info(&mut self, client_id: ClientId, channel_info: ChannelNetworkInfo, time: Duration) {
+        if let Some(client) = self.clients.get_mut(&client_id) {
+            client.push_channel_network_info(channel_info, time);
+            self.record(time, RecordedEvent::ChannelInfo(Some(client_id), channel_info));
         }
     }
 
@@ -260,155 +612,217 @@ impl<const N: usize> RenetServerVisualizer<N> {
     /// visualizer.update(&renet_server);
     /// ```
     pub fn update(&mut self, server: &RenetServer) {
+        // Forget clients that were removed from the server without calling remove_client
+        let removed: Vec<ClientId> = self
+            .clients
+            .keys()
+            .copied()
+            .filter(|client_id| !server.has_client(*client_id))
+            .collect();
+        for client_id in removed {
+            self.remove_client(client_id);
+        }
+        if self.selected_client.is_some_and(|client_id| !self.clients.contains_key(&client_id)) {
+            self.selected_client = None;
+        }
+        if self
+            .overview
+            .expanded_client
+            .is_some_and(|client_id| !self.clients.contains_key(&client_id))
+        {
+            self.overview.expanded_client = None;
+        }
+
+        let time = unix_time();
         for client_id in server.clients_id_iter() {
             if let Ok(network_info) = server.network_info(client_id) {
-                self.add_network_info(client_id, network_info);
+                self.add_network_info(client_id, network_info, time);
+            }
+            for channel_info in server.channels_network_info(client_id).unwrap_or_default() {
+                self.add_channel_network_info(client_id, channel_info, time);
             }
         }
     }
 
-    /// Draw all metrics without a window or layout for the specified client.
-    pub fn draw_client_metrics(&self, client_id: ClientId, ui: &mut egui::Ui) {
-        if let Some(client) = self.clients.get(&client_id) {
-            client.draw_all(ui);
+    /// Sets the column used to sort the overview table, with the same column the order is reversed.
+    pub fn sort_overview_by(&mut self, column: OverviewColumn) {
+        if self.overview.sort_column == column {
+            self.overview.descending = !self.overview.descending;
+        } else {
+            self.overview.sort_column = column;
+            self.overview.descending = false;
         }
     }
 
-    /// Renders a new window with all the graphs metrics drawn. You can choose to show metrics for
-    /// all connected clients or for only one chosen by a dropdown.
-    pub fn show_window(&mut self, ctx: &egui::Context) {
-        egui::Window::new("Server Network Info")
-            .resizable(false)
-            .collapsible(true)
-            .show(ctx, |ui| {
-                ui.horizontal(|ui| {
-                    ui.checkbox(&mut self.show_all_clients, "Show all clients");
-                    ui.add_enabled_ui(!self.show_all_clients, |ui| {
-                        let selected_text = match self.selected_client {
-                            Some(client_id) => format!("{}", client_id),
-                            None => "------".to_string(),
-                        };
-                        egui::ComboBox::from_label("Select client")
-                            .selected_text(selected_text)
-                            .show_ui(ui, |ui| {
-                                for client_id in self.clients.keys() {
-                                    ui.selectable_value(&mut self.selected_client, Some(*client_id), format!("{}", client_id));
-                                }
-                            })
-                    });
-                });
-                ui.vertical(|ui| {
-                    if self.show_all_clients {
-                        for (client_id, client) in self.clients.iter() {
-                            ui.vertical(|ui| {
-                                ui.heading(format!("Client {}", client_id));
-                                ui.horizontal(|ui| {
-                                    client.draw_all(ui);
-                                });
-                            });
-                        }
-                    } else if let Some(selected_client) = self.selected_client {
-                        if let Some(client) = self.clients.get(&selected_client) {
-                            ui.horizontal(|ui| {
-                                client.draw_all(ui);
-                            });
-                        }
-                    }
-                });
-            });
+    /// Only shows the clients whose id contains the filter in the overview table.
+    pub fn set_overview_filter(&mut self, filter: impl Into<String>) {
+        self.overview.filter = filter.into();
     }
-}
 
-fn show_graph(
-    ui: &mut egui::Ui,
-    style: &RenetVisualizerStyle,
-    label: &str,
-    text_format: TextFormat,
-    top_value: TopValue,
-    values: Vec<f32>,
-) {
-    if values.is_empty() {
-        return;
+    /// Returns the rows of the overview table, filtered and sorted.
+    pub fn overview_rows(&self) -> Vec<OverviewRow> {
+        let mut rows: Vec<OverviewRow> = self
+            .clients
+            .iter()
+            .filter(|(client_id, _)| client_id.to_string().contains(self.overview.filter.trim()))
+            .map(|(client_id, client)| OverviewRow {
+                client_id: *client_id,
+                rtt: client.rtt.last().avg,
+                packet_loss: client.packet_loss.last().avg,
+                sent_kbps: client.sent_bandwidth_kbps.last().avg,
+                received_kbps: client.received_bandwidth_kbps.last().avg,
+                buffered_bytes: client.channels.values().map(|graphs| graphs.buffered_bytes.last().avg).sum(),
+                uptime: client.created_at.elapsed(),
+            })
+            .collect();
+
+        rows.sort_by(|a, b| {
+            let ordering = match self.overview.sort_column {
+                OverviewColumn::ClientId => a.client_id.cmp(&b.client_id),
+                OverviewColumn::Rtt => a.rtt.total_cmp(&b.rtt),
+                OverviewColumn::PacketLoss => a.packet_loss.total_cmp(&b.packet_loss),
+                OverviewColumn::SentKbps => a.sent_kbps.total_cmp(&b.sent_kbps),
+                OverviewColumn::ReceivedKbps => a.received_kbps.total_cmp(&b.received_kbps),
+                OverviewColumn::BufferedBytes => a.buffered_bytes.total_cmp(&b.buffered_bytes),
+                OverviewColumn::Uptime => a.uptime.cmp(&b.uptime),
+            };
+            // Keep the order stable between frames when the values are equal
+            let ordering = ordering.then(a.client_id.cmp(&b.client_id));
+            if self.overview.descending {
+                ordering.reverse()
+            } else {
+                ordering
+            }
+        });
+        rows
     }
+}
+// Hours:minutes:seconds
+fn format_uptime(uptime: Duration) -> String {
+    let uptime = uptime.as_secs();
+    format!("{}:{:02}:{:02}", uptime / 3600, uptime / 60 % 60, uptime % 60)
+}
 
-    ui.vertical(|ui| {
-        ui.label(RichText::new(label).heading().color(style.text_color));
+fn unix_time() -> Duration {
+    SystemTime::now().duration_since(SystemTime::UNIX_EPOCH).unwrap_or_default()
+}
 
-        let last_value = values.last().unwrap();
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn network_info(rtt: f64) -> NetworkInfo {
+        NetworkInfo {
+            rtt,
+            packet_loss: 0.0,
+            receive_packet_loss: 0.0,
+            jitter: 0.0,
+            bytes_sent_per_second: 0.0,
+            bytes_received_per_second: 0.0,
+        }
+    }
 
-        let min = 0.0;
-        let mut max = values.iter().copied().fold(f32::NEG_INFINITY, f32::max);
-        match top_value {
-            TopValue::MaxValue { multiplicated } => {
-                max *= multiplicated;
-            }
-            TopValue::SuggestedValues(suggested_values) => {
-                for value in suggested_values.into_iter() {
-                    if max < value {
-                        max = value;
-                        break;
-                    }
-                }
-            }
+    #[test]
+    fn overview_sort_and_filter() {
+        let mut visualizer = RenetServerVisualizer::<5>::default();
+        for (client_id, rtt) in [(12, 0.05), (3, 0.2), (21, 0.1)] {
+            visualizer.add_client(client_id);
+            visualizer.add_network_info(client_id, network_info(rtt), unix_time());
         }
+        let client_ids =
+            |visualizer: &RenetServerVisualizer<5>| visualizer.overview_rows().iter().map(|row| row.client_id).collect::<Vec<_>>();
+        assert_eq!(client_ids(&visualizer), vec![3, 12, 21]);
+
+        visualizer.sort_overview_by(OverviewColumn::Rtt);
+        assert_eq!(client_ids(&visualizer), vec![12, 21, 3]);
+        visualizer.sort_overview_by(OverviewColumn::Rtt);
+        assert_eq!(client_ids(&visualizer), vec![3, 21, 12]);
 
-        let spacing_x = ui.spacing().item_spacing.x;
+        visualizer.set_overview_filter("2");
+        assert_eq!(client_ids(&visualizer), vec![21, 12]);
+    }
 
-        let last_text: WidgetText = match text_format {
-            TextFormat::Normal => format!("{:.2}", last_value).into(),
-            TextFormat::Percentage => format!("{:.1}%", last_value * 100.).into(),
+    #[test]
+    fn transport_stats() {
+        let mut visualizer = RenetServerVisualizer::<5>::default();
+        visualizer.add_client(1);
+        visualizer.add_network_info(1, network_info(0.05), unix_time());
+        assert!(!visualizer.render_client_text(1, 3).unwrap().contains("Transport"));
+
+        let transport_stats = TransportStats {
+            rtt: 0.03,
+            connection_quality: 0.9,
+            relayed: true,
+            pending_bytes: 600,
         };
-        let galley = last_text.into_galley(ui, Some(egui::TextWrapMode::Wrap), f32::INFINITY, TextStyle::Button);
-        let (outer_rect, _) = ui.allocate_exact_size(Vec2::new(style.width + galley.size().x + spacing_x, style.height), Sense::hover());
-        let rect = Rect::from_min_size(outer_rect.left_top(), vec2(style.width, style.height));
-        let text_pos = rect.right_center() + vec2(spacing_x / 2.0, -galley.size().y / 2.);
-        ui.painter().with_clip_rect(outer_rect).galley(text_pos, galley, style.text_color);
-
-        let body = Shape::Rect(RectShape {
-            rect,
-            fill: Rgba::TRANSPARENT.into(),
-            stroke: style.rectangle_stroke,
-            stroke_kind: StrokeKind::Inside,
-            blur_width: 0.0,
-            corner_radius: CornerRadius::ZERO,
-            round_to_pixels: None,
-            brush: None,
-        });
-        ui.painter().add(body);
-        let init_point = rect.left_bottom();
+        visualizer.add_transport_stats(1, transport_stats);
+        visualizer.add_network_info(1, network_info(0.05), unix_time());
+        let client = &visualizer.clients[&1];
+        assert_eq!(client.transport_stats(), Some(transport_stats));
+        assert_eq!(client.transport_rtt.last().avg, 30.);
+        assert_eq!(client.transport_pending_bytes.last().avg, 600.);
+        assert!(client.transport_rtt.values()[0].avg.is_nan());
+
+        let text = visualizer.render_client_text(1, 3).unwrap();
+        assert!(text.contains("Transport RTT      30.0 ms    ▁█\n"));
+        assert!(text.contains("Transport: quality 90%, relayed\n"));
+
+        // The stats are only used by one sample, the next one without stats is missing
+        visualizer.add_network_info(1, network_info(0.05), unix_time());
+        let client = &visualizer.clients[&1];
+        assert_eq!(client.transport_stats(), Some(transport_stats));
+        assert!(client.transport_rtt.last().avg.is_nan());
+        let text = visualizer.render_client_text(1, 3).unwrap();
+        assert!(text.contains("Transport RTT            -   ▁█▁\n"));
+    }
 
-        let size = values.len();
-        let points = values
-            .iter()
-            .enumerate()
-            .map(|(i, value)| {
-                let x = remap(i as f32, 0.0..=size as f32, 0.0..=style.width);
-                let y = if max == 0.0 { 0.0 } else { remap(*value, min..=max, 0.0..=style.height) };
+    #[test]
+    fn paused_samples_are_not_evicted() {
+        let mut visualizer = RenetClientVisualizer::<5>::default();
+        let rtts = |visualizer: &RenetClientVisualizer<5>| visualizer.rtt.values().iter().map(|sample| sample.avg).collect::<Vec<_>>();
+        for rtt in 1..=3 {
+            visualizer.add_network_info(network_info(rtt as f64 / 1000.));
+        }
 
-                pos2(x + init_point.x, init_point.y - y)
-            })
-            .collect();
+        visualizer.set_paused(true);
+        for rtt in 4..=10 {
+            visualizer.add_network_info(network_info(rtt as f64 / 1000.));
+        }
+        assert_eq!(rtts(&visualizer), vec![1., 2., 3.]);
+        assert_eq!(visualizer.held_samples.len(), 5);
 
-        let path = PathShape::line(points, style.line_stroke);
-        ui.painter().add(path);
+        visualizer.set_paused(false);
+        assert_eq!(rtts(&visualizer), vec![6., 7., 8., 9., 10.]);
+        assert!(visualizer.held_samples.is_empty());
+    }
 
-        {
-            let text: WidgetText = match text_format {
-                TextFormat::Normal => format!("{:.0}", max).into(),
-                TextFormat::Percentage => format!("{:.0}%", max * 100.).into(),
-            };
-            let galley = text.into_galley(ui, Some(egui::TextWrapMode::Wrap), f32::INFINITY, TextStyle::Button);
-            let text_pos = rect.left_top() + Vec2::new(0.0, galley.size().y / 2.) + vec2(spacing_x, 0.0);
-            ui.painter().with_clip_rect(outer_rect).galley(text_pos, galley, style.text_color);
-        }
-        {
-            let text: WidgetText = match text_format {
-                TextFormat::Normal => format!("{:.0}", min).into(),
-                TextFormat::Percentage => format!("{:.0}%", min * 100.).into(),
-            };
-            let galley = text.into_galley(ui, Some(egui::TextWrapMode::Wrap), f32::INFINITY, TextStyle::Button);
-            let text_pos = rect.left_bottom() - Vec2::new(0.0, galley.size().y * 1.5) + vec2(spacing_x, 0.0);
-            ui.painter().with_clip_rect(outer_rect).galley(text_pos, galley, style.text_color);
-        }
-    });
+    #[test]
+    fn server_incidents() {
+        let style = RenetVisualizerStyle {
+            thresholds: Thresholds {
+                rtt_ms: Some(150.),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let mut visualizer = RenetServerVisualizer::<5>::new(style);
+        visualizer.add_client(1);
+        visualizer.add_client(2);
+        visualizer.add_network_info(1, network_info(0.2), unix_time());
+        visualizer.add_network_info(2, network_info(0.1), unix_time());
+        assert_eq!(visualizer.client_incidents(1).unwrap().len(), 1);
+        assert!(visualizer.client_incidents(2).unwrap().is_empty());
+
+        // Kept after the client is removed, and ended
+        visualizer.remove_client(1);
+        let incidents = visualizer.incidents();
+        assert_eq!(incidents.len(), 1);
+        assert_eq!(incidents[0].0, 1);
+        assert_eq!(incidents[0].1.metric, IncidentMetric::Rtt);
+        assert_eq!(incidents[0].1.peak, 200.);
+        assert!(incidents[0].1.end.is_some());
+
+        visualizer.clear_incidents();
+        assert!(visualizer.incidents().is_empty());
+    }
 }
@@ -0,0 +1,586 @@
+use std::{fmt, time::Duration};
+
+use renet::{ChannelNetworkInfo, ClientId, NetworkInfo};
+
+use crate::{RenetClientVisualizer, RenetServerVisualizer, RenetVisualizerStyle};
+
+const MAGIC: &[u8; 4] = b"RNVR";
+// Increase when the format changes, and keep decoding the older versions
+const VERSION: u16 = 3;
+
+const CLIENT_ADDED: u8 = 0;
+const CLIENT_REMOVED: u8 = 1;
+const NETWORK_INFO: u8 = 2;
+const CHANNEL_INFO: u8 = 3;
+
+/// Error when loading a [`Recording`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RecordingError {
+    /// The data is not a visualizer recording.
+    InvalidHeader,
+    /// The recording was made by a newer version of the visualizer.
+    UnsupportedVersion(u16),
+    /// The recording ends in the middle of a sample.
+    Truncated,
+    /// Unknown kind of sample.
+    InvalidRecord(u8),
+}
+
+impl fmt::Display for RecordingError {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            RecordingError::InvalidHeader => write!(fmt, "data is not a visualizer recording"),
+            RecordingError::UnsupportedVersion(version) => write!(fmt, "unsupported recording version {version}"),
+            RecordingError::Truncated => write!(fmt, "recording is truncated"),
+            RecordingError::InvalidRecord(kind) => write!(fmt, "invalid record kind {kind}"),
+        }
+    }
+}
+
+impl std::error::Error for RecordingError {}
+
+// The client id is None for the samples of a client visualizer
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum RecordedEvent {
+    ClientAdded(ClientId),
+    ClientRemoved(ClientId),
+    NetworkInfo {
+        client_id: Option<ClientId>,
+        rtt: f32,
+        packet_loss: f32,
+        bytes_sent_per_second: f32,
+        bytes_received_per_second: f32,
+        // None in the recordings before version 3
+        receive_packet_loss: Option<f32>,
+        jitter: Option<f32>,
+    },
+    ChannelInfo(Option<ClientId>, ChannelNetworkInfo),
+}
+
+impl RecordedEvent {
+    pub fn network_info(client_id: Option<ClientId>, network_info: &NetworkInfo) -> Self {
+        RecordedEvent::NetworkInfo {
+            client_id,
+            rtt: network_info.rtt as f32,
+            packet_loss: network_info.packet_loss as f32,
+            bytes_sent_per_second: network_info.bytes_sent_per_second as f32,
+            bytes_received_per_second: network_info.bytes_received_per_second as f32,
+            receive_packet_loss: Some(network_info.receive_packet_loss as f32),
+            jitter: Some(network_info.jitter as f32),
+        }
+    }
+}
+
+/// Every sample added to a visualizer while recording, with the time it was added and its client id.
+/// Save it with [`to_bytes`](Self::to_bytes) and play it back with a [`RecordingPlayer`].
+///
+/// # Usage
+/// ```
+/// # use renet_visualizer::{Recording, RenetServerVisualizer};
+/// let mut visualizer = RenetServerVisualizer::<200>::default();
+/// visualizer.start_recording();
+/// // Update the visualizer during the session
+/// let bytes = visualizer.stop_recording().unwrap().to_bytes();
+///
+/// // Later, on another machine
+/// let recording = Recording::from_bytes(&bytes).unwrap();
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Recording {
+    // Time since the unix epoch of each event, in the order they were added
+    events: Vec<(Duration, RecordedEvent)>,
+}
+
+impl Recording {
+    pub(crate) fn push(&mut self, time: Duration, event: RecordedEvent) {
+        self.events.push((time, event));
+    }
+
+    /// Returns the number of recorded samples.
+    pub fn len(&self) -> usize {
+        self.events.len()
+    }
+
+    /// Returns true if nothing was recorded.
+    pub fn is_empty(&self) -> bool {
+        self.events.is_empty()
+    }
+
+    /// Returns the time between the first and the last recorded samples.
+    pub fn duration(&self) -> Duration {
+        match (self.events.first(), self.events.last()) {
+            (Some((start, _)), Some((end, _))) => end.saturating_sub(*start),
+            _ => Duration::ZERO,
+        }
+    }
+
+    fn start(&self) -> Duration {
+        self.events.first().map(|(time, _)| *time).unwrap_or_default()
+    }
+
+    /// Serializes the recording in a compact binary format, starting with a version header.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(6 + self.events.len() * 32);
+        bytes.extend_from_slice(MAGIC);
+        bytes.extend_from_slice(&VERSION.to_le_bytes());
+
+        let write_client_id = |bytes: &mut Vec<u8>, client_id: Option<ClientId>| match client_id {
+            Some(client_id) => {
+                bytes.push(1);
+                bytes.extend_from_slice(&client_id.to_le_bytes());
+            }
+            None => bytes.push(0),
+        };
+        for (time, event) in self.events.iter() {
+            let kind = match event {
+                RecordedEvent::ClientAdded(_) => CLIENT_ADDED,
+                RecordedEvent::ClientRemoved(_) => CLIENT_REMOVED,
+                RecordedEvent::NetworkInfo { .. } => NETWORK_INFO,
+                RecordedEvent::ChannelInfo(..) => CHANNEL_INFO,
+            };
+            bytes.push(kind);
+            bytes.extend_from_slice(&(time.as_micros() as u64).to_le_bytes());
+
+            match *event {
+                RecordedEvent::ClientAdded(client_id) | RecordedEvent::ClientRemoved(client_id) => {
+                    bytes.extend_from_slice(&client_id.to_le_bytes());
+                }
+                RecordedEvent::NetworkInfo {
+                    client_id,
+                    rtt,
+                    packet_loss,
+                    bytes_sent_per_second,
+                    bytes_received_per_second,
+                    receive_packet_loss,
+                    jitter,
+                } => {
+                    write_client_id(&mut bytes, client_id);
+                    let receive_packet_loss = receive_packet_loss.unwrap_or_default();
+                    let jitter = jitter.unwrap_or_default();
+                    for value in [
+                        rtt,
+                        packet_loss,
+                        bytes_sent_per_second,
+                        bytes_received_per_second,
+                        receive_packet_loss,
+                        jitter,
+                    ] {
+                        bytes.extend_from_slice(&value.to_le_bytes());
+                    }
+                }
+                RecordedEvent::ChannelInfo(client_id, channel_info) => {
+                    write_client_id(&mut bytes, client_id);
+                    bytes.push(channel_info.channel_id);
+                    bytes.extend_from_slice(&(channel_info.bytes_sent_per_second as f32).to_le_bytes());
+                    bytes.extend_from_slice(&(channel_info.bytes_received_per_second as f32).to_le_bytes());
+                    for value in [
+                        channel_info.buffered_bytes,
+                        channel_info.max_memory_usage_bytes,
+                        channel_info.bytes_in_flight,
+                    ] {
+                        bytes.extend_from_slice(&(value.min(u32::MAX as usize) as u32).to_le_bytes());
+                    }
+                }
+            }
+        }
+
+        bytes
+    }
+
+    /// Loads a recording serialized with [`to_bytes`](Self::to_bytes), by this or an older version.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, RecordingError> {
+        let mut reader = Reader { bytes };
+        if reader.take::<4>().ok() != Some(*MAGIC) {
+            return Err(RecordingError::InvalidHeader);
+        }
+        let version = u16::from_le_bytes(reader.take()?);
+        match version {
+            1..=3 => Self::read_events(reader, version),
+            _ => Err(RecordingError::UnsupportedVersion(version)),
+        }
+    }
+
+    // Version 1 does not have the memory limit and the bytes in flight of the channels,
+    // and versions 1 and 2 the receive loss and jitter of the connection
+    fn read_events(mut reader: Reader, version: u16) -> Result<Self, RecordingError> {
+        let mut recording = Recording::default();
+        while !reader.bytes.is_empty() {
+            let [kind] = reader.take()?;
+            let time = Duration::from_micros(u64::from_le_bytes(reader.take()?));
+            let event = match kind {
+                CLIENT_ADDED => RecordedEvent::ClientAdded(reader.client_id()?),
+                CLIENT_REMOVED => RecordedEvent::ClientRemoved(reader.client_id()?),
+                NETWORK_INFO => RecordedEvent::NetworkInfo {
+                    client_id: reader.optional_client_id()?,
+                    rtt: reader.f32()?,
+                    packet_loss: reader.f32()?,
+                    bytes_sent_per_second: reader.f32()?,
+                    bytes_received_per_second: reader.f32()?,
+                    receive_packet_loss: if version >= 3 { Some(reader.f32()?) } else { None },
+                    jitter: if version >= 3 { Some(reader.f32()?) } else { None },
+                },
+                CHANNEL_INFO => {
+                    let client_id = reader.optional_client_id()?;
+                    let [channel_id] = reader.take()?;
+                    let mut channel_info = ChannelNetworkInfo {
+                        channel_id,
+                        bytes_sent_per_second: reader.f32()? as f64,
+                        bytes_received_per_second: reader.f32()? as f64,
+                        buffered_bytes: reader.u32()? as usize,
+                        ..Default::default()
+                    };
+                    if version >= 2 {
+                        channel_info.max_memory_usage_bytes = reader.u32()? as usize;
+                        channel_info.bytes_in_flight = reader.u32()? as usize;
+                    }
+                    RecordedEvent::ChannelInfo(client_id, channel_info)
+                }
+                kind => return Err(RecordingError::InvalidRecord(kind)),
+            };
+            recording.push(time, event);
+        }
+
+        Ok(recording)
+    }
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl Reader<'_> {
+    fn take<const L: usize>(&mut self) -> Result<[u8; L], RecordingError> {
+        let Some((value, rest)) = self.bytes.split_first_chunk::<L>() else {
+            return Err(RecordingError::Truncated);
+        };
+        self.bytes = rest;
+        Ok(*value)
+    }
+
+    fn client_id(&mut self) -> Result<ClientId, RecordingError> {
+        Ok(ClientId::from_le_bytes(self.take()?))
+    }
+
+    fn optional_client_id(&mut self) -> Result<Option<ClientId>, RecordingError> {
+        match self.take()? {
+            [0] => Ok(None),
+            _ => Ok(Some(self.client_id()?)),
+        }
+    }
+
+    fn f32(&mut self) -> Result<f32, RecordingError> {
+        Ok(f32::from_le_bytes(self.take()?))
+    }
+
+    fn u32(&mut self) -> Result<u32, RecordingError> {
+        Ok(u32::from_le_bytes(self.take()?))
+    }
+}
+
+impl<const N: usize> RenetClientVisualizer<N> {
+    /// Starts recording every sample added to the visualizer, restarting the recording if one was started.
+    pub fn start_recording(&mut self) {
+        self.recording = Some(Recording::default());
+    }
+
+    /// Stops the recording and returns it, None if it was not recording.
+    pub fn stop_recording(&mut self) -> Option<Recording> {
+        self.recording.take()
+    }
+
+    /// Returns true if the samples are being recorded.
+    pub fn is_recording(&self) -> bool {
+        self.recording.is_some()
+    }
+
+    pub(crate) fn record(&mut self, time: Duration, event: RecordedEvent) {
+        if let Some(recording) = &mut self.recording {
+            recording.push(time, event);
+        }
+    }
+
+    fn replay(&mut self, time: Duration, event: &RecordedEvent) {
+        match *event {
+            RecordedEvent::NetworkInfo {
+                rtt,
+                packet_loss,
+                bytes_sent_per_second,
+                bytes_received_per_second,
+                receive_packet_loss,
+                jitter,
+                ..
+            } => {
+                let network_info = NetworkInfo {
+                    rtt: rtt as f64,
+                    packet_loss: packet_loss as f64,
+                    receive_packet_loss: receive_packet_loss.unwrap_or_default() as f64,
+                    jitter: jitter.unwrap_or_default() as f64,
+                    bytes_sent_per_second: bytes_sent_per_second as f64,
+                    bytes_received_per_second: bytes_received_per_second as f64,
+                };
+                let direction_stats = receive_packet_loss.is_some() && jitter.is_some();
+                self.push_network_info(&network_info, direction_stats, time);
+            }
+            RecordedEvent::ChannelInfo(_, channel_info) => self.push_channel_network_info(channel_info, time),
+            RecordedEvent::ClientAdded(_) | RecordedEvent::ClientRemoved(_) => {}
+        }
+    }
+}
+
+impl<const N: usize> RenetServerVisualizer<N> {
+    /// Starts recording every sample added to the visualizer with the client ids,
+    /// restarting the recording if one was started.
+    pub fn start_recording(&mut self) {
+        self.recording = Some(Recording::default());
+    }
+
+    /// Stops the recording and returns it, None if it was not recording.
+    pub fn stop_recording(&mut self) -> Option<Recording> {
+        self.recording.take()
+    }
+
+    /// Returns true if the samples are being recorded.
+    pub fn is_recording(&self) -> bool {
+        self.recording.is_some()
+    }
+
+    pub(crate) fn record(&mut self, time: Duration, event: RecordedEvent) {
+        if let Some(recording) = &mut self.recording {
+            recording.push(time, event);
+        }
+    }
+
+    fn replay(&mut self, time: Duration, event: &RecordedEvent) {
+        match *event {
+            RecordedEvent::ClientAdded(client_id) => self.add_client(client_id),
+            RecordedEvent::ClientRemoved(client_id) => self.remove_client(client_id),
+            RecordedEvent::NetworkInfo {
+                client_id: Some(client_id),
+                ..
+            }
+            | RecordedEvent::ChannelInfo(Some(client_id), _) => {
+                if let Some(client) = self.clients.get_mut(&client_id) {
+                    client.replay(time, event);
+                }
+            }
+            RecordedEvent::NetworkInfo { client_id: None, .. } | RecordedEvent::ChannelInfo(None, _) => {}
+        }
+    }
+}
+
+/// Plays back a [`Recording`] in the visualizers, without a live client or server.
+/// The recording is replayed up to the position, drawn with a slider to scrub through it.
+///
+/// # Usage
+/// ```
+/// # use renet_visualizer::{Recording, RecordingPlayer};
+/// # #[cfg(feature = "egui")]
+/// # fn run(bytes: &[u8], ctx: &egui::Context) {
+/// let recording = Recording::from_bytes(bytes).unwrap();
+/// let mut player = RecordingPlayer::<200>::new(recording, Default::default());
+/// // Every frame
+/// player.show_window(ctx);
+/// # }
+/// ```
+pub struct RecordingPlayer<const N: usize> {
+    recording: Recording,
+    position: Duration,
+    // Number of events replayed in the visualizers
+    replayed: usize,
+    style: RenetVisualizerStyle,
+    client: RenetClientVisualizer<N>,
+    server: RenetServerVisualizer<N>,
+}
+
+impl<const N: usize> RecordingPlayer<N> {
+    /// Creates a player at the start of the recording.
+    pub fn new(recording: Recording, style: RenetVisualizerStyle) -> Self {
+        let mut player = Self {
+            recording,
+            position: Duration::ZERO,
+            replayed: 0,
+            client: RenetClientVisualizer::new(style.clone()),
+            server: RenetServerVisualizer::new(style.clone()),
+            style,
+        };
+        player.replay_until(Duration::ZERO);
+        player
+    }
+
+    /// Returns the position of the playback, since the start of the recording.
+    pub fn position(&self) -> Duration {
+        self.position
+    }
+
+    /// Returns the duration of the recording.
+    pub fn duration(&self) -> Duration {
+        self.recording.duration()
+    }
+
+    /// Moves the playback to the position since the start of the recording.
+    pub fn seek(&mut self, position: Duration) {
+        let position = position.min(self.duration());
+        if position < self.position {
+            // The visualizers only go forward, replay from the start
+            self.client = RenetClientVisualizer::new(self.style.clone());
+            self.server = RenetServerVisualizer::new(self.style.clone());
+            self.replayed = 0;
+        }
+        self.replay_until(position);
+    }
+
+    fn replay_until(&mut self, position: Duration) {
+        self.position = position;
+        let end = self.recording.start() + position;
+        while let Some((time, event)) = self.recording.events.get(self.replayed) {
+            if *time > end {
+                break;
+            }
+            match event {
+                RecordedEvent::NetworkInfo { client_id: None, .. } | RecordedEvent::ChannelInfo(None, _) => {
+                    self.client.replay(*time, event)
+                }
+                _ => self.server.replay(*time, event),
+            }
+            self.replayed += 1;
+        }
+    }
+
+    /// Returns the visualizer with the samples of a recorded client visualizer.
+    pub fn client_visualizer(&self) -> &RenetClientVisualizer<N> {
+        &self.client
+    }
+
+    /// Returns the visualizer with the samples of a recorded server visualizer.
+    pub fn server_visualizer(&self) -> &RenetServerVisualizer<N> {
+        &self.server
+    }
+
+    /// Draws the playback slider and the graphs at the position without a window.
+    #[cfg(feature = "egui")]
+    pub fn draw(&mut self, ui: &mut egui::Ui) {
+        let duration = self.duration().as_secs_f32();
+        let mut position = self.position.as_secs_f32();
+        let slider = egui::Slider::new(&mut position, 0.0..=duration).suffix("s").text("Position");
+        if ui.add(slider).changed() {
+            self.seek(Duration::from_secs_f32(position));
+        }
+
+        if self.server.clients.is_empty() && !self.client.sample_times.values().is_empty() {
+            self.client.draw_pause_toggle(ui);
+            ui.horizontal(|ui| {
+                self.client.draw_all(ui);
+            });
+            self.client.draw_channels(ui);
+        } else {
+            self.server.draw_all(ui);
+        }
+    }
+
+    /// Renders a new window with the playback slider and the graphs.
+    #[cfg(feature = "egui")]
+    pub fn show_window(&mut self, ctx: &egui::Context) {
+        egui::Window::new("Recording Playback")
+            .resizable(false)
+            .collapsible(true)
+            .show(ctx, |ui| {
+                self.draw(ui);
+            });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn network_info(rtt: f64) -> NetworkInfo {
+        NetworkInfo {
+            rtt,
+            packet_loss: 0.5,
+            receive_packet_loss: 0.25,
+            jitter: 0.01,
+            bytes_sent_per_second: 100.,
+            bytes_received_per_second: 50.,
+        }
+    }
+
+    fn test_recording() -> Recording {
+        let mut recording = Recording::default();
+        let at = Duration::from_millis;
+        recording.push(at(1000), RecordedEvent::ClientAdded(7));
+        recording.push(at(1000), RecordedEvent::network_info(Some(7), &network_info(0.1)));
+        let channel_info = ChannelNetworkInfo {
+            channel_id: 2,
+            bytes_sent_per_second: 10.,
+            bytes_received_per_second: 20.,
+            buffered_bytes: 30,
+            max_memory_usage_bytes: 1000,
+            bytes_in_flight: 10,
+        };
+        recording.push(at(1000), RecordedEvent::ChannelInfo(Some(7), channel_info));
+        recording.push(at(2000), RecordedEvent::network_info(Some(7), &network_info(0.2)));
+        recording.push(at(3000), RecordedEvent::ClientRemoved(7));
+        recording.push(at(3000), RecordedEvent::network_info(None, &network_info(0.3)));
+        recording
+    }
+
+    #[test]
+    fn serialize_recording() {
+        let recording = test_recording();
+        let bytes = recording.to_bytes();
+        assert_eq!(&bytes[..6], b"RNVR\x03\x00");
+        assert_eq!(Recording::from_bytes(&bytes), Ok(recording));
+
+        assert_eq!(Recording::from_bytes(b"RIFF\x01\x00"), Err(RecordingError::InvalidHeader));
+        assert_eq!(Recording::from_bytes(b"RNVR\x04\x00"), Err(RecordingError::UnsupportedVersion(4)));
+        assert_eq!(Recording::from_bytes(&bytes[..bytes.len() - 1]), Err(RecordingError::Truncated));
+        let invalid_record = [b"RNVR\x01\x00\x09".as_slice(), &[0; 8]].concat();
+        assert_eq!(Recording::from_bytes(&invalid_record), Err(RecordingError::InvalidRecord(9)));
+
+        // Version 1 channel records without the memory limit and the bytes in flight
+        let channel_record = [b"RNVR\x01\x00\x03".as_slice(), &[0; 8], &[0, 2], &10f32.to_le_bytes(), &[0; 8]].concat();
+        let RecordedEvent::ChannelInfo(None, channel_info) = Recording::from_bytes(&channel_record).unwrap().events[0].1 else {
+            panic!("expected a channel record");
+        };
+        assert_eq!(channel_info.bytes_sent_per_second, 10.);
+        assert_eq!(channel_info.max_memory_usage_bytes, 0);
+
+        // Version 2 network records without the receive loss and jitter, their graphs are hidden when replayed
+        let network_record = [b"RNVR\x02\x00\x02".as_slice(), &[0; 8], &[0], &[0; 16]].concat();
+        let player = RecordingPlayer::<5>::new(Recording::from_bytes(&network_record).unwrap(), Default::default());
+        assert_eq!(player.client_visualizer().rtt.values().len(), 1);
+        assert!(!player.client_visualizer().direction_stats);
+    }
+
+    #[test]
+    fn record_and_play() {
+        let mut visualizer = RenetServerVisualizer::<5>::default();
+        visualizer.start_recording();
+        visualizer.add_client(1);
+        visualizer.add_network_info(1, network_info(0.1), Duration::from_secs(10));
+        let recording = visualizer.stop_recording().unwrap();
+        assert_eq!(recording.len(), 2);
+        assert!(!visualizer.is_recording());
+
+        let mut player = RecordingPlayer::<5>::new(test_recording(), Default::default());
+        assert_eq!(player.duration(), Duration::from_secs(2));
+        let rtt = |player: &RecordingPlayer<5>| player.server_visualizer().clients[&7].rtt.values();
+        assert_eq!(rtt(&player).len(), 1);
+        assert!(player.server_visualizer().clients[&7].direction_stats);
+
+        player.seek(Duration::from_secs(1));
+        assert_eq!(rtt(&player).len(), 2);
+        assert_eq!(rtt(&player)[1].avg, 200.);
+        let times = player.server_visualizer().clients[&7].sample_times.values();
+        assert_eq!(times, vec![Duration::from_secs(1), Duration::from_secs(2)]);
+
+        player.seek(Duration::from_secs(5));
+        assert!(player.server_visualizer().clients.is_empty());
+        assert_eq!(player.client_visualizer().rtt.values().len(), 1);
+
+        // Seeking back replays from the start
+        player.seek(Duration::ZERO);
+        assert_eq!(rtt(&player).len(), 1);
+        assert!(player.client_visualizer().rtt.values().is_empty());
+    }
+}
@@ -0,0 +1,156 @@
+use std::time::Duration;
+
+use crate::circular_buffer::CircularBuffer;
+
+/// Value of a point in the graphs, with the minimum and maximum of the samples aggregated in it.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Sample {
+    pub min: f32,
+    pub avg: f32,
+    pub max: f32,
+}
+
+impl Sample {
+    pub fn new(value: f32) -> Self {
+        Self {
+            min: value,
+            avg: value,
+            max: value,
+        }
+    }
+}
+
+// Samples of M metrics added during the current sample interval,
+// missing values are NaN and are not aggregated
+#[derive(Debug)]
+pub struct Bucket<const M: usize> {
+    start: Duration,
+    count: [u32; M],
+    sum: [f32; M],
+    min: [f32; M],
+    max: [f32; M],
+}
+
+impl<const M: usize> Bucket<M> {
+    fn new(start: Duration, values: [f32; M]) -> Self {
+        let mut bucket = Self {
+            start,
+            count: [0; M],
+            sum: [0.; M],
+            min: [f32::NAN; M],
+            max: [f32::NAN; M],
+        };
+        bucket.add(values);
+        bucket
+    }
+
+    fn add(&mut self, values: [f32; M]) {
+        for (i, value) in values.into_iter().enumerate().filter(|(_, value)| !value.is_nan()) {
+            self.count[i] += 1;
+            self.sum[i] += value;
+            self.min[i] = self.min[i].min(value);
+            self.max[i] = self.max[i].max(value);
+        }
+    }
+
+    fn sample(&self, i: usize) -> Sample {
+        Sample {
+            min: self.min[i],
+            avg: self.sum[i] / self.count[i] as f32,
+            max: self.max[i],
+        }
+    }
+}
+
+/// Pushes the values of metrics sampled together. Values added less than `interval` after the
+/// first one of the bucket are aggregated in the newest point instead of pushing one.
+pub fn push_values<const N: usize, const M: usize>(
+    times: &mut CircularBuffer<N, Duration>,
+    metrics: [&mut CircularBuffer<N, Sample>; M],
+    bucket: &mut Option<Bucket<M>>,
+    values: [f32; M],
+    now: Duration,
+    interval: Duration,
+) {
+    match bucket {
+        Some(bucket) if now < bucket.start + interval => {
+            bucket.add(values);
+            for (i, metric) in metrics.into_iter().enumerate() {
+                metric.set_last(bucket.sample(i));
+            }
+        }
+        _ => {
+            times.push(now);
+            for (metric, value) in metrics.into_iter().zip(values) {
+                metric.push(Sample::new(value));
+            }
+            *bucket = Some(Bucket::new(now, values));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn aggregate_samples() {
+        let mut times: CircularBuffer<4, Duration> = CircularBuffer::default();
+        let mut values: CircularBuffer<4, Sample> = CircularBuffer::default();
+        let mut bucket = None;
+        let interval = Duration::from_millis(100);
+        let mut push = |value: f32, millis: u64| {
+            push_values(
+                &mut times,
+                [&mut values],
+                &mut bucket,
+                [value],
+                Duration::from_millis(millis),
+                interval,
+            );
+        };
+
+        push(1., 0);
+        push(3., 50);
+        push(8., 99);
+        push(4., 100);
+
+        assert_eq!(times.values(), vec![Duration::ZERO, Duration::from_millis(100)]);
+        let expected = Sample { min: 1., avg: 4., max: 8. };
+        assert_eq!(values.values(), vec![expected, Sample::new(4.)]);
+    }
+
+    #[test]
+    fn aggregate_missing_samples() {
+        let mut times: CircularBuffer<4, Duration> = CircularBuffer::default();
+        let mut values: CircularBuffer<4, Sample> = CircularBuffer::default();
+        let mut bucket = None;
+        let interval = Duration::from_millis(100);
+        for (value, millis) in [(f32::NAN, 0), (2., 50), (f32::NAN, 100)] {
+            push_values(
+                &mut times,
+                [&mut values],
+                &mut bucket,
+                [value],
+                Duration::from_millis(millis),
+                interval,
+            );
+        }
+
+        let values = values.values();
+        assert_eq!(values[0], Sample::new(2.));
+        assert!(values[1].avg.is_nan());
+    }
+
+    #[test]
+    fn no_interval() {
+        let mut times: CircularBuffer<4, Duration> = CircularBuffer::default();
+        let mut values: CircularBuffer<4, Sample> = CircularBuffer::default();
+        let mut bucket = None;
+        for value in [1., 2., 3.] {
+            push_values(&mut times, [&mut values], &mut bucket, [value], Duration::ZERO, Duration::ZERO);
+        }
+
+        assert_eq!(values.values(), vec![Sample::new(1.), Sample::new(2.), Sample::new(3.)]);
+    }
+}
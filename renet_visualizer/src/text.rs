@@ -0,0 +1,219 @@
+use std::fmt::Write;
+
+use renet::ClientId;
+
+use crate::{format_uptime, IncidentMetric, RenetClientVisualizer, RenetServerVisualizer, CHANNEL_MEMORY_ALERT};
+
+// From the lowest to the highest value
+const SPARKLINE_LEVELS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+impl<const N: usize> RenetClientVisualizer<N> {
+    /// Renders the latest value of each metric with a sparkline of its last `width` samples,
+    /// and the latest traffic of each channel. Values above their threshold, and buffered bytes
+    /// above 80% of the memory limit of the channel, are marked with a `!`.
+    ///
+    /// Available without the `egui` feature, for periodic logging or a terminal dashboard.
+    ///
+    /// # Usage
+    /// ```
+    /// # use renet_visualizer::RenetClientVisualizer;
+    /// # let visualizer = RenetClientVisualizer::<200>::default();
+    /// println!("{}", visualizer.render_text(40));
+    /// ```
+    pub fn render_text(&self, width: usize) -> String {
+        let mut out = String::new();
+        let metrics = [
+            IncidentMetric::Rtt,
+            IncidentMetric::PacketLoss,
+            IncidentMetric::SentKbps,
+            IncidentMetric::ReceivedKbps,
+        ];
+        for metric in metrics {
+            let samples = self.metric_values(metric).values();
+            let values: Vec<f32> = samples.iter().map(|sample| sample.avg).collect();
+            let last = match samples.last() {
+                Some(sample) => {
+                    let exceeded = self.style.thresholds.get(metric).is_some_and(|threshold| sample.max > threshold);
+                    format!("{}{}", metric.format(sample.avg), if exceeded { " !" } else { "  " })
+                }
+                None => "-  ".to_string(),
+            };
+            let _ = writeln!(out, "{:<16}{:>12} {}", metric.label(), last, sparkline(&values, width));
+        }
+
+        let mut rows = vec![];
+        if self.direction_stats {
+            rows.push(("Upstream Loss", &self.upstream_packet_loss, 100., "%"));
+            rows.push(("Downstream Loss", &self.downstream_packet_loss, 100., "%"));
+            rows.push(("Jitter", &self.jitter, 1., " ms"));
+        }
+        if self.transport_stats.is_some() {
+            rows.push(("Transport RTT", &self.transport_rtt, 1., " ms"));
+            rows.push(("Transport Queue", &self.transport_pending_bytes, 1., " B"));
+        }
+        for (label, buffer, scale, unit) in rows {
+            let samples = buffer.values();
+            let values: Vec<f32> = samples.iter().map(|sample| sample.avg).collect();
+            let last = match samples.last().filter(|sample| !sample.avg.is_nan()) {
+                Some(sample) => format!("{:.1}{unit}  ", sample.avg * scale),
+                None => "-  ".to_string(),
+            };
+            let _ = writeln!(out, "{:<16}{:>12} {}", label, last, sparkline(&values, width));
+        }
+        if let Some(transport_stats) = self.transport_stats {
+            let route = if transport_stats.relayed { "relayed" } else { "direct" };
+            let _ = writeln!(out, "Transport: quality {:.0}%, {route}", transport_stats.connection_quality * 100.);
+        }
+
+        for (channel_id, graphs) in self.channels.iter() {
+            let label = match self.channel_names.get(channel_id) {
+                Some(name) => format!("Channel {channel_id}: {name}"),
+                None => format!("Channel {channel_id}"),
+            };
+            let buffered_bytes = graphs.buffered_bytes.last().avg;
+            let (limit, alert) = match graphs.max_memory_usage_bytes {
+                0 => (String::new(), ""),
+                limit if buffered_bytes > limit as f32 * CHANNEL_MEMORY_ALERT => (format!("/{limit}"), " !"),
+                limit => (format!("/{limit}"), ""),
+            };
+            let _ = writeln!(
+                out,
+                "{label}: sent {:.1} Kbitps, received {:.1} Kbitps, buffered {:.0}{limit} bytes{alert}, in flight {:.0} bytes",
+                graphs.sent_bandwidth_kbps.last().avg,
+                graphs.received_bandwidth_kbps.last().avg,
+                buffered_bytes,
+                graphs.bytes_in_flight.last().avg
+            );
+        }
+        out
+    }
+}
+
+impl<const N: usize> RenetServerVisualizer<N> {
+    /// Renders a table with the latest metrics of each client, filtered and sorted like the
+    /// [`overview_rows`](Self::overview_rows), with a sparkline of the last `width` RTT samples.
+    ///
+    /// Available without the `egui` feature, for periodic logging or a terminal dashboard.
+    ///
+    /// # Usage
+    /// ```
+    /// # use renet_visualizer::RenetServerVisualizer;
+    /// # let visualizer = RenetServerVisualizer::<200>::default();
+    /// println!("{}", visualizer.render_text(20));
+    /// ```
+    pub fn render_text(&self, width: usize) -> String {
+        let mut out = String::new();
+        let _ = writeln!(
+            out,
+            "{:>10} {:>9} {:>7} {:>12} {:>16} {:>9} {:>9}  RTT history",
+            "Client", "RTT (ms)", "Loss", "Sent Kbitps", "Received Kbitps", "Buffered", "Uptime"
+        );
+        for row in self.overview_rows() {
+            let rtt: Vec<f32> = self.clients[&row.client_id]
+                .metric_values(IncidentMetric::Rtt)
+                .values()
+                .iter()
+                .map(|sample| sample.avg)
+                .collect();
+            let _ = writeln!(
+                out,
+                "{:>10} {:>9.0} {:>6.1}% {:>12.1} {:>16.1} {:>9.0} {:>9}  {}",
+                row.client_id,
+                row.rtt,
+                row.packet_loss * 100.,
+                row.sent_kbps,
+                row.received_kbps,
+                row.buffered_bytes,
+                format_uptime(row.uptime),
+                sparkline(&rtt, width)
+            );
+        }
+        out
+    }
+
+    /// Renders the metrics of a single client, see [`RenetClientVisualizer::render_text`].
+    /// Returns None if the client is not tracked.
+    pub fn render_client_text(&self, client_id: ClientId, width: usize) -> Option<String> {
+        self.clients.get(&client_id).map(|client| client.render_text(width))
+    }
+}
+
+// The last `width` values with the newest on the right, scaled from zero to the highest one
+fn sparkline(values: &[f32], width: usize) -> String {
+    let values = &values[values.len().saturating_sub(width)..];
+    let max = values.iter().copied().filter(|value| value.is_finite()).fold(0.0, f32::max);
+    let mut line = " ".repeat(width - values.len());
+    for value in values {
+        let level = if max > 0.0 && value.is_finite() { (value / max * (SPARKLINE_LEVELS.len() - 1) as f32).round() as usize } else { 0 };
+        line.push(SPARKLINE_LEVELS[level.min(SPARKLINE_LEVELS.len() - 1)]);
+    }
+    line
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use renet::{ChannelNetworkInfo, NetworkInfo};
+
+    use super::*;
+    use crate::{RenetVisualizerStyle, Thresholds};
+
+    #[test]
+    fn render_sparkline() {
+        assert_eq!(sparkline(&[0., 1., 2., 3., 4., 5., 6., 7.], 8), "▁▂▃▄▅▆▇█");
+        assert_eq!(sparkline(&[0., 2., 14.], 2), "▂█");
+        assert_eq!(sparkline(&[5.], 3), "  █");
+        assert_eq!(sparkline(&[0., f32::NAN], 2), "▁▁");
+        assert_eq!(sparkline(&[], 0), "");
+    }
+
+    #[test]
+    fn render_server_text() {
+        let style = RenetVisualizerStyle {
+            thresholds: Thresholds {
+                rtt_ms: Some(150.),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let mut visualizer = RenetServerVisualizer::<5>::new(style);
+        for (client_id, rtt) in [(2, 0.2), (1, 0.05)] {
+            visualizer.add_client(client_id);
+            let network_info = NetworkInfo {
+                rtt,
+                packet_loss: 0.25,
+                receive_packet_loss: 0.1,
+                jitter: 0.004,
+                bytes_sent_per_second: 1000.,
+                bytes_received_per_second: 0.,
+            };
+            visualizer.add_network_info(client_id, network_info, Duration::from_secs(1));
+        }
+
+        let text = visualizer.render_text(3);
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].trim_start().starts_with("Client"));
+        assert!(lines[1].trim_start().starts_with("1        50   25.0%          8.0"));
+        assert!(lines[2].ends_with("  █"));
+
+        let channel_info = ChannelNetworkInfo {
+            channel_id: 0,
+            buffered_bytes: 900,
+            max_memory_usage_bytes: 1000,
+            bytes_in_flight: 400,
+            ..Default::default()
+        };
+        visualizer.add_channel_network_info(2, channel_info, Duration::from_secs(1));
+        let client_text = visualizer.render_client_text(2, 3).unwrap();
+        assert_eq!(
+            client_text.lines().last(),
+            Some("Channel 0: sent 0.0 Kbitps, received 0.0 Kbitps, buffered 900/1000 bytes !, in flight 400 bytes")
+        );
+        assert_eq!(client_text.lines().next(), Some("RTT                 200 ms !   █"));
+        assert!(client_text.contains("Upstream Loss        10.0%     █\n"));
+        assert!(client_text.contains("Jitter              4.0 ms     █\n"));
+        assert_eq!(visualizer.render_client_text(3, 3), None);
+    }
+}
@@ -0,0 +1,779 @@
+use std::{ops::Range, time::Duration};
+
+use egui::{
+    epaint::{PathShape, RectShape},
+    pos2, remap, vec2, Color32, CornerRadius, Rect, Rgba, RichText, Sense, Shape, Stroke, StrokeKind, TextStyle, Vec2, WidgetText,
+};
+use renet::ClientId;
+
+use crate::{
+    circular_buffer::CircularBuffer, format_uptime, sample::Sample, IncidentMetric, OverviewColumn, RenetClientVisualizer,
+    RenetServerVisualizer, RenetVisualizerStyle, CHANNEL_MEMORY_ALERT,
+};
+
+enum TopValue {
+    SuggestedValues([f32; 5]),
+    MaxValue { multiplicated: f32 },
+}
+
+#[derive(Clone, Copy)]
+enum TextFormat {
+    Percentage,
+    Normal,
+}
+
+// Samples of a graph, from the oldest to the newest
+struct GraphData {
+    // Id of the visualizer, with the label it keys the zoom and pan of the graph
+    graph_id: u64,
+    values: Vec<Sample>,
+    times: Vec<Duration>,
+    capacity: usize,
+    paused_at: Option<Duration>,
+    threshold: Option<f32>,
+    // Drawn as a reference line, the graph is scaled to show it
+    limit: Option<f32>,
+    // Shown when hovering the label
+    tooltip: Option<&'static str>,
+    // Second metric drawn dashed over the values, with the same times
+    overlay: Option<Vec<Sample>>,
+}
+
+// Zoom and pan of a graph, kept in the egui memory
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct GraphView {
+    // Number of samples shown
+    visible: f32,
+    // Number of samples between the newest shown and the newest one
+    offset: f32,
+}
+
+const MIN_VISIBLE_SAMPLES: f32 = 10.;
+
+/// Style of the compact graph of a client, see [`RenetClientVisualizer::draw_compact`].
+#[derive(Debug, Clone)]
+pub struct CompactStyle {
+    pub text_color: Color32,
+    pub rectangle_stroke: Stroke,
+    pub line_width: f32,
+    /// Metrics drawn in the graph with the color of their line, each one scaled to its own range.
+    pub metrics: Vec<(IncidentMetric, Color32)>,
+    /// Draws the latest value of each metric at the end of its line.
+    pub show_labels: bool,
+    /// Draws the name of each metric in the color of its line above the graph.
+    pub show_legend: bool,
+}
+
+impl Default for CompactStyle {
+    fn default() -> Self {
+        Self {
+            text_color: Color32::WHITE,
+            rectangle_stroke: Stroke::new(1., Color32::WHITE),
+            line_width: 1.,
+            metrics: vec![
+                (IncidentMetric::Rtt, Color32::LIGHT_BLUE),
+                (IncidentMetric::PacketLoss, Color32::GOLD),
+                (IncidentMetric::SentKbps, Color32::LIGHT_GREEN),
+                (IncidentMetric::ReceivedKbps, Color32::ORANGE),
+            ],
+            show_labels: true,
+            show_legend: true,
+        }
+    }
+}
+
+impl<const N: usize> RenetClientVisualizer<N> {
+    /// Draws a checkbox to pause the graphs.
+    pub fn draw_pause_toggle(&mut self, ui: &mut egui::Ui) {
+        let mut paused = self.is_paused();
+        if ui.checkbox(&mut paused, "Pause").changed() {
+            self.set_paused(paused);
+        }
+    }
+
+    fn graph_data(&self, values: &CircularBuffer<N, Sample>, sample_times: &CircularBuffer<N, Duration>) -> GraphData {
+        GraphData {
+            graph_id: self.graph_id,
+            values: values.values(),
+            times: sample_times.values(),
+            capacity: N,
+            paused_at: self.paused_at,
+            threshold: None,
+            limit: None,
+            tooltip: None,
+            overlay: None,
+        }
+    }
+
+    fn metric_graph_data(&self, metric: IncidentMetric) -> GraphData {
+        GraphData {
+            threshold: self.style.thresholds.get(metric),
+            ..self.graph_data(self.metric_values(metric), &self.sample_times)
+        }
+    }
+
+    /// Renders a new window with all the graphs metrics drawn.
+    pub fn show_window(&mut self, ctx: &egui::Context) {
+        egui::Window::new("Client Network Info")
+            .resizable(false)
+            .collapsible(true)
+            .show(ctx, |ui| {
+                self.draw_pause_toggle(ui);
+                ui.horizontal(|ui| {
+                    self.draw_all(ui);
+                });
+                self.draw_channels(ui);
+                let incidents_count = self.incidents().len();
+                egui::CollapsingHeader::new(format!("Incidents ({incidents_count})")).show(ui, |ui| {
+                    self.draw_incidents(ui);
+                });
+            });
+    }
+
+    /// Draws only the Received Kilobits Per Second metric.
+    pub fn draw_received_kbps(&self, ui: &mut egui::Ui) {
+        show_graph(
+            ui,
+            &self.style,
+            "Received Kbitps",
+            TextFormat::Normal,
+            metric_top_value(IncidentMetric::ReceivedKbps),
+            self.metric_graph_data(IncidentMetric::ReceivedKbps),
+        );
+    }
+
+    /// Draws only the Sent Kilobits Per Second metric.
+    pub fn draw_sent_kbps(&self, ui: &mut egui::Ui) {
+        show_graph(
+            ui,
+            &self.style,
+            "Sent Kbitps",
+            TextFormat::Normal,
+            metric_top_value(IncidentMetric::SentKbps),
+            self.metric_graph_data(IncidentMetric::SentKbps),
+        );
+    }
+
+    /// Draws only the Packet Loss metric.
+    pub fn draw_packet_loss(&self, ui: &mut egui::Ui) {
+        show_graph(
+            ui,
+            &self.style,
+            "Packet Loss",
+            TextFormat::Percentage,
+            metric_top_value(IncidentMetric::PacketLoss),
+            self.metric_graph_data(IncidentMetric::PacketLoss),
+        );
+    }
+
+    /// Draws only the loss of the packets sent by the client to the server.
+    pub fn draw_upstream_packet_loss(&self, ui: &mut egui::Ui) {
+        show_graph(
+            ui,
+            &self.style,
+            "Upstream Packet Loss",
+            TextFormat::Percentage,
+            TopValue::SuggestedValues([0.05, 0.1, 0.25, 0.5, 1.]),
+            GraphData {
+                tooltip: Some("Packets sent by the client that the server did not receive"),
+                ..self.graph_data(&self.upstream_packet_loss, &self.sample_times)
+            },
+        );
+    }
+
+    /// Draws only the loss of the packets sent by the server to the client.
+    pub fn draw_downstream_packet_loss(&self, ui: &mut egui::Ui) {
+        show_graph(
+            ui,
+            &self.style,
+            "Downstream Packet Loss",
+            TextFormat::Percentage,
+            TopValue::SuggestedValues([0.05, 0.1, 0.25, 0.5, 1.]),
+            GraphData {
+                tooltip: Some("Packets sent by the server that the client did not receive"),
+                ..self.graph_data(&self.downstream_packet_loss, &self.sample_times)
+            },
+        );
+    }
+
+    /// Draws only the Jitter metric, the variation of the round time trip.
+    pub fn draw_jitter(&self, ui: &mut egui::Ui) {
+        show_graph(
+            ui,
+            &self.style,
+            "Jitter (ms)",
+            TextFormat::Normal,
+            TopValue::SuggestedValues([8., 16., 32., 64., 128.]),
+            GraphData {
+                tooltip: Some("Variation of the round time trip"),
+                ..self.graph_data(&self.jitter, &self.sample_times)
+            },
+        );
+    }
+
+    /// Draws only the Round Time Trip metric.
+    pub fn draw_rtt(&self, ui: &mut egui::Ui) {
+        show_graph(
+            ui,
+            &self.style,
+            "Round Time Trip (ms)",
+            TextFormat::Normal,
+            metric_top_value(IncidentMetric::Rtt),
+            GraphData {
+                tooltip: self.transport_stats.map(|_| "Dashed: RTT measured by the transport"),
+                overlay: self.transport_stats.map(|_| self.transport_rtt.values()),
+                ..self.metric_graph_data(IncidentMetric::Rtt)
+            },
+        );
+    }
+
+    /// Draws only the bytes waiting in the buffers of the transport, with its connection quality and route.
+    /// Requires the stats of [`add_transport_stats`](Self::add_transport_stats).
+    pub fn draw_transport_stats(&self, ui: &mut egui::Ui) {
+        let Some(transport_stats) = self.transport_stats else {
+            return;
+        };
+        show_graph(
+            ui,
+            &self.style,
+            "Transport Pending Bytes",
+            TextFormat::Normal,
+            TopValue::MaxValue { multiplicated: 1.5 },
+            GraphData {
+                tooltip: Some("Bytes waiting to be sent in the buffers of the transport"),
+                ..self.graph_data(&self.transport_pending_bytes, &self.sample_times)
+            },
+        );
+        ui.vertical(|ui| {
+            let quality = format!("Quality {:.0}%", transport_stats.connection_quality * 100.);
+            ui.label(RichText::new(quality).color(self.style.text_color));
+            let route = if transport_stats.relayed { "Relayed" } else { "Direct" };
+            ui.label(RichText::new(route).color(self.style.text_color));
+        });
+    }
+
+    /// Draw all metrics without a window or layout.
+    /// The per direction loss and jitter are hidden when replaying recordings without them,
+    /// and the transport stats when they are not added.
+    pub fn draw_all(&self, ui: &mut egui::Ui) {
+        self.draw_received_kbps(ui);
+        self.draw_sent_kbps(ui);
+        self.draw_rtt(ui);
+        self.draw_packet_loss(ui);
+        if self.direction_stats {
+            self.draw_upstream_packet_loss(ui);
+            self.draw_downstream_packet_loss(ui);
+            self.draw_jitter(ui);
+        }
+        self.draw_transport_stats(ui);
+    }
+
+    /// Draws the metrics of the [`CompactStyle`] overlaid in a single graph of the given size in the current ui,
+    /// to embed it in other layouts like an options screen. Each metric is scaled to its own range.
+    ///
+    /// # Usage
+    /// ```no_run
+    /// # use renet_visualizer::{CompactStyle, IncidentMetric, RenetClientVisualizer};
+    /// # let visualizer = RenetClientVisualizer::<200>::default();
+    /// # let ui: &mut egui::Ui = todo!();
+    /// let style = CompactStyle {
+    ///     metrics: vec![(IncidentMetric::Rtt, egui::Color32::LIGHT_BLUE)],
+    ///     show_legend: false,
+    ///     ..Default::default()
+    /// };
+    /// visualizer.draw_compact(ui, egui::vec2(160., 40.), &style);
+    /// ```
+    pub fn draw_compact(&self, ui: &mut egui::Ui, size: Vec2, style: &CompactStyle) {
+        let (outer_rect, _) = ui.allocate_exact_size(size, Sense::hover());
+        let painter = ui.painter().with_clip_rect(outer_rect);
+        let spacing = ui.spacing().item_spacing;
+
+        let mut rect = outer_rect;
+        if style.show_legend {
+            let mut x = outer_rect.left();
+            let mut legend_height: f32 = 0.;
+            for (metric, color) in style.metrics.iter() {
+                let text: WidgetText = metric.label().into();
+                let galley = text.into_galley(ui, Some(egui::TextWrapMode::Extend), f32::INFINITY, TextStyle::Small);
+                legend_height = legend_height.max(galley.size().y);
+                let width = galley.size().x;
+                painter.galley(pos2(x, outer_rect.top()), galley, *color);
+                x += width + spacing.x;
+            }
+            rect.min.y += legend_height + spacing.y;
+        }
+        painter.add(RectShape::stroke(
+            rect,
+            CornerRadius::ZERO,
+            style.rectangle_stroke,
+            StrokeKind::Inside,
+        ));
+
+        let mut view = GraphView {
+            visible: N as f32,
+            offset: 0.,
+        };
+        let range = visible_range(&self.sample_times.values(), self.paused_at, &mut view);
+        for (metric, color) in style.metrics.iter() {
+            let values = self.metric_values(*metric).values();
+            let values = &values[range.clone()];
+            let max = graph_max(values, metric_top_value(*metric));
+            // The newest sample is on the right edge
+            let first_slot = N - values.len();
+            let point = |i: usize, value: f32| {
+                let x = remap((first_slot + i) as f32, 0.0..=(N - 1).max(1) as f32, rect.left()..=rect.right());
+                let y = if max == 0.0 { rect.bottom() } else { remap(value, 0.0..=max, rect.bottom()..=rect.top()) };
+                pos2(x, y)
+            };
+            let points = values.iter().enumerate().map(|(i, sample)| point(i, sample.avg)).collect();
+            painter.add(PathShape::line(points, Stroke::new(style.line_width, *color)));
+
+            if let Some(last) = values.last().filter(|_| style.show_labels) {
+                let text: WidgetText = metric.format(last.avg).into();
+                let galley = text.into_galley(ui, Some(egui::TextWrapMode::Extend), f32::INFINITY, TextStyle::Small);
+                let last_point = point(values.len() - 1, last.avg);
+                let y = (last_point.y - galley.size().y).clamp(rect.top(), (rect.bottom() - galley.size().y).max(rect.top()));
+                let text_pos = pos2(rect.right() - galley.size().x - spacing.x, y);
+                painter.galley(text_pos, galley, *color);
+            }
+        }
+    }
+
+    /// Draws the Kbitps Sent/Received, buffered bytes and reliable bytes in flight of each channel,
+    /// in a collapsing header to toggle them. The buffered bytes are drawn against the memory limit of the channel,
+    /// highlighted above 80% of it.
+    pub fn draw_channels(&self, ui: &mut egui::Ui) {
+        for (channel_id, graphs) in self.channels.iter() {
+            let label = match self.channel_names.get(channel_id) {
+                Some(name) => format!("Channel {channel_id}: {name}"),
+                None => format!("Channel {channel_id}"),
+            };
+            egui::CollapsingHeader::new(label).id_salt(channel_id).show(ui, |ui| {
+                ui.horizontal(|ui| {
+                    let memory_limit = Some(graphs.max_memory_usage_bytes as f32).filter(|&limit| limit > 0.);
+                    let metrics = [
+                        ("Received Kbitps", &graphs.received_bandwidth_kbps, None),
+                        ("Sent Kbitps", &graphs.sent_bandwidth_kbps, None),
+                        ("Buffered Bytes", &graphs.buffered_bytes, memory_limit),
+                        ("Bytes In Flight", &graphs.bytes_in_flight, None),
+                    ];
+                    for (label, values, limit) in metrics {
+                        let top_value = TopValue::MaxValue { multiplicated: 1.5 };
+                        let data = GraphData {
+                            threshold: limit.map(|limit| limit * CHANNEL_MEMORY_ALERT),
+                            limit,
+                            ..self.graph_data(values, &graphs.sample_times)
+                        };
+                        show_graph(ui, &self.style, label, TextFormat::Normal, top_value, data);
+                    }
+                });
+            });
+        }
+    }
+}
+
+impl<const N: usize> RenetServerVisualizer<N> {
+    /// Draws a checkbox to pause the graphs of all clients.
+    pub fn draw_pause_toggle(&mut self, ui: &mut egui::Ui) {
+        let mut paused = self.is_paused();
+        if ui.checkbox(&mut paused, "Pause").changed() {
+            self.set_paused(paused);
+        }
+    }
+
+    /// Draw all metrics without a window or layout for the specified client.
+    pub fn draw_client_metrics(&self, client_id: ClientId, ui: &mut egui::Ui) {
+        if let Some(client) = self.clients.get(&client_id) {
+            client.draw_all(ui);
+        }
+    }
+
+    /// Draw the metrics of each channel without a window for the specified client.
+    pub fn draw_client_channels(&self, client_id: ClientId, ui: &mut egui::Ui) {
+        if let Some(client) = self.clients.get(&client_id) {
+            client.draw_channels(ui);
+        }
+    }
+
+    /// Draws the compact graph of the specified client in the current ui, see [`RenetClientVisualizer::draw_compact`].
+    /// The space is still allocated if the client is not tracked, to keep the layout of the caller.
+    pub fn draw_client_compact(&self, client_id: ClientId, ui: &mut egui::Ui, size: Vec2, style: &CompactStyle) {
+        match self.clients.get(&client_id) {
+            Some(client) => client.draw_compact(ui, size, style),
+            None => {
+                ui.allocate_exact_size(size, Sense::hover());
+            }
+        }
+    }
+
+    /// Renders a new window with all the graphs metrics drawn. You can choose to show metrics for
+    /// all connected clients or for only one chosen by a dropdown.
+    pub fn show_window(&mut self, ctx: &egui::Context) {
+        egui::Window::new("Server Network Info")
+            .resizable(false)
+            .collapsible(true)
+            .show(ctx, |ui| {
+                self.draw_all(ui);
+                let incidents_count = self.incidents().len();
+                egui::CollapsingHeader::new(format!("Incidents ({incidents_count})")).show(ui, |ui| {
+                    self.draw_incidents(ui);
+                });
+            });
+    }
+
+    /// Draws the client selection and the metrics of the selected clients without a window.
+    pub fn draw_all(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            self.draw_pause_toggle(ui);
+            ui.checkbox(&mut self.show_all_clients, "Show all clients");
+            ui.add_enabled_ui(!self.show_all_clients, |ui| {
+                let selected_text = match self.selected_client {
+                    Some(client_id) => format!("{}", client_id),
+                    None => "------".to_string(),
+                };
+                egui::ComboBox::from_label("Select client")
+                    .selected_text(selected_text)
+                    .show_ui(ui, |ui| {
+                        for client_id in self.clients.keys() {
+                            ui.selectable_value(&mut self.selected_client, Some(*client_id), format!("{}", client_id));
+                        }
+                    })
+            });
+        });
+        ui.vertical(|ui| {
+            if self.show_all_clients {
+                for (client_id, client) in self.clients.iter() {
+                    ui.vertical(|ui| {
+                        ui.heading(format!("Client {}", client_id));
+                        ui.horizontal(|ui| {
+                            client.draw_all(ui);
+                        });
+                        client.draw_channels(ui);
+                    });
+                }
+            } else if let Some(selected_client) = self.selected_client {
+                if let Some(client) = self.clients.get(&selected_client) {
+                    ui.horizontal(|ui| {
+                        client.draw_all(ui);
+                    });
+                    client.draw_channels(ui);
+                }
+            }
+        });
+    }
+
+    /// Draws a compact table with one row per client, for servers with many clients.
+    /// Click a header to sort by its column, and a client to show its graphs below the table.
+    pub fn draw_overview(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.label("Filter client id");
+            ui.text_edit_singleline(&mut self.overview.filter);
+            self.draw_pause_toggle(ui);
+        });
+
+        let rows = self.overview_rows();
+        let columns = [
+            (OverviewColumn::ClientId, "Client"),
+            (OverviewColumn::Rtt, "RTT (ms)"),
+            (OverviewColumn::PacketLoss, "Loss"),
+            (OverviewColumn::SentKbps, "Sent Kbitps"),
+            (OverviewColumn::ReceivedKbps, "Received Kbitps"),
+            (OverviewColumn::BufferedBytes, "Buffered"),
+            (OverviewColumn::Uptime, "Uptime"),
+        ];
+        egui::ScrollArea::vertical().max_height(self.style.height * 3.).show(ui, |ui| {
+            egui::Grid::new("renet_server_overview").striped(true).show(ui, |ui| {
+                for (column, label) in columns {
+                    let label = match (self.overview.sort_column == column, self.overview.descending) {
+                        (true, false) => format!("{label} ^"),
+                        (true, true) => format!("{label} v"),
+                        (false, _) => label.to_string(),
+                    };
+                    if ui.button(RichText::new(label).color(self.style.text_color)).clicked() {
+                        self.sort_overview_by(column);
+                    }
+                }
+                ui.end_row();
+
+                for row in rows.iter() {
+                    let expanded = self.overview.expanded_client == Some(row.client_id);
+                    if ui.selectable_label(expanded, row.client_id.to_string()).clicked() {
+                        self.overview.expanded_client = if expanded { None } else { Some(row.client_id) };
+                    }
+                    ui.label(format!("{:.0}", row.rtt));
+                    ui.label(format!("{:.1}%", row.packet_loss * 100.));
+                    ui.label(format!("{:.1}", row.sent_kbps));
+                    ui.label(format!("{:.1}", row.received_kbps));
+                    ui.label(format!("{:.0}", row.buffered_bytes));
+                    ui.label(format_uptime(row.uptime));
+                    ui.end_row();
+                }
+            });
+        });
+
+        if let Some(client_id) = self.overview.expanded_client {
+            ui.heading(format!("Client {}", client_id));
+            ui.horizontal(|ui| {
+                self.draw_client_metrics(client_id, ui);
+            });
+            self.draw_client_channels(client_id, ui);
+        }
+    }
+}
+
+fn show_graph(ui: &mut egui::Ui, style: &RenetVisualizerStyle, label: &str, text_format: TextFormat, top_value: TopValue, data: GraphData) {
+    ui.vertical(|ui| {
+        let label_response = ui.label(RichText::new(label).heading().color(style.text_color));
+        if let Some(tooltip) = data.tooltip {
+            label_response.on_hover_text(tooltip);
+        }
+
+        let id = ui.make_persistent_id((data.graph_id, label));
+        let default_view = GraphView {
+            visible: data.capacity as f32,
+            offset: 0.,
+        };
+        let mut view = ui.data(|data| data.get_temp::<GraphView>(id)).unwrap_or(default_view);
+        let range = visible_range(&data.times, data.paused_at, &mut view);
+        let values = &data.values[range.clone()];
+        let overlay = data.overlay.as_ref().map(|overlay| &overlay[range.clone()]);
+        let times = &data.times[range];
+
+        let min = 0.0;
+        let mut max = match overlay {
+            Some(overlay) => graph_max(&[values, overlay].concat(), top_value),
+            None => graph_max(values, top_value),
+        };
+        if let Some(limit) = data.limit {
+            max = max.max(limit * 1.1);
+        }
+
+        let spacing_x = ui.spacing().item_spacing.x;
+
+        let last_text: WidgetText = match (text_format, values.last()) {
+            (_, None) => "-".into(),
+            (TextFormat::Normal, Some(last_value)) => format!("{:.2}", last_value.avg).into(),
+            (TextFormat::Percentage, Some(last_value)) => format!("{:.1}%", last_value.avg * 100.).into(),
+        };
+        let galley = last_text.into_galley(ui, Some(egui::TextWrapMode::Wrap), f32::INFINITY, TextStyle::Button);
+        let (outer_rect, response) = ui.allocate_exact_size(
+            Vec2::new(style.width + galley.size().x + spacing_x, style.height),
+            Sense::click_and_drag(),
+        );
+        let rect = Rect::from_min_size(outer_rect.left_top(), vec2(style.width, style.height));
+        let text_pos = rect.right_center() + vec2(spacing_x / 2.0, -galley.size().y / 2.);
+        let exceeded = |sample: &Sample| data.threshold.is_some_and(|threshold| sample.max > threshold);
+        let last_color = if values.last().is_some_and(exceeded) { style.alert_color } else { style.text_color };
+        ui.painter().with_clip_rect(outer_rect).galley(text_pos, galley, last_color);
+
+        let body = Shape::Rect(RectShape {
+            rect,
+            fill: Rgba::TRANSPARENT.into(),
+            stroke: style.rectangle_stroke,
+            stroke_kind: StrokeKind::Inside,
+            blur_width: 0.0,
+            corner_radius: CornerRadius::ZERO,
+            round_to_pixels: None,
+            brush: None,
+        });
+        ui.painter().add(body);
+        let init_point = rect.left_bottom();
+
+        // The newest sample is on the right, with less samples than the visible ones the line starts in the middle
+        let slots = (view.visible.round() as usize).max(values.len());
+        let first_slot = slots - values.len();
+        let point = |i: usize, value: f32| {
+            let x = remap((first_slot + i) as f32, 0.0..=slots as f32, 0.0..=style.width);
+            let y = if max == 0.0 { 0.0 } else { remap(value, min..=max, 0.0..=style.height) };
+
+            pos2(x + init_point.x, init_point.y - y)
+        };
+        // Aggregated points also show the range of their samples
+        let range_stroke = Stroke::new(style.line_stroke.width, style.line_stroke.color.gamma_multiply(0.4));
+        for (i, sample) in values.iter().enumerate().filter(|(_, sample)| sample.max > sample.min) {
+            ui.painter()
+                .line_segment([point(i, sample.min), point(i, sample.max)], range_stroke);
+        }
+
+        if let Some(limit) = data.limit {
+            let y = point(0, limit).y;
+            ui.painter()
+                .hline(rect.x_range(), y, Stroke::new(style.line_stroke.width, style.alert_color));
+        }
+
+        if let Some(threshold) = data.threshold {
+            // Background band behind the points above the threshold
+            let slot_width = style.width / slots as f32;
+            let band_color = style.alert_color.gamma_multiply(0.2);
+            for (i, _) in values.iter().enumerate().filter(|(_, sample)| exceeded(sample)) {
+                let x = point(i, 0.0).x;
+                let band = Rect::from_x_y_ranges((x - slot_width / 2.)..=(x + slot_width / 2.), rect.y_range()).intersect(rect);
+                ui.painter().rect_filled(band, CornerRadius::ZERO, band_color);
+            }
+            if threshold <= max {
+                let y = point(0, threshold).y;
+                let stroke = Stroke::new(style.line_stroke.width, style.alert_color.gamma_multiply(0.5));
+                ui.painter().hline(rect.x_range(), y, stroke);
+            }
+
+            let alert_stroke = Stroke::new(style.line_stroke.width, style.alert_color);
+            for (i, pair) in values.windows(2).enumerate() {
+                let stroke = if pair.iter().any(exceeded) { alert_stroke } else { style.line_stroke };
+                ui.painter()
+                    .line_segment([point(i, pair[0].avg), point(i + 1, pair[1].avg)], stroke);
+            }
+        } else {
+            // Missing samples are NaN, the line is split around them
+            let points: Vec<_> = values.iter().enumerate().map(|(i, sample)| point(i, sample.avg)).collect();
+            for points in points.split(|point| point.y.is_nan()) {
+                ui.painter().add(PathShape::line(points.to_vec(), style.line_stroke));
+            }
+        }
+
+        if let Some(overlay) = overlay {
+            let points: Vec<_> = overlay.iter().enumerate().map(|(i, sample)| point(i, sample.avg)).collect();
+            for points in points.split(|point| point.y.is_nan()) {
+                ui.painter().extend(Shape::dashed_line(points, style.line_stroke, 4., 3.));
+            }
+        }
+
+        {
+            let text: WidgetText = match text_format {
+                TextFormat::Normal => format!("{:.0}", max).into(),
+                TextFormat::Percentage => format!("{:.0}%", max * 100.).into(),
+            };
+            let galley = text.into_galley(ui, Some(egui::TextWrapMode::Wrap), f32::INFINITY, TextStyle::Button);
+            let text_pos = rect.left_top() + Vec2::new(0.0, galley.size().y / 2.) + vec2(spacing_x, 0.0);
+            ui.painter().with_clip_rect(outer_rect).galley(text_pos, galley, style.text_color);
+        }
+        {
+            let text: WidgetText = match text_format {
+                TextFormat::Normal => format!("{:.0}", min).into(),
+                TextFormat::Percentage => format!("{:.0}%", min * 100.).into(),
+            };
+            let galley = text.into_galley(ui, Some(egui::TextWrapMode::Wrap), f32::INFINITY, TextStyle::Button);
+            let text_pos = rect.left_bottom() - Vec2::new(0.0, galley.size().y * 1.5) + vec2(spacing_x, 0.0);
+            ui.painter().with_clip_rect(outer_rect).galley(text_pos, galley, style.text_color);
+        }
+
+        if response.hovered() {
+            let scroll = ui.input(|input| input.smooth_scroll_delta.y);
+            if scroll != 0.0 {
+                let max_visible = data.capacity as f32;
+                view.visible = (view.visible * (1.0 - scroll * 0.002)).clamp(MIN_VISIBLE_SAMPLES.min(max_visible), max_visible);
+            }
+        }
+        if response.dragged() {
+            // Dragging to the right shows older samples
+            view.offset = (view.offset + response.drag_delta().x / style.width * slots as f32).max(0.0);
+        }
+        if response.double_clicked() {
+            view = default_view;
+        }
+
+        if let Some(pointer) = response.hover_pos().filter(|pointer| rect.contains(*pointer)) {
+            let slot = remap(pointer.x - rect.left(), 0.0..=style.width, 0.0..=slots as f32).round() as usize;
+            let hovered = slot
+                .checked_sub(first_slot)
+                .filter(|i| *i < values.len() && !values[*i].avg.is_nan());
+            if let Some(i) = hovered {
+                let sample = values[i];
+                let position = point(i, sample.avg);
+                let stroke = Stroke::new(style.line_stroke.width, style.text_color);
+                ui.painter().vline(position.x, rect.y_range(), stroke);
+                ui.painter().circle_filled(position, 2.5, style.text_color);
+
+                let format_value = |value: f32| match text_format {
+                    TextFormat::Normal => format!("{:.2}", value),
+                    TextFormat::Percentage => format!("{:.1}%", value * 100.),
+                };
+                let mut value = format_value(sample.avg);
+                if sample.max > sample.min {
+                    value = format!("{value} ({} - {})", format_value(sample.min), format_value(sample.max));
+                }
+                let newest = data.times.last().copied().unwrap_or_default();
+                let age = newest.saturating_sub(times[i]).as_secs_f32();
+                response.on_hover_text_at_pointer(format!("{value}\n{age:.2}s ago"));
+            }
+        }
+
+        ui.data_mut(|data| data.insert_temp(id, view));
+    });
+}
+
+fn metric_top_value(metric: IncidentMetric) -> TopValue {
+    match metric {
+        IncidentMetric::Rtt => TopValue::SuggestedValues([32., 64., 128., 256., 512.]),
+        IncidentMetric::PacketLoss => TopValue::SuggestedValues([0.05, 0.1, 0.25, 0.5, 1.]),
+        IncidentMetric::SentKbps | IncidentMetric::ReceivedKbps => TopValue::MaxValue { multiplicated: 1.5 },
+    }
+}
+
+// Top of the graph scale for the samples, the bottom is always zero
+fn graph_max(values: &[Sample], top_value: TopValue) -> f32 {
+    let mut max = values.iter().map(|sample| sample.max).fold(0.0, f32::max);
+    match top_value {
+        TopValue::MaxValue { multiplicated } => {
+            max *= multiplicated;
+        }
+        TopValue::SuggestedValues(suggested_values) => {
+            for value in suggested_values.into_iter() {
+                if max < value {
+                    max = value;
+                    break;
+                }
+            }
+        }
+    }
+    max
+}
+
+// Range of the samples shown by the view, also clamps the view offset to the history
+fn visible_range(times: &[Duration], paused_at: Option<Duration>, view: &mut GraphView) -> Range<usize> {
+    let newest = match paused_at {
+        Some(paused_at) => times.partition_point(|time| *time <= paused_at),
+        None => times.len(),
+    };
+    let visible = view.visible.round() as usize;
+    view.offset = view.offset.min(newest.saturating_sub(visible) as f32);
+
+    let end = newest - view.offset.round() as usize;
+    end.saturating_sub(visible)..end
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn graph_scale() {
+        let values = [Sample::new(10.), Sample::new(40.)];
+        assert_eq!(graph_max(&values, metric_top_value(IncidentMetric::Rtt)), 64.);
+        assert_eq!(graph_max(&values, metric_top_value(IncidentMetric::SentKbps)), 60.);
+        // Above the suggested values the highest sample is the top
+        assert_eq!(graph_max(&[Sample::new(2.)], metric_top_value(IncidentMetric::PacketLoss)), 2.);
+        assert_eq!(graph_max(&[], metric_top_value(IncidentMetric::Rtt)), 32.);
+    }
+
+    #[test]
+    fn graph_visible_range() {
+        let times: Vec<Duration> = (1..=20).map(Duration::from_secs).collect();
+        let mut view = GraphView { visible: 5., offset: 0. };
+        assert_eq!(visible_range(&times, None, &mut view), 15..20);
+
+        // Paused, the newer samples are not shown
+        assert_eq!(visible_range(&times, Some(Duration::from_secs(12)), &mut view), 7..12);
+
+        view.offset = 3.;
+        assert_eq!(visible_range(&times, None, &mut view), 12..17);
+
+        // The offset can't pan past the oldest sample
+        view.offset = 100.;
+        assert_eq!(visible_range(&times, None, &mut view), 0..5);
+        assert_eq!(view.offset, 15.);
+
+        let mut view = GraphView { visible: 200., offset: 0. };
+        assert_eq!(visible_range(&times, None, &mut view), 0..20);
+        assert_eq!(visible_range(&[], None, &mut view), 0..0);
+    }
+}
@@ -53,6 +53,8 @@ pub struct NetcodeClient {
     state: ClientState,
     client_id: u64,
     connect_start_time: Duration,
+    connect_begin_time: Duration,
+    connect_timeout: Option<Duration>,
     last_packet_send_time: Option<Duration>,
     last_packet_received_time: Duration,
     current_time: Duration,
@@ -66,6 +68,7 @@ pub struct NetcodeClient {
     client_index: u32,
     send_rate: Duration,
     replay_protection: ReplayProtection,
+    disconnect_payload: Option<Vec<u8>>,
     out: [u8; NETCODE_MAX_PACKET_BYTES],
 }
 
@@ -118,6 +121,8 @@ impl NetcodeClient {
             challenge_token_sequence: 0,
             state: ClientState::SendingConnectionRequest,
             connect_start_time: current_time,
+            connect_begin_time: current_time,
+            connect_timeout: None,
             last_packet_send_time: None,
             last_packet_received_time: current_time,
             current_time,
@@ -127,6 +132,7 @@ impl NetcodeClient {
             challenge_token_data: [0u8; NETCODE_CHALLENGE_TOKEN_BYTES],
             connect_token,
             replay_protection: ReplayProtection::new(),
+            disconnect_payload: None,
             out: [0u8; NETCODE_MAX_PACKET_BYTES],
         })
     }
@@ -160,6 +166,43 @@ impl NetcodeClient {
         self.current_time - self.last_packet_received_time
     }
 
+    /// Limits the total time to connect, including the retries with the next server addresses.
+    /// The connection also fails when the connect token expires or the server stops answering.
+    pub fn set_connect_timeout(&mut self, connect_timeout: Option<Duration>) {
+        self.connect_timeout = connect_timeout;
+    }
+
+    pub fn connect_timeout(&self) -> Option<Duration> {
+        self.connect_timeout
+    }
+
+    /// Returns the time left before the client gives up connecting, None if it's not connecting anymore.
+    ///
+    /// This is the earliest of the connect timeout, the expiration of the connect token,
+    /// and the timeout of the last server address if the server doesn't answer.
+    pub fn time_remaining_to_connect(&self) -> Option<Duration> {
+        if !self.is_connecting() {
+            return None;
+        }
+
+        let expire_seconds = self.connect_token.expire_timestamp - self.connect_token.create_timestamp;
+        let mut remaining = Duration::from_secs(expire_seconds).saturating_sub(self.current_time - self.connect_start_time);
+        if let Some(connect_timeout) = self.connect_timeout {
+            remaining = remaining.min(connect_timeout.saturating_sub(self.current_time - self.connect_begin_time));
+        }
+        let is_last_server = self
+            .connect_token
+            .server_addresses
+            .get(self.server_addr_index + 1)
+            .is_none_or(|server_addr| server_addr.is_none());
+        if is_last_server && self.connect_token.timeout_seconds > 0 {
+            let timeout = Duration::from_secs(self.connect_token.timeout_seconds as u64);
+            remaining = remaining.min(timeout.saturating_sub(self.time_since_last_received_packet()));
+        }
+
+        Some(remaining)
+    }
+
     /// Returns the reason that the client was disconnected for.
     pub fn disconnect_reason(&self) -> Option<DisconnectReason> {
         if let ClientState::Disconnected(reason) = &self.state {
@@ -168,6 +211,12 @@ impl NetcodeClient {
         None
     }
 
+    /// Returns the reason sent by the server when it disconnected the client,
+    /// None if the client disconnected itself, the connection was lost or the server gave no reason.
+    pub fn disconnect_payload(&self) -> Option<&[u8]> {
+        self.disconnect_payload.as_deref()
+    }
+
     /// Returns the current server address the client is connected or trying to connect.
     pub fn server_addr(&self) -> SocketAddr {
         self.server_addr
@@ -177,7 +226,7 @@ impl NetcodeClient {
     /// Returns a disconnect packet that should be sent to the server.
     pub fn disconnect(&mut self) -> Result<(SocketAddr, &mut [u8]), NetcodeError> {
         self.state = ClientState::Disconnected(DisconnectReason::DisconnectedByClient);
-        let packet = Packet::Disconnect;
+        let packet = Packet::Disconnect(&[]);
         let len = packet.encode(
             &mut self.out,
             self.connect_token.protocol_id,
@@ -236,8 +285,11 @@ impl NetcodeClient {
                 self.last_packet_received_time = self.current_time;
                 return Some(p);
             }
-            (Packet::Disconnect, ClientState::Connected) => {
+            (Packet::Disconnect(reason), ClientState::Connected) => {
                 self.state = ClientState::Disconnected(DisconnectReason::DisconnectedByServer);
+                if !reason.is_empty() {
+                    self.disconnect_payload = Some(reason.to_vec());
+                }
                 self.last_packet_received_time = self.current_time;
             }
             _ => {}
@@ -293,13 +345,19 @@ impl NetcodeClient {
                     self.state = ClientState::Disconnected(DisconnectReason::ConnectTokenExpired);
                     return Err(NetcodeError::Expired);
                 }
-                if connection_timed_out {
+                let connect_timed_out = self
+                    .connect_timeout
+                    .is_some_and(|connect_timeout| self.current_time - self.connect_begin_time >= connect_timeout);
+                if connect_timed_out || connection_timed_out {
                     let reason = if self.state == ClientState::SendingConnectionResponse {
                         DisconnectReason::ConnectionResponseTimedOut
                     } else {
                         DisconnectReason::ConnectionRequestTimedOut
                     };
                     self.state = ClientState::Disconnected(reason);
+                    if connect_timed_out {
+                        return Err(NetcodeError::Disconnected(reason));
+                    }
                     // Try to connect to the next server address
                     self.server_addr_index += 1;
                     if self.server_addr_index >= 32 {
@@ -446,4 +504,34 @@ mod tests {
             _ => unreachable!(),
         }
     }
+
+    #[test]
+    fn connect_timeout() {
+        let server_addresses: Vec<SocketAddr> = vec!["127.0.0.1:8080".parse().unwrap(), "127.0.0.2:3000".parse().unwrap()];
+        let connect_token = ConnectToken::generate(Duration::ZERO, 2, 30, 4, 5, server_addresses, None, &[0; NETCODE_KEY_BYTES]).unwrap();
+        let mut client = NetcodeClient::new(Duration::ZERO, ClientAuthentication::Secure { connect_token }).unwrap();
+        client.set_connect_timeout(Some(Duration::from_secs(8)));
+        assert_eq!(client.time_remaining_to_connect(), Some(Duration::from_secs(8)));
+
+        // The first server doesn't answer, the client tries the next one
+        client.update(Duration::from_secs(6));
+        assert!(client.is_connecting());
+        assert_eq!(client.server_addr(), "127.0.0.2:3000".parse().unwrap());
+        assert_eq!(client.time_remaining_to_connect(), Some(Duration::from_secs(2)));
+
+        client.update(Duration::from_secs(2));
+        assert_eq!(client.disconnect_reason(), Some(DisconnectReason::ConnectionRequestTimedOut));
+        assert_eq!(client.time_remaining_to_connect(), None);
+    }
+
+    #[test]
+    fn time_remaining_to_connect_last_server() {
+        let server_addresses: Vec<SocketAddr> = vec!["127.0.0.1:8080".parse().unwrap()];
+        let connect_token = ConnectToken::generate(Duration::ZERO, 2, 30, 4, 5, server_addresses, None, &[0; NETCODE_KEY_BYTES]).unwrap();
+        let mut client = NetcodeClient::new(Duration::ZERO, ClientAuthentication::Secure { connect_token }).unwrap();
+        assert_eq!(client.time_remaining_to_connect(), Some(Duration::from_secs(5)));
+
+        client.update(Duration::from_secs(2));
+        assert_eq!(client.time_remaining_to_connect(), Some(Duration::from_secs(3)));
+    }
 }
@@ -24,6 +24,8 @@ pub enum NetcodeError {
     NoMoreServers,
     /// The connect token has expired.
     Expired,
+    /// The connect token was revoked by the server.
+    TokenRevoked,
     /// The client is disconnected.
     Disconnected(DisconnectReason),
     /// An error ocurred while encrypting or decrypting.
@@ -52,6 +54,7 @@ impl fmt::Display for NetcodeError {
             PacketTooSmall => write!(fmt, "packet is too small"),
             PayloadAboveLimit => write!(fmt, "payload is above the {} bytes limit", NETCODE_MAX_PAYLOAD_BYTES),
             Expired => write!(fmt, "connection expired"),
+            TokenRevoked => write!(fmt, "connect token was revoked"),
             DuplicatedSequence => write!(fmt, "sequence already received"),
             Disconnected(reason) => write!(fmt, "disconnected: {}", reason),
             NoMoreServers => write!(fmt, "client has no more servers to connect"),
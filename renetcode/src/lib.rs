@@ -28,7 +28,7 @@ mod token;
 pub use client::{ClientAuthentication, DisconnectReason, NetcodeClient};
 pub use crypto::generate_random_bytes;
 pub use error::NetcodeError;
-pub use server::{NetcodeServer, ServerAuthentication, ServerConfig, ServerResult};
+pub use server::{NetcodeServer, NetcodeStats, ServerAuthentication, ServerConfig, ServerResult};
 pub use token::{ConnectToken, TokenGenerationError};
 
 use std::time::Duration;
@@ -46,6 +46,8 @@ const NETCODE_CONNECT_TOKEN_PRIVATE_BYTES: usize = 1024;
 pub const NETCODE_MAX_PACKET_BYTES: usize = 1400;
 /// The maximum number of bytes that a payload can have when generating a payload packet.
 pub const NETCODE_MAX_PAYLOAD_BYTES: usize = 1300;
+/// The maximum number of bytes of the reason sent with a disconnect packet.
+pub const NETCODE_MAX_DISCONNECT_REASON_BYTES: usize = 256;
 
 /// The number of bytes in a private key;
 pub const NETCODE_KEY_BYTES: usize = 32;
@@ -45,7 +45,8 @@ pub enum Packet<'a> {
         max_clients: u32,
     },
     Payload(&'a [u8]),
-    Disconnect,
+    /// Optional reason for the disconnect, empty when none was given.
+    Disconnect(&'a [u8]),
 }
 
 #[derive(Debug, PartialEq, Eq)]
@@ -87,7 +88,7 @@ impl<'a> Packet<'a> {
             Packet::Response { .. } => PacketType::Response,
             Packet::KeepAlive { .. } => PacketType::KeepAlive,
             Packet::Payload { .. } => PacketType::Payload,
-            Packet::Disconnect => PacketType::Disconnect,
+            Packet::Disconnect(_) => PacketType::Disconnect,
         }
     }
 
@@ -152,18 +153,20 @@ impl<'a> Packet<'a> {
                 writer.write_all(&client_index.to_le_bytes())?;
                 writer.write_all(&max_clients.to_le_bytes())?;
             }
-            Packet::Payload(p) => {
+            Packet::Payload(p) | Packet::Disconnect(p) => {
                 writer.write_all(p)?;
             }
-            Packet::ConnectionDenied | Packet::Disconnect => {}
+            Packet::ConnectionDenied => {}
         }
 
         Ok(())
     }
 
     fn read(packet_type: PacketType, src: &'a [u8]) -> Result<Self, io::Error> {
-        if matches!(packet_type, PacketType::Payload) {
-            return Ok(Packet::Payload(src));
+        match packet_type {
+            PacketType::Payload => return Ok(Packet::Payload(src)),
+            PacketType::Disconnect => return Ok(Packet::Disconnect(src)),
+            _ => {}
         }
 
         let src = &mut Cursor::new(src);
@@ -209,8 +212,7 @@ impl<'a> Packet<'a> {
                 Ok(Packet::KeepAlive { client_index, max_clients })
             }
             PacketType::ConnectionDenied => Ok(Packet::ConnectionDenied),
-            PacketType::Disconnect => Ok(Packet::Disconnect),
-            PacketType::Payload => unreachable!(),
+            PacketType::Payload | PacketType::Disconnect => unreachable!(),
         }
     }
 
@@ -428,7 +430,7 @@ mod tests {
 
     #[test]
     fn prefix_sequence() {
-        let packet_type = Packet::Disconnect.id();
+        let packet_type = Packet::Disconnect(&[]).id();
         let sequence = 99999;
 
         let mut buffer = vec![];
@@ -448,7 +450,7 @@ mod tests {
     fn encrypt_decrypt_disconnect_packet() {
         let mut buffer = [0u8; NETCODE_MAX_PACKET_BYTES];
         let key = b"an example very very secret key."; // 32-bytes
-        let packet = Packet::Disconnect;
+        let packet = Packet::Disconnect(b"server closed");
         let protocol_id = 12;
         let sequence = 1;
         let len = packet.encode(&mut buffer, protocol_id, Some((sequence, key))).unwrap();
@@ -6,8 +6,8 @@ use crate::{
     replay_protection::ReplayProtection,
     token::PrivateConnectToken,
     NetcodeError, NETCODE_CONNECT_TOKEN_PRIVATE_BYTES, NETCODE_CONNECT_TOKEN_XNONCE_BYTES, NETCODE_KEY_BYTES, NETCODE_MAC_BYTES,
-    NETCODE_MAX_CLIENTS, NETCODE_MAX_PACKET_BYTES, NETCODE_MAX_PAYLOAD_BYTES, NETCODE_MAX_PENDING_CLIENTS, NETCODE_SEND_RATE,
-    NETCODE_USER_DATA_BYTES, NETCODE_VERSION_INFO,
+    NETCODE_MAX_CLIENTS, NETCODE_MAX_DISCONNECT_REASON_BYTES, NETCODE_MAX_PACKET_BYTES, NETCODE_MAX_PAYLOAD_BYTES,
+    NETCODE_MAX_PENDING_CLIENTS, NETCODE_SEND_RATE, NETCODE_USER_DATA_BYTES, NETCODE_VERSION_INFO,
 };
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -31,6 +31,7 @@ struct Connection {
     timeout_seconds: i32,
     sequence: u64,
     expire_timestamp: u64,
+    xnonce: [u8; NETCODE_CONNECT_TOKEN_XNONCE_BYTES],
     replay_protection: ReplayProtection,
 }
 
@@ -41,6 +42,21 @@ struct ConnectTokenEntry {
     mac: [u8; NETCODE_MAC_BYTES],
 }
 
+/// Counters of the netcode protocol events handled by the server.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct NetcodeStats {
+    /// Connection requests with a valid connect token.
+    pub tokens_validated: u64,
+    /// Connection requests with an invalid, expired or mismatched connect token.
+    pub tokens_rejected: u64,
+    /// Clients that completed the challenge handshake and were connected.
+    pub handshakes_completed: u64,
+    /// Pending clients that did not complete the handshake before the connect token expired.
+    pub handshakes_timed_out: u64,
+    /// Packets discarded because their sequence was already received.
+    pub replay_attacks_rejected: u64,
+}
+
 /// A server that can generate packets from connect clients, that are encrypted, or process
 /// incoming encrypted packets from clients. The server is agnostic from the transport layer, only
 /// consuming and generating bytes that can be transported in any way desired.
@@ -49,6 +65,8 @@ pub struct NetcodeServer {
     clients: Box<[Option<Connection>]>,
     pending_clients: HashMap<SocketAddr, Connection>,
     connect_token_entries: Box<[Option<ConnectTokenEntry>; NETCODE_MAX_CLIENTS * 2]>,
+    // Nonce of the revoked connect tokens with their expire timestamp
+    revoked_tokens: HashMap<[u8; NETCODE_CONNECT_TOKEN_XNONCE_BYTES], u64>,
     protocol_id: u64,
     connect_key: [u8; NETCODE_KEY_BYTES],
     max_clients: usize,
@@ -59,6 +77,7 @@ pub struct NetcodeServer {
     global_sequence: u64,
     secure: bool,
     out: [u8; NETCODE_MAX_PACKET_BYTES],
+    stats: NetcodeStats,
 }
 
 /// Result from processing an packet in the server
@@ -135,6 +154,7 @@ impl NetcodeServer {
         Self {
             clients,
             connect_token_entries: Box::new([None; NETCODE_MAX_CLIENTS * 2]),
+            revoked_tokens: HashMap::new(),
             pending_clients: HashMap::new(),
             protocol_id: config.protocol_id,
             connect_key,
@@ -146,6 +166,7 @@ impl NetcodeServer {
             current_time: config.current_time,
             secure,
             out: [0u8; NETCODE_MAX_PACKET_BYTES],
+            stats: NetcodeStats::default(),
         }
     }
 
@@ -169,6 +190,11 @@ impl NetcodeServer {
         self.current_time
     }
 
+    /// Returns the counters of the netcode protocol events handled by the server.
+    pub fn stats(&self) -> NetcodeStats {
+        self.stats
+    }
+
     fn find_or_add_connect_token_entry(&mut self, new_entry: ConnectTokenEntry) -> bool {
         let mut min = Duration::MAX;
         let mut oldest_entry = 0;
@@ -241,32 +267,16 @@ impl NetcodeServer {
         xnonce: [u8; NETCODE_CONNECT_TOKEN_XNONCE_BYTES],
         data: [u8; NETCODE_CONNECT_TOKEN_PRIVATE_BYTES],
     ) -> Result<ServerResult<'a, '_>, NetcodeError> {
-        if version_info != *NETCODE_VERSION_INFO {
-            return Err(NetcodeError::InvalidVersion);
-        }
-
-        if protocol_id != self.protocol_id {
-            return Err(NetcodeError::InvalidProtocolID);
-        }
-
-        if self.current_time.as_secs() >= expire_timestamp {
-            return Err(NetcodeError::Expired);
-        }
-
-        let connect_token = PrivateConnectToken::decode(&data, self.protocol_id, expire_timestamp, &xnonce, &self.connect_key)?;
-
-        // Skip host list check when unsecure
-        if self.secure {
-            let in_host_list = connect_token
-                .server_addresses
-                .iter()
-                .filter_map(|host| *host)
-                .any(|addr| self.public_addresses.contains(&addr));
-
-            if !in_host_list {
-                return Err(NetcodeError::NotInHostList);
+        let connect_token = match self.validate_connect_token(version_info, protocol_id, expire_timestamp, &xnonce, &data) {
+            Ok(connect_token) => {
+                self.stats.tokens_validated += 1;
+                connect_token
             }
-        }
+            Err(e) => {
+                self.stats.tokens_rejected += 1;
+                return Err(e);
+            }
+        };
 
         let addr_already_connected = find_client_mut_by_addr(&mut self.clients, addr).is_some();
         let id_already_connected = find_client_mut_by_id(&mut self.clients, connect_token.client_id).is_some();
@@ -344,6 +354,7 @@ impl NetcodeServer {
             receive_key: connect_token.client_to_server_key,
             timeout_seconds: connect_token.timeout_seconds,
             expire_timestamp,
+            xnonce,
             user_data: connect_token.user_data,
             replay_protection: ReplayProtection::new(),
         });
@@ -356,6 +367,48 @@ impl NetcodeServer {
         })
     }
 
+    fn validate_connect_token(
+        &self,
+        version_info: [u8; 13],
+        protocol_id: u64,
+        expire_timestamp: u64,
+        xnonce: &[u8; NETCODE_CONNECT_TOKEN_XNONCE_BYTES],
+        data: &[u8; NETCODE_CONNECT_TOKEN_PRIVATE_BYTES],
+    ) -> Result<PrivateConnectToken, NetcodeError> {
+        if version_info != *NETCODE_VERSION_INFO {
+            return Err(NetcodeError::InvalidVersion);
+        }
+
+        if protocol_id != self.protocol_id {
+            return Err(NetcodeError::InvalidProtocolID);
+        }
+
+        if self.current_time.as_secs() >= expire_timestamp {
+            return Err(NetcodeError::Expired);
+        }
+
+        if self.revoked_tokens.contains_key(xnonce) {
+            return Err(NetcodeError::TokenRevoked);
+        }
+
+        let connect_token = PrivateConnectToken::decode(data, self.protocol_id, expire_timestamp, xnonce, &self.connect_key)?;
+
+        // Skip host list check when unsecure
+        if self.secure {
+            let in_host_list = connect_token
+                .server_addresses
+                .iter()
+                .filter_map(|host| *host)
+                .any(|addr| self.public_addresses.contains(&addr));
+
+            if !in_host_list {
+                return Err(NetcodeError::NotInHostList);
+            }
+        }
+
+        Ok(connect_token)
+    }
+
     /// Returns an encoded packet payload to be sent to the client
     pub fn generate_payload_packet<'s>(&'s mut self, client_id: u64, payload: &[u8]) -> Result<(SocketAddr, &'s mut [u8]), NetcodeError> {
         if payload.len() > NETCODE_MAX_PAYLOAD_BYTES {
@@ -398,7 +451,12 @@ impl NetcodeServer {
                 self.protocol_id,
                 Some(&client.receive_key),
                 Some(&mut client.replay_protection),
-            )?;
+            )
+            .inspect_err(|e| {
+                if matches!(e, NetcodeError::DuplicatedSequence) {
+                    self.stats.replay_attacks_rejected += 1;
+                }
+            })?;
             log::trace!(
                 "Received packet from connected client ({}): {:?}",
                 client.client_id,
@@ -408,7 +466,7 @@ impl NetcodeServer {
             client.last_packet_received_time = self.current_time;
             match client.state {
                 ConnectionState::Connected => match packet {
-                    Packet::Disconnect => {
+                    Packet::Disconnect(_) => {
                         client.state = ConnectionState::Disconnected;
                         let client_id = client.client_id;
                         self.clients[slot] = None;
@@ -449,7 +507,12 @@ impl NetcodeServer {
                 self.protocol_id,
                 Some(&pending.receive_key),
                 Some(&mut pending.replay_protection),
-            )?;
+            )
+            .inspect_err(|e| {
+                if matches!(e, NetcodeError::DuplicatedSequence) {
+                    self.stats.replay_attacks_rejected += 1;
+                }
+            })?;
             pending.last_packet_received_time = self.current_time;
             log::trace!("Received packet from pending client ({}): {:?}", addr, packet.packet_type());
             match packet {
@@ -479,9 +542,7 @@ impl NetcodeServer {
                         None => {
                             let packet = Packet::ConnectionDenied;
                             let len = packet.encode(&mut self.out, self.protocol_id, Some((self.global_sequence, &pending.send_key)))?;
-                            pending.state = ConnectionState::Disconnected;
                             self.global_sequence += 1;
-                            pending.last_packet_send_time = self.current_time;
                             return Ok(ServerResult::PacketToSend {
                                 addr,
                                 payload: &mut self.out[..len],
@@ -502,6 +563,7 @@ impl NetcodeServer {
                             let client_id: u64 = pending.client_id;
                             let user_data: [u8; NETCODE_USER_DATA_BYTES] = pending.user_data;
                             self.clients[client_index] = Some(pending);
+                            self.stats.handshakes_completed += 1;
 
                             return Ok(ServerResult::ClientConnected {
                                 client_id,
@@ -578,10 +640,28 @@ impl NetcodeServer {
             if self.current_time.as_secs() > client.expire_timestamp {
                 log::debug!("Pending Client {} disconnected, connection token expired.", client.client_id);
                 client.state = ConnectionState::Disconnected;
+                self.stats.handshakes_timed_out += 1;
             }
         }
 
         self.pending_clients.retain(|_, c| c.state != ConnectionState::Disconnected);
+
+        // Expired tokens are already rejected
+        let current_time = self.current_time.as_secs();
+        self.revoked_tokens.retain(|_, expire_timestamp| current_time < *expire_timestamp);
+    }
+
+    /// Revokes the connect token used by a connected or pending client, so it can't be used to connect again
+    /// even if it has not expired. The client is not disconnected.
+    pub fn revoke_token_for_client_id(&mut self, client_id: u64) -> Result<(), NetcodeError> {
+        let client =
+            find_client_by_id(&self.clients, client_id).or_else(|| self.pending_clients.values().find(|c| c.client_id == client_id));
+        let Some(client) = client else {
+            return Err(NetcodeError::ClientNotFound);
+        };
+
+        self.revoked_tokens.insert(client.xnonce, client.expire_timestamp);
+        Ok(())
     }
 
     /// Updates the client, returns a ServerResult.
@@ -613,7 +693,7 @@ impl NetcodeServer {
             }
 
             if client.state == ConnectionState::Disconnected {
-                let packet = Packet::Disconnect;
+                let packet = Packet::Disconnect(&[]);
                 let sequence = client.sequence;
                 let send_key = client.send_key;
                 let addr = client.addr;
@@ -672,9 +752,18 @@ impl NetcodeServer {
     //       but the library user would need to be aware that he has to run
     //       the same code as Result::ClientDisconnected
     pub fn disconnect(&mut self, client_id: u64) -> ServerResult<'_, '_> {
+        self.disconnect_with_reason(client_id, &[])
+    }
+
+    /// Disconnect an client with a reason that the client can read with [`crate::NetcodeClient::disconnect_payload`].
+    /// The reason is truncated to [`NETCODE_MAX_DISCONNECT_REASON_BYTES`].
+    pub fn disconnect_with_reason(&mut self, client_id: u64, reason: &[u8]) -> ServerResult<'_, '_> {
         if let Some(slot) = find_client_slot_by_id(&self.clients, client_id) {
             let client = self.clients[slot].take().unwrap();
-            let packet = Packet::Disconnect;
+            if reason.len() > NETCODE_MAX_DISCONNECT_REASON_BYTES {
+                log::warn!("Disconnect reason for client {} is too big, truncating it", client_id);
+            }
+            let packet = Packet::Disconnect(&reason[..reason.len().min(NETCODE_MAX_DISCONNECT_REASON_BYTES)]);
 
             let len = match packet.encode(&mut self.out, self.protocol_id, Some((client.sequence, &client.send_key))) {
                 Err(e) => {
@@ -722,7 +811,7 @@ fn find_client_mut_by_addr(clients: &mut [Option<Connection>], addr: SocketAddr)
 
 #[cfg(test)]
 mod tests {
-    use crate::{client::NetcodeClient, token::ConnectToken, ClientAuthentication};
+    use crate::{client::NetcodeClient, token::ConnectToken, ClientAuthentication, DisconnectReason};
 
     use super::*;
 
@@ -856,4 +945,134 @@ mod tests {
         // Don't allow same token with different address
         assert!(!server.find_or_add_connect_token_entry(connect_token));
     }
+
+    #[test]
+    fn server_stats() {
+        let mut server = new_server();
+        let client_addr: SocketAddr = "127.0.0.1:3000".parse().unwrap();
+        let client_id = 4;
+        let connect_token = ConnectToken::generate(
+            Duration::ZERO,
+            TEST_PROTOCOL_ID,
+            3,
+            client_id,
+            5,
+            server.addresses(),
+            None,
+            TEST_KEY,
+        )
+        .unwrap();
+        let mut client = NetcodeClient::new(Duration::ZERO, ClientAuthentication::Secure { connect_token }).unwrap();
+
+        let (client_packet, _) = client.update(Duration::ZERO).unwrap();
+        match server.process_packet(client_addr, client_packet) {
+            ServerResult::PacketToSend { payload, .. } => client.process_packet(payload),
+            _ => unreachable!(),
+        };
+        let (client_packet, _) = client.update(Duration::ZERO).unwrap();
+        match server.process_packet(client_addr, client_packet) {
+            ServerResult::ClientConnected { payload, .. } => client.process_packet(payload),
+            _ => unreachable!(),
+        };
+
+        let (_, packet) = client.generate_payload_packet(&[1u8; 10]).unwrap();
+        let mut replayed_packet = packet.to_vec();
+        assert!(matches!(server.process_packet(client_addr, packet), ServerResult::Payload { .. }));
+        assert_eq!(server.process_packet(client_addr, &mut replayed_packet), ServerResult::None);
+
+        let stats = server.stats();
+        assert_eq!(stats.tokens_validated, 1);
+        assert_eq!(stats.tokens_rejected, 0);
+        assert_eq!(stats.handshakes_completed, 1);
+        assert_eq!(stats.handshakes_timed_out, 0);
+        assert_eq!(stats.replay_attacks_rejected, 1);
+    }
+
+    #[test]
+    fn revoked_token() {
+        let mut server = new_server();
+        let client_addr: SocketAddr = "127.0.0.1:3000".parse().unwrap();
+        let client_id = 4;
+        let connect_token = ConnectToken::generate(
+            Duration::ZERO,
+            TEST_PROTOCOL_ID,
+            3,
+            client_id,
+            5,
+            server.addresses(),
+            None,
+            TEST_KEY,
+        )
+        .unwrap();
+        let client_auth = ClientAuthentication::Secure {
+            connect_token: connect_token.clone(),
+        };
+        let mut client = NetcodeClient::new(Duration::ZERO, client_auth).unwrap();
+
+        let (client_packet, _) = client.update(Duration::ZERO).unwrap();
+        match server.process_packet(client_addr, client_packet) {
+            ServerResult::PacketToSend { payload, .. } => client.process_packet(payload),
+            _ => unreachable!(),
+        };
+        let (client_packet, _) = client.update(Duration::ZERO).unwrap();
+        assert!(matches!(
+            server.process_packet(client_addr, client_packet),
+            ServerResult::ClientConnected { .. }
+        ));
+
+        server.revoke_token_for_client_id(client_id).unwrap();
+        assert!(matches!(server.revoke_token_for_client_id(42), Err(NetcodeError::ClientNotFound)));
+        server.disconnect(client_id);
+
+        let mut client = NetcodeClient::new(Duration::ZERO, ClientAuthentication::Secure { connect_token }).unwrap();
+        let (client_packet, _) = client.update(Duration::ZERO).unwrap();
+        assert_eq!(server.process_packet(client_addr, client_packet), ServerResult::None);
+        assert_eq!(server.stats().tokens_rejected, 1);
+
+        // The revoked token is forgotten once it expires
+        server.update(Duration::from_secs(3));
+        assert!(server.revoked_tokens.is_empty());
+    }
+
+    #[test]
+    fn disconnect_with_reason() {
+        let mut server = new_server();
+        let client_addr: SocketAddr = "127.0.0.1:3000".parse().unwrap();
+        let client_id = 4;
+        let connect_token = ConnectToken::generate(
+            Duration::ZERO,
+            TEST_PROTOCOL_ID,
+            3,
+            client_id,
+            5,
+            server.addresses(),
+            None,
+            TEST_KEY,
+        )
+        .unwrap();
+        let mut client = NetcodeClient::new(Duration::ZERO, ClientAuthentication::Secure { connect_token }).unwrap();
+
+        let (client_packet, _) = client.update(Duration::ZERO).unwrap();
+        match server.process_packet(client_addr, client_packet) {
+            ServerResult::PacketToSend { payload, .. } => client.process_packet(payload),
+            _ => unreachable!(),
+        };
+        let (client_packet, _) = client.update(Duration::ZERO).unwrap();
+        match server.process_packet(client_addr, client_packet) {
+            ServerResult::ClientConnected { payload, .. } => client.process_packet(payload),
+            _ => unreachable!(),
+        };
+        assert!(client.is_connected());
+        assert!(client.disconnect_payload().is_none());
+
+        match server.disconnect_with_reason(client_id, b"server is restarting") {
+            ServerResult::ClientDisconnected {
+                payload: Some(payload), ..
+            } => client.process_packet(payload),
+            _ => unreachable!(),
+        };
+
+        assert_eq!(client.disconnect_reason(), Some(DisconnectReason::DisconnectedByServer));
+        assert_eq!(client.disconnect_payload(), Some(&b"server is restarting"[..]));
+    }
 }